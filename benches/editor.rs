@@ -0,0 +1,139 @@
+//! Benchmarks for parsing and writing saves, meshing splines, and snapping.
+//!
+//! The editor is a single binary, so the modules it needs are included by path. Run with
+//! `cargo bench`.
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use bevy::render::render_resource::PrimitiveTopology;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::io::Cursor;
+
+#[path = "../src/bevy_obj/mod.rs"]
+mod bevy_obj;
+#[path = "../src/coords.rs"]
+mod coords;
+#[path = "../src/registry.rs"]
+mod registry;
+#[path = "../src/spline/mod.rs"]
+mod spline;
+
+use rro_gvas as gvas;
+
+/// `spline` only needs the spline meshes from the editor's assets
+mod control {
+    use crate::registry::SplineTypeMap;
+    use bevy::prelude::*;
+
+    pub struct DefaultAssets {
+        pub spline_mesh: SplineTypeMap<Handle<Mesh>>,
+        pub draft_spline_mesh: SplineTypeMap<Handle<Mesh>>,
+    }
+}
+
+use gvas::{CurveDataOwned, RROSave, SplineType};
+use spline::mesh::{draft_mesh, mesh_on_curve_detail, MeshQuality};
+use spline::{nearest_point, Bezier, PolyBezier};
+
+/// Splines in the generated save
+const SPLINES: usize = 2000;
+/// Control points in each generated spline
+const POINTS: usize = 10;
+/// Points to snap against
+const SNAP_POINTS: usize = 20000;
+
+/// Deterministic pseudo random points, so runs can be compared
+fn points(n: usize, seed: u32) -> Vec<Vec3> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        (state >> 8) as f32 / (1 << 24) as f32
+    };
+    (0..n)
+        .map(|_| Vec3::new(next() * 200. - 100., next() * 2., next() * 200. - 100.))
+        .collect()
+}
+
+/// The included default save, with `SPLINES` splines of `POINTS` points each
+fn large_save() -> Vec<u8> {
+    let mut gvas = RROSave::read(&mut Cursor::new(include_bytes!("../assets/default.sav")))
+        .expect("Failed to parse included save");
+    gvas.set_curves((0..SPLINES).map(|i| {
+        let control_points: Vec<_> = (0..POINTS)
+            .map(|j| [i as f32 * 500., j as f32 * 1000., 0.])
+            .collect();
+        CurveDataOwned {
+            location: control_points[0],
+            ty: SplineType::Track,
+            visibility: vec![true; POINTS - 1],
+            control_points,
+        }
+    }))
+    .expect("Failed to set curves");
+    let mut bytes = Cursor::new(vec![]);
+    gvas.write(&mut bytes).expect("Failed to write save");
+    bytes.into_inner()
+}
+
+fn gvas(c: &mut Criterion) {
+    let bytes = large_save();
+    c.bench_function("parse save", |b| {
+        b.iter(|| RROSave::read(&mut Cursor::new(black_box(&bytes))).unwrap())
+    });
+    let gvas = RROSave::read(&mut Cursor::new(&bytes)).unwrap();
+    c.bench_function("write save", |b| {
+        b.iter(|| {
+            let mut out = Cursor::new(Vec::with_capacity(bytes.len()));
+            gvas.write(&mut out).unwrap();
+            out
+        })
+    });
+    c.bench_function("export save as JSON", |b| {
+        b.iter(|| gvas.to_json().unwrap())
+    });
+    let json = gvas.to_json().unwrap();
+    c.bench_function("import save from JSON", |b| {
+        b.iter(|| RROSave::from_json(black_box(&json)).unwrap())
+    });
+}
+
+fn meshing(c: &mut Criterion) {
+    let mut track = Mesh::new(PrimitiveTopology::TriangleList);
+    bevy_obj::load_obj_from_bytes(include_bytes!("../assets/models/track.obj"), &mut track)
+        .unwrap();
+    let draft = draft_mesh(&track);
+    let bezier = PolyBezier::new(points(100, 1), vec![true; 99], SplineType::Track);
+    for (name, mesh, quality) in [
+        ("mesh 100 segments draft", &draft, MeshQuality::Draft),
+        ("mesh 100 segments normal", &track, MeshQuality::Normal),
+        ("mesh 100 segments high", &track, MeshQuality::High),
+    ] {
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                for part in bezier.segments() {
+                    black_box(mesh_on_curve_detail(
+                        mesh,
+                        part.centroid(),
+                        part,
+                        &quality.detail(),
+                    ));
+                }
+            })
+        });
+    }
+}
+
+fn snapping(c: &mut Criterion) {
+    let targets = points(SNAP_POINTS, 2);
+    let queries = points(100, 3);
+    c.bench_function("snap 100 points", |b| {
+        b.iter(|| {
+            for pt in &queries {
+                black_box(nearest_point(*pt, targets.iter().copied()));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, gvas, meshing, snapping);
+criterion_main!(benches);
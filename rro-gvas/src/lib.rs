@@ -0,0 +1,2850 @@
+//! Reading and writing Railroads Online saves.
+//!
+//! Saves are Unreal Engine GVAS files. [`GVASFile`] reads and writes the raw property list, and
+//! [`RROSave`] wraps it with typed accessors for the arrays the game uses, such as
+//! [`RROSave::curves`] and [`RROSave::set_curves`]. Every error is a [`GVASError`].
+//!
+//! ```no_run
+//! use rro_gvas::RROSave;
+//!
+//! let mut save = RROSave::read(&mut std::fs::File::open("slot1.sav")?)?;
+//! for curve in save.curves()? {
+//!     println!("{:?} with {} points", curve.ty, curve.control_points.len());
+//! }
+//! save.write(&mut std::fs::File::create("slot2.sav")?)?;
+//! # Ok::<(), rro_gvas::GVASError>(())
+//! ```
+//!
+//! [`builder::SaveBuilder`] makes new saves from scratch, e.g. for tests.
+//!
+//! With the `bevy` feature, the per object data types are Bevy components.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Read, Write},
+    mem::size_of,
+};
+
+pub mod builder;
+pub mod schema;
+pub mod validate;
+pub mod version;
+use serde::{Deserialize, Serialize};
+use version::SaveVersion;
+
+/// Any error reading, writing, or accessing a save
+#[derive(Debug)]
+pub enum GVASError {
+    IOError(Error),
+    /// A property the save should have isn't there
+    Missing(&'static str),
+    /// A property has a different type than expected
+    WrongType,
+    /// A property or array type the parser doesn't understand
+    Unsupported(String),
+    Json(serde_json::Error),
+    /// A written save differs from what was meant to be written, starting at this byte
+    Mismatch(usize),
+    /// Bytes that can't be what the parser expects, e.g. a string without its terminator
+    Malformed(String),
+    /// An error reading a file, with the offset in bytes it was found at
+    At { offset: u64, error: Box<GVASError> },
+}
+
+impl GVASError {
+    /// Adds where the error was found, unless it already says
+    fn at(self, offset: u64) -> Self {
+        match self {
+            Self::At { .. } => self,
+            error => Self::At {
+                offset,
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for GVASError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOError(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                write!(f, "the file ends too early")
+            }
+            Self::IOError(e) => write!(f, "{}", e),
+            Self::Missing(name) => write!(f, "the save has no {}", name),
+            Self::WrongType => write!(f, "a property has the wrong type"),
+            Self::Unsupported(what) => write!(f, "{} isn't supported", what),
+            Self::Json(e) => write!(f, "{}", e),
+            Self::Mismatch(offset) => write!(f, "the written file differs at byte {}", offset),
+            Self::Malformed(what) => write!(f, "{}", what),
+            Self::At { offset, error } => write!(f, "{} (at byte {})", error, offset),
+        }
+    }
+}
+
+impl std::error::Error for GVASError {}
+
+impl From<Error> for GVASError {
+    fn from(e: Error) -> Self {
+        Self::IOError(e)
+    }
+}
+
+impl From<serde_json::Error> for GVASError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, GVASError>;
+
+/// The GUID a property has after its header, if its flag is set
+pub type PropertyGuid = Option<[u8; 16]>;
+
+pub trait ReadExt: Read {
+    fn read_uestring(&mut self) -> Result<String>;
+    fn read_string_len(&mut self, len: i64) -> Result<String>;
+    fn read_u64(&mut self) -> Result<u64>;
+    fn read_i64(&mut self) -> Result<i64>;
+    fn read_u32(&mut self) -> Result<u32>;
+    fn read_i32(&mut self) -> Result<i32>;
+    fn read_f32(&mut self) -> Result<f32>;
+    fn read_u16(&mut self) -> Result<u16>;
+    fn read_u8(&mut self) -> Result<u8>;
+    fn read_i8(&mut self) -> Result<i8>;
+    fn read_guid(&mut self) -> Result<[u8; 16]>;
+    /// Reads the flag after a property's header, and the GUID that follows it if it's set
+    fn read_property_guid(&mut self) -> Result<PropertyGuid>;
+}
+trait WriteExt: Write {
+    /// Writes `s` the way `read_uestring` reads it: ASCII as single bytes, and anything else as
+    /// UTF-16 with a negative length
+    fn write_string(&mut self, s: &str) -> Result<()> {
+        if s.is_empty() {
+            self.write_all(&0u32.to_le_bytes())?;
+        } else if s.is_ascii() {
+            self.write_all(&(s.len() as u32 + 1).to_le_bytes())?;
+            self.write_all(s.as_bytes())?;
+            self.write_all(&[0u8])?;
+        } else {
+            let units: Vec<u16> = s.encode_utf16().collect();
+            self.write_all(&(-(units.len() as i32 + 1)).to_le_bytes())?;
+            for unit in units {
+                self.write_all(&unit.to_le_bytes())?;
+            }
+            self.write_all(&[0u8; 2])?;
+        }
+        Ok(())
+    }
+
+    /// Writes the flag after a property's header, and `guid` if there is one
+    fn write_property_guid(&mut self, guid: &PropertyGuid) -> Result<()> {
+        match guid {
+            Some(guid) => {
+                self.write_all(&[1u8])?;
+                self.write_all(guid)?;
+            }
+            None => self.write_all(&[0u8])?,
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> WriteExt for W {}
+
+impl<R: Read> ReadExt for R {
+    fn read_uestring(&mut self) -> Result<String> {
+        let len = self.read_i32()?;
+        if len > 0 {
+            let mut buf = read_bytes(self, len as u64)?;
+            if buf.pop() != Some(0) {
+                return Err(GVASError::Malformed("string not terminated".to_string()));
+            }
+            Ok(encoding_rs::WINDOWS_1252
+                .decode_without_bom_handling(&buf)
+                .0
+                .into_owned())
+        } else if len < 0 {
+            let mut buf = read_bytes(self, len.unsigned_abs() as u64 * 2)?;
+            let (e, e2) = (buf.pop(), buf.pop());
+            if e != Some(0) || e2 != Some(0) {
+                return Err(GVASError::Malformed("string not terminated".to_string()));
+            }
+            Ok(encoding_rs::UTF_16LE
+                .decode_without_bom_handling(&buf)
+                .0
+                .into_owned())
+        } else {
+            Ok(String::new())
+        }
+    }
+
+    fn read_string_len(&mut self, exp_len: i64) -> Result<String> {
+        let len = self.read_i32()?;
+        // Negative lengths are UTF-16
+        let bytes = if len < 0 { len.unsigned_abs() as i64 * 2 } else { len as i64 };
+        if bytes + size_of::<i32>() as i64 != exp_len {
+            return Err(GVASError::Malformed(format!(
+                "string of {} bytes in a property of {}",
+                bytes, exp_len
+            )));
+        }
+        if len > 0 {
+            let mut buf = read_bytes(self, len as u64)?;
+            if buf.pop() != Some(0) {
+                return Err(GVASError::Malformed("string not terminated".to_string()));
+            }
+            Ok(encoding_rs::WINDOWS_1252
+                .decode_without_bom_handling(&buf)
+                .0
+                .into_owned())
+        } else if len < 0 {
+            let mut buf = read_bytes(self, len.unsigned_abs() as u64 * 2)?;
+            let (e, e2) = (buf.pop(), buf.pop());
+            if e != Some(0) || e2 != Some(0) {
+                return Err(GVASError::Malformed("string not terminated".to_string()));
+            }
+            Ok(encoding_rs::UTF_16LE
+                .decode_without_bom_handling(&buf)
+                .0
+                .into_owned())
+        } else {
+            Ok(String::new())
+        }
+    }
+
+    fn read_f32(&mut self) -> Result<f32> {
+        let mut buf = [0u8; size_of::<f32>()];
+        self.read_exact(&mut buf)?;
+        Ok(f32::from_ne_bytes(buf))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        let mut buf = [0u8; size_of::<u64>()];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_ne_bytes(buf))
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let mut buf = [0u8; size_of::<i64>()];
+        self.read_exact(&mut buf)?;
+        Ok(i64::from_ne_bytes(buf))
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let mut buf = [0u8; size_of::<u32>()];
+        self.read_exact(&mut buf)?;
+        Ok(u32::from_ne_bytes(buf))
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        let mut buf = [0u8; size_of::<i32>()];
+        self.read_exact(&mut buf)?;
+        Ok(i32::from_ne_bytes(buf))
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let mut buf = [0u8; size_of::<u16>()];
+        self.read_exact(&mut buf)?;
+        Ok(u16::from_ne_bytes(buf))
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0u8; size_of::<u8>()];
+        self.read_exact(&mut buf)?;
+        Ok(u8::from_ne_bytes(buf))
+    }
+
+    fn read_i8(&mut self) -> Result<i8> {
+        let mut buf = [0u8; size_of::<i8>()];
+        self.read_exact(&mut buf)?;
+        Ok(i8::from_ne_bytes(buf))
+    }
+
+    fn read_guid(&mut self) -> Result<[u8; 16]> {
+        let mut buf = [0u8; 16];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_property_guid(&mut self) -> Result<PropertyGuid> {
+        if self.read_u8()? != 0 {
+            Ok(Some(self.read_guid()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Reads exactly `len` bytes. The buffer grows as bytes arrive, so a corrupt length runs into
+/// the end of the file instead of allocating all of it up front
+fn read_bytes(r: &mut impl Read, len: u64) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+    r.by_ref().take(len).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < len {
+        return Err(Error::from(ErrorKind::UnexpectedEof).into());
+    }
+    Ok(buf)
+}
+
+/// Counts the bytes read through it, so errors can say where they were found
+pub struct OffsetReader<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R: Read> OffsetReader<R> {
+    /// Reads from `inner`, which starts `offset` bytes into the file
+    pub fn new(inner: R, offset: u64) -> Self {
+        Self { inner, offset }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+/// Any GVAS file, as a header and a list of properties
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GVASFile {
+    save_game_version: u32,
+    package_version: u32,
+    engine_version: EngineVersion,
+    custom_format_version: u32,
+    // format_data_count: u32,
+    custom_format_data: Vec<DataEntry>,
+    save_game_type: String,
+    properties: Vec<Property>,
+}
+
+impl GVASFile {
+    pub fn read(r: &mut impl ReadExt) -> Result<Self> {
+        Self::read_with_progress(r, |_| ())
+    }
+
+    /// Reads a file, calling `progress` with the number of properties read so far after each one.
+    /// Errors are `GVASError::At` the offset they were found at
+    pub fn read_with_progress(r: &mut impl ReadExt, progress: impl FnMut(usize)) -> Result<Self> {
+        let mut r = OffsetReader::new(r, 0);
+        Self::read_from(&mut r, progress).map_err(|e| e.at(r.offset()))
+    }
+
+    fn read_from(r: &mut OffsetReader<impl Read>, mut progress: impl FnMut(usize)) -> Result<Self> {
+        let mut buf = [0u8; 4];
+        r.read_exact(&mut buf)?;
+        if &buf != b"GVAS" {
+            return Err(GVASError::Malformed(format!(
+                "not a GVAS file, it starts with {:?}",
+                buf
+            )));
+        }
+        let save_game_version = r.read_u32()?;
+        let package_version = r.read_u32()?;
+        let engine_version = EngineVersion::read(r)?;
+        let custom_format_version = r.read_u32()?;
+        let custom_format_count = r.read_u32()?;
+        let custom_format_data = (0..custom_format_count)
+            .map(|_| DataEntry::read(r))
+            .collect::<Result<_>>()?;
+        let save_game_type = r.read_uestring()?;
+        let mut properties = vec![];
+        while let Some(prop) = Property::read(r)? {
+            properties.push(prop);
+            progress(properties.len());
+        }
+        let mut buf = [0u8; 100];
+        let _len = r.read(&mut buf)?;
+        Ok(Self {
+            save_game_version,
+            package_version,
+            engine_version,
+            custom_format_version,
+            custom_format_data,
+            save_game_type,
+            properties,
+        })
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        write!(w, "GVAS")?;
+        w.write_all(&self.save_game_version.to_le_bytes())?;
+        w.write_all(&self.package_version.to_le_bytes())?;
+        self.engine_version.write(w)?;
+        w.write_all(&self.custom_format_version.to_le_bytes())?;
+        w.write_all(&(self.custom_format_data.len() as u32).to_le_bytes())?;
+        for entry in &self.custom_format_data {
+            entry.write(w)?;
+        }
+        w.write_string(self.save_game_type.as_str())?;
+        for prop in &self.properties {
+            prop.write(w)?;
+        }
+        Ok(())
+    }
+
+    fn get_prop<'a>(&'a self, name: &'static str) -> Result<&'a Value> {
+        self.properties
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| &p.val)
+            .ok_or(GVASError::Missing(name))
+    }
+
+    fn get_prop_mut<'a>(&'a mut self, name: &'static str) -> Result<&'a mut Value> {
+        self.properties
+            .iter_mut()
+            .find(|p| p.name == name)
+            .map(|p| &mut p.val)
+            .ok_or(GVASError::Missing(name))
+    }
+
+    /// Replace the value of a property, adding it if the save doesn't have it yet
+    fn set_prop(&mut self, name: &'static str, val: Value) {
+        if let Some(prop) = self.properties.iter_mut().find(|p| p.name == name) {
+            prop.val = val;
+        } else {
+            // New properties have to go before the terminating `None`
+            let i = self
+                .properties
+                .iter()
+                .position(|p| p.name == "None")
+                .unwrap_or(self.properties.len());
+            self.properties.insert(
+                i,
+                Property {
+                    name: name.to_string(),
+                    val,
+                },
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct EngineVersion {
+    major: u16,
+    minor: u16,
+    patch: u16,
+    build: u32,
+    build_id: String,
+}
+
+impl EngineVersion {
+    pub fn read(r: &mut impl ReadExt) -> Result<Self> {
+        let major = r.read_u16()?;
+        let minor = r.read_u16()?;
+        let patch = r.read_u16()?;
+        let build = r.read_u32()?;
+        let build_id = r.read_uestring()?;
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            build,
+            build_id,
+        })
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        w.write_all(&self.major.to_le_bytes())?;
+        w.write_all(&self.minor.to_le_bytes())?;
+        w.write_all(&self.patch.to_le_bytes())?;
+        w.write_all(&self.build.to_le_bytes())?;
+        w.write_string(self.build_id.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DataEntry {
+    #[serde(with = "schema::guid")]
+    guid: [u8; 16],
+    value: u32,
+}
+
+impl DataEntry {
+    pub fn read(r: &mut impl Read) -> Result<Self> {
+        let mut guid = [0u8; 16];
+        r.read_exact(&mut guid)?;
+        let mut buf = [0u8; 4];
+        r.read_exact(&mut buf)?;
+        let value = u32::from_ne_bytes(buf);
+        Ok(Self { guid, value })
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        w.write_all(&self.guid)?;
+        w.write_all(&self.value.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Property {
+    name: String,
+    #[serde(rename = "value")]
+    val: Value,
+}
+
+impl Property {
+    pub fn read(r: &mut OffsetReader<impl Read>) -> Result<Option<Self>> {
+        let name = match r.read_uestring() {
+            Ok(name) => name,
+            Err(GVASError::IOError(e)) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let val = Value::read(r, name.as_str())?;
+        Ok(Some(Self { name, val }))
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        w.write_string(self.name.as_str())?;
+        self.val.write(w, self.name.as_str())
+    }
+}
+
+/// The value of a single top level property
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum Value {
+    #[serde(rename = "string")]
+    String(String),
+    #[serde(rename = "string_array")]
+    StringArray(Vec<String>),
+    #[serde(rename = "int32_array")]
+    Int32Array(Vec<u32>),
+    #[serde(rename = "bool_array")]
+    BoolArray(Vec<bool>),
+    #[serde(rename = "float_array")]
+    FloatArray(#[serde(with = "schema::floats")] Vec<f32>),
+    #[serde(rename = "text_array")]
+    TextArray(Vec<TextProperty>),
+    #[serde(rename = "vector_array")]
+    VectorArray(#[serde(with = "schema::vectors")] Vec<[f32; 3]>),
+    #[serde(rename = "rotator_array")]
+    RotatorArray(#[serde(with = "schema::vectors")] Vec<[f32; 3]>),
+    /// A ByteProperty without an enum type
+    #[serde(rename = "byte")]
+    Byte(u8, #[serde(with = "schema::property_guid")] PropertyGuid),
+    /// (enum, value) A ByteProperty holding a value of the given enum
+    #[serde(rename = "byte_enum")]
+    ByteEnum(
+        String,
+        String,
+        #[serde(with = "schema::property_guid")] PropertyGuid,
+    ),
+    /// (enum, value)
+    #[serde(rename = "enum")]
+    Enum(
+        String,
+        String,
+        #[serde(with = "schema::property_guid")] PropertyGuid,
+    ),
+    #[serde(rename = "name")]
+    Name(String, #[serde(with = "schema::property_guid")] PropertyGuid),
+    /// (ty, bytes) A property of an unsupported type, along with every byte after the type name,
+    /// so it can be written back unchanged
+    #[serde(rename = "raw")]
+    Raw(String, #[serde(with = "schema::hex")] Vec<u8>),
+    #[serde(rename = "none")]
+    None,
+}
+
+impl Value {
+    pub fn is_array(&self) -> bool {
+        match self {
+            Self::None
+            | Self::String(_)
+            | Self::Byte(..)
+            | Self::ByteEnum(..)
+            | Self::Enum(..)
+            | Self::Name(..)
+            | Self::Raw(_, _) => false,
+            Self::StringArray(_)
+            | Self::Int32Array(_)
+            | Self::BoolArray(_)
+            | Self::FloatArray(_)
+            | Self::TextArray(_)
+            | Self::VectorArray(_)
+            | Self::RotatorArray(_) => true,
+        }
+    }
+    /// Name of the UE property type, with the element type for arrays
+    pub fn type_name(&self) -> &str {
+        match self {
+            Self::String(_) => "StrProperty",
+            Self::StringArray(_) => "ArrayProperty<StrProperty>",
+            Self::Int32Array(_) => "ArrayProperty<IntProperty>",
+            Self::BoolArray(_) => "ArrayProperty<BoolProperty>",
+            Self::FloatArray(_) => "ArrayProperty<FloatProperty>",
+            Self::TextArray(_) => "ArrayProperty<TextProperty>",
+            Self::VectorArray(_) => "ArrayProperty<Vector>",
+            Self::RotatorArray(_) => "ArrayProperty<Rotator>",
+            Self::Byte(..) | Self::ByteEnum(..) => "ByteProperty",
+            Self::Enum(..) => "EnumProperty",
+            Self::Name(..) => "NameProperty",
+            Self::Raw(ty, _) => ty.as_str(),
+            Self::None => "None",
+        }
+    }
+
+    pub fn write(&self, w: &mut impl Write, name: &str) -> Result<()> {
+        match self {
+            Self::None => w.write_all(&[0u8; size_of::<u32>()])?,
+            Self::String(s) => write_sized(w, "StrProperty", |_| Ok(()), |b| b.write_string(s))?,
+            Self::StringArray(arr) => {
+                write_array(w, "StrProperty", |b| Self::write_str_array(b, arr))?
+            }
+            Self::Int32Array(arr) => {
+                write_array(w, "IntProperty", |b| Self::write_int_array(b, arr))?
+            }
+            Self::FloatArray(arr) => {
+                write_array(w, "FloatProperty", |b| Self::write_float_array(b, arr))?
+            }
+            Self::BoolArray(arr) => {
+                write_array(w, "BoolProperty", |b| Self::write_bool_array(b, arr))?
+            }
+            Self::VectorArray(arr) => write_array(w, "StructProperty", |b| {
+                Self::write_struct_array(b, arr, name, "Vector")
+            })?,
+            Self::RotatorArray(arr) => write_array(w, "StructProperty", |b| {
+                Self::write_struct_array(b, arr, name, "Rotator")
+            })?,
+            Self::TextArray(arr) => {
+                write_array(w, "TextProperty", |b| Self::write_text_array(b, arr))?
+            }
+            Self::Byte(byte, guid) => write_sized_with_guid(
+                w,
+                "ByteProperty",
+                guid,
+                |h| h.write_string("None"),
+                |b| Ok(b.write_all(&[*byte])?),
+            )?,
+            Self::ByteEnum(e, val, guid) => write_sized_with_guid(
+                w,
+                "ByteProperty",
+                guid,
+                |h| h.write_string(e.as_str()),
+                |b| b.write_string(val.as_str()),
+            )?,
+            Self::Enum(e, val, guid) => write_sized_with_guid(
+                w,
+                "EnumProperty",
+                guid,
+                |h| h.write_string(e.as_str()),
+                |b| b.write_string(val.as_str()),
+            )?,
+            Self::Name(s, guid) => write_sized_with_guid(
+                w,
+                "NameProperty",
+                guid,
+                |_| Ok(()),
+                |b| b.write_string(s),
+            )?,
+            Self::Raw(ty, bytes) => {
+                w.write_string(ty.as_str())?;
+                w.write_all(bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_bool_array(w: &mut impl Write, arr: &Vec<bool>) -> Result<()> {
+        w.write_all(&(arr.len() as u32).to_le_bytes())?;
+        for s in arr {
+            w.write_all(&[if *s { 1u8 } else { 0u8 }])?;
+        }
+        Ok(())
+    }
+
+    pub fn write_float_array(w: &mut impl Write, arr: &Vec<f32>) -> Result<()> {
+        w.write_all(&(arr.len() as u32).to_le_bytes())?;
+        for s in arr {
+            w.write_all(&s.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn write_int_array(w: &mut impl Write, arr: &Vec<u32>) -> Result<()> {
+        w.write_all(&(arr.len() as u32).to_le_bytes())?;
+        for s in arr {
+            w.write_all(&s.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn write_str_array(w: &mut impl Write, arr: &Vec<String>) -> Result<()> {
+        w.write_all(&(arr.len() as u32).to_le_bytes())?;
+        for s in arr {
+            w.write_string(s.as_str())?;
+        }
+        Ok(())
+    }
+
+    pub fn write_text_array(w: &mut impl Write, arr: &Vec<TextProperty>) -> Result<()> {
+        w.write_all(&(arr.len() as u32).to_le_bytes())?;
+        for t in arr {
+            t.write(w)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_struct_array(
+        w: &mut impl Write,
+        arr: &Vec<[f32; 3]>,
+        name: &str,
+        ty: &str,
+    ) -> Result<()> {
+        w.write_all(&(arr.len() as u32).to_le_bytes())?;
+        let mut data = vec![];
+        for [a, b, c] in arr {
+            data.write_all(&a.to_le_bytes())?;
+            data.write_all(&b.to_le_bytes())?;
+            data.write_all(&c.to_le_bytes())?;
+        }
+        w.write_string(name)?;
+        // The inner struct header has its own size, followed by a 16 byte GUID
+        write_sized(
+            w,
+            "StructProperty",
+            |h| {
+                h.write_string(ty)?;
+                Ok(h.write_all(&[0u8; 16])?)
+            },
+            |b| Ok(b.write_all(&data)?),
+        )
+    }
+
+    pub fn read(r: &mut OffsetReader<impl Read>, name: &str) -> Result<Self> {
+        let ty = r.read_uestring()?;
+        match ty.as_str() {
+            "StrProperty" => Self::read_str(r),
+            "ArrayProperty" => Self::read_array(r, name),
+            "ByteProperty" => Self::read_byte(r),
+            "EnumProperty" => Self::read_enum(r),
+            "NameProperty" => Self::read_name(r),
+            "" => Ok(Self::None),
+            _ => Self::read_raw(r, ty),
+        }
+    }
+
+    pub fn read_byte(r: &mut impl Read) -> Result<Self> {
+        let _sz = r.read_u64()?;
+        let e = r.read_uestring()?;
+        let guid = r.read_property_guid()?;
+        if e == "None" {
+            Ok(Self::Byte(r.read_u8()?, guid))
+        } else {
+            Ok(Self::ByteEnum(e, r.read_uestring()?, guid))
+        }
+    }
+
+    pub fn read_enum(r: &mut impl Read) -> Result<Self> {
+        let _sz = r.read_u64()?;
+        let e = r.read_uestring()?;
+        let guid = r.read_property_guid()?;
+        Ok(Self::Enum(e, r.read_uestring()?, guid))
+    }
+
+    pub fn read_name(r: &mut impl Read) -> Result<Self> {
+        let _sz = r.read_u64()?;
+        let guid = r.read_property_guid()?;
+        Ok(Self::Name(r.read_uestring()?, guid))
+    }
+
+    /// Reads a property without interpreting it. Most types have a one byte header, but a few
+    /// have extra type names before the data
+    pub fn read_raw(r: &mut impl Read, ty: String) -> Result<Self> {
+        let size = r.read_u64()?;
+        let mut raw = size.to_le_bytes().to_vec();
+        match ty.as_str() {
+            "StructProperty" => {
+                read_raw_string(r, &mut raw)?;
+                read_raw_bytes(r, &mut raw, 16 + 1)?;
+            }
+            "ArrayProperty" | "SetProperty" => {
+                read_raw_string(r, &mut raw)?;
+                read_raw_bytes(r, &mut raw, 1)?;
+            }
+            "MapProperty" => {
+                read_raw_string(r, &mut raw)?;
+                read_raw_string(r, &mut raw)?;
+                read_raw_bytes(r, &mut raw, 1)?;
+            }
+            // The value is stored in the header, and the size is always 0
+            "BoolProperty" => read_raw_bytes(r, &mut raw, 2)?,
+            _ => read_raw_bytes(r, &mut raw, 1)?,
+        }
+        read_raw_bytes(r, &mut raw, size as usize)?;
+        Ok(Self::Raw(ty, raw))
+    }
+
+    pub fn read_str(r: &mut impl Read) -> Result<Self> {
+        let _sz = r.read_u64()?;
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            Err(GVASError::Unsupported("StrProperty with a property GUID".to_string()))
+        } else {
+            Ok(Self::String(r.read_uestring()?))
+        }
+    }
+
+    pub fn read_array(r: &mut OffsetReader<impl Read>, name: &str) -> Result<Self> {
+        let plen = r.read_u64()?;
+        let dtype = r.read_uestring()?;
+        // Read the whole array first, so unsupported arrays can be kept as is
+        let start = r.offset();
+        let body = read_bytes(r, plen.saturating_add(1))?;
+        let b = &mut OffsetReader::new(std::io::Cursor::new(&body), start);
+        let val = match dtype.as_str() {
+            "StructProperty" => Self::read_struct_array(b, plen, name),
+            "BoolProperty" => Self::read_bool_array(b, plen),
+            "IntProperty" => Self::read_int_array(b, plen),
+            "FloatProperty" => Self::read_float_array(b, plen),
+            "StrProperty" => Self::read_str_array(b, plen),
+            "TextProperty" => Self::read_text_array(b, plen),
+            a => Err(GVASError::Unsupported(format!("array of {}", a))),
+        };
+        match val {
+            Err(GVASError::Unsupported(_)) => {
+                let mut raw = plen.to_le_bytes().to_vec();
+                raw.write_string(dtype.as_str())?;
+                raw.extend_from_slice(&body);
+                Ok(Self::Raw("ArrayProperty".to_string(), raw))
+            }
+            // The outer reader is already past the array
+            val => val.map_err(|e| e.at(b.offset())),
+        }
+    }
+
+    pub fn read_bool_array(r: &mut impl Read, plen: u64) -> Result<Self> {
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            return Err(GVASError::Unsupported(
+                "array with a property GUID".to_string(),
+            ));
+        }
+        let nint = r.read_u32()?;
+        let mut data = Vec::with_capacity(capacity(nint as u64, plen));
+        for _ in 0..nint {
+            data.push(r.read_u8()? != 0);
+        }
+        Ok(Self::BoolArray(data))
+    }
+
+    pub fn read_float_array(r: &mut impl Read, plen: u64) -> Result<Self> {
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            return Err(GVASError::Unsupported(
+                "array with a property GUID".to_string(),
+            ));
+        }
+        let nint = r.read_u32()?;
+        let mut data = Vec::with_capacity(capacity(nint as u64, plen));
+        for _ in 0..nint {
+            data.push(r.read_f32()?);
+        }
+        Ok(Self::FloatArray(data))
+    }
+
+    pub fn read_int_array(r: &mut impl Read, plen: u64) -> Result<Self> {
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            return Err(GVASError::Unsupported(
+                "array with a property GUID".to_string(),
+            ));
+        }
+        let nint = r.read_u32()?;
+        let mut data = Vec::with_capacity(capacity(nint as u64, plen));
+        for _ in 0..nint {
+            data.push(r.read_u32()?);
+        }
+        Ok(Self::Int32Array(data))
+    }
+
+    pub fn read_struct_array(r: &mut impl Read, plen: u64, name: &str) -> Result<Self> {
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            return Err(GVASError::Unsupported(
+                "array with a property GUID".to_string(),
+            ));
+        }
+        let struct_size = r.read_u32()?;
+        let pname = r.read_uestring()?;
+        if pname != name {
+            return Err(GVASError::Malformed(format!(
+                "struct array {} names its structs {}",
+                name, pname
+            )));
+        }
+        let inner_ty = r.read_uestring()?;
+        if inner_ty != "StructProperty" {
+            return Err(GVASError::Malformed(format!(
+                "struct array {} holds {}",
+                name, inner_ty
+            )));
+        }
+        let field_size = r.read_u64()?;
+        let field_name = r.read_uestring()?;
+        let mut guid = [0u8; 16];
+        r.read_exact(&mut guid)?;
+        if guid != [0u8; 16] {
+            return Err(GVASError::Unsupported(format!("struct {} with a GUID", field_name)));
+        }
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            return Err(GVASError::Unsupported(
+                "array with a property GUID".to_string(),
+            ));
+        }
+        match field_name.as_str() {
+            "Vector" => {
+                check_struct_size(name, field_size, struct_size)?;
+                let mut data = Vec::with_capacity(capacity(field_size / 12, plen));
+                for _ in 0..field_size / 12 {
+                    data.push([r.read_f32()?, r.read_f32()?, r.read_f32()?]);
+                }
+                Ok(Self::VectorArray(data))
+            }
+            "Rotator" => {
+                check_struct_size(name, field_size, struct_size)?;
+                let mut data = Vec::with_capacity(capacity(field_size / 12, plen));
+                for _ in 0..field_size / 12 {
+                    data.push([r.read_f32()?, r.read_f32()?, r.read_f32()?]);
+                }
+                Ok(Self::RotatorArray(data))
+            }
+            _ => Err(GVASError::Unsupported(format!("struct {}", field_name))),
+        }
+    }
+
+    pub fn read_str_array(r: &mut impl Read, plen: u64) -> Result<Self> {
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            return Err(GVASError::Unsupported(
+                "array with a property GUID".to_string(),
+            ));
+        }
+        let ntext = r.read_u32()?;
+        let mut data = Vec::with_capacity(capacity(ntext as u64, plen));
+        for _ in 0..ntext {
+            data.push(r.read_uestring()?);
+        }
+        Ok(Self::StringArray(data))
+    }
+
+    pub fn read_text_array(r: &mut impl Read, plen: u64) -> Result<Self> {
+        let ch_bool = r.read_u8()? == 0;
+        if !ch_bool {
+            return Err(GVASError::Unsupported(
+                "array with a property GUID".to_string(),
+            ));
+        }
+        let ntext = r.read_u32()?;
+        let mut data = Vec::with_capacity(capacity(ntext as u64, plen));
+        for _ in 0..ntext {
+            data.push(TextProperty::read(r)?);
+        }
+        Ok(Self::TextArray(data))
+    }
+}
+
+/// Space to reserve for `count` elements of an array of `plen` bytes. Every element takes at
+/// least a byte, so a corrupt count can't reserve more than the array could hold
+fn capacity(count: u64, plen: u64) -> usize {
+    count.min(plen) as usize
+}
+
+/// Checks that a vector or rotator array of `count` structs takes up `field_size` bytes
+fn check_struct_size(name: &str, field_size: u64, count: u32) -> Result<()> {
+    if field_size != count as u64 * 12 {
+        return Err(GVASError::Malformed(format!(
+            "{} has {} bytes for {} structs of 12 bytes",
+            name, field_size, count
+        )));
+    }
+    Ok(())
+}
+
+/// Writes a property as `ty`, the size of its body, `header`, an empty property GUID, then
+/// `body`. The body is written to a buffer first, so the size always matches it and `w` doesn't
+/// need to seek back to fill it in
+fn write_sized(
+    w: &mut impl Write,
+    ty: &str,
+    header: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+    body: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    write_sized_with_guid(w, ty, &None, header, body)
+}
+
+/// Writes a property like `write_sized`, with `guid` after the header
+fn write_sized_with_guid(
+    w: &mut impl Write,
+    ty: &str,
+    guid: &PropertyGuid,
+    header: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+    body: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    let mut head = vec![];
+    header(&mut head)?;
+    let mut data = vec![];
+    body(&mut data)?;
+    w.write_string(ty)?;
+    w.write_all(&(data.len() as u64).to_le_bytes())?;
+    w.write_all(&head)?;
+    w.write_property_guid(guid)?;
+    w.write_all(&data)?;
+    Ok(())
+}
+
+/// Writes an array property of `element` typed values, see `write_sized`
+fn write_array(
+    w: &mut impl Write,
+    element: &str,
+    body: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    write_sized(w, "ArrayProperty", |h| h.write_string(element), body)
+}
+
+/// Reads a length-prefixed string into `raw`, without decoding it
+fn read_raw_string(r: &mut impl Read, raw: &mut Vec<u8>) -> Result<()> {
+    let len = r.read_i32()?;
+    raw.extend_from_slice(&len.to_le_bytes());
+    // Negative lengths are UTF-16
+    let bytes = if len < 0 { len.unsigned_abs() * 2 } else { len as u32 };
+    read_raw_bytes(r, raw, bytes as usize)
+}
+
+/// Reads `len` bytes into `raw`
+fn read_raw_bytes(r: &mut impl Read, raw: &mut Vec<u8>, len: usize) -> Result<()> {
+    raw.extend(read_bytes(r, len as u64)?);
+    Ok(())
+}
+
+impl<'a> TryInto<&'a Vec<f32>> for &'a Value {
+    type Error = GVASError;
+    fn try_into(self) -> Result<&'a Vec<f32>> {
+        match self {
+            Value::FloatArray(f) => Ok(f),
+            _ => Err(GVASError::WrongType),
+        }
+    }
+}
+
+impl<'a> TryInto<&'a Vec<u32>> for &'a Value {
+    type Error = GVASError;
+    fn try_into(self) -> Result<&'a Vec<u32>> {
+        match self {
+            Value::Int32Array(f) => Ok(f),
+            _ => Err(GVASError::WrongType),
+        }
+    }
+}
+
+impl<'a> TryInto<&'a Vec<bool>> for &'a Value {
+    type Error = GVASError;
+    fn try_into(self) -> Result<&'a Vec<bool>> {
+        match self {
+            Value::BoolArray(f) => Ok(f),
+            _ => Err(GVASError::WrongType),
+        }
+    }
+}
+
+impl<'a> TryInto<&'a Vec<[f32; 3]>> for &'a Value {
+    type Error = GVASError;
+    fn try_into(self) -> Result<&'a Vec<[f32; 3]>> {
+        match self {
+            Value::RotatorArray(f) => Ok(f),
+            Value::VectorArray(f) => Ok(f),
+            _ => Err(GVASError::WrongType),
+        }
+    }
+}
+
+impl<'a> TryInto<&'a Vec<String>> for &'a Value {
+    type Error = GVASError;
+    fn try_into(self) -> Result<&'a Vec<String>> {
+        match self {
+            Value::StringArray(f) => Ok(f),
+            _ => Err(GVASError::WrongType),
+        }
+    }
+}
+
+impl<'a> TryInto<&'a Vec<TextProperty>> for &'a Value {
+    type Error = GVASError;
+    fn try_into(self) -> Result<&'a Vec<TextProperty>> {
+        match self {
+            Value::TextArray(f) => Ok(f),
+            _ => Err(GVASError::WrongType),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum TextProperty {
+    #[serde(rename = "simple")]
+    Simple(String),
+    #[serde(rename = "fmt_str")]
+    FmtStr(String, String),
+    #[serde(rename = "none")]
+    None,
+    /// Any other text layout, kept as the raw bytes
+    #[serde(rename = "other", with = "schema::hex")]
+    Other(Vec<u8>),
+}
+
+/// Key of the `{0}<br>{1}` format string used for two line texts
+const FMT_STR_KEY: &str = "56F8D27149CC5E2D12103BBEBFCA9097";
+const FMT_STR: &str = "{0}<br>{1}";
+
+/// A text as stored by UE, only used while parsing
+#[derive(Debug, Clone, PartialEq)]
+enum Text {
+    /// Culture invariant text. The value is missing for empty texts
+    Invariant { flags: u32, value: Option<String> },
+    Base {
+        flags: u32,
+        namespace: String,
+        key: String,
+        source: String,
+    },
+    /// A format string with named (or numbered) arguments
+    Format {
+        flags: u32,
+        source: Box<Text>,
+        args: Vec<(String, TextArg)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TextArg {
+    Int(i64),
+    UInt(u64),
+    Float(f32),
+    Double(f64),
+    Text(Text),
+}
+
+/// Format texts nested deeper than this are rejected, rather than overflowing the stack
+const MAX_TEXT_DEPTH: usize = 16;
+
+impl Text {
+    fn read(r: &mut impl Read, depth: usize) -> Result<Self> {
+        if depth > MAX_TEXT_DEPTH {
+            return Err(GVASError::Malformed("text nested too deeply".to_string()));
+        }
+        let flags = r.read_u32()?;
+        match r.read_i8()? {
+            -1 => {
+                let value = if r.read_u32()? == 1 {
+                    Some(r.read_uestring()?)
+                } else {
+                    None
+                };
+                Ok(Self::Invariant { flags, value })
+            }
+            0 => Ok(Self::Base {
+                flags,
+                namespace: r.read_uestring()?,
+                key: r.read_uestring()?,
+                source: r.read_uestring()?,
+            }),
+            // Named and argument formats differ only in how UE uses them
+            1 | 3 => {
+                let source = Box::new(Self::read(r, depth + 1)?);
+                let count = r.read_u32()?;
+                let mut args = vec![];
+                for _ in 0..count {
+                    let name = r.read_uestring()?;
+                    args.push((name, TextArg::read(r, depth + 1)?));
+                }
+                Ok(Self::Format {
+                    flags,
+                    source,
+                    args,
+                })
+            }
+            ty => Err(GVASError::Unsupported(format!("text history {}", ty))),
+        }
+    }
+
+    /// The value of a culture invariant text written with the given flags
+    fn invariant(&self, expected_flags: u32) -> Option<&str> {
+        match self {
+            Self::Invariant { flags, value } if *flags == expected_flags => {
+                Some(value.as_ref().map_or("", |v| v.as_str()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TextArg {
+    fn read(r: &mut impl Read, depth: usize) -> Result<Self> {
+        match r.read_u8()? {
+            0 => Ok(Self::Int(r.read_i64()?)),
+            1 => Ok(Self::UInt(r.read_u64()?)),
+            2 => Ok(Self::Float(r.read_f32()?)),
+            3 => {
+                let mut buf = [0u8; size_of::<f64>()];
+                r.read_exact(&mut buf)?;
+                Ok(Self::Double(f64::from_le_bytes(buf)))
+            }
+            4 => Ok(Self::Text(Text::read(r, depth)?)),
+            ty => Err(GVASError::Unsupported(format!("text argument {}", ty))),
+        }
+    }
+}
+
+/// Reader that keeps a copy of every byte read
+struct RecordingReader<'a, R: Read> {
+    inner: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<'a, R: Read> Read for RecordingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl TextProperty {
+    /// Reads a text. Layouts other than the ones written by `write` are kept as raw bytes, so
+    /// they survive a round trip unchanged
+    pub fn read(r: &mut impl Read) -> Result<Self> {
+        let mut rec = RecordingReader {
+            inner: r,
+            bytes: vec![],
+        };
+        let text = Text::read(&mut rec, 0)?;
+        let simple = match &text {
+            Text::Invariant { flags: 0, value: None } => Some(Self::None),
+            Text::Invariant {
+                flags: 2,
+                value: Some(v),
+            } => Some(Self::Simple(v.clone())),
+            Text::Format {
+                flags: 1,
+                source,
+                args,
+            } => match (source.as_ref(), args.as_slice()) {
+                (
+                    Text::Base {
+                        flags: 8,
+                        namespace,
+                        key,
+                        source,
+                    },
+                    [(a, TextArg::Text(first)), (b, TextArg::Text(second))],
+                ) if namespace.is_empty()
+                    && key == FMT_STR_KEY
+                    && source == FMT_STR
+                    && a == "0"
+                    && b == "1" =>
+                {
+                    match (first.invariant(2), second.invariant(2)) {
+                        (Some(first), Some(second)) => {
+                            Some(Self::FmtStr(first.to_string(), second.to_string()))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        Ok(simple.unwrap_or(Self::Other(rec.bytes)))
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        match self {
+            Self::None => {
+                w.write_all(&0u32.to_le_bytes())?;
+                w.write_all(&(-1i8).to_le_bytes())?;
+                w.write_all(&0u32.to_le_bytes())?;
+            }
+            Self::Simple(s) => {
+                w.write_all(&2u32.to_le_bytes())?;
+                w.write_all(&(-1i8).to_le_bytes())?;
+                w.write_all(&1u32.to_le_bytes())?;
+                w.write_string(s.as_str())?;
+            }
+            Self::FmtStr(first, second) => {
+                w.write_all(&1u32.to_le_bytes())?;
+                w.write_all(&3u8.to_le_bytes())?;
+                w.write_all(&8u64.to_le_bytes())?;
+                w.write_all(&0u8.to_le_bytes())?;
+                w.write_string(FMT_STR_KEY)?;
+                w.write_string(FMT_STR)?;
+                w.write_all(&2u32.to_le_bytes())?;
+                for (key, arg) in [("0", first), ("1", second)] {
+                    w.write_string(key)?;
+                    w.write_all(&4u8.to_le_bytes())?;
+                    w.write_all(&2u32.to_le_bytes())?;
+                    w.write_all(&(-1i8).to_le_bytes())?;
+                    if arg.is_empty() {
+                        w.write_all(&0u32.to_le_bytes())?;
+                    } else {
+                        w.write_all(&1u32.to_le_bytes())?;
+                        w.write_string(arg.as_str())?;
+                    }
+                }
+            }
+            Self::Other(bytes) => w.write_all(bytes)?,
+        }
+        Ok(())
+    }
+}
+
+/// A Railroads Online save
+#[derive(Debug, Clone)]
+pub struct RROSave {
+    inner: GVASFile,
+}
+
+impl RROSave {
+    /// Reads a save as written by the game
+    pub fn read(r: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            inner: GVASFile::read(r)?,
+        })
+    }
+
+    /// Reads a save, see `GVASFile::read_with_progress`
+    pub fn read_with_progress(r: &mut impl Read, progress: impl FnMut(usize)) -> Result<Self> {
+        Ok(Self {
+            inner: GVASFile::read_with_progress(r, progress)?,
+        })
+    }
+
+    /// Writes the save as the game expects it
+    pub fn write(&self, r: &mut impl Write) -> Result<()> {
+        self.inner.write(r)
+    }
+
+    /// Checks that `written`, e.g. read back from a file this save was just written to, is
+    /// exactly what `write` produces, and that it can be read again
+    pub fn verify(&self, written: &[u8]) -> Result<()> {
+        let mut expected = std::io::Cursor::new(vec![]);
+        self.write(&mut expected)?;
+        let expected = expected.into_inner();
+        let differs = expected.iter().zip(written).position(|(a, b)| a != b);
+        if let Some(offset) = differs {
+            return Err(GVASError::Mismatch(offset));
+        }
+        if expected.len() != written.len() {
+            return Err(GVASError::Mismatch(expected.len().min(written.len())));
+        }
+        Self::read(&mut std::io::Cursor::new(written))?;
+        Ok(())
+    }
+
+    /// Every property in the save, in order, including ones the editor doesn't use
+    pub fn properties(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.inner.properties.iter().map(|p| (p.name.as_str(), &p.val))
+    }
+
+    /// Every property in the save, for editing in place
+    pub fn properties_mut(&mut self) -> impl Iterator<Item = (&str, &mut Value)> {
+        self.inner
+            .properties
+            .iter_mut()
+            .map(|p| (p.name.as_str(), &mut p.val))
+    }
+
+    /// The full save as JSON, with every property in order. See [`schema`] for the layout
+    pub fn to_json(&self) -> Result<String> {
+        schema::to_json(&self.inner)
+    }
+
+    /// Reads a save written by `to_json`
+    pub fn from_json(s: &str) -> Result<Self> {
+        Ok(Self {
+            inner: schema::from_json(s)?,
+        })
+    }
+
+    fn get_string(&self, name: &'static str) -> Result<&str> {
+        match self.inner.get_prop(name)? {
+            Value::String(s) => Ok(s.as_str()),
+            _ => Err(GVASError::WrongType),
+        }
+    }
+
+    fn set_string(&mut self, name: &'static str, value: String) -> Result<()> {
+        match self.inner.get_prop_mut(name)? {
+            Value::String(s) => {
+                *s = value;
+                Ok(())
+            }
+            _ => Err(GVASError::WrongType),
+        }
+    }
+
+    /// When the save was written, as the game formats it, e.g. `2022.03.06-17.01.45`
+    pub fn save_date(&self) -> Result<&str> {
+        self.get_string("SaveGameDate")
+    }
+
+    pub fn set_save_date(&mut self, date: String) -> Result<()> {
+        self.set_string("SaveGameDate", date)
+    }
+
+    /// Identifies this save, as `<steam id>_<date>`
+    pub fn unique_id(&self) -> Result<&str> {
+        self.get_string("SaveGameUniqueID")
+    }
+
+    pub fn set_unique_id(&mut self, id: String) -> Result<()> {
+        self.set_string("SaveGameUniqueID", id)
+    }
+
+    /// Identifies the world the save was started in, as `<steam id>_<date>`. Saves of the same
+    /// world share it
+    pub fn world_id(&self) -> Result<&str> {
+        self.get_string("SaveGameUniqueWorldID")
+    }
+
+    pub fn set_world_id(&mut self, id: String) -> Result<()> {
+        self.set_string("SaveGameUniqueWorldID", id)
+    }
+
+    /// Version of the game that wrote this save
+    pub fn version(&self) -> SaveVersion {
+        SaveVersion::read(&self.inner)
+    }
+
+    /// Every spline in the save. Index ranges that are inverted or out of bounds return
+    /// `GVASError::Malformed`, see [`validate::validate`]
+    pub fn curves<'a>(&'a self) -> Result<RROCurveIter<'a>> {
+        let iter = RROCurveIter {
+            i: 0,
+            len: 0,
+            spline_location_array: self.inner.get_prop("SplineLocationArray")?.try_into()?,
+            spline_type_array: self.inner.get_prop("SplineTypeArray")?.try_into()?,
+            spline_control_points_array: self
+                .inner
+                .get_prop("SplineControlPointsArray")?
+                .try_into()?,
+            spline_control_points_index_start_array: self
+                .inner
+                .get_prop("SplineControlPointsIndexStartArray")?
+                .try_into()?,
+            spline_control_points_index_end_array: self
+                .inner
+                .get_prop("SplineControlPointsIndexEndArray")?
+                .try_into()?,
+            spline_segments_visibility_array: self
+                .inner
+                .get_prop("SplineSegmentsVisibilityArray")?
+                .try_into()?,
+            spline_visibility_start_array: self
+                .inner
+                .get_prop("SplineVisibilityStartArray")?
+                .try_into()?,
+            spline_visibility_end_array: self
+                .inner
+                .get_prop("SplineVisibilityEndArray")?
+                .try_into()?,
+        };
+        let len = parallel_len(
+            "spline",
+            &[
+                iter.spline_location_array.len(),
+                iter.spline_type_array.len(),
+                iter.spline_control_points_index_start_array.len(),
+                iter.spline_control_points_index_end_array.len(),
+                iter.spline_visibility_start_array.len(),
+                iter.spline_visibility_end_array.len(),
+            ],
+        )?;
+        for i in 0..len {
+            check_range(
+                "control points",
+                i,
+                iter.spline_control_points_index_start_array[i],
+                iter.spline_control_points_index_end_array[i],
+                iter.spline_control_points_array.len(),
+            )?;
+            check_range(
+                "visibility",
+                i,
+                iter.spline_visibility_start_array[i],
+                iter.spline_visibility_end_array[i],
+                iter.spline_segments_visibility_array.len(),
+            )?;
+        }
+        Ok(RROCurveIter { len, ..iter })
+    }
+
+    /// Counts the splines in the save by type
+    pub fn spline_stats(&self) -> Result<SplineStats> {
+        let mut stats = SplineStats::default();
+        for curve in self.curves()? {
+            stats.add(curve.ty, curve.control_points.len());
+        }
+        Ok(stats)
+    }
+
+    /// Replaces every spline in the save. A spline without any control points or segments
+    /// returns `GVASError::Malformed`, since its range couldn't be written
+    pub fn set_curves(&mut self, iter: impl Iterator<Item = CurveDataOwned>) -> Result<()> {
+        let mut spline_location_array = vec![];
+        let mut spline_type_array = vec![];
+        let mut spline_control_points_array = vec![];
+        let mut spline_control_points_index_start_array = vec![];
+        let mut spline_control_points_index_end_array = vec![];
+        let mut spline_segments_visibility_array = vec![];
+        let mut spline_visibility_start_array = vec![];
+        let mut spline_visibility_end_array = vec![];
+        for (i, curve) in iter.enumerate() {
+            // Ends are inclusive, so an empty range can't be written
+            if curve.control_points.is_empty() || curve.visibility.is_empty() {
+                return Err(GVASError::Malformed(format!(
+                    "spline {} has {} control points and {} segments",
+                    i,
+                    curve.control_points.len(),
+                    curve.visibility.len()
+                )));
+            }
+            spline_location_array.push(curve.location);
+            spline_type_array.push(curve.ty.into());
+            spline_control_points_index_start_array.push(spline_control_points_array.len() as u32);
+            for p in curve.control_points {
+                spline_control_points_array.push(p);
+            }
+            spline_control_points_index_end_array
+                .push(spline_control_points_array.len() as u32 - 1);
+            spline_visibility_start_array.push(spline_segments_visibility_array.len() as u32);
+            for p in curve.visibility {
+                spline_segments_visibility_array.push(p);
+            }
+            spline_visibility_end_array.push(spline_segments_visibility_array.len() as u32 - 1);
+        }
+        *self.inner.get_prop_mut("SplineLocationArray")? =
+            Value::VectorArray(spline_location_array);
+        *self.inner.get_prop_mut("SplineTypeArray")? = Value::Int32Array(spline_type_array);
+        *self.inner.get_prop_mut("SplineControlPointsArray")? =
+            Value::VectorArray(spline_control_points_array);
+        *self
+            .inner
+            .get_prop_mut("SplineControlPointsIndexStartArray")? =
+            Value::Int32Array(spline_control_points_index_start_array);
+        *self
+            .inner
+            .get_prop_mut("SplineControlPointsIndexEndArray")? =
+            Value::Int32Array(spline_control_points_index_end_array);
+        *self.inner.get_prop_mut("SplineSegmentsVisibilityArray")? =
+            Value::BoolArray(spline_segments_visibility_array);
+        *self.inner.get_prop_mut("SplineVisibilityStartArray")? =
+            Value::Int32Array(spline_visibility_start_array);
+        *self.inner.get_prop_mut("SplineVisibilityEndArray")? =
+            Value::Int32Array(spline_visibility_end_array);
+        Ok(())
+    }
+
+    /// Every switch in the save. A switch type the editor doesn't know returns
+    /// `GVASError::Malformed`
+    pub fn switches<'a>(&'a self) -> Result<SwitchIter<'a>> {
+        let switch_type_array: &Vec<u32> = self.inner.get_prop("SwitchTypeArray")?.try_into()?;
+        let iter = SwitchIter {
+            i: 0,
+            len: 0,
+            switch_type_array: switch_type_array
+                .iter()
+                .map(|&ty| {
+                    SwitchType::try_from(ty)
+                        .map_err(|_| GVASError::Malformed(format!("unknown switch type {}", ty)))
+                })
+                .collect::<Result<_>>()?,
+            switch_location_array: self.inner.get_prop("SwitchLocationArray")?.try_into()?,
+            switch_rotation_array: self.inner.get_prop("SwitchRotationArray")?.try_into()?,
+            switch_state_array: self.inner.get_prop("SwitchStateArray")?.try_into()?,
+        };
+        let len = parallel_len(
+            "switch",
+            &[
+                iter.switch_type_array.len(),
+                iter.switch_location_array.len(),
+                iter.switch_rotation_array.len(),
+                iter.switch_state_array.len(),
+            ],
+        )?;
+        Ok(SwitchIter { len, ..iter })
+    }
+
+    pub fn set_switches(&mut self, i: impl Iterator<Item = SwitchData>) -> Result<()> {
+        let mut switch_type_array = vec![];
+        let mut switch_location_array = vec![];
+        let mut switch_rotation_array = vec![];
+        let mut switch_state_array = vec![];
+        for switch in i {
+            switch_type_array.push(switch.ty as u32);
+            switch_location_array.push(switch.location);
+            switch_rotation_array.push(switch.rotation);
+            switch_state_array.push(switch.state);
+        }
+        *self.inner.get_prop_mut("SwitchTypeArray")? = Value::Int32Array(switch_type_array);
+        *self.inner.get_prop_mut("SwitchLocationArray")? =
+            Value::VectorArray(switch_location_array);
+        *self.inner.get_prop_mut("SwitchRotationArray")? =
+            Value::RotatorArray(switch_rotation_array);
+        *self.inner.get_prop_mut("SwitchStateArray")? = Value::Int32Array(switch_state_array);
+        Ok(())
+    }
+
+    /// Players in the save. The player arrays are the only ones with lowercase names
+    pub fn players<'a>(&'a self) -> Result<PlayerIter<'a>> {
+        let iter = PlayerIter {
+            i: 0,
+            len: 0,
+            player_name_array: self.inner.get_prop("playernamearray")?.try_into()?,
+            player_money_array: self.inner.get_prop("playermoneyarray")?.try_into()?,
+            player_xp_array: self.inner.get_prop("playerxparray")?.try_into()?,
+        };
+        let len = parallel_len(
+            "player",
+            &[
+                iter.player_name_array.len(),
+                iter.player_money_array.len(),
+                iter.player_xp_array.len(),
+            ],
+        )?;
+        Ok(PlayerIter { len, ..iter })
+    }
+
+    /// Sets the money and XP of every player. Players can't be added or removed, and their
+    /// names are left as is
+    pub fn set_players(&mut self, i: impl Iterator<Item = PlayerData>) -> Result<()> {
+        let mut player_money_array = vec![];
+        let mut player_xp_array = vec![];
+        for player in i {
+            player_money_array.push(player.money);
+            player_xp_array.push(player.xp);
+        }
+        *self.inner.get_prop_mut("playermoneyarray")? = Value::FloatArray(player_money_array);
+        *self.inner.get_prop_mut("playerxparray")? = Value::Int32Array(player_xp_array);
+        Ok(())
+    }
+
+    /// Removes what identifies the players, so the save can be shared. Names become `Player 1`
+    /// and so on, player ids are blanked, money and XP are zeroed, and the steam id is taken
+    /// out of the save and world ids. The world itself is kept as is, and player arrays the save
+    /// doesn't have are skipped
+    pub fn anonymize(&mut self) -> Result<()> {
+        match self.inner.get_prop_mut("playernamearray") {
+            Ok(Value::StringArray(names)) => {
+                for (i, name) in names.iter_mut().enumerate() {
+                    *name = format!("Player {}", i + 1);
+                }
+            }
+            Ok(_) => return Err(GVASError::WrongType),
+            Err(_) => (),
+        }
+        // Older saves don't have player ids
+        if let Ok(Value::StringArray(ids)) = self.inner.get_prop_mut("playeridarray") {
+            for id in ids.iter_mut() {
+                id.clear();
+            }
+        }
+        if let Ok(Value::FloatArray(money)) = self.inner.get_prop_mut("playermoneyarray") {
+            money.iter_mut().for_each(|money| *money = 0.);
+        }
+        if let Ok(Value::Int32Array(xp)) = self.inner.get_prop_mut("playerxparray") {
+            xp.iter_mut().for_each(|xp| *xp = 0);
+        }
+        for name in ["SaveGameUniqueID", "SaveGameUniqueWorldID"] {
+            // Both are `<steam id>_<date>`, if the save has them
+            if let Ok(id) = self.get_string(name) {
+                let id = id
+                    .split_once('_')
+                    .map_or(String::new(), |(_steam, date)| format!("0_{}", date));
+                self.set_string(name, id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rolling stock in the save. Saves without any may not have the arrays at all, which
+    /// returns `GVASError::Missing`
+    pub fn frames<'a>(&'a self) -> Result<FrameIter<'a>> {
+        let iter = FrameIter {
+            i: 0,
+            len: 0,
+            frame_type_array: self.inner.get_prop("FrameTypeArray")?.try_into()?,
+            frame_location_array: self.inner.get_prop("FrameLocationArray")?.try_into()?,
+            frame_rotation_array: self.inner.get_prop("FrameRotationArray")?.try_into()?,
+            frame_name_array: self.inner.get_prop("FrameNameArray")?.try_into()?,
+            frame_number_array: self.inner.get_prop("FrameNumberArray")?.try_into()?,
+            freight_type_array: self.inner.get_prop("FreightTypeArray")?.try_into()?,
+            freight_amount_array: self.inner.get_prop("FreightAmountArray")?.try_into()?,
+            coupler_front_state_array: self.inner.get_prop("CouplerFrontStateArray")?.try_into()?,
+            coupler_rear_state_array: self.inner.get_prop("CouplerRearStateArray")?.try_into()?,
+        };
+        let len = parallel_len(
+            "frame",
+            &[
+                iter.frame_type_array.len(),
+                iter.frame_location_array.len(),
+                iter.frame_rotation_array.len(),
+                iter.frame_name_array.len(),
+                iter.frame_number_array.len(),
+                iter.freight_type_array.len(),
+                iter.freight_amount_array.len(),
+                iter.coupler_front_state_array.len(),
+                iter.coupler_rear_state_array.len(),
+            ],
+        )?;
+        Ok(FrameIter { len, ..iter })
+    }
+
+    /// Writes frames back to the save. Frames are written in order of `FrameData::index`, since
+    /// the save also holds per-frame arrays (boiler, lights, etc) that the editor doesn't touch.
+    pub fn set_frames(&mut self, i: impl Iterator<Item = FrameData>) -> Result<()> {
+        let mut frames: Vec<_> = i.collect();
+        frames.sort_by_key(|f| f.index);
+        let mut frame_type_array = vec![];
+        let mut frame_location_array = vec![];
+        let mut frame_rotation_array = vec![];
+        let mut frame_name_array = vec![];
+        let mut frame_number_array = vec![];
+        let mut freight_type_array = vec![];
+        let mut freight_amount_array = vec![];
+        let mut coupler_front_state_array = vec![];
+        let mut coupler_rear_state_array = vec![];
+        for frame in frames {
+            frame_type_array.push(frame.ty);
+            frame_location_array.push(frame.location);
+            frame_rotation_array.push(frame.rotation);
+            frame_name_array.push(frame.name);
+            frame_number_array.push(frame.number);
+            freight_type_array.push(frame.freight_type);
+            freight_amount_array.push(frame.freight_amount);
+            coupler_front_state_array.push(frame.coupler_front);
+            coupler_rear_state_array.push(frame.coupler_rear);
+        }
+        // Leave saves without frames untouched. Frames can't be added to them, since the
+        // per-frame arrays the editor doesn't touch would be missing
+        if self.inner.get_prop("FrameTypeArray").is_err() {
+            if frame_type_array.is_empty() {
+                return Ok(());
+            } else {
+                return Err(GVASError::Unsupported(
+                    "frames in a save without any".to_string(),
+                ));
+            }
+        }
+        self.inner
+            .set_prop("FrameTypeArray", Value::StringArray(frame_type_array));
+        self.inner.set_prop(
+            "FrameLocationArray",
+            Value::VectorArray(frame_location_array),
+        );
+        self.inner.set_prop(
+            "FrameRotationArray",
+            Value::RotatorArray(frame_rotation_array),
+        );
+        self.inner
+            .set_prop("FrameNameArray", Value::TextArray(frame_name_array));
+        self.inner
+            .set_prop("FrameNumberArray", Value::TextArray(frame_number_array));
+        self.inner
+            .set_prop("FreightTypeArray", Value::StringArray(freight_type_array));
+        self.inner.set_prop(
+            "FreightAmountArray",
+            Value::Int32Array(freight_amount_array),
+        );
+        self.inner.set_prop(
+            "CouplerFrontStateArray",
+            Value::BoolArray(coupler_front_state_array),
+        );
+        self.inner.set_prop(
+            "CouplerRearStateArray",
+            Value::BoolArray(coupler_rear_state_array),
+        );
+        Ok(())
+    }
+
+    /// Industries in the save. Saves without any may not have the arrays at all, which returns
+    /// `GVASError::Missing`
+    pub fn industries<'a>(&'a self) -> Result<IndustryIter<'a>> {
+        let iter = IndustryIter {
+            i: 0,
+            len: 0,
+            industry_type_array: self.inner.get_prop("IndustryTypeArray")?.try_into()?,
+            industry_location_array: self.inner.get_prop("IndustryLocationArray")?.try_into()?,
+            industry_rotation_array: self.inner.get_prop("IndustryRotationArray")?.try_into()?,
+            industry_storage_educt_arrays: [
+                self.inner.get_prop("IndustryStorageEduct1Array")?.try_into()?,
+                self.inner.get_prop("IndustryStorageEduct2Array")?.try_into()?,
+                self.inner.get_prop("IndustryStorageEduct3Array")?.try_into()?,
+                self.inner.get_prop("IndustryStorageEduct4Array")?.try_into()?,
+            ],
+            industry_storage_product_arrays: [
+                self.inner.get_prop("IndustryStorageProduct1Array")?.try_into()?,
+                self.inner.get_prop("IndustryStorageProduct2Array")?.try_into()?,
+                self.inner.get_prop("IndustryStorageProduct3Array")?.try_into()?,
+                self.inner.get_prop("IndustryStorageProduct4Array")?.try_into()?,
+            ],
+        };
+        let mut lengths = vec![
+            iter.industry_type_array.len(),
+            iter.industry_location_array.len(),
+            iter.industry_rotation_array.len(),
+        ];
+        lengths.extend(iter.industry_storage_educt_arrays.iter().map(|a| a.len()));
+        lengths.extend(iter.industry_storage_product_arrays.iter().map(|a| a.len()));
+        let len = parallel_len("industry", &lengths)?;
+        Ok(IndustryIter { len, ..iter })
+    }
+
+    /// Sets the industry arrays, adding them to the save if needed
+    pub fn set_industries(&mut self, i: impl Iterator<Item = IndustryData>) -> Result<()> {
+        let mut industry_type_array = vec![];
+        let mut industry_location_array = vec![];
+        let mut industry_rotation_array = vec![];
+        let mut industry_storage_educt_arrays: [Vec<u32>; 4] = Default::default();
+        let mut industry_storage_product_arrays: [Vec<u32>; 4] = Default::default();
+        for industry in i {
+            industry_type_array.push(industry.ty);
+            industry_location_array.push(industry.location);
+            industry_rotation_array.push(industry.rotation);
+            for j in 0..4 {
+                industry_storage_educt_arrays[j].push(industry.educts[j]);
+                industry_storage_product_arrays[j].push(industry.products[j]);
+            }
+        }
+        // Leave saves without industries untouched
+        if self.inner.get_prop("IndustryTypeArray").is_err() {
+            if industry_type_array.is_empty() {
+                return Ok(());
+            } else if !self.version().quirks().optional_arrays {
+                return Err(GVASError::Unsupported(
+                    "industries in a save from this version".to_string(),
+                ));
+            }
+        }
+        self.inner
+            .set_prop("IndustryTypeArray", Value::Int32Array(industry_type_array));
+        self.inner.set_prop(
+            "IndustryLocationArray",
+            Value::VectorArray(industry_location_array),
+        );
+        self.inner.set_prop(
+            "IndustryRotationArray",
+            Value::RotatorArray(industry_rotation_array),
+        );
+        let [e1, e2, e3, e4] = industry_storage_educt_arrays;
+        self.inner
+            .set_prop("IndustryStorageEduct1Array", Value::Int32Array(e1));
+        self.inner
+            .set_prop("IndustryStorageEduct2Array", Value::Int32Array(e2));
+        self.inner
+            .set_prop("IndustryStorageEduct3Array", Value::Int32Array(e3));
+        self.inner
+            .set_prop("IndustryStorageEduct4Array", Value::Int32Array(e4));
+        let [p1, p2, p3, p4] = industry_storage_product_arrays;
+        self.inner
+            .set_prop("IndustryStorageProduct1Array", Value::Int32Array(p1));
+        self.inner
+            .set_prop("IndustryStorageProduct2Array", Value::Int32Array(p2));
+        self.inner
+            .set_prop("IndustryStorageProduct3Array", Value::Int32Array(p3));
+        self.inner
+            .set_prop("IndustryStorageProduct4Array", Value::Int32Array(p4));
+        Ok(())
+    }
+
+    /// Turntables in the save. Saves without any turntables may not have these arrays at all,
+    /// which returns `GVASError::Missing`
+    pub fn turntables<'a>(&'a self) -> Result<TurntableIter<'a>> {
+        let iter = TurntableIter {
+            i: 0,
+            len: 0,
+            turntable_type_array: self.inner.get_prop("TurntableTypeArray")?.try_into()?,
+            turntable_location_array: self.inner.get_prop("TurntableLocationArray")?.try_into()?,
+            turntable_rotator_array: self.inner.get_prop("TurntableRotatorArray")?.try_into()?,
+            turntable_deck_rotation_array: self
+                .inner
+                .get_prop("TurntableDeckRotationArray")?
+                .try_into()?,
+        };
+        let len = parallel_len(
+            "turntable",
+            &[
+                iter.turntable_type_array.len(),
+                iter.turntable_location_array.len(),
+                iter.turntable_rotator_array.len(),
+                iter.turntable_deck_rotation_array.len(),
+            ],
+        )?;
+        Ok(TurntableIter { len, ..iter })
+    }
+
+    /// Sets the turntable arrays, adding them to the save if needed
+    pub fn set_turntables(&mut self, i: impl Iterator<Item = TurntableData>) -> Result<()> {
+        let mut turntable_type_array = vec![];
+        let mut turntable_location_array = vec![];
+        let mut turntable_rotator_array = vec![];
+        let mut turntable_deck_rotation_array = vec![];
+        for turntable in i {
+            turntable_type_array.push(turntable.ty);
+            turntable_location_array.push(turntable.location);
+            turntable_rotator_array.push(turntable.rotation);
+            turntable_deck_rotation_array.push(turntable.deck_rotation);
+        }
+        // Leave saves without turntables untouched
+        if self.inner.get_prop("TurntableTypeArray").is_err() {
+            if turntable_type_array.is_empty() {
+                return Ok(());
+            } else if !self.version().quirks().optional_arrays {
+                return Err(GVASError::Unsupported(
+                    "turntables in a save from this version".to_string(),
+                ));
+            }
+        }
+        self.inner
+            .set_prop("TurntableTypeArray", Value::Int32Array(turntable_type_array));
+        self.inner.set_prop(
+            "TurntableLocationArray",
+            Value::VectorArray(turntable_location_array),
+        );
+        self.inner.set_prop(
+            "TurntableRotatorArray",
+            Value::RotatorArray(turntable_rotator_array),
+        );
+        self.inner.set_prop(
+            "TurntableDeckRotationArray",
+            Value::RotatorArray(turntable_deck_rotation_array),
+        );
+        Ok(())
+    }
+
+    /// Service structures of the given kind. Saves without any may not have the arrays at all,
+    /// which returns `GVASError::Missing`
+    pub fn services<'a>(&'a self, kind: ServiceKind) -> Result<ServiceIter<'a>> {
+        let [ty, location, rotation, level] = kind.arrays();
+        let iter = ServiceIter {
+            i: 0,
+            len: 0,
+            kind,
+            type_array: self.inner.get_prop(ty)?.try_into()?,
+            location_array: self.inner.get_prop(location)?.try_into()?,
+            rotation_array: self.inner.get_prop(rotation)?.try_into()?,
+            level_array: self.inner.get_prop(level)?.try_into()?,
+        };
+        let len = parallel_len(
+            kind.name(),
+            &[
+                iter.type_array.len(),
+                iter.location_array.len(),
+                iter.rotation_array.len(),
+                iter.level_array.len(),
+            ],
+        )?;
+        Ok(ServiceIter { len, ..iter })
+    }
+
+    /// Sets the arrays for service structures of the given kind, adding them to the save if
+    /// needed. Structures of other kinds are ignored
+    pub fn set_services(
+        &mut self,
+        kind: ServiceKind,
+        i: impl Iterator<Item = ServiceData>,
+    ) -> Result<()> {
+        let mut type_array = vec![];
+        let mut location_array = vec![];
+        let mut rotation_array = vec![];
+        let mut level_array = vec![];
+        for service in i.filter(|s| s.kind == kind) {
+            type_array.push(service.ty);
+            location_array.push(service.location);
+            rotation_array.push(service.rotation);
+            level_array.push(service.level);
+        }
+        let [ty, location, rotation, level] = kind.arrays();
+        // Leave saves without these structures untouched
+        if self.inner.get_prop(ty).is_err() {
+            if type_array.is_empty() {
+                return Ok(());
+            } else if !self.version().quirks().optional_arrays {
+                return Err(GVASError::Unsupported(format!(
+                    "{} in a save from this version",
+                    kind.name()
+                )));
+            }
+        }
+        self.inner.set_prop(ty, Value::Int32Array(type_array));
+        self.inner.set_prop(location, Value::VectorArray(location_array));
+        self.inner.set_prop(rotation, Value::RotatorArray(rotation_array));
+        self.inner.set_prop(level, Value::FloatArray(level_array));
+        Ok(())
+    }
+
+    /// Locations of trees and rocks the players have removed. Saves where nothing has been
+    /// removed may not have the array, which returns `GVASError::Missing`
+    pub fn removed_vegetation(&self) -> Result<&[[f32; 3]]> {
+        let locations: &Vec<[f32; 3]> = self
+            .inner
+            .get_prop("RemovedVegetationAssetsArray")?
+            .try_into()?;
+        Ok(locations.as_slice())
+    }
+
+    /// Sets the removed vegetation, adding the array to the save if needed. Removing a location
+    /// from the array restores the tree or rock there
+    pub fn set_removed_vegetation(&mut self, locations: Vec<[f32; 3]>) -> Result<()> {
+        if self.inner.get_prop("RemovedVegetationAssetsArray").is_err() {
+            if locations.is_empty() {
+                return Ok(());
+            } else if !self.version().quirks().optional_arrays {
+                return Err(GVASError::Unsupported(
+                    "removed vegetation in a save from this version".to_string(),
+                ));
+            }
+        }
+        self.inner
+            .set_prop("RemovedVegetationAssetsArray", Value::VectorArray(locations));
+        Ok(())
+    }
+}
+
+/// Number of items in arrays that hold one entry per item, which all have to be the same length
+fn parallel_len(what: &str, lengths: &[usize]) -> Result<usize> {
+    match lengths.split_first() {
+        Some((&len, rest)) if rest.iter().all(|&l| l == len) => Ok(len),
+        Some(_) => Err(GVASError::Malformed(format!(
+            "the {} arrays have different lengths {:?}",
+            what, lengths
+        ))),
+        None => Ok(0),
+    }
+}
+
+/// Checks that spline `i`'s range `start..=end` is inside an array `len` long
+fn check_range(what: &str, i: usize, start: u32, end: u32, len: usize) -> Result<()> {
+    if start > end || end as usize >= len {
+        Err(GVASError::Malformed(format!(
+            "spline {} has {} {}..={}, but there are only {}",
+            i, what, start, end, len
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerData {
+    pub name: String,
+    pub money: f32,
+    pub xp: u32,
+}
+
+pub struct PlayerIter<'a> {
+    i: usize,
+    /// Length of the arrays, checked to be the same
+    len: usize,
+    player_name_array: &'a Vec<String>,
+    player_money_array: &'a Vec<f32>,
+    player_xp_array: &'a Vec<u32>,
+}
+
+impl<'a> Iterator for PlayerIter<'a> {
+    type Item = PlayerData;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i < self.len {
+            let player = PlayerData {
+                name: self.player_name_array[self.i].clone(),
+                money: self.player_money_array[self.i],
+                xp: self.player_xp_array[self.i],
+            };
+            self.i += 1;
+            Some(player)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.i, Some(self.len - self.i))
+    }
+}
+
+/// A single piece of rolling stock (locomotive, tender, or car)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct FrameData {
+    /// Index of this frame in the save's frame arrays
+    pub index: usize,
+    pub ty: String,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+    pub name: TextProperty,
+    pub number: TextProperty,
+    pub freight_type: String,
+    pub freight_amount: u32,
+    pub coupler_front: bool,
+    pub coupler_rear: bool,
+}
+
+pub struct FrameIter<'a> {
+    i: usize,
+    /// Length of the arrays, checked to be the same
+    len: usize,
+    frame_type_array: &'a Vec<String>,
+    frame_location_array: &'a Vec<[f32; 3]>,
+    frame_rotation_array: &'a Vec<[f32; 3]>,
+    frame_name_array: &'a Vec<TextProperty>,
+    frame_number_array: &'a Vec<TextProperty>,
+    freight_type_array: &'a Vec<String>,
+    freight_amount_array: &'a Vec<u32>,
+    coupler_front_state_array: &'a Vec<bool>,
+    coupler_rear_state_array: &'a Vec<bool>,
+}
+
+impl<'a> Iterator for FrameIter<'a> {
+    type Item = FrameData;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i < self.len {
+            let frame = FrameData {
+                index: self.i,
+                ty: self.frame_type_array[self.i].clone(),
+                location: self.frame_location_array[self.i],
+                rotation: self.frame_rotation_array[self.i],
+                name: self.frame_name_array[self.i].clone(),
+                number: self.frame_number_array[self.i].clone(),
+                freight_type: self.freight_type_array[self.i].clone(),
+                freight_amount: self.freight_amount_array[self.i],
+                coupler_front: self.coupler_front_state_array[self.i],
+                coupler_rear: self.coupler_rear_state_array[self.i],
+            };
+            self.i += 1;
+            Some(frame)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.i, Some(self.len - self.i))
+    }
+}
+
+/// An industry, along with the amount of each input (educt) and output (product) in storage
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct IndustryData {
+    pub ty: u32,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+    pub educts: [u32; 4],
+    pub products: [u32; 4],
+}
+
+impl IndustryData {
+    pub fn new(ty: u32, location: [f32; 3], rotation: [f32; 3]) -> Self {
+        Self {
+            ty,
+            location,
+            rotation,
+            educts: [0; 4],
+            products: [0; 4],
+        }
+    }
+}
+
+/// Display name of the industry type `ty`
+pub fn industry_name(ty: u32) -> Cow<'static, str> {
+    match ty {
+        1 => "Logging Camp".into(),
+        2 => "Sawmill".into(),
+        3 => "Smelter".into(),
+        4 => "Ironworks".into(),
+        5 => "Oil Field".into(),
+        6 => "Refinery".into(),
+        7 => "Coal Mine".into(),
+        8 => "Iron Mine".into(),
+        9 => "Freight Depot".into(),
+        FIREWOOD_DEPOT => "Firewood Depot".into(),
+        _ => format!("Industry {}", ty).into(),
+    }
+}
+
+/// Industry types known to the editor
+pub const INDUSTRY_TYPES: std::ops::RangeInclusive<u32> = 1..=10;
+
+pub struct IndustryIter<'a> {
+    i: usize,
+    /// Length of the arrays, checked to be the same
+    len: usize,
+    industry_type_array: &'a Vec<u32>,
+    industry_location_array: &'a Vec<[f32; 3]>,
+    industry_rotation_array: &'a Vec<[f32; 3]>,
+    industry_storage_educt_arrays: [&'a Vec<u32>; 4],
+    industry_storage_product_arrays: [&'a Vec<u32>; 4],
+}
+
+impl<'a> Iterator for IndustryIter<'a> {
+    type Item = IndustryData;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i < self.len {
+            let i = self.i;
+            let industry = IndustryData {
+                ty: self.industry_type_array[i],
+                location: self.industry_location_array[i],
+                rotation: self.industry_rotation_array[i],
+                educts: self.industry_storage_educt_arrays.map(|a| a[i]),
+                products: self.industry_storage_product_arrays.map(|a| a[i]),
+            };
+            self.i += 1;
+            Some(industry)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.i, Some(self.len - self.i))
+    }
+}
+
+/// Structures that service locomotives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, enum_map::Enum)]
+pub enum ServiceKind {
+    Watertower,
+    Sandhouse,
+}
+
+impl ServiceKind {
+    /// Names of the (type, location, rotation, level) arrays for this kind
+    fn arrays(&self) -> [&'static str; 4] {
+        match self {
+            Self::Watertower => [
+                "WatertowerTypeArray",
+                "WatertowerLocationArray",
+                "WatertowerRotationArray",
+                "WatertowerWaterlevelArray",
+            ],
+            Self::Sandhouse => [
+                "SandhouseTypeArray",
+                "SandhouseLocationArray",
+                "SandhouseRotationArray",
+                "SandhouseSandlevelArray",
+            ],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Watertower => "Watertower",
+            Self::Sandhouse => "Sandhouse",
+        }
+    }
+}
+
+/// Every kind of service structure
+pub const SERVICE_KINDS: [ServiceKind; 2] = [ServiceKind::Watertower, ServiceKind::Sandhouse];
+
+/// Industry type of firewood depots, which are placed like service structures
+pub const FIREWOOD_DEPOT: u32 = 10;
+
+/// A watertower or sandhouse, along with how full it is
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct ServiceData {
+    pub kind: ServiceKind,
+    pub ty: u32,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+    pub level: f32,
+}
+
+impl ServiceData {
+    pub fn new(kind: ServiceKind, location: [f32; 3], rotation: [f32; 3]) -> Self {
+        Self {
+            kind,
+            ty: 0,
+            location,
+            rotation,
+            level: 0.,
+        }
+    }
+}
+
+pub struct ServiceIter<'a> {
+    i: usize,
+    /// Length of the arrays, checked to be the same
+    len: usize,
+    kind: ServiceKind,
+    type_array: &'a Vec<u32>,
+    location_array: &'a Vec<[f32; 3]>,
+    rotation_array: &'a Vec<[f32; 3]>,
+    level_array: &'a Vec<f32>,
+}
+
+impl<'a> Iterator for ServiceIter<'a> {
+    type Item = ServiceData;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i < self.len {
+            let service = ServiceData {
+                kind: self.kind,
+                ty: self.type_array[self.i],
+                location: self.location_array[self.i],
+                rotation: self.rotation_array[self.i],
+                level: self.level_array[self.i],
+            };
+            self.i += 1;
+            Some(service)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.i, Some(self.len - self.i))
+    }
+}
+
+/// A turntable. The deck rotation is in world space, not relative to the turntable
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct TurntableData {
+    pub ty: u32,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+    pub deck_rotation: [f32; 3],
+}
+
+pub struct TurntableIter<'a> {
+    i: usize,
+    /// Length of the arrays, checked to be the same
+    len: usize,
+    turntable_type_array: &'a Vec<u32>,
+    turntable_location_array: &'a Vec<[f32; 3]>,
+    turntable_rotator_array: &'a Vec<[f32; 3]>,
+    turntable_deck_rotation_array: &'a Vec<[f32; 3]>,
+}
+
+impl<'a> Iterator for TurntableIter<'a> {
+    type Item = TurntableData;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i < self.len {
+            let turntable = TurntableData {
+                ty: self.turntable_type_array[self.i],
+                location: self.turntable_location_array[self.i],
+                rotation: self.turntable_rotator_array[self.i],
+                deck_rotation: self.turntable_deck_rotation_array[self.i],
+            };
+            self.i += 1;
+            Some(turntable)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.i, Some(self.len - self.i))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct SwitchData {
+    pub ty: SwitchType,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+    pub state: u32,
+}
+
+pub struct SwitchIter<'a> {
+    i: usize,
+    /// Length of the arrays, checked to be the same
+    len: usize,
+    switch_type_array: Vec<SwitchType>,
+    switch_location_array: &'a Vec<[f32; 3]>,
+    switch_rotation_array: &'a Vec<[f32; 3]>,
+    switch_state_array: &'a Vec<u32>,
+}
+
+impl<'a> Iterator for SwitchIter<'a> {
+    type Item = SwitchData;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i < self.len {
+            let ty = self.switch_type_array[self.i];
+            let location = self.switch_location_array[self.i];
+            let rotation = self.switch_rotation_array[self.i];
+            let state = self.switch_state_array[self.i];
+            self.i += 1;
+            Some(SwitchData {
+                ty,
+                location,
+                rotation,
+                state,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.i, Some(self.len - self.i))
+    }
+}
+
+/// Number of splines and segments of each type, and control points overall, see
+/// [`RROSave::spline_stats`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplineStats {
+    pub splines: BTreeMap<SplineType, usize>,
+    pub segments: BTreeMap<SplineType, usize>,
+    pub control_points: usize,
+}
+
+impl SplineStats {
+    /// Counts one spline with `control_points` points
+    pub fn add(&mut self, ty: SplineType, control_points: usize) {
+        *self.splines.entry(ty).or_default() += 1;
+        *self.segments.entry(ty).or_default() += control_points.saturating_sub(1);
+        self.control_points += control_points;
+    }
+
+    /// Segments of every type
+    pub fn total_segments(&self) -> usize {
+        self.segments.values().sum()
+    }
+}
+
+/// A spline as it's stored in a save, borrowed from the save's arrays, see [`RROSave::curves`]
+#[derive(Debug)]
+pub struct CurveData<'a> {
+    pub location: &'a [f32; 3],
+    pub ty: SplineType,
+    pub control_points: &'a [[f32; 3]],
+    /// Whether each segment, between two control points, is shown
+    pub visibility: &'a [bool],
+}
+
+/// A spline to write to a save, see [`RROSave::set_curves`]
+#[derive(Debug)]
+pub struct CurveDataOwned {
+    pub location: [f32; 3],
+    pub ty: SplineType,
+    pub control_points: Vec<[f32; 3]>,
+    /// Whether each segment, between two control points, is shown. One shorter than
+    /// `control_points`
+    pub visibility: Vec<bool>,
+}
+
+pub struct RROCurveIter<'a> {
+    i: usize,
+    /// Length of the arrays, checked to be the same
+    len: usize,
+    spline_location_array: &'a Vec<[f32; 3]>,
+    spline_type_array: &'a Vec<u32>,
+    spline_control_points_array: &'a Vec<[f32; 3]>,
+    spline_control_points_index_start_array: &'a Vec<u32>,
+    spline_control_points_index_end_array: &'a Vec<u32>,
+    spline_segments_visibility_array: &'a Vec<bool>,
+    spline_visibility_start_array: &'a Vec<u32>,
+    spline_visibility_end_array: &'a Vec<u32>,
+}
+
+impl<'a> Iterator for RROCurveIter<'a> {
+    type Item = CurveData<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i < self.len {
+            let ctrl_s = self.spline_control_points_index_start_array[self.i] as usize;
+            let ctrl_e = self.spline_control_points_index_end_array[self.i] as usize;
+            let vis_s = self.spline_visibility_start_array[self.i] as usize;
+            let vis_e = self.spline_visibility_end_array[self.i] as usize;
+            let curve = CurveData {
+                location: &self.spline_location_array[self.i],
+                ty: self.spline_type_array[self.i].into(),
+                control_points: &self.spline_control_points_array[ctrl_s..=ctrl_e],
+                visibility: &self.spline_segments_visibility_array[vis_s..=vis_e],
+            };
+            self.i += 1;
+            Some(curve)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.i, Some(self.len - self.i))
+    }
+}
+
+impl<'a> ExactSizeIterator for RROCurveIter<'a> {}
+
+pub use scoped::*;
+
+mod scoped {
+    #[cfg(feature = "bevy")]
+    use bevy::math::Vec3;
+
+    /// The type of a spline, as stored in the save. This isn't an enum, so saves with types
+    /// added by newer versions of the game can still be read and written back unchanged. The
+    /// editor's view of each type comes from its spline type file, see `registry.rs`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct SplineType(pub u32);
+
+    #[allow(non_upper_case_globals)]
+    impl SplineType {
+        pub const Track: Self = Self(0);
+        pub const GroundWork: Self = Self(1);
+        pub const ConstGroundWork: Self = Self(2);
+        pub const WoodBridge: Self = Self(3);
+        pub const TrackBed: Self = Self(4);
+        pub const StoneGroundWork: Self = Self(5);
+        pub const ConstStoneGroundWork: Self = Self(6);
+        pub const SteelBridge: Self = Self(7);
+
+        /// Every type known when this was written
+        pub const KNOWN: [Self; 8] = [
+            Self::Track,
+            Self::GroundWork,
+            Self::ConstGroundWork,
+            Self::WoodBridge,
+            Self::TrackBed,
+            Self::StoneGroundWork,
+            Self::ConstStoneGroundWork,
+            Self::SteelBridge,
+        ];
+
+        /// Name of a known type
+        pub fn name(&self) -> Option<&'static str> {
+            Some(match *self {
+                Self::Track => "Track",
+                Self::GroundWork => "GroundWork",
+                Self::ConstGroundWork => "ConstGroundWork",
+                Self::WoodBridge => "WoodBridge",
+                Self::TrackBed => "TrackBed",
+                Self::StoneGroundWork => "StoneGroundWork",
+                Self::ConstStoneGroundWork => "ConstStoneGroundWork",
+                Self::SteelBridge => "SteelBridge",
+                _ => return None,
+            })
+        }
+
+        /// The known type with the given name, or a type given by number
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::KNOWN
+                .into_iter()
+                .find(|ty| ty.name() == Some(name))
+                .or_else(|| name.parse().ok().map(Self))
+        }
+    }
+
+    impl std::fmt::Display for SplineType {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self.name() {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "{}", self.0),
+            }
+        }
+    }
+
+    impl From<u32> for SplineType {
+        fn from(ty: u32) -> Self {
+            Self(ty)
+        }
+    }
+
+    impl From<SplineType> for u32 {
+        fn from(ty: SplineType) -> Self {
+            ty.0
+        }
+    }
+
+    /// Known types are written by name, so JSON written before types were numbers can still be
+    /// read. Other types are written as their number
+    impl serde::Serialize for SplineType {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self.name() {
+                Some(name) => serializer.serialize_str(name),
+                None => serializer.serialize_u32(self.0),
+            }
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for SplineType {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visitor;
+            impl<'de> serde::de::Visitor<'de> for Visitor {
+                type Value = SplineType;
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    write!(f, "a spline type name or number")
+                }
+                fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<SplineType, E> {
+                    u32::try_from(v)
+                        .map(SplineType)
+                        .map_err(|_| E::custom(format!("spline type {} out of range", v)))
+                }
+                fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<SplineType, E> {
+                    SplineType::from_name(v)
+                        .ok_or_else(|| E::custom(format!("unknown spline type {}", v)))
+                }
+            }
+            deserializer.deserialize_any(Visitor)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, enum_utils::TryFromRepr, Hash, enum_map::Enum)]
+    #[repr(u32)]
+    pub enum SwitchType {
+        SwitchLeft = 0,
+        SwitchLeftAlt = 5,
+        SwitchRight = 1,
+        SwitchRightAlt = 4,
+        Crossover90 = 6,
+    }
+
+    impl SwitchType {
+        /// The switch that's this one's mirror image, i.e. left switches turn into right ones
+        pub fn mirrored(self) -> Self {
+            match self {
+                Self::SwitchLeft => Self::SwitchRight,
+                Self::SwitchLeftAlt => Self::SwitchRightAlt,
+                Self::SwitchRight => Self::SwitchLeft,
+                Self::SwitchRightAlt => Self::SwitchLeftAlt,
+                Self::Crossover90 => Self::Crossover90,
+            }
+        }
+    }
+
+    #[cfg(feature = "bevy")]
+    impl SwitchType {
+        /// Scale of the switch model in the editor
+        pub fn scale(&self) -> Vec3 {
+            match self {
+                Self::SwitchLeft | Self::SwitchLeftAlt => Vec3::new(-0.1, 0.1, -0.1),
+                _ => Vec3::new(-0.1, 0.1, 0.1),
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// The save the editor opens with, as written by the game
+    const DEFAULT_SAVE: &[u8] = include_bytes!("../../assets/default.sav");
+
+    /// Writes `val` as a property called `name`, and reads it back
+    fn round_trip(name: &str, val: &Value) -> Value {
+        let mut buf = vec![];
+        val.write(&mut buf, name).unwrap();
+        let mut r = OffsetReader::new(Cursor::new(&buf), 0);
+        let read = Value::read(&mut r, name).unwrap();
+        assert_eq!(r.offset(), buf.len() as u64, "not read to the end");
+        read
+    }
+
+    #[test]
+    fn byte_enum_and_name_properties_round_trip() {
+        let guid = Some(*b"0123456789abcdef");
+        let values = [
+            Value::Byte(7, None),
+            Value::Byte(7, guid),
+            Value::ByteEnum(
+                "EWeather".to_string(),
+                "EWeather::Rain".to_string(),
+                None,
+            ),
+            Value::ByteEnum(
+                "EWeather".to_string(),
+                "EWeather::Rain".to_string(),
+                guid,
+            ),
+            Value::Enum(
+                "ESeason".to_string(),
+                "ESeason::Winter".to_string(),
+                None,
+            ),
+            Value::Enum(
+                "ESeason".to_string(),
+                "ESeason::Winter".to_string(),
+                guid,
+            ),
+            Value::Name("Engine".to_string(), None),
+            Value::Name("Engine".to_string(), guid),
+        ];
+        for val in values.iter() {
+            assert_eq!(&round_trip("Test", val), val);
+        }
+    }
+
+    #[test]
+    fn non_ascii_strings_round_trip_as_utf16() {
+        let values = [
+            Value::String("Großer Bahnhof".to_string()),
+            Value::Name("Köf".to_string(), None),
+            Value::StringArray(vec!["Zürich".to_string(), "ascii".to_string()]),
+        ];
+        for val in values.iter() {
+            assert_eq!(&round_trip("Test", val), val);
+        }
+        let mut buf = vec![];
+        buf.write_string("é").unwrap();
+        // One UTF-16 unit and the terminator
+        assert_eq!(buf, [0xfe, 0xff, 0xff, 0xff, 0xe9, 0, 0, 0]);
+    }
+
+    #[test]
+    fn unknown_properties_are_kept_raw() {
+        // An IntProperty of 42: size, guid flag, then the value
+        let mut raw = 4u64.to_le_bytes().to_vec();
+        raw.push(0);
+        raw.extend_from_slice(&42u32.to_le_bytes());
+        let val = Value::Raw("IntProperty".to_string(), raw);
+        assert_eq!(round_trip("Test", &val), val);
+        // Bools are stored in the header, after a size of 0
+        let mut raw = 0u64.to_le_bytes().to_vec();
+        raw.extend_from_slice(&[1, 0]);
+        let val = Value::Raw("BoolProperty".to_string(), raw);
+        assert_eq!(round_trip("Test", &val), val);
+    }
+
+    /// Reads `bytes` as a text, checks it's written back the same, and returns it
+    fn text_round_trip(bytes: &[u8]) -> TextProperty {
+        let mut r = Cursor::new(bytes);
+        let text = TextProperty::read(&mut r).unwrap();
+        assert_eq!(r.position(), bytes.len() as u64, "not read to the end");
+        let mut written = vec![];
+        text.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+        text
+    }
+
+    /// A culture invariant text, history -1
+    fn invariant_text(w: &mut Vec<u8>, flags: u32, value: Option<&str>) {
+        w.write_all(&flags.to_le_bytes()).unwrap();
+        w.write_all(&(-1i8).to_le_bytes()).unwrap();
+        match value {
+            Some(value) => {
+                w.write_all(&1u32.to_le_bytes()).unwrap();
+                w.write_string(value).unwrap();
+            }
+            None => w.write_all(&0u32.to_le_bytes()).unwrap(),
+        }
+    }
+
+    /// A localized text, history 0
+    fn base_text(w: &mut Vec<u8>, flags: u32, namespace: &str, key: &str, source: &str) {
+        w.write_all(&flags.to_le_bytes()).unwrap();
+        w.write_all(&0i8.to_le_bytes()).unwrap();
+        w.write_string(namespace).unwrap();
+        w.write_string(key).unwrap();
+        w.write_string(source).unwrap();
+    }
+
+    /// The header of a format text, history 1 or 3, and its source. The arguments follow
+    fn format_text(w: &mut Vec<u8>, history: i8, key: &str, args: u32) {
+        w.write_all(&1u32.to_le_bytes()).unwrap();
+        w.write_all(&history.to_le_bytes()).unwrap();
+        base_text(w, 8, "", key, FMT_STR);
+        w.write_all(&args.to_le_bytes()).unwrap();
+    }
+
+    #[test]
+    fn invariant_texts_round_trip() {
+        let mut bytes = vec![];
+        invariant_text(&mut bytes, 0, None);
+        assert_eq!(text_round_trip(&bytes), TextProperty::None);
+
+        let mut bytes = vec![];
+        invariant_text(&mut bytes, 2, Some("Sign"));
+        assert_eq!(text_round_trip(&bytes), TextProperty::Simple("Sign".to_string()));
+
+        // Flags the editor doesn't write are kept as they are
+        let mut bytes = vec![];
+        invariant_text(&mut bytes, 6, Some("Sign"));
+        assert_eq!(text_round_trip(&bytes), TextProperty::Other(bytes));
+    }
+
+    #[test]
+    fn base_texts_are_kept_raw() {
+        let mut bytes = vec![];
+        base_text(&mut bytes, 0, "Game", "SignKey", "Sign");
+        assert_eq!(text_round_trip(&bytes), TextProperty::Other(bytes));
+    }
+
+    #[test]
+    fn format_texts_round_trip() {
+        let mut bytes = vec![];
+        format_text(&mut bytes, 3, FMT_STR_KEY, 2);
+        for (key, value) in [("0", "Top"), ("1", "")] {
+            bytes.write_string(key).unwrap();
+            bytes.write_all(&[4]).unwrap();
+            invariant_text(&mut bytes, 2, Some(value).filter(|v| !v.is_empty()));
+        }
+        assert_eq!(
+            text_round_trip(&bytes),
+            TextProperty::FmtStr("Top".to_string(), String::new())
+        );
+
+        // Named formats, other keys, and numeric arguments are kept as they are
+        let mut bytes = vec![];
+        format_text(&mut bytes, 1, "OtherKey", 4);
+        bytes.write_string("0").unwrap();
+        bytes.write_all(&[0]).unwrap();
+        bytes.write_all(&(-5i64).to_le_bytes()).unwrap();
+        bytes.write_string("1").unwrap();
+        bytes.write_all(&[1]).unwrap();
+        bytes.write_all(&5u64.to_le_bytes()).unwrap();
+        bytes.write_string("2").unwrap();
+        bytes.write_all(&[2]).unwrap();
+        bytes.write_all(&1.5f32.to_le_bytes()).unwrap();
+        bytes.write_string("3").unwrap();
+        bytes.write_all(&[3]).unwrap();
+        bytes.write_all(&2.5f64.to_le_bytes()).unwrap();
+        assert_eq!(text_round_trip(&bytes), TextProperty::Other(bytes));
+    }
+
+    #[test]
+    fn unknown_text_histories_are_errors() {
+        let mut bytes = 0u32.to_le_bytes().to_vec();
+        bytes.push(9);
+        assert!(matches!(
+            TextProperty::read(&mut Cursor::new(&bytes)),
+            Err(GVASError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn default_save_round_trips() {
+        let save = RROSave::read(&mut Cursor::new(DEFAULT_SAVE)).unwrap();
+        let mut written = vec![];
+        save.write(&mut written).unwrap();
+        // Not assert_eq, which would print both saves
+        assert!(written == DEFAULT_SAVE, "the written save differs");
+        save.verify(&written).unwrap();
+    }
+
+    #[test]
+    fn anonymize_skips_missing_player_arrays() {
+        let mut save = builder::SaveBuilder::new().build().unwrap();
+        save.anonymize().unwrap();
+        assert_eq!(save.players().unwrap().next().unwrap().name, "Player 1");
+        save.inner
+            .properties
+            .retain(|p| !p.name.starts_with("player"));
+        save.anonymize().unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+//! The serde schema saves are exported with.
+//!
+//! [`GVASFile`] and everything in it serialize to a stable layout, so exports can be diffed,
+//! snapshotted, or read by other tools. Every enum is tagged with `type`, and holds its data in
+//! `value`. Variant names are fixed here, not taken from the Rust names, and raw bytes are hex
+//! strings. Floats that aren't finite, which JSON has no numbers for, are the strings `"NaN"`,
+//! `"inf"`, and `"-inf"`. A document is `{"schema": 2, "file": {...}}`; `SCHEMA_VERSION` goes up whenever the
+//! layout changes in a way older readers can't handle.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{GVASError, GVASFile, Result};
+
+/// Version of the layout written by `to_json`
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    schema: u32,
+    file: &'a GVASFile,
+}
+
+/// The rest of a document, once `Header` has checked its version
+#[derive(Deserialize)]
+struct Document {
+    file: GVASFile,
+}
+
+/// Just the version, so a newer document is reported as such rather than as malformed
+#[derive(Deserialize)]
+struct Header {
+    schema: Option<u32>,
+}
+
+pub fn to_json(file: &GVASFile) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&DocumentRef {
+        schema: SCHEMA_VERSION,
+        file,
+    })?)
+}
+
+pub fn from_json(s: &str) -> Result<GVASFile> {
+    match serde_json::from_str::<Header>(s)?.schema {
+        Some(SCHEMA_VERSION) => Ok(serde_json::from_str::<Document>(s)?.file),
+        Some(schema) => Err(GVASError::Unsupported(format!("JSON schema {}", schema))),
+        None => Err(GVASError::Malformed(
+            "JSON has no schema version, it's from an older editor".to_string(),
+        )),
+    }
+}
+
+/// Bytes as a lower case hex string
+pub(crate) mod hex {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bytes: impl AsRef<[u8]>,
+        s: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let hex: String = bytes
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        s.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(d)?;
+        if hex.len() % 2 != 0 {
+            return Err(serde::de::Error::custom("hex string has an odd length"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+                    .ok_or_else(|| serde::de::Error::custom(format!("invalid hex {:?}", hex)))
+            })
+            .collect()
+    }
+}
+
+/// A GUID as a hex string
+pub(crate) mod guid {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(guid: &[u8; 16], s: S) -> std::result::Result<S::Ok, S::Error> {
+        hex::serialize(guid, s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<[u8; 16], D::Error> {
+        let bytes = hex::deserialize(d)?;
+        bytes
+            .try_into()
+            .map_err(|_| serde::de::Error::custom("a GUID is 16 bytes"))
+    }
+}
+
+/// A property GUID as a hex string, or null if the property has none
+pub(crate) mod property_guid {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        guid: &Option<[u8; 16]>,
+        s: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match guid {
+            Some(guid) => hex::serialize(guid, s),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> std::result::Result<Option<[u8; 16]>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|hex| guid::deserialize(serde::de::value::StringDeserializer::new(hex)))
+            .transpose()
+    }
+}
+
+/// A float that may not be finite, see the module docs
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Float(f32);
+
+impl Serialize for Float {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            f if f.is_finite() => s.serialize_f32(f),
+            f if f.is_nan() => s.serialize_str("NaN"),
+            f if f > 0. => s.serialize_str("inf"),
+            _ => s.serialize_str("-inf"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Float {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f32),
+            Named(String),
+        }
+        match Repr::deserialize(d)? {
+            Repr::Number(f) => Ok(Self(f)),
+            Repr::Named(name) => match name.as_str() {
+                "NaN" => Ok(Self(f32::NAN)),
+                "inf" => Ok(Self(f32::INFINITY)),
+                "-inf" => Ok(Self(f32::NEG_INFINITY)),
+                _ => Err(serde::de::Error::custom(format!(
+                    "invalid float {:?}",
+                    name
+                ))),
+            },
+        }
+    }
+}
+
+/// Floats, any of which may not be finite
+pub(crate) mod floats {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(floats: &[f32], s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.collect_seq(floats.iter().map(|f| Float(*f)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Vec<f32>, D::Error> {
+        Ok(Vec::<Float>::deserialize(d)?
+            .into_iter()
+            .map(|f| f.0)
+            .collect())
+    }
+}
+
+/// Vectors or rotators, any part of which may not be finite
+pub(crate) mod vectors {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        vectors: &[[f32; 3]],
+        s: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        s.collect_seq(vectors.iter().map(|v| v.map(Float)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> std::result::Result<Vec<[f32; 3]>, D::Error> {
+        Ok(Vec::<[Float; 3]>::deserialize(d)?
+            .into_iter()
+            .map(|v| v.map(|f| f.0))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RROSave, Value};
+    use std::io::Cursor;
+
+    #[test]
+    fn non_finite_floats_round_trip() {
+        let floats = Value::FloatArray(vec![1.5, f32::NAN, f32::INFINITY, f32::NEG_INFINITY]);
+        let json = serde_json::to_string(&floats).unwrap();
+        assert!(json.contains(r#"[1.5,"NaN","inf","-inf"]"#), "{}", json);
+        match serde_json::from_str(&json).unwrap() {
+            Value::FloatArray(read) => {
+                assert_eq!(read[0], 1.5);
+                assert!(read[1].is_nan());
+                assert_eq!(read[2..], [f32::INFINITY, f32::NEG_INFINITY]);
+            }
+            other => panic!("read {:?}", other),
+        }
+        let vectors = Value::VectorArray(vec![[0., f32::NAN, -2.25], [f32::INFINITY, 1., 2.]]);
+        let read: Value = serde_json::from_str(&serde_json::to_string(&vectors).unwrap()).unwrap();
+        match read {
+            Value::VectorArray(read) => {
+                assert!(read[0][1].is_nan());
+                assert_eq!([read[0][0], read[0][2]], [0., -2.25]);
+                assert_eq!(read[1], [f32::INFINITY, 1., 2.]);
+            }
+            other => panic!("read {:?}", other),
+        }
+        assert!(
+            serde_json::from_str::<Value>(r#"{"type":"float_array","value":["one"]}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn default_save_round_trips_through_json() {
+        let default = include_bytes!("../../assets/default.sav");
+        let save = RROSave::read(&mut Cursor::new(&default[..])).unwrap();
+        let read = RROSave::from_json(&save.to_json().unwrap()).unwrap();
+        let mut written = vec![];
+        read.write(&mut written).unwrap();
+        assert!(written == default, "the written save differs");
+    }
+}
@@ -0,0 +1,181 @@
+//! Checks for the spline index arrays. Each spline's control points and segment visibility are
+//! ranges of shared arrays, and saves edited by other tools sometimes have ranges that are out of
+//! bounds, inverted, or overlapping, which `RROSave::curves` can't read.
+
+use super::{CurveDataOwned, RROSave, Result, SplineType};
+
+/// A problem with the spline arrays
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// The spline with the problem, or `None` for problems with the arrays as a whole
+    pub spline: Option<usize>,
+    pub description: String,
+}
+
+struct SplineArrays<'a> {
+    location: &'a Vec<[f32; 3]>,
+    ty: &'a Vec<u32>,
+    points: &'a Vec<[f32; 3]>,
+    start: &'a Vec<u32>,
+    end: &'a Vec<u32>,
+    visibility: &'a Vec<bool>,
+    visibility_start: &'a Vec<u32>,
+    visibility_end: &'a Vec<u32>,
+}
+
+impl<'a> SplineArrays<'a> {
+    fn read(save: &'a RROSave) -> Result<Self> {
+        let inner = &save.inner;
+        Ok(Self {
+            location: inner.get_prop("SplineLocationArray")?.try_into()?,
+            ty: inner.get_prop("SplineTypeArray")?.try_into()?,
+            points: inner.get_prop("SplineControlPointsArray")?.try_into()?,
+            start: inner
+                .get_prop("SplineControlPointsIndexStartArray")?
+                .try_into()?,
+            end: inner
+                .get_prop("SplineControlPointsIndexEndArray")?
+                .try_into()?,
+            visibility: inner
+                .get_prop("SplineSegmentsVisibilityArray")?
+                .try_into()?,
+            visibility_start: inner.get_prop("SplineVisibilityStartArray")?.try_into()?,
+            visibility_end: inner.get_prop("SplineVisibilityEndArray")?.try_into()?,
+        })
+    }
+
+    /// The visibility of spline `i`, if its range is valid and has one entry per segment
+    fn visibility(&self, i: usize, segments: usize) -> Option<&'a [bool]> {
+        let (s, e) = (
+            *self.visibility_start.get(i)? as usize,
+            *self.visibility_end.get(i)? as usize,
+        );
+        if s <= e && e < self.visibility.len() && e - s + 1 == segments {
+            Some(&self.visibility[s..=e])
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks every spline's control point and visibility ranges. An empty list means
+/// `RROSave::curves` can read the save
+pub fn validate(save: &RROSave) -> Result<Vec<Problem>> {
+    let arrays = SplineArrays::read(save)?;
+    let n = arrays.location.len();
+    let mut problems = vec![];
+    for (name, len) in [
+        ("type", arrays.ty.len()),
+        ("control point start", arrays.start.len()),
+        ("control point end", arrays.end.len()),
+        ("visibility start", arrays.visibility_start.len()),
+        ("visibility end", arrays.visibility_end.len()),
+    ] {
+        if len != n {
+            problems.push(Problem {
+                spline: None,
+                description: format!("{} {} entries for {} splines", len, name, n),
+            });
+        }
+    }
+    let mut last_end = None;
+    for i in 0..n {
+        let mut problem = |description: String| {
+            problems.push(Problem {
+                spline: Some(i),
+                description,
+            })
+        };
+        let (s, e) = match (arrays.start.get(i), arrays.end.get(i)) {
+            (Some(&s), Some(&e)) => (s as usize, e as usize),
+            _ => continue,
+        };
+        if s > e {
+            problem(format!("control points {}..={} are inverted", s, e));
+            continue;
+        } else if e >= arrays.points.len() {
+            problem(format!(
+                "control points {}..={} are past the end of the {} control points",
+                s,
+                e,
+                arrays.points.len()
+            ));
+            continue;
+        } else if s == e {
+            problem("only has one control point".to_string());
+        }
+        if last_end.is_some_and(|last| s <= last) {
+            problem("control points overlap the previous spline".to_string());
+        }
+        last_end = Some(e);
+        match (arrays.visibility_start.get(i), arrays.visibility_end.get(i)) {
+            (Some(&vs), Some(&ve)) if vs > ve => {
+                problem(format!("visibility {}..={} is inverted", vs, ve))
+            }
+            (Some(&vs), Some(&ve)) if ve as usize >= arrays.visibility.len() => problem(format!(
+                "visibility {}..={} is past the end of the {} entries",
+                vs,
+                ve,
+                arrays.visibility.len()
+            )),
+            (Some(&vs), Some(&ve)) if (ve - vs) as usize + 1 != e - s => problem(format!(
+                "{} visibility entries for {} segments",
+                ve - vs + 1,
+                e - s
+            )),
+            _ => (),
+        }
+    }
+    Ok(problems)
+}
+
+/// Rebuilds the index arrays from the data arrays, and returns the number of splines kept.
+///
+/// Each spline's location is its first control point, so the control points are split where
+/// they match the next spline's location, using the stored start where it still points at the
+/// right place. Splines left with fewer than two control points are dropped. Visibility is kept
+/// for splines whose range was valid, and every other segment is made visible. Splines missing
+/// a type become track
+pub fn repair(save: &mut RROSave) -> Result<usize> {
+    let arrays = SplineArrays::read(save)?;
+    let points = arrays.points;
+    let mut starts = Vec::with_capacity(arrays.location.len());
+    let mut cursor = 0;
+    for (i, location) in arrays.location.iter().enumerate() {
+        let stored = arrays.start.get(i).map(|&s| s as usize);
+        let start = match stored {
+            Some(s) if s >= cursor && points.get(s) == Some(location) => s,
+            _ => points
+                .iter()
+                .skip(cursor)
+                .position(|p| p == location)
+                .map_or(cursor, |j| j + cursor),
+        };
+        starts.push(start);
+        // Every spline has at least two control points
+        cursor = start + 2;
+    }
+    let mut curves = vec![];
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts
+            .get(i + 1)
+            .map_or(points.len(), |&next| next.min(points.len()));
+        if start + 2 > end {
+            continue;
+        }
+        let control_points = points[start..end].to_vec();
+        let segments = control_points.len() - 1;
+        let visibility = arrays
+            .visibility(i, segments)
+            .map_or(vec![true; segments], |v| v.to_vec());
+        curves.push(CurveDataOwned {
+            location: control_points[0],
+            ty: arrays.ty.get(i).map_or(SplineType::Track, |&ty| ty.into()),
+            control_points,
+            visibility,
+        });
+    }
+    let kept = curves.len();
+    save.set_curves(curves.into_iter())?;
+    Ok(kept)
+}
@@ -0,0 +1,137 @@
+use super::{EngineVersion, GVASFile, Value};
+
+/// Version information read from a save's header and `SaveGameVersion` property
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveVersion {
+    /// Value of the `SaveGameVersion` property, e.g. "220127", if the save has one
+    pub save_game_version: Option<String>,
+    /// Unreal engine version the save was written with, as major, minor, patch
+    pub engine: [u16; 3],
+}
+
+/// A release of Railroads Online, and how its saves differ from the others
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Release {
+    /// `SaveGameVersion` written by the release, as a number
+    pub version: u32,
+    pub name: &'static str,
+    pub quirks: Quirks,
+}
+
+/// Differences in how saves from a release have to be read and written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// Whether the release reads the optional industry, turntable and service arrays, so they can
+    /// be added to saves that don't have them yet
+    pub optional_arrays: bool,
+}
+
+/// Quirks for saves we don't know anything about. Nothing is added to the save that it doesn't
+/// already have
+const UNKNOWN_QUIRKS: Quirks = Quirks {
+    optional_arrays: false,
+};
+
+/// Every release the editor has been tested with, oldest first
+pub const RELEASES: &[Release] = &[Release {
+    version: 220127,
+    name: "Alpha 2022-01-27",
+    quirks: Quirks {
+        optional_arrays: true,
+    },
+}];
+
+/// The newest release the editor supports
+pub fn latest() -> &'static Release {
+    RELEASES.last().unwrap()
+}
+
+/// How a save's version compares to the releases we know about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Support {
+    /// Written by a known release
+    Known(&'static Release),
+    /// Older than every known release
+    Older,
+    /// Newer than the latest known release. Reading is likely to work, but unknown properties
+    /// may be lost or misread
+    Newer,
+    /// The save doesn't have a readable `SaveGameVersion`
+    Unknown,
+}
+
+impl SaveVersion {
+    pub(super) fn read(file: &GVASFile) -> Self {
+        let save_game_version = match file.get_prop("SaveGameVersion") {
+            Ok(Value::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+        let EngineVersion {
+            major,
+            minor,
+            patch,
+            ..
+        } = file.engine_version;
+        Self {
+            save_game_version,
+            engine: [major, minor, patch],
+        }
+    }
+
+    /// `SaveGameVersion` as a number, which is the release date as YYMMDD
+    pub fn number(&self) -> Option<u32> {
+        self.save_game_version.as_ref()?.trim().parse().ok()
+    }
+
+    pub fn support(&self) -> Support {
+        let number = if let Some(number) = self.number() {
+            number
+        } else {
+            return Support::Unknown;
+        };
+        if let Some(release) = RELEASES.iter().find(|r| r.version == number) {
+            Support::Known(release)
+        } else if number > latest().version {
+            Support::Newer
+        } else {
+            Support::Older
+        }
+    }
+
+    /// Quirks to apply when reading and writing this save. Newer saves are treated like the
+    /// latest release, and saves between known releases like the release before them
+    pub fn quirks(&self) -> Quirks {
+        match self.support() {
+            Support::Known(release) => release.quirks,
+            Support::Newer => latest().quirks,
+            Support::Older | Support::Unknown => {
+                let number = self.number().unwrap_or(0);
+                RELEASES
+                    .iter()
+                    .rev()
+                    .find(|r| r.version <= number)
+                    .map_or(UNKNOWN_QUIRKS, |r| r.quirks)
+            }
+        }
+    }
+
+    /// A warning to show the user, if the editor may not handle this save correctly
+    pub fn warning(&self) -> Option<String> {
+        match self.support() {
+            Support::Known(_) => None,
+            Support::Newer => Some(format!(
+                "This save is from version {}, which is newer than the latest supported version ({}). Some data may be lost when saving.",
+                self.save_game_version.as_deref().unwrap_or("?"),
+                latest().version
+            )),
+            Support::Older => Some(format!(
+                "This save is from version {}, which is older than any supported version. New turntables and service structures can't be saved.",
+                self.save_game_version.as_deref().unwrap_or("?"),
+            )),
+            Support::Unknown => Some(format!(
+                "Could not read the version of this save (engine {}.{}.{}).",
+                self.engine[0], self.engine[1], self.engine[2]
+            )),
+        }
+    }
+}
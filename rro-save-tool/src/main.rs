@@ -0,0 +1,221 @@
+//! Command line tool for Railroads Online saves, for use in scripts. Run without arguments for
+//! usage.
+
+use rro_gvas::validate::{repair, validate as validate_indices};
+use rro_gvas::{CurveDataOwned, GVASError, RROSave, SERVICE_KINDS};
+use std::fs::File;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: rro-save-tool <command> [args]
+
+Commands:
+    to-json <save> <json>           Export a save as JSON
+    from-json <json> <save>         Write a save from JSON exported by the tool or the editor
+    list <save>                     List the splines in a save
+    translate <save> <out> <x> <y> <z>
+                                    Move everything placed by players, in meters
+    strip-vegetation <save> <out>   Restore every removed tree and rock
+    validate <save>                 Check that every array the editor uses can be read
+    repair <save> <out>             Rebuild broken spline indices from the control points";
+
+/// Save units (centimeters) per meter
+const SAVE_UNITS_PER_METER: f32 = 100.;
+
+#[derive(Debug)]
+enum Error {
+    Usage,
+    Gvas(GVASError),
+}
+
+impl From<GVASError> for Error {
+    fn from(e: GVASError) -> Self {
+        Self::Gvas(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Gvas(e.into())
+    }
+}
+
+fn read(path: &str) -> Result<RROSave, Error> {
+    Ok(RROSave::read(&mut File::open(path)?)?)
+}
+
+fn write(save: &RROSave, path: &str) -> Result<(), Error> {
+    Ok(save.write(&mut File::create(path)?)?)
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn list(save: &RROSave) -> Result<(), Error> {
+    for (i, curve) in save.curves()?.enumerate() {
+        let hidden = curve.visibility.iter().filter(|v| !**v).count();
+        println!(
+            "{}: {:?}, {} points, {} hidden segments, starting at {:?}",
+            i,
+            curve.ty,
+            curve.control_points.len(),
+            hidden,
+            curve.location
+        );
+    }
+    Ok(())
+}
+
+/// Moves splines, switches, rolling stock, industries, turntables and service structures by
+/// `offset`, in save units. Removed vegetation stays where it is, since it refers to the map
+fn translate(save: &mut RROSave, offset: [f32; 3]) -> Result<(), Error> {
+    let curves: Vec<_> = save
+        .curves()?
+        .map(|c| CurveDataOwned {
+            location: add(*c.location, offset),
+            ty: c.ty,
+            control_points: c.control_points.iter().map(|p| add(*p, offset)).collect(),
+            visibility: c.visibility.to_vec(),
+        })
+        .collect();
+    save.set_curves(curves.into_iter())?;
+    let switches: Vec<_> = save.switches()?.collect();
+    save.set_switches(switches.into_iter().map(|mut s| {
+        s.location = add(s.location, offset);
+        s
+    }))?;
+    let frames: Vec<_> = save.frames()?.collect();
+    save.set_frames(frames.into_iter().map(|mut f| {
+        f.location = add(f.location, offset);
+        f
+    }))?;
+    let industries: Vec<_> = save.industries()?.collect();
+    save.set_industries(industries.into_iter().map(|mut i| {
+        i.location = add(i.location, offset);
+        i
+    }))?;
+    let turntables: Vec<_> = save.turntables()?.collect();
+    save.set_turntables(turntables.into_iter().map(|mut t| {
+        t.location = add(t.location, offset);
+        t
+    }))?;
+    for kind in SERVICE_KINDS {
+        // Saves without any of these don't have the arrays
+        let services: Vec<_> = match save.services(kind) {
+            Ok(services) => services.collect(),
+            Err(GVASError::Missing(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        save.set_services(
+            kind,
+            services.into_iter().map(|mut s| {
+                s.location = add(s.location, offset);
+                s
+            }),
+        )?;
+    }
+    Ok(())
+}
+
+/// Checks the spline indices and reads every array the editor uses, and returns whether there
+/// were no problems
+fn validate(save: &RROSave) -> bool {
+    match validate_indices(save) {
+        Ok(problems) if problems.is_empty() => (),
+        Ok(problems) => {
+            for problem in problems {
+                match problem.spline {
+                    Some(i) => println!("spline {}: {}", i, problem.description),
+                    None => println!("splines: {}", problem.description),
+                }
+            }
+            // The splines can't be read, so nothing else will work
+            return false;
+        }
+        Err(e) => {
+            println!("splines: Error: {:?}", e);
+            return false;
+        }
+    }
+    let mut results = vec![
+        ("splines", save.curves().map(|c| c.count())),
+        ("switches", save.switches().map(|s| s.count())),
+        ("players", save.players().map(|p| p.count())),
+        ("frames", save.frames().map(|f| f.count())),
+        ("industries", save.industries().map(|i| i.count())),
+        ("turntables", save.turntables().map(|t| t.count())),
+    ];
+    for kind in SERVICE_KINDS {
+        match save.services(kind) {
+            Err(GVASError::Missing(_)) => (),
+            r => results.push((kind.name(), r.map(|s| s.count()))),
+        }
+    }
+    match save.removed_vegetation() {
+        Err(GVASError::Missing(_)) => (),
+        r => results.push(("removed vegetation", r.map(|v| v.len()))),
+    }
+    if let Some(warning) = save.version().warning() {
+        println!("Warning: {}", warning);
+    }
+    let mut ok = true;
+    for (name, result) in results {
+        match result {
+            Ok(n) => println!("{}: {}", name, n),
+            Err(e) => {
+                println!("{}: Error: {:?}", name, e);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+fn run(args: &[String]) -> Result<bool, Error> {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match args.as_slice() {
+        ["to-json", save, json] => std::fs::write(json, read(save)?.to_json()?)?,
+        ["from-json", json, save] => {
+            write(&RROSave::from_json(&std::fs::read_to_string(json)?)?, save)?
+        }
+        ["list", save] => list(&read(save)?)?,
+        ["translate", save, out, x, y, z] => {
+            let mut offset = [0.; 3];
+            for (o, v) in offset.iter_mut().zip([x, y, z]) {
+                *o = v.parse::<f32>().map_err(|_| Error::Usage)? * SAVE_UNITS_PER_METER;
+            }
+            let mut gvas = read(save)?;
+            translate(&mut gvas, offset)?;
+            write(&gvas, out)?;
+        }
+        ["strip-vegetation", save, out] => {
+            let mut gvas = read(save)?;
+            gvas.set_removed_vegetation(vec![])?;
+            write(&gvas, out)?;
+        }
+        ["validate", save] => return Ok(validate(&read(save)?)),
+        ["repair", save, out] => {
+            let mut gvas = read(save)?;
+            println!("{} splines kept", repair(&mut gvas)?);
+            write(&gvas, out)?;
+        }
+        _ => return Err(Error::Usage),
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(Error::Usage) => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+        Err(Error::Gvas(e)) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
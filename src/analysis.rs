@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::path::Path;
+
+use crate::graph::NetworkGraph;
+use crate::coords::METERS_PER_UNIT;
+use crate::dialog::Dialogs;
+use crate::gvas::{SplineType, SwitchData};
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Plugin for the network statistics window
+pub struct AnalysisPlugin;
+
+impl Plugin for AnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AnalysisSettings {
+            max_grade: 3.,
+            min_radius: 100.,
+        });
+        app.insert_resource(GraphFile {
+            path: "network".to_string(),
+        });
+        app.add_system(analysis_window);
+    }
+}
+
+/// Thresholds beyond which a segment is reported as an outlier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalysisSettings {
+    /// Percent grade
+    pub max_grade: f32,
+    /// Radius in meters
+    pub min_radius: f32,
+}
+
+/// Path entered for exporting the network graph. The extension is set by the format
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphFile {
+    pub path: String,
+}
+
+/// Upper bounds of the grade histogram bins, in percent
+const GRADE_BINS: [f32; 7] = [0.5, 1., 1.5, 2., 3., 4., 6.];
+/// Upper bounds of the radius histogram bins, in meters
+const RADIUS_BINS: [f32; 7] = [50., 75., 100., 150., 200., 300., 500.];
+
+/// A single track segment
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SegmentStats {
+    /// Start of the segment
+    location: Vec3,
+    /// Percent grade
+    grade: f32,
+    /// Radius in meters
+    radius: f32,
+}
+
+/// Counts values into bins given by their upper bound, with one extra bin for anything larger
+fn bin_counts(values: impl Iterator<Item = f32>, bins: &[f32]) -> Vec<usize> {
+    let mut counts = vec![0; bins.len() + 1];
+    for v in values {
+        let i = bins.iter().position(|b| v < *b).unwrap_or(bins.len());
+        counts[i] += 1;
+    }
+    counts
+}
+
+/// Draws a horizontal bar chart, with bars for which `outlier` is true in red
+fn histogram(ui: &mut egui::Ui, labels: &[String], counts: &[usize], outlier: impl Fn(usize) -> bool) {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    egui::Grid::new(&labels[0]).show(ui, |ui| {
+        for (i, (label, count)) in labels.iter().zip(counts.iter()).enumerate() {
+            ui.label(label.as_str());
+            let (rect, _response) =
+                ui.allocate_exact_size(egui::vec2(200., 12.), egui::Sense::hover());
+            let bar = egui::Rect::from_min_size(
+                rect.min,
+                egui::vec2(rect.width() * *count as f32 / max as f32, rect.height()),
+            );
+            let color = if outlier(i) {
+                egui::Color32::RED
+            } else {
+                egui::Color32::LIGHT_BLUE
+            };
+            ui.painter().rect_filled(bar, 0., color);
+            ui.label(format!("{}", count));
+            ui.end_row();
+        }
+    });
+}
+
+/// Labels for bins given by their upper bound
+fn bin_labels(bins: &[f32], unit: &str) -> Vec<String> {
+    let mut labels = vec![];
+    let mut low = 0.;
+    for b in bins {
+        labels.push(format!("{}-{}{}", low, b, unit));
+        low = *b;
+    }
+    labels.push(format!(">{}{}", low, unit));
+    labels
+}
+
+fn analysis_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut settings: ResMut<AnalysisSettings>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    switches: Query<&Transform, With<SwitchData>>,
+    user_settings: Res<Settings>,
+    mut graph_file: ResMut<GraphFile>,
+    mut dialogs: ResMut<Dialogs>,
+) {
+    if !palette.show_stats {
+        return;
+    }
+    let units = user_settings.units;
+    let mut segments = vec![];
+    for bez in beziers.iter().filter(|b| b.ty() == SplineType::Track) {
+        for i in 0..bez.len() - 1 {
+            segments.push(SegmentStats {
+                location: bez.get_control_point(i),
+                grade: bez.segment_grade(i).abs(),
+                radius: bez.segment_min_radius(i) * METERS_PER_UNIT,
+            });
+        }
+    }
+    let grades = bin_counts(segments.iter().map(|s| s.grade), &GRADE_BINS);
+    let radii = bin_counts(segments.iter().map(|s| s.radius), &RADIUS_BINS);
+    let settings = settings.as_mut();
+    egui::Window::new("Network Statistics")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("{} track segments", segments.len()));
+            ui.add(egui::Slider::new(&mut settings.max_grade, 0.5..=10.0).text("Max grade %"));
+            let mut min_radius = units.from_meters(settings.min_radius);
+            let range = units.from_meters(10.)..=units.from_meters(500.);
+            if ui
+                .add(
+                    egui::Slider::new(&mut min_radius, range)
+                        .text(format!("Min radius ({})", units.suffix())),
+                )
+                .changed()
+            {
+                settings.min_radius = units.to_meters(min_radius);
+            }
+            ui.separator();
+            ui.label("Grade");
+            histogram(ui, &bin_labels(&GRADE_BINS, "%"), &grades, |i| {
+                i > 0 && GRADE_BINS[i - 1] >= settings.max_grade
+            });
+            ui.separator();
+            ui.label("Minimum radius");
+            let bins = RADIUS_BINS.map(|b| units.from_meters(b).round());
+            histogram(ui, &bin_labels(&bins, units.suffix()), &radii, |i| {
+                i < RADIUS_BINS.len() && RADIUS_BINS[i] <= settings.min_radius
+            });
+            ui.separator();
+            let mut outliers: Vec<_> = segments
+                .iter()
+                .filter(|s| s.grade > settings.max_grade || s.radius < settings.min_radius)
+                .collect();
+            outliers.sort_by(|a, b| b.grade.total_cmp(&a.grade));
+            ui.label(format!("{} outliers", outliers.len()));
+            egui::ScrollArea::vertical()
+                .max_height(150.)
+                .show(ui, |ui| {
+                    for s in outliers {
+                        ui.label(format!(
+                            "{}: {:.2}%, {}",
+                            units.point(s.location),
+                            s.grade,
+                            units.length(s.radius)
+                        ));
+                    }
+                });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Graph File");
+                ui.text_edit_singleline(&mut graph_file.path);
+            });
+            ui.horizontal(|ui| {
+                let graph =
+                    || NetworkGraph::build(beziers.iter(), switches.iter().map(|t| t.translation));
+                let export = if ui.button("Export DOT").clicked() {
+                    Some(("dot", graph().to_dot()))
+                } else if ui.button("Export GraphML").clicked() {
+                    Some(("graphml", graph().to_graphml()))
+                } else {
+                    None
+                };
+                if let Some((extension, contents)) = export {
+                    let path = Path::new(&graph_file.path).with_extension(extension);
+                    match std::fs::write(&path, contents) {
+                        Ok(()) => dialogs.message(
+                            "graph_exported",
+                            "Exported Network",
+                            format!("Wrote the network graph to {}", path.display()),
+                        ),
+                        Err(e) => dialogs.message(
+                            "graph_failed",
+                            "Could Not Export Network",
+                            format!("{}\n{}", path.display(), e),
+                        ),
+                    }
+                }
+            });
+        });
+}
@@ -10,6 +10,15 @@ impl Plugin for Background {
     }
 }
 
+/// Marker for the map meshes
+#[derive(Debug, Component, Default)]
+pub struct Terrain;
+
+/// Height of the ground under `p`. The terrain is flat for now, so it's the height of its plane
+pub fn ground_height(terrain: &Query<&Transform, With<Terrain>>, _p: Vec3) -> f32 {
+    terrain.iter().next().map_or(0., |t| t.translation.y)
+}
+
 fn load_height_map(
     mut commands: Commands,
     // _asset_server: Res<AssetServer>,
@@ -21,7 +30,8 @@ fn load_height_map(
             mesh: meshes.add(Mesh::from(shape::Plane { size: 100. })),
             material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
             ..Default::default()
-        });
+        })
+        .insert(Terrain);
     // commands
     //     .spawn_bundle(PbrBundle {
     //         mesh: asset_server.load("rro_height_map.obj"),
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::gvas::{RROSave, SplineStats};
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Plugin for the spline budget window, comparing spline counts to the limits in `Settings`
+pub struct BudgetPlugin;
+
+impl Plugin for BudgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(budget_window);
+    }
+}
+
+/// Fraction of a limit at which usage is shown as a warning
+const WARN_FRACTION: f32 = 0.9;
+
+/// Draws usage of a limit as a bar, or nothing if there is no limit. Returns whether the limit
+/// is exceeded
+fn usage_bar(ui: &mut egui::Ui, used: usize, limit: Option<usize>) -> bool {
+    let limit = match limit {
+        Some(limit) if limit > 0 => limit,
+        _ => {
+            ui.label("");
+            return false;
+        }
+    };
+    let fraction = used as f32 / limit as f32;
+    let (rect, _response) = ui.allocate_exact_size(egui::vec2(120., 12.), egui::Sense::hover());
+    ui.painter()
+        .rect_filled(rect, 0., egui::Color32::from_gray(40));
+    let bar = egui::Rect::from_min_size(
+        rect.min,
+        egui::vec2(rect.width() * fraction.min(1.), rect.height()),
+    );
+    let color = if fraction > 1. {
+        egui::Color32::RED
+    } else if fraction > WARN_FRACTION {
+        egui::Color32::YELLOW
+    } else {
+        egui::Color32::LIGHT_BLUE
+    };
+    ui.painter().rect_filled(bar, 0., color);
+    fraction > 1.
+}
+
+/// Edits a limit, where 0 is no limit
+fn limit_value(ui: &mut egui::Ui, limit: Option<usize>) -> Option<usize> {
+    let mut v = limit.unwrap_or(0);
+    ui.add(egui::DragValue::new(&mut v).speed(10.));
+    Some(v).filter(|v| *v > 0)
+}
+
+fn budget_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut settings: ResMut<Settings>,
+    gvas: Res<RROSave>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+) {
+    if !palette.show_budget {
+        return;
+    }
+    // Counted from the editor rather than the save, so it follows every edit
+    let mut stats = SplineStats::default();
+    for bez in beziers.iter() {
+        stats.add(bez.ty(), bez.len());
+    }
+    let settings = settings.as_mut();
+    egui::Window::new("Spline Budget")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let mut over = vec![];
+            egui::Grid::new("spline_budget").show(ui, |ui| {
+                ui.label("Type");
+                ui.label("Splines");
+                ui.label("Segments");
+                ui.label("Limit");
+                ui.end_row();
+                for (ty, segments) in stats.segments.iter() {
+                    let name = settings.spline_types.name(*ty);
+                    ui.label(name.as_str());
+                    ui.label(format!("{}", stats.splines[ty]));
+                    ui.label(format!("{}", segments));
+                    let limit = limit_value(ui, settings.segment_limits.get(ty).copied());
+                    match limit {
+                        Some(limit) => settings.segment_limits.insert(*ty, limit),
+                        None => settings.segment_limits.remove(ty),
+                    };
+                    if usage_bar(ui, *segments, limit) {
+                        over.push(name);
+                    }
+                    ui.end_row();
+                }
+                ui.label("Control Points");
+                ui.label("");
+                ui.label(format!("{}", stats.control_points));
+                settings.control_point_limit = limit_value(ui, settings.control_point_limit);
+                if usage_bar(ui, stats.control_points, settings.control_point_limit) {
+                    over.push("Control Points".to_string());
+                }
+                ui.end_row();
+            });
+            ui.label("A limit of 0 is no limit. Set them for good in the settings file");
+            for name in over {
+                ui.colored_label(egui::Color32::RED, format!("{} is over its limit", name));
+            }
+            ui.separator();
+            match gvas.spline_stats() {
+                Ok(saved) => ui.label(format!(
+                    "Last loaded or saved: {} segments, {} control points",
+                    saved.total_segments(),
+                    saved.control_points
+                )),
+                Err(e) => ui.label(format!("Last loaded or saved: {}", e)),
+            };
+        });
+}
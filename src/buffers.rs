@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use bevy_mod_picking::Hover;
+
+use crate::control::DefaultAssets;
+use crate::junction::JUNCTION_EPSILON;
+use crate::palette::{MouseAction, Palette};
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::DragState;
+
+/// Plugin for end of track markers. The game has nothing like them in the save, so they only
+/// live in the editor, and are kept in workspaces and templates
+pub struct BufferStopPlugin;
+
+impl Plugin for BufferStopPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PendingStops::default());
+        app.add_system(place_buffer_stop);
+        app.add_system(attach_pending_stops);
+        app.add_system(follow_splines);
+    }
+}
+
+/// Frames a pending stop waits for its spline before it is dropped
+const PENDING_FRAMES: usize = 5;
+
+/// Size of the marker, in editor units
+const MARKER_SIZE: Vec3 = Vec3::new(0.3, 0.15, 0.05);
+
+/// Marks the end of a spline as the end of the track
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct BufferStop {
+    pub spline: Entity,
+    /// Whether the stop is at the first control point, rather than the last
+    pub at_start: bool,
+}
+
+/// Stops waiting for a spline to end at their location, in editor space. Used for stops in
+/// workspaces and templates, since their splines are spawned later
+#[derive(Debug, Default)]
+pub struct PendingStops {
+    pub locations: Vec<Vec3>,
+    frames: usize,
+}
+
+/// Location of one end of a spline, and the direction the track leaves in
+pub fn spline_end(bez: &PolyBezier<CubicBezier>, at_start: bool) -> (Vec3, Vec3) {
+    if at_start {
+        (bez.get_control_point(0), -bez.tangent(0))
+    } else {
+        let last = bez.len() - 1;
+        (bez.get_control_point(last), bez.tangent(last))
+    }
+}
+
+/// Stands the marker on the end of the track, facing along it
+fn marker_transform(location: Vec3, direction: Vec3) -> Transform {
+    Transform {
+        translation: location + Vec3::new(0., MARKER_SIZE.y / 2., 0.),
+        rotation: Quat::from_rotation_y(direction.x.atan2(direction.z)),
+        scale: MARKER_SIZE,
+    }
+}
+
+fn spawn_stop(
+    commands: &mut Commands,
+    assets: &DefaultAssets,
+    bez: &PolyBezier<CubicBezier>,
+    stop: BufferStop,
+) {
+    let (location, direction) = spline_end(bez, stop.at_start);
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: assets.cube_mesh.clone(),
+            material: assets.buffer_stop_material.clone(),
+            transform: marker_transform(location, direction),
+            ..Default::default()
+        })
+        .insert(stop);
+}
+
+/// Adds or removes a stop on the clicked end of a spline
+fn place_buffer_stop(
+    palette: Res<Palette>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    handles: Query<(&DragState, &Hover, &Parent)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    stops: Query<(Entity, &BufferStop)>,
+    assets: Res<DefaultAssets>,
+    mut commands: Commands,
+) {
+    if palette.action != MouseAction::BufferStop
+        || !mouse_button_input.just_pressed(MouseButton::Left)
+    {
+        return;
+    }
+    for (state, hover, parent) in handles.iter() {
+        if !hover.hovered() {
+            continue;
+        }
+        let bez = match beziers.get(parent.0) {
+            Ok(bez) if bez.is_endpoint(state.pt) => bez,
+            _ => continue,
+        };
+        let stop = BufferStop {
+            spline: parent.0,
+            at_start: state.pt == 0,
+        };
+        match stops.iter().find(|(_e, s)| **s == stop) {
+            Some((entity, _s)) => commands.entity(entity).despawn(),
+            None => spawn_stop(&mut commands, &assets, bez, stop),
+        }
+        break;
+    }
+}
+
+fn attach_pending_stops(
+    mut pending: ResMut<PendingStops>,
+    beziers: Query<(Entity, &PolyBezier<CubicBezier>)>,
+    stops: Query<&BufferStop>,
+    assets: Res<DefaultAssets>,
+    mut commands: Commands,
+) {
+    if pending.locations.is_empty() {
+        return;
+    }
+    let pending = pending.as_mut();
+    pending.locations.retain(|location| {
+        let found = beziers.iter().find_map(|(entity, bez)| {
+            [true, false]
+                .into_iter()
+                .find(|at_start| {
+                    spline_end(bez, *at_start).0.distance(*location) < JUNCTION_EPSILON
+                })
+                .map(|at_start| {
+                    (
+                        bez,
+                        BufferStop {
+                            spline: entity,
+                            at_start,
+                        },
+                    )
+                })
+        });
+        match found {
+            Some((bez, stop)) => {
+                // Pasting a template in place can land on a stop that's already there
+                if !stops.iter().any(|s| *s == stop) {
+                    spawn_stop(&mut commands, &assets, bez, stop);
+                }
+                false
+            }
+            None => true,
+        }
+    });
+    if pending.locations.is_empty() {
+        pending.frames = 0;
+    } else {
+        pending.frames += 1;
+        if pending.frames >= PENDING_FRAMES {
+            warn!(
+                "Buffer stops: {} stops don't match the end of a spline",
+                pending.locations.len()
+            );
+            pending.locations.clear();
+            pending.frames = 0;
+        }
+    }
+}
+
+/// Keeps stops on the end of their spline, and removes them with it
+fn follow_splines(
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut stops: Query<(Entity, &BufferStop, &mut Transform)>,
+    mut commands: Commands,
+) {
+    for (entity, stop, mut transform) in stops.iter_mut() {
+        if let Ok(bez) = beziers.get(stop.spline) {
+            let (location, direction) = spline_end(bez, stop.at_start);
+            let target = marker_transform(location, direction);
+            if *transform != target {
+                *transform = target;
+            }
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+}
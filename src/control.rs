@@ -1,14 +1,30 @@
-use crate::gvas::{gvas_to_vec, vec_to_gvas, CurveDataOwned, RROSave, SplineType, SwitchData, rotator_to_quat, quat_to_rotator, SwitchType};
+use crate::coords::{gvas_to_vec, quat_to_rotator, rotator_to_quat, vec_to_gvas, METERS_PER_UNIT};
+use crate::dialog::{DialogEvent, DialogResponse, Dialogs};
+use crate::gizmo::GizmoAxis;
+use crate::gvas::validate::validate;
+use crate::history::History;
+use crate::gvas::{CurveDataOwned, FrameData, GVASError, IndustryData, RROSave, SwitchData, SwitchType, TurntableData, ServiceData, ServiceKind, SERVICE_KINDS};
 use crate::palette::FileEvent;
-use crate::spline::mesh::curve_offset;
+use crate::registry::{SplineTypeInfo, SplineTypeMap};
+use crate::settings::Settings;
+use crate::spline::mesh::{box_mesh, draft_mesh};
 use crate::spline::{CubicBezier, PolyBezier};
-use crate::update::{BezierModificaiton, DragState, UpdatePlugin, BezierSectionUpdate, SwitchDrag};
+use crate::tabs::{Tab, TabEvent, Tabs};
+use crate::inspector::SelectedSpline;
+use crate::validation::Validation;
+use crate::update::{spawn_handle, BezierModificaiton, BezierSection, DeckHandle, UpdatePlugin, BezierSectionUpdate, ObjectDrag, TurntableDeck};
+use crate::vegetation::RemovedVegetation;
 use bevy::prelude::*;
 use bevy::render::render_resource::PrimitiveTopology;
-use bevy_mod_picking::PickableButton;
+use bevy_mod_picking::{Hover, PickableButton};
 use enum_map::{enum_map, EnumMap};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Write};
 use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Mutex;
 
 /// Plugin for loading, saving, and updates
 pub struct ControlPlugin;
@@ -16,24 +32,35 @@ pub struct ControlPlugin;
 impl Plugin for ControlPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system(init_assets);
-        app.insert_resource(
-            RROSave::read(&mut std::io::Cursor::new(include_bytes!(
-                "../assets/default.sav"
-            )))
-            .expect("Failed to parse included save"),
-        );
+        app.insert_resource(default_save());
         app.add_event::<BezierModificaiton>();
+        app.add_event::<LoadProgress>();
+        app.insert_resource(PendingLoad::default());
+        app.insert_resource(SaveCheck::default());
         app.add_system(load_save);
+        app.add_system(load_dialog);
+        app.add_system(spawn_deferred_handles);
         app.add_plugin(UpdatePlugin);
     }
 }
 
+/// The empty save the editor starts with
+pub fn default_save() -> RROSave {
+    RROSave::read(&mut std::io::Cursor::new(include_bytes!(
+        "../assets/default.sav"
+    )))
+    .expect("Failed to parse included save")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, enum_map::Enum)]
 pub enum SplineState {
     Normal,
     Hidden,
     Hover,
     HoverHidden,
+    /// Splines other than the selected one, see `fade.rs`
+    Faded,
+    FadedHidden,
 }
 
 /// Default Assets, to prevent duplicate assets where possible
@@ -41,14 +68,110 @@ pub struct DefaultAssets {
     pub handle_mesh: Handle<Mesh>,
     pub handle_material: Handle<StandardMaterial>,
     pub handle_hover_material: Handle<StandardMaterial>,
-    pub spline_mesh: EnumMap<SplineType, Handle<Mesh>>,
-    pub spline_material: EnumMap<SplineType, EnumMap<SplineState, Handle<StandardMaterial>>>,
+    /// Handles in the selection, see `selection.rs`
+    pub handle_selected_material: Handle<StandardMaterial>,
+    /// Material used to preview what will be removed in delete mode
+    pub delete_material: Handle<StandardMaterial>,
+    /// Material used for previews of objects that haven't been placed yet
+    pub ghost_material: Handle<StandardMaterial>,
+    /// By spline type, from `Settings::spline_types`
+    pub spline_mesh: SplineTypeMap<Handle<Mesh>>,
+    /// Boxes with the bounds of each spline mesh, for `MeshQuality::Draft`
+    pub draft_spline_mesh: SplineTypeMap<Handle<Mesh>>,
+    pub spline_material: SplineTypeMap<EnumMap<SplineState, Handle<StandardMaterial>>>,
     pub switch_mesh: EnumMap<SwitchType, Handle<Mesh>>,
     pub switch_material: EnumMap<SwitchType, EnumMap<bool, Handle<StandardMaterial>>>,
+    /// Unit cube, scaled to size for objects without a model
+    pub cube_mesh: Handle<Mesh>,
+    pub frame_material: EnumMap<bool, Handle<StandardMaterial>>,
+    pub industry_material: EnumMap<bool, Handle<StandardMaterial>>,
+    /// Ring around the edge of a turntable pit
+    pub turntable_mesh: Handle<Mesh>,
+    pub turntable_material: EnumMap<bool, Handle<StandardMaterial>>,
+    pub service_material: EnumMap<ServiceKind, EnumMap<bool, Handle<StandardMaterial>>>,
+    /// Markers for removed vegetation, by whether the vegetation has been restored
+    pub vegetation_material: EnumMap<bool, Handle<StandardMaterial>>,
+    /// End of track markers, see `buffers.rs`
+    pub buffer_stop_material: Handle<StandardMaterial>,
+    /// Parts of the axis gizmo, see `gizmo.rs`
+    pub gizmo_material: EnumMap<GizmoAxis, Handle<StandardMaterial>>,
+}
+
+impl DefaultAssets {
+    /// Every mesh kept here, so they aren't purged while unused
+    pub fn meshes(&self) -> Vec<&Handle<Mesh>> {
+        let mut meshes = vec![&self.handle_mesh, &self.cube_mesh, &self.turntable_mesh];
+        meshes.extend(self.spline_mesh.values());
+        meshes.extend(self.draft_spline_mesh.values());
+        meshes.extend(self.switch_mesh.values());
+        meshes
+    }
+
+    /// Every material kept here, so they aren't purged while unused
+    pub fn materials(&self) -> Vec<&Handle<StandardMaterial>> {
+        let mut materials = vec![
+            &self.handle_material,
+            &self.handle_hover_material,
+            &self.handle_selected_material,
+            &self.delete_material,
+            &self.ghost_material,
+            &self.buffer_stop_material,
+        ];
+        materials.extend(self.spline_material.values().flat_map(|m| m.values()));
+        materials.extend(self.switch_material.values().flat_map(|m| m.values()));
+        materials.extend(self.service_material.values().flat_map(|m| m.values()));
+        for m in [
+            &self.frame_material,
+            &self.industry_material,
+            &self.turntable_material,
+            &self.vegetation_material,
+        ] {
+            materials.extend(m.values());
+        }
+        materials.extend(self.gizmo_material.values());
+        materials
+    }
+}
+
+/// Models bundled with the editor, which spline types can use by name
+fn bundled_model(name: &str) -> Option<&'static [u8]> {
+    Some(match name {
+        "track.obj" => include_bytes!("../assets/models/track.obj"),
+        "tube.obj" => include_bytes!("../assets/models/tube.obj"),
+        "groundwork.obj" => include_bytes!("../assets/models/groundwork.obj"),
+        "stonewall.obj" => include_bytes!("../assets/models/stonewall.obj"),
+        _ => return None,
+    })
+}
+
+/// Loads the model of a spline type, or a box of its cross section if it has no model or the
+/// model can't be loaded
+fn spline_model(info: &SplineTypeInfo) -> Mesh {
+    if let Some(path) = &info.mesh {
+        let bytes = match bundled_model(path) {
+            Some(bytes) => Ok(bytes.to_vec()),
+            None => std::fs::read(path),
+        };
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        match bytes {
+            Ok(bytes) => match crate::bevy_obj::load_obj_from_bytes(&bytes, &mut mesh) {
+                Ok(()) => return mesh,
+                Err(e) => println!("Error: {:?}", e),
+            },
+            Err(e) => println!("Error: {:?}", e),
+        }
+    }
+    let [width, height] = info.cross_section;
+    // Same length as the bundled models
+    box_mesh(
+        Vec3::new(-0.05, 0., -width / 2.),
+        Vec3::new(10.95, height, width / 2.),
+    )
 }
 
 fn init_assets(
     // asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut commands: Commands,
@@ -63,25 +186,38 @@ fn init_assets(
     let handle_mesh = meshes.add(Mesh::from(shape::Cube { size: 0.3 }));
     let handle_material = materials.add(Color::rgb(0.8, 0.0, 0.0).into());
     let handle_hover_material = materials.add(Color::rgb(0.8, 0.8, 0.8).into());
-    let spline_mesh = enum_map! {
-        SplineType::Track => load_obj!(meshes, "track.obj"),
-        SplineType::TrackBed => load_obj!(meshes, "tube.obj"),
-        SplineType::WoodBridge => load_obj!(meshes, "tube.obj"),
-        SplineType::SteelBridge => load_obj!(meshes, "tube.obj"),
-        SplineType::GroundWork | SplineType::ConstGroundWork => load_obj!(meshes, "groundwork.obj"),
-        SplineType::StoneGroundWork | SplineType::ConstStoneGroundWork => load_obj!(meshes, "stonewall.obj"),
-    };
-    let spline_colors = enum_map! {
-            SplineType::GroundWork => Color::rgb(0.8, 0.7, 0.6),
-            SplineType::ConstGroundWork => Color::rgb(0.8, 0.7, 0.6),
-            SplineType::Track => Color::rgb(0.8, 0.7, 0.6),
-            SplineType::TrackBed => Color::rgb(0.8, 0.7, 0.6),
-            SplineType::WoodBridge => Color::rgb(0.8, 0.7, 0.6),
-            SplineType::SteelBridge => Color::rgb(0.8, 0.7, 0.6),
-            SplineType::StoneGroundWork => Color::rgb(0.8, 0.7, 0.6),
-            SplineType::ConstStoneGroundWork => Color::rgb(0.8, 0.7, 0.6),
-    };
-    let spline_material = spline_colors.map(|_k, e| enum_map! {
+    let handle_selected_material = materials.add(Color::rgb(1.0, 0.8, 0.0).into());
+    let delete_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.1, 0.1),
+        unlit: true,
+        ..Default::default()
+    });
+    let ghost_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(0.2, 0.4, 1.0, 0.4),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..Default::default()
+    });
+    // Types with the same model share its meshes
+    let mut models = HashMap::new();
+    let spline_models = SplineTypeMap::new(&settings.spline_types, |info| {
+        let mut load = || {
+            let model = spline_model(info);
+            let draft = draft_mesh(&model);
+            (meshes.add(model), meshes.add(draft))
+        };
+        match &info.mesh {
+            Some(path) => models.entry(path.clone()).or_insert_with(load).clone(),
+            None => load(),
+        }
+    });
+    let spline_mesh = spline_models.map(|(mesh, _draft)| mesh.clone());
+    let draft_spline_mesh = spline_models.map(|(_mesh, draft)| draft.clone());
+    let spline_colors = SplineTypeMap::new(&settings.spline_types, |info| {
+        let [r, g, b] = info.color;
+        Color::rgb(r, g, b)
+    });
+    let spline_material = spline_colors.map(|&e| enum_map! {
         SplineState::Normal => materials.add(e.into()),
         SplineState::Hidden => {
             let mut e = e;
@@ -96,6 +232,12 @@ fn init_assets(
             mat.alpha_mode = AlphaMode::Blend;
             materials.add(mat)
         },
+        // The alpha of these is set by the fade slider
+        SplineState::Faded | SplineState::FadedHidden => {
+            let mut mat: StandardMaterial = e.into();
+            mat.alpha_mode = AlphaMode::Blend;
+            materials.add(mat)
+        },
     });
     // let hidden_spline_material = spline_colors.map(|_k, mut e| {
     //     e.set_a(0.3);
@@ -113,14 +255,85 @@ fn init_assets(
             true => materials.add(Color::rgb(0.8, 0.8, 0.8).into()),
         },
     };
+    let cube_mesh = meshes.add(Mesh::from(shape::Cube { size: 1. }));
+    let frame_material = enum_map! {
+        false => materials.add(Color::rgb(0.3, 0.3, 0.4).into()),
+        true => materials.add(Color::rgb(0.8, 0.8, 0.8).into()),
+    };
+    let industry_material = enum_map! {
+        false => materials.add(Color::rgb(0.5, 0.35, 0.2).into()),
+        true => materials.add(Color::rgb(0.8, 0.8, 0.8).into()),
+    };
+    let turntable_mesh = meshes.add(Mesh::from(shape::Torus {
+        radius: TURNTABLE_RADIUS,
+        ring_radius: 0.1,
+        subdivisions_segments: 32,
+        subdivisions_sides: 8,
+    }));
+    let turntable_material = enum_map! {
+        false => materials.add(Color::rgb(0.4, 0.4, 0.4).into()),
+        true => materials.add(Color::rgb(0.8, 0.8, 0.8).into()),
+    };
+    let service_material = enum_map! {
+        ServiceKind::Watertower => enum_map! {
+            false => materials.add(Color::rgb(0.2, 0.3, 0.5).into()),
+            true => materials.add(Color::rgb(0.8, 0.8, 0.8).into()),
+        },
+        ServiceKind::Sandhouse => enum_map! {
+            false => materials.add(Color::rgb(0.7, 0.6, 0.3).into()),
+            true => materials.add(Color::rgb(0.8, 0.8, 0.8).into()),
+        },
+    };
+    let vegetation_material = enum_map! {
+        false => materials.add(StandardMaterial {
+            base_color: Color::rgb(0.3, 0.6, 0.2),
+            unlit: true,
+            ..Default::default()
+        }),
+        true => materials.add(StandardMaterial {
+            base_color: Color::rgba(0.3, 0.6, 0.2, 0.3),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..Default::default()
+        }),
+    };
+    let buffer_stop_material = materials.add(Color::rgb(0.9, 0.1, 0.1).into());
+    let mut unlit = |base_color| {
+        materials.add(StandardMaterial {
+            base_color,
+            unlit: true,
+            ..Default::default()
+        })
+    };
+    let gizmo_material = enum_map! {
+        GizmoAxis::X => unlit(Color::rgb(0.9, 0.2, 0.2)),
+        GizmoAxis::Y => unlit(Color::rgb(0.2, 0.8, 0.2)),
+        GizmoAxis::Z => unlit(Color::rgb(0.2, 0.4, 0.9)),
+        GizmoAxis::XY => unlit(Color::rgb(0.6, 0.3, 0.9)),
+        GizmoAxis::XZ => unlit(Color::rgb(0.9, 0.7, 0.2)),
+        GizmoAxis::YZ => unlit(Color::rgb(0.2, 0.8, 0.8)),
+    };
     commands.insert_resource(DefaultAssets {
         handle_mesh,
         handle_material,
         handle_hover_material,
+        handle_selected_material,
+        delete_material,
+        ghost_material,
         spline_mesh,
+        draft_spline_mesh,
         spline_material,
         switch_mesh,
         switch_material,
+        cube_mesh,
+        frame_material,
+        industry_material,
+        turntable_mesh,
+        turntable_material,
+        service_material,
+        vegetation_material,
+        buffer_stop_material,
+        gizmo_material,
     });
 }
 
@@ -129,22 +342,273 @@ fn load_save(
     assets: Res<DefaultAssets>,
     beziers: Query<(Entity, &PolyBezier<CubicBezier>, &Children)>,
     switches: Query<(Entity, &Transform, &SwitchData)>,
+    frames: Query<(Entity, &Transform, &FrameData)>,
+    industries: Query<(Entity, &Transform, &IndustryData)>,
+    turntables: Query<(Entity, &Transform, &TurntableData, &Children)>,
+    decks: Query<&Transform, With<TurntableDeck>>,
+    services: Query<(Entity, &Transform, &ServiceData)>,
+    vegetation: Query<(Entity, &RemovedVegetation)>,
     mut gvas: ResMut<RROSave>,
+    settings: Res<Settings>,
     mut commands: Commands,
     mut section_update: EventWriter<BezierSectionUpdate>,
+    (
+        mut pending,
+        mut progress,
+        mut validation,
+        mut tab_events,
+        mut tabs,
+        mut check,
+        mut dialogs,
+        mut history,
+    ): (
+        ResMut<PendingLoad>,
+        EventWriter<LoadProgress>,
+        ResMut<Validation>,
+        EventReader<TabEvent>,
+        ResMut<Tabs>,
+        ResMut<SaveCheck>,
+        ResMut<Dialogs>,
+        ResMut<History>,
+    ),
 ) {
+    let pending = pending.as_mut();
+    let mut finished = None;
+    if let Some(receiver) = &pending.receiver {
+        let receiver = receiver.lock().unwrap();
+        loop {
+            match receiver.try_recv() {
+                Ok(LoadMessage::Progress(p)) => {
+                    pending.last = Some(p.clone());
+                    progress.send(p);
+                }
+                Ok(LoadMessage::Done(result)) => {
+                    finished = Some(result);
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+                // The reader thread panicked before sending what it read
+                Err(TryRecvError::Disconnected) => {
+                    let e = std::io::Error::new(ErrorKind::Other, "the save reader stopped");
+                    finished = Some(Err(e.into()));
+                    break;
+                }
+            }
+        }
+    }
+    let mut loaded = validation.repaired.take();
+    if let Some(result) = finished {
+        pending.receiver = None;
+        let path = pending.last.as_ref().map(|last| last.path.clone());
+        if let Some(last) = pending.last.take() {
+            progress.send(LoadProgress {
+                done: true,
+                failed: result.is_err(),
+                ..last
+            });
+        }
+        match result {
+            // Saves with broken spline indices wait for the validation window
+            Ok(gvas) => match validate(&gvas) {
+                Ok(problems) if !problems.is_empty() => {
+                    validation.problems = problems;
+                    validation.save = Some(gvas);
+                }
+                _ => loaded = Some(gvas),
+            },
+            Err(e) => dialogs.message(
+                "load_failed",
+                "Could Not Open Save",
+                format!("{}\n{}", path.unwrap_or_default().display(), e),
+            ),
+        }
+    }
+    // Switching tabs replaces the world, so it waits for any load to finish first
+    let busy = loaded.is_some() || pending.receiver.is_some();
+    if let Some(gvas) = loaded {
+        if let Err(e) = load_file(
+            gvas,
+            &assets,
+            &settings,
+            &beziers,
+            &switches,
+            &frames,
+            &industries,
+            &turntables,
+            &services,
+            &vegetation,
+            &mut commands,
+            &mut section_update,
+        ) {
+            dialogs.message("load_failed", "Could Not Open Save", e.to_string());
+        }
+    }
+    if !busy {
+        // Only one switch per frame, since the world isn't updated until the commands run
+        if let Some(event) = tab_events.iter().next() {
+            let tabs = tabs.as_mut();
+            let (target, close) = match *event {
+                TabEvent::New => {
+                    tabs.tabs.push(Tab {
+                        name: "New Save".to_string(),
+                        save: Some(default_save()),
+                        history: History::default(),
+                    });
+                    (tabs.tabs.len() - 1, None)
+                }
+                TabEvent::Switch(i) => (i, None),
+                TabEvent::Close(i) if i == tabs.active => (if i == 0 { 1 } else { i - 1 }, Some(i)),
+                TabEvent::Close(i) => (tabs.active, Some(i)),
+            };
+            if target != tabs.active && target < tabs.tabs.len() {
+                // The target's save is only taken once it's loaded, so a failed switch leaves
+                // both tabs as they were
+                let save = tabs.tabs[target].save.clone().unwrap_or_else(default_save);
+                let switched = update_save(
+                    &beziers,
+                    &switches,
+                    &frames,
+                    &industries,
+                    &turntables,
+                    &decks,
+                    &services,
+                    &vegetation,
+                    &mut gvas,
+                )
+                .and_then(|()| {
+                    load_file(
+                        save,
+                        &assets,
+                        &settings,
+                        &beziers,
+                        &switches,
+                        &frames,
+                        &industries,
+                        &turntables,
+                        &services,
+                        &vegetation,
+                        &mut commands,
+                        &mut section_update,
+                    )
+                });
+                match switched {
+                    Ok(()) => {
+                        let active = tabs.active;
+                        tabs.tabs[active].save = Some(RROSave::clone(&gvas));
+                        tabs.tabs[target].save = None;
+                        // Edits can only be undone in the tab they were made in
+                        let target_history = std::mem::take(&mut tabs.tabs[target].history);
+                        tabs.tabs[active].history =
+                            std::mem::replace(history.as_mut(), target_history);
+                        tabs.active = target;
+                    }
+                    Err(e) => dialogs.message("tab_failed", "Could Not Switch Tabs", e.to_string()),
+                }
+            }
+            // The last tab can't be closed
+            if let Some(i) = close.filter(|i| *i != tabs.active && *i < tabs.tabs.len()) {
+                tabs.tabs.remove(i);
+                if i < tabs.active {
+                    tabs.active -= 1;
+                }
+            }
+        }
+    }
     for event in events.iter() {
         if let Err(e) = match event {
-            FileEvent::Load(path) => {
-                load_file(path, &assets, &beziers, &switches, &mut commands, &mut section_update)
+            FileEvent::Load(path) | FileEvent::Import(path) => {
+                tabs.rename_active(path);
+                // Replaces any load still in progress. Its thread still finishes, but nothing reads
+                // what it sends
+                let json = matches!(event, FileEvent::Import(_));
+                pending.receiver = Some(start_load(path.clone(), json));
+                let start = LoadProgress {
+                    path: path.clone(),
+                    bytes_read: 0,
+                    total_bytes: 0,
+                    properties: 0,
+                    done: false,
+                    failed: false,
+                };
+                pending.last = Some(start.clone());
+                progress.send(start);
+                Ok(())
+            }
+            FileEvent::Save(path) | FileEvent::Export(path) | FileEvent::Share(path) => {
+                let json = matches!(event, FileEvent::Export(_));
+                let share = matches!(event, FileEvent::Share(_));
+                let result = save_file(
+                    path,
+                    &beziers,
+                    &switches,
+                    &frames,
+                    &industries,
+                    &turntables,
+                    &decks,
+                    &services,
+                    &vegetation,
+                    &mut gvas,
+                    json,
+                    share,
+                    settings.verify_saves,
+                );
+                // Failures are always shown, successes only once verified
+                if result.is_err() || (!json && settings.verify_saves) {
+                    check.0 = Some(SaveResult {
+                        path: path.clone(),
+                        error: result.as_ref().err().map(|e| e.to_string()),
+                    });
+                }
+                result
             }
-            FileEvent::Save(path) => save_file(path, &beziers, &switches, &mut gvas),
         } {
             println!("Error: {:?}", e);
         }
     }
 }
 
+/// Marks a spline loaded without its handles, see `Settings::fast_load`. The handles are
+/// spawned the first time the spline is hovered or selected
+#[derive(Debug, Component, Default)]
+pub struct DeferredHandles;
+
+/// Spawns the handles for every point of a spline, as children of the spline
+pub fn spawn_handles(
+    commands: &mut Commands,
+    assets: &DefaultAssets,
+    settings: &Settings,
+    entity: Entity,
+    bezier: &PolyBezier<CubicBezier>,
+) {
+    let offset = settings.curve_offset(bezier.ty());
+    let handles: Vec<_> = bezier
+        .get_control_points()
+        .enumerate()
+        .map(|(i, point)| spawn_handle(commands, assets, point + offset, i))
+        .collect();
+    commands.entity(entity).push_children(&handles);
+}
+
+fn spawn_deferred_handles(
+    selected: Res<SelectedSpline>,
+    sections: Query<(&Hover, &Parent), With<BezierSection>>,
+    deferred: Query<&PolyBezier<CubicBezier>, With<DeferredHandles>>,
+    assets: Res<DefaultAssets>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let hovered = sections
+        .iter()
+        .filter(|(hover, _p)| hover.hovered())
+        .map(|(_h, parent)| parent.0);
+    for entity in hovered.chain(selected.0) {
+        if let Ok(bezier) = deferred.get(entity) {
+            spawn_handles(&mut commands, &assets, &settings, entity, bezier);
+            commands.entity(entity).remove::<DeferredHandles>();
+        }
+    }
+}
+
 /// The minimal set of components to create an empty parent for meshes
 #[derive(Debug, Bundle, Default)]
 pub struct ParentBundle {
@@ -152,18 +616,231 @@ pub struct ParentBundle {
     _global: GlobalTransform,
 }
 
+/// How far along the save being loaded is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub path: PathBuf,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    /// Top level properties parsed so far, which includes the spline and switch arrays
+    pub properties: usize,
+    /// Sent once, after the save has finished loading or failed to
+    pub done: bool,
+    /// Whether the save couldn't be loaded, or its load was cancelled. Only set once `done`
+    pub failed: bool,
+}
+
+enum LoadMessage {
+    Progress(LoadProgress),
+    Done(Result<RROSave, GVASError>),
+}
+
+/// How writing or reading a file went
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveResult {
+    pub path: PathBuf,
+    /// Why it failed, or why the written file didn't match, if it did
+    pub error: Option<String>,
+}
+
+/// How the last verified save went, see `Settings::verify_saves`
+#[derive(Debug, Default)]
+pub struct SaveCheck(pub Option<SaveResult>);
+
+/// A save being read on another thread, see `start_load`
+#[derive(Default)]
+pub struct PendingLoad {
+    receiver: Option<Mutex<Receiver<LoadMessage>>>,
+    /// The last progress sent for this load
+    last: Option<LoadProgress>,
+}
+
+impl PendingLoad {
+    pub fn loading(&self) -> bool {
+        self.receiver.is_some()
+    }
+}
+
+/// Id of the dialog shown while a save loads
+const LOAD_DIALOG: &str = "load";
+
+/// Shows the progress of the save being loaded, which can be cancelled to keep the current one
+fn load_dialog(
+    mut pending: ResMut<PendingLoad>,
+    mut dialogs: ResMut<Dialogs>,
+    mut responses: EventReader<DialogEvent>,
+    mut progress: EventWriter<LoadProgress>,
+) {
+    let cancelled = responses
+        .iter()
+        .any(|r| r.id == LOAD_DIALOG && r.response == DialogResponse::Cancel);
+    if cancelled {
+        // Its thread still finishes, but nothing reads what it sends
+        pending.receiver = None;
+        if let Some(last) = pending.last.take() {
+            progress.send(LoadProgress {
+                done: true,
+                failed: true,
+                ..last
+            });
+        }
+    }
+    match &pending.last {
+        Some(last) if pending.receiver.is_some() => {
+            // JSON saves don't report progress, so the bar stays empty
+            let fraction = if last.total_bytes > 0 {
+                last.bytes_read as f32 / last.total_bytes as f32
+            } else {
+                0.
+            };
+            dialogs.progress(
+                LOAD_DIALOG,
+                "Loading",
+                last.path.display().to_string(),
+                fraction,
+                format!("{} properties", last.properties),
+            );
+        }
+        _ => dialogs.close(LOAD_DIALOG),
+    }
+}
+
+/// Counts the bytes read through it
+struct CountingReader<'a, R> {
+    inner: R,
+    count: &'a Cell<u64>,
+}
+
+impl<'a, R: Read> Read for CountingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// Starts reading a save on another thread, so large saves don't freeze the editor
+fn start_load(path: PathBuf, json: bool) -> Mutex<Receiver<LoadMessage>> {
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        let result = read_save(&path, json, &sender);
+        // The receiver is gone if another save was opened in the meantime
+        let _ = sender.send(LoadMessage::Done(result));
+    });
+    Mutex::new(receiver)
+}
+
+/// Reads a save, either as written by the game or exported as JSON. Progress is only reported
+/// for saves written by the game
+fn read_save(path: &PathBuf, json: bool, sender: &Sender<LoadMessage>) -> Result<RROSave, GVASError> {
+    if json {
+        return RROSave::from_json(&std::fs::read_to_string(path)?);
+    }
+    let file = File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+    let count = Cell::new(0);
+    let mut reader = CountingReader {
+        inner: BufReader::new(file),
+        count: &count,
+    };
+    RROSave::read_with_progress(&mut reader, |properties| {
+        let _ = sender.send(LoadMessage::Progress(LoadProgress {
+            path: path.clone(),
+            bytes_read: count.get(),
+            total_bytes,
+            properties,
+            done: false,
+            failed: false,
+        }));
+    })
+}
+
+/// Updates the save from the world and writes it, either as the game expects or as JSON
 fn save_file(
     path: &PathBuf,
     beziers: &Query<(Entity, &PolyBezier<CubicBezier>, &Children)>,
     switches: &Query<(Entity, &Transform, &SwitchData)>,
+    frames: &Query<(Entity, &Transform, &FrameData)>,
+    industries: &Query<(Entity, &Transform, &IndustryData)>,
+    turntables: &Query<(Entity, &Transform, &TurntableData, &Children)>,
+    decks: &Query<&Transform, With<TurntableDeck>>,
+    services: &Query<(Entity, &Transform, &ServiceData)>,
+    vegetation: &Query<(Entity, &RemovedVegetation)>,
+    gvas: &mut ResMut<RROSave>,
+    json: bool,
+    share: bool,
+    verify: bool,
+) -> Result<(), crate::gvas::GVASError> {
+    update_save(
+        beziers, switches, frames, industries, turntables, decks, services, vegetation, gvas,
+    )?;
+    // Shared copies are anonymized, the open save keeps its players
+    let shared;
+    let gvas: &RROSave = if share {
+        let mut copy = RROSave::clone(gvas);
+        copy.anonymize()?;
+        shared = copy;
+        &shared
+    } else {
+        &**gvas
+    };
+    // Written next to the file first, and only moved over it once it's complete, so a crash or
+    // failed write never leaves a half written save behind
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let temp = path.with_file_name(name);
+    let written = write_temp(&temp, gvas, json, verify).and_then(|()| {
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    });
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Writes and syncs a save, verifying it if it's written as the game expects
+fn write_temp(
+    temp: &PathBuf,
+    gvas: &RROSave,
+    json: bool,
+    verify: bool,
+) -> Result<(), crate::gvas::GVASError> {
+    let mut file = File::create(temp)?;
+    if json {
+        file.write_all(gvas.to_json()?.as_bytes())?;
+    } else {
+        gvas.write(&mut file)?;
+    }
+    file.sync_all()?;
+    if verify && !json {
+        gvas.verify(&std::fs::read(temp)?)?;
+    }
+    Ok(())
+}
+
+/// Writes everything in the world into `gvas`
+fn update_save(
+    beziers: &Query<(Entity, &PolyBezier<CubicBezier>, &Children)>,
+    switches: &Query<(Entity, &Transform, &SwitchData)>,
+    frames: &Query<(Entity, &Transform, &FrameData)>,
+    industries: &Query<(Entity, &Transform, &IndustryData)>,
+    turntables: &Query<(Entity, &Transform, &TurntableData, &Children)>,
+    decks: &Query<&Transform, With<TurntableDeck>>,
+    services: &Query<(Entity, &Transform, &ServiceData)>,
+    vegetation: &Query<(Entity, &RemovedVegetation)>,
     gvas: &mut ResMut<RROSave>,
 ) -> Result<(), crate::gvas::GVASError> {
     gvas.set_curves(beziers.iter().map(|(_e, b, _c)| {
         let control_points: Vec<_> = b.get_control_points().map(|v| vec_to_gvas(v)).collect();
+        // Exactly one entry per segment, since some tools create splines with extra entries
+        let visibility = (0..control_points.len() - 1)
+            .map(|i| b.get_visibility().get(i).copied().unwrap_or(true))
+            .collect();
         CurveDataOwned {
             location: control_points[0],
             ty: b.ty(),
-            visibility: vec![true; control_points.len() - 1],
+            visibility,
             control_points,
         }
     }))?;
@@ -173,18 +850,82 @@ fn save_file(
         tmp.rotation = quat_to_rotator(t.rotation);
         tmp
     }))?;
-    gvas.write(&mut File::create(path)?)?;
-    Ok(())
+    gvas.set_frames(frames.iter().map(|(_e, t, f)| {
+        let mut tmp = f.clone();
+        tmp.location = vec_to_gvas(t.translation);
+        tmp.rotation = quat_to_rotator(t.rotation);
+        tmp
+    }))?;
+    gvas.set_industries(industries.iter().map(|(_e, t, i)| {
+        let mut tmp = *i;
+        tmp.location = vec_to_gvas(t.translation);
+        tmp.rotation = quat_to_rotator(t.rotation);
+        tmp
+    }))?;
+    gvas.set_turntables(turntables.iter().map(|(_e, t, d, children)| {
+        let mut tmp = *d;
+        tmp.location = vec_to_gvas(t.translation);
+        tmp.rotation = quat_to_rotator(t.rotation);
+        if let Some(deck) = children.iter().find_map(|c| decks.get(*c).ok()) {
+            tmp.deck_rotation = quat_to_rotator(t.rotation * deck.rotation);
+        }
+        tmp
+    }))?;
+    for kind in SERVICE_KINDS {
+        gvas.set_services(kind, services.iter().map(|(_e, t, s)| {
+            let mut tmp = *s;
+            tmp.location = vec_to_gvas(t.translation);
+            tmp.rotation = quat_to_rotator(t.rotation);
+            tmp
+        }))?;
+    }
+    gvas.set_removed_vegetation(
+        vegetation
+            .iter()
+            .filter(|(_e, v)| !v.restored)
+            .map(|(_e, v)| v.location)
+            .collect(),
+    )
 }
 
 fn load_file(
-    path: &PathBuf,
+    gvas: RROSave,
     assets: &Res<DefaultAssets>,
+    settings: &Settings,
     beziers: &Query<(Entity, &PolyBezier<CubicBezier>, &Children)>,
     switches: &Query<(Entity, &Transform, &SwitchData)>,
+    frames: &Query<(Entity, &Transform, &FrameData)>,
+    industries: &Query<(Entity, &Transform, &IndustryData)>,
+    turntables: &Query<(Entity, &Transform, &TurntableData, &Children)>,
+    services: &Query<(Entity, &Transform, &ServiceData)>,
+    vegetation: &Query<(Entity, &RemovedVegetation)>,
     commands: &mut Commands,
     section_update: &mut EventWriter<BezierSectionUpdate>,
 ) -> Result<(), crate::gvas::GVASError> {
+    if let Some(warning) = gvas.version().warning() {
+        warn!("{}", warning);
+    }
+    // Read everything before clearing the world, so a save that can't be read leaves it as is
+    let curves: Vec<_> = gvas
+        .curves()?
+        .map(|curve| {
+            let points = curve
+                .control_points
+                .iter()
+                .map(|arr| gvas_to_vec(*arr))
+                .collect();
+            PolyBezier::new(points, curve.visibility.iter().copied().collect(), curve.ty)
+        })
+        .collect();
+    let new_switches: Vec<_> = gvas.switches()?.collect();
+    let new_frames = optional(gvas.frames())?;
+    let new_industries = optional(gvas.industries())?;
+    let new_turntables = optional(gvas.turntables())?;
+    let mut new_services = vec![];
+    for kind in SERVICE_KINDS {
+        new_services.extend(optional(gvas.services(kind))?);
+    }
+    let new_vegetation = optional(gvas.removed_vegetation().map(|l| l.iter().copied()))?;
     // Clear the world
     for (e, _c, children) in beziers.iter() {
         commands.entity(e).despawn();
@@ -195,42 +936,33 @@ fn load_file(
     for (e, _t, _s) in switches.iter() {
         commands.entity(e).despawn();
     }
+    for (e, _t, _f) in frames.iter() {
+        commands.entity(e).despawn();
+    }
+    for (e, _t, _i) in industries.iter() {
+        commands.entity(e).despawn();
+    }
+    for (e, _t, _d, _c) in turntables.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (e, _t, _s) in services.iter() {
+        commands.entity(e).despawn();
+    }
+    for (e, _v) in vegetation.iter() {
+        commands.entity(e).despawn();
+    }
     // Load from file
-    let gvas = crate::gvas::RROSave::read(&mut File::open(path)?)?;
-    for curve in gvas.curves()? {
-        // TODO: spawn curves
-        let mut entity = commands.spawn_bundle(ParentBundle::default());
-        let points: Vec<_> = curve
-            .control_points
-            .iter()
-            .map(|arr| gvas_to_vec(*arr))
-            .collect();
-        entity.with_children(|commands| {
-            for (i, point) in points.iter().enumerate() {
-                commands
-                    .spawn_bundle(PbrBundle {
-                        mesh: assets.handle_mesh.clone(),
-                        material: assets.handle_material.clone(),
-                        transform: Transform::from_translation(*point + curve_offset(curve.ty)),
-                        ..Default::default()
-                    })
-                    .insert_bundle(bevy_mod_picking::PickableBundle {
-                        pickable_button: PickableButton {
-                            initial: Some(assets.handle_material.clone()),
-                            hovered: Some(assets.handle_hover_material.clone()),
-                            pressed: Some(assets.handle_hover_material.clone()),
-                            selected: Some(assets.handle_material.clone()),
-                        },
-                        ..Default::default()
-                    })
-                    .insert(DragState::new(i));
-            }
-        });
-        let bezier = PolyBezier::new(points, curve.visibility.iter().copied().collect(), curve.ty);
-        entity.insert(bezier);
-        section_update.send(BezierSectionUpdate { bezier: entity.id() });
+    for bezier in curves {
+        let entity = commands.spawn_bundle(ParentBundle::default()).id();
+        if settings.fast_load {
+            commands.entity(entity).insert(DeferredHandles);
+        } else {
+            spawn_handles(commands, assets, settings, entity, &bezier);
+        }
+        commands.entity(entity).insert(bezier);
+        section_update.send(BezierSectionUpdate { bezier: entity });
     }
-    for switch in gvas.switches()? {
+    for switch in new_switches {
         commands
             .spawn_bundle(PbrBundle {
                 mesh: assets.switch_mesh[switch.ty].clone(),
@@ -251,9 +983,236 @@ fn load_file(
                 },
                 ..Default::default()
             })
-            .insert(SwitchDrag::default())
+            .insert(ObjectDrag::default())
             .insert(switch);
     }
+    for frame in new_frames {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: assets.cube_mesh.clone(),
+                material: assets.frame_material[false].clone(),
+                transform: Transform {
+                    translation: gvas_to_vec(frame.location),
+                    scale: frame_scale(frame.ty.as_str()),
+                    rotation: rotator_to_quat(frame.rotation),
+                },
+                ..Default::default()
+            })
+            .insert_bundle(bevy_mod_picking::PickableBundle {
+                pickable_button: PickableButton {
+                    initial: Some(assets.frame_material[false].clone()),
+                    hovered: Some(assets.frame_material[true].clone()),
+                    pressed: Some(assets.frame_material[true].clone()),
+                    selected: Some(assets.frame_material[false].clone()),
+                },
+                ..Default::default()
+            })
+            .insert(ObjectDrag::default())
+            .insert(frame);
+    }
+    for industry in new_industries {
+        spawn_industry(commands, assets, industry);
+    }
+    for turntable in new_turntables {
+        spawn_turntable(commands, assets, turntable);
+    }
+    for service in new_services {
+        spawn_service(commands, assets, service);
+    }
+    for location in new_vegetation {
+        spawn_vegetation(commands, assets, location, false);
+    }
     commands.insert_resource(gvas);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Items of arrays a save may not have. Saves without any leave the arrays out, which is the
+/// same as having none
+fn optional<T>(items: Result<impl Iterator<Item = T>, GVASError>) -> Result<Vec<T>, GVASError> {
+    match items {
+        Ok(items) => Ok(items.collect()),
+        Err(GVASError::Missing(_)) => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Approximate size of a frame, based on the in-game length of each type in meters
+fn frame_scale(ty: &str) -> Vec3 {
+    let length = match ty {
+        "handcar" => 2.202,
+        "porter_040" => 3.912,
+        "porter_042" => 4.6135,
+        "eureka" => 8.0213,
+        "eureka_tender" => 4.9708,
+        "cooke260" => 8.3783,
+        "cooke260_tender" => 6.4173,
+        "class70" => 9.389,
+        "class70_tender" => 6.7881,
+        "climax" => 8.4989,
+        "heisler" => 9.1373,
+        "boxcar" => 8.2282,
+        _ => 7.856,
+    };
+    // Every frame is about 2.5m tall and 1.93m wide
+    Vec3::new(length, 2.5, 1.9327) / METERS_PER_UNIT
+}
+
+/// Approximate (length, width) of an industry's footprint, in editor units
+fn industry_footprint(ty: u32) -> (f32, f32) {
+    match ty {
+        1 => (4., 3.),
+        2 => (5., 3.),
+        3 => (4., 3.),
+        4 => (5., 4.),
+        5 => (4., 4.),
+        6 => (4., 3.),
+        7 => (4., 3.),
+        8 => (4., 3.),
+        9 => (3., 1.5),
+        10 => (1., 0.5),
+        _ => (2., 2.),
+    }
+}
+
+pub fn spawn_industry(commands: &mut Commands, assets: &DefaultAssets, industry: IndustryData) -> Entity {
+    let (length, width) = industry_footprint(industry.ty);
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: assets.cube_mesh.clone(),
+            material: assets.industry_material[false].clone(),
+            transform: Transform {
+                translation: gvas_to_vec(industry.location),
+                scale: Vec3::new(length, 0.3, width),
+                rotation: rotator_to_quat(industry.rotation),
+            },
+            ..Default::default()
+        })
+        .insert_bundle(bevy_mod_picking::PickableBundle {
+            pickable_button: PickableButton {
+                initial: Some(assets.industry_material[false].clone()),
+                hovered: Some(assets.industry_material[true].clone()),
+                pressed: Some(assets.industry_material[true].clone()),
+                selected: Some(assets.industry_material[false].clone()),
+            },
+            ..Default::default()
+        })
+        .insert(ObjectDrag::default())
+        .insert(industry)
+        .id()
+}
+
+/// Approximate (length, height, width) of a service structure, in editor units
+fn service_size(kind: ServiceKind) -> Vec3 {
+    match kind {
+        ServiceKind::Watertower => Vec3::new(0.5, 1.0, 0.5),
+        ServiceKind::Sandhouse => Vec3::new(0.8, 0.5, 0.5),
+    }
+}
+
+pub fn spawn_service(commands: &mut Commands, assets: &DefaultAssets, service: ServiceData) -> Entity {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: assets.cube_mesh.clone(),
+            material: assets.service_material[service.kind][false].clone(),
+            transform: Transform {
+                translation: gvas_to_vec(service.location),
+                scale: service_size(service.kind),
+                rotation: rotator_to_quat(service.rotation),
+            },
+            ..Default::default()
+        })
+        .insert_bundle(bevy_mod_picking::PickableBundle {
+            pickable_button: PickableButton {
+                initial: Some(assets.service_material[service.kind][false].clone()),
+                hovered: Some(assets.service_material[service.kind][true].clone()),
+                pressed: Some(assets.service_material[service.kind][true].clone()),
+                selected: Some(assets.service_material[service.kind][false].clone()),
+            },
+            ..Default::default()
+        })
+        .insert(ObjectDrag::default())
+        .insert(service)
+        .id()
+}
+
+/// Spawns a marker for removed vegetation. Markers aren't pickable, since there can be
+/// thousands of them; they're edited with the vegetation brush instead
+pub fn spawn_vegetation(commands: &mut Commands, assets: &DefaultAssets, location: [f32; 3], restored: bool) -> Entity {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: assets.cube_mesh.clone(),
+            material: assets.vegetation_material[restored].clone(),
+            transform: Transform {
+                translation: gvas_to_vec(location),
+                scale: Vec3::splat(0.1),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(RemovedVegetation { location, restored })
+        .id()
+}
+
+/// Radius of a turntable pit, in editor units
+pub const TURNTABLE_RADIUS: f32 = 1.2;
+
+/// Spawns a turntable, with its deck and a handle for rotating the deck as children
+pub fn spawn_turntable(commands: &mut Commands, assets: &DefaultAssets, turntable: TurntableData) -> Entity {
+    let rotation = rotator_to_quat(turntable.rotation);
+    // The deck is a child of the turntable, so its rotation is relative
+    let deck = rotation.inverse() * rotator_to_quat(turntable.deck_rotation);
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: assets.turntable_mesh.clone(),
+            material: assets.turntable_material[false].clone(),
+            transform: Transform {
+                translation: gvas_to_vec(turntable.location),
+                rotation,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert_bundle(bevy_mod_picking::PickableBundle {
+            pickable_button: PickableButton {
+                initial: Some(assets.turntable_material[false].clone()),
+                hovered: Some(assets.turntable_material[true].clone()),
+                pressed: Some(assets.turntable_material[true].clone()),
+                selected: Some(assets.turntable_material[false].clone()),
+            },
+            ..Default::default()
+        })
+        .insert(ObjectDrag::default())
+        .insert(turntable)
+        .with_children(|commands| {
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: assets.cube_mesh.clone(),
+                    material: assets.turntable_material[false].clone(),
+                    transform: Transform {
+                        rotation: deck,
+                        scale: Vec3::new(2. * TURNTABLE_RADIUS, 0.05, 0.3),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(TurntableDeck);
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: assets.handle_mesh.clone(),
+                    material: assets.handle_material.clone(),
+                    transform: Transform::from_translation(DeckHandle::offset(deck)),
+                    ..Default::default()
+                })
+                .insert_bundle(bevy_mod_picking::PickableBundle {
+                    pickable_button: PickableButton {
+                        initial: Some(assets.handle_material.clone()),
+                        hovered: Some(assets.handle_hover_material.clone()),
+                        pressed: Some(assets.handle_hover_material.clone()),
+                        selected: Some(assets.handle_material.clone()),
+                    },
+                    ..Default::default()
+                })
+                .insert(DeckHandle::default());
+        })
+        .id()
+}
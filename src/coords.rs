@@ -0,0 +1,158 @@
+// Conversions between save coordinates and editor space. Saves store locations in centimeters
+// as [x, y, z] with z up, while the editor uses a y up space where one unit is 10 meters.
+
+use bevy::math::{EulerRot, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// Size of one editor unit in meters
+pub const METERS_PER_UNIT: f32 = 10.;
+
+/// Save units (centimeters) per editor unit
+pub const SAVE_UNITS_PER_UNIT: f32 = METERS_PER_UNIT * 100.;
+
+pub fn gvas_to_vec(arr: [f32; 3]) -> Vec3 {
+    let [a, b, c] = arr;
+    Vec3::new(
+        -b / SAVE_UNITS_PER_UNIT,
+        c / SAVE_UNITS_PER_UNIT,
+        a / SAVE_UNITS_PER_UNIT,
+    )
+}
+
+pub fn vec_to_gvas(v: Vec3) -> [f32; 3] {
+    [
+        v.z * SAVE_UNITS_PER_UNIT,
+        -v.x * SAVE_UNITS_PER_UNIT,
+        v.y * SAVE_UNITS_PER_UNIT,
+    ]
+}
+
+// the Gvas rotator can be read like a Vector, so:
+// Rotator = [ x, y, z, ]: [f32; 3]
+// X = rotates east side over sky to west side, Y = rotates like a carussel on ground, Z rotates front over top to back
+// [a, b, c] => b = around Z, a = around x, c = around y?
+const ROT: EulerRot = EulerRot::YXZ;
+pub fn rotator_to_quat(arr: [f32; 3]) -> Quat {
+    let [a, b, c] = arr;
+    Quat::from_euler(ROT, -b.to_radians(), a.to_radians(), c.to_radians())
+}
+
+pub fn quat_to_rotator(q: Quat) -> [f32; 3] {
+    let (b, a, c) = q.to_euler(ROT);
+    [a.to_degrees(), -b.to_degrees(), c.to_degrees()]
+}
+
+/// Feet per meter, for imperial units
+const FEET_PER_METER: f32 = 3.28084;
+
+/// Units lengths are shown and entered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Units {
+    Metric,
+    Imperial,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self::Metric
+    }
+}
+
+impl Units {
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Metric => "m",
+            Self::Imperial => "ft",
+        }
+    }
+
+    /// A length in meters, in these units
+    pub fn from_meters(self, m: f32) -> f32 {
+        match self {
+            Self::Metric => m,
+            Self::Imperial => m * FEET_PER_METER,
+        }
+    }
+
+    /// A length in these units, in meters
+    pub fn to_meters(self, v: f32) -> f32 {
+        match self {
+            Self::Metric => v,
+            Self::Imperial => v / FEET_PER_METER,
+        }
+    }
+
+    /// Formats a length in meters, e.g. "12.5m"
+    pub fn length(self, m: f32) -> String {
+        format!("{:.1}{}", self.from_meters(m), self.suffix())
+    }
+
+    /// Formats a radius of curvature given in meters, calling anything broader than 10km straight
+    pub fn radius(self, m: f32) -> String {
+        if m > 10000. {
+            "straight".to_string()
+        } else {
+            self.length(m)
+        }
+    }
+
+    /// Formats a point in editor space, e.g. "(10.0, 2.5, -4.0)m"
+    pub fn point(self, p: Vec3) -> String {
+        let [x, y, z] = [p.x, p.y, p.z].map(|c| self.from_meters(c * METERS_PER_UNIT));
+        format!("({:.1}, {:.1}, {:.1}){}", x, y, z, self.suffix())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+        a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-3)
+    }
+
+    #[test]
+    fn locations_round_trip() {
+        for p in [
+            [0., 0., 0.],
+            [1234.5, -678.9, 42.],
+            [-100000., 250000., -3000.],
+        ] {
+            assert!(close(vec_to_gvas(gvas_to_vec(p)), p), "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn one_unit_is_ten_meters() {
+        // Save z is up, and editor y is up
+        assert_eq!(gvas_to_vec([0., 0., 1000.]), Vec3::Y);
+        assert_eq!(gvas_to_vec([1000., 0., 0.]), Vec3::Z);
+        assert_eq!(gvas_to_vec([0., -1000., 0.]), Vec3::X);
+        assert_eq!(METERS_PER_UNIT * 100., SAVE_UNITS_PER_UNIT);
+    }
+
+    #[test]
+    fn rotators_round_trip() {
+        for r in [
+            [0., 0., 0.],
+            [0., 90., 0.],
+            [10., -45., 5.],
+            [-30., 170., 20.],
+        ] {
+            assert!(close(quat_to_rotator(rotator_to_quat(r)), r), "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn units_convert_both_ways() {
+        for units in [Units::Metric, Units::Imperial] {
+            assert!((units.to_meters(units.from_meters(12.5)) - 12.5).abs() < 1e-4);
+        }
+        assert!((Units::Imperial.from_meters(1.) - FEET_PER_METER).abs() < 1e-6);
+        assert_eq!(Units::Metric.radius(20000.), "straight");
+        assert_eq!(
+            Units::Metric.point(Vec3::new(1., 0.25, -0.4)),
+            "(10.0, 2.5, -4.0)m"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::fmt::Write as _;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use crate::coords::vec_to_gvas;
+use crate::dialog::{DialogEvent, DialogResponse, Dialogs};
+use crate::gvas::SplineType;
+use crate::palette::Palette;
+use crate::patch::{PatchEvent, PatchOp};
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Plugin for exporting and importing spline control points as CSV
+pub struct CsvPlugin;
+
+impl Plugin for CsvPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CsvFile {
+            path: "splines.csv".to_string(),
+        });
+        app.add_event::<CsvEvent>();
+        app.add_system(csv_window);
+        app.add_system(csv_handler);
+    }
+}
+
+const HEADER: &str = "spline,type,index,x,y,z,visible";
+
+/// A spline read from a CSV file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvSpline {
+    pub ty: SplineType,
+    /// Control points, in save coordinates
+    pub points: Vec<[f32; 3]>,
+    pub visibility: Vec<bool>,
+}
+
+/// Writes one row per control point. `visible` is the visibility of the segment starting at the
+/// point, and is left empty for the last point of each spline
+pub fn write_csv<'a>(beziers: impl Iterator<Item = &'a PolyBezier<CubicBezier>>) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", HEADER).unwrap();
+    for (id, bez) in beziers.enumerate() {
+        let visibility = bez.get_visibility();
+        for (i, p) in bez.get_control_points().enumerate() {
+            let [x, y, z] = vec_to_gvas(p);
+            let visible = visibility.get(i).map_or("", |v| if *v { "1" } else { "0" });
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                id,
+                u32::from(bez.ty()),
+                i,
+                x,
+                y,
+                z,
+                visible
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+/// Reads splines written by `write_csv`. Rows are grouped into splines by id, and ordered by
+/// index, so rows can be sorted in any order
+pub fn read_csv(s: &str) -> std::io::Result<Vec<CsvSpline>> {
+    let invalid = |line: usize, msg: &str| {
+        Error::new(ErrorKind::InvalidData, format!("line {}: {}", line + 1, msg))
+    };
+    // (id, ty, index, point, visible)
+    let mut rows = vec![];
+    for (line, row) in s.lines().enumerate() {
+        let row = row.trim();
+        if row.is_empty() || row == HEADER {
+            continue;
+        }
+        let cols: Vec<_> = row.split(',').map(|c| c.trim()).collect();
+        if cols.len() != 7 {
+            return Err(invalid(line, "expected 7 columns"));
+        }
+        let id: u32 = cols[0].parse().map_err(|_| invalid(line, "invalid spline id"))?;
+        let ty = cols[1]
+            .parse::<u32>()
+            .map(SplineType::from)
+            .map_err(|_| invalid(line, "invalid spline type"))?;
+        let index: usize = cols[2].parse().map_err(|_| invalid(line, "invalid index"))?;
+        let mut point = [0.; 3];
+        for (p, c) in point.iter_mut().zip(&cols[3..6]) {
+            *p = c.parse().map_err(|_| invalid(line, "invalid coordinate"))?;
+        }
+        let visible = match cols[6] {
+            "" => None,
+            "1" | "true" => Some(true),
+            "0" | "false" => Some(false),
+            _ => return Err(invalid(line, "invalid visibility")),
+        };
+        rows.push((id, ty, index, point, visible));
+    }
+    rows.sort_by_key(|(id, _ty, index, _p, _v)| (*id, *index));
+    let mut splines: Vec<(u32, CsvSpline)> = vec![];
+    for (id, ty, _index, point, visible) in rows {
+        match splines.last_mut() {
+            Some((last, spline)) if *last == id => {
+                spline.points.push(point);
+                spline.visibility.push(visible.unwrap_or(true));
+            }
+            _ => splines.push((
+                id,
+                CsvSpline {
+                    ty,
+                    points: vec![point],
+                    visibility: vec![visible.unwrap_or(true)],
+                },
+            )),
+        }
+    }
+    splines
+        .into_iter()
+        .map(|(id, mut spline)| {
+            if spline.points.len() < 2 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("spline {} needs at least two points", id),
+                ));
+            }
+            // The last point doesn't start a segment
+            spline.visibility.pop();
+            Ok(spline)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsvEvent {
+    Export(PathBuf),
+    /// Replace every spline with the ones in the file
+    Import(PathBuf),
+}
+
+/// Path entered in the CSV window
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvFile {
+    pub path: String,
+}
+
+/// Id of the dialog confirming an import, which deletes every spline
+const IMPORT_DIALOG: &str = "csv_import";
+
+fn csv_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut csv_file: ResMut<CsvFile>,
+    mut csv_events: EventWriter<CsvEvent>,
+    beziers: Query<(), With<PolyBezier<CubicBezier>>>,
+    mut dialogs: ResMut<Dialogs>,
+    mut responses: EventReader<DialogEvent>,
+) {
+    for response in responses.iter().filter(|r| r.id == IMPORT_DIALOG) {
+        if response.response == DialogResponse::Ok {
+            csv_events.send(CsvEvent::Import(PathBuf::from(&csv_file.path)));
+        }
+    }
+    if !palette.show_csv {
+        return;
+    }
+    egui::Window::new("CSV")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("File");
+                ui.text_edit_singleline(&mut csv_file.path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Export").clicked() {
+                    csv_events.send(CsvEvent::Export(PathBuf::from(&csv_file.path)));
+                }
+                if ui.button("Import").clicked() {
+                    dialogs.confirm(
+                        IMPORT_DIALOG,
+                        "Import CSV",
+                        format!(
+                            "This deletes all {} splines, and replaces them with the ones in {}",
+                            beziers.iter().count(),
+                            csv_file.path
+                        ),
+                        "Import",
+                    );
+                }
+            });
+        });
+}
+
+fn csv_handler(
+    mut events: EventReader<CsvEvent>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut patch_events: EventWriter<PatchEvent>,
+) {
+    for event in events.iter() {
+        match event {
+            CsvEvent::Export(path) => {
+                let csv = write_csv(beziers.iter());
+                if let Err(e) = std::fs::write(path, csv) {
+                    println!("Error: {:?}", e);
+                }
+            }
+            CsvEvent::Import(path) => {
+                let splines = match std::fs::read_to_string(path).and_then(|s| read_csv(&s)) {
+                    Ok(splines) => splines,
+                    Err(e) => {
+                        println!("Error: {:?}", e);
+                        continue;
+                    }
+                };
+                // Applied as one patch, so it's held by the safety net if it's large, and can be
+                // undone in one go
+                let mut ops: Vec<_> = beziers.iter().map(PatchOp::delete_spline).collect();
+                ops.extend(splines.into_iter().map(|spline| PatchOp::AddSpline {
+                    ty: spline.ty.into(),
+                    points: spline.points,
+                    visibility: Some(spline.visibility),
+                }));
+                patch_events.send(PatchEvent::Ops(ops));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::gvas_to_vec;
+
+    fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+        a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-2)
+    }
+
+    #[test]
+    fn splines_round_trip() {
+        let splines = vec![
+            CsvSpline {
+                ty: SplineType::Track,
+                points: vec![[0., 0., 0.], [1000., 250., 10.], [2000., -500., 20.]],
+                visibility: vec![true, false],
+            },
+            CsvSpline {
+                ty: SplineType::WoodBridge,
+                points: vec![[-300., 40., 5.], [-1200.5, 80., 6.]],
+                visibility: vec![true],
+            },
+        ];
+        let beziers: Vec<_> = splines
+            .iter()
+            .map(|s| {
+                PolyBezier::new(
+                    s.points.iter().copied().map(gvas_to_vec).collect(),
+                    s.visibility.clone(),
+                    s.ty,
+                )
+            })
+            .collect();
+        let read = read_csv(&write_csv(beziers.iter())).unwrap();
+        assert_eq!(read.len(), splines.len());
+        for (read, spline) in read.iter().zip(splines.iter()) {
+            assert_eq!(read.ty, spline.ty);
+            assert_eq!(read.visibility, spline.visibility);
+            assert_eq!(read.points.len(), spline.points.len());
+            for (a, b) in read.points.iter().zip(spline.points.iter()) {
+                assert!(close(*a, *b), "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn rows_in_any_order() {
+        let csv = format!(
+            "{}\n0,0,1,10,0,0,\n1,0,0,0,5,0,1\n0,0,0,0,0,0,0\n1,0,1,0,6,0,\n",
+            HEADER
+        );
+        let read = read_csv(&csv).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].points, vec![[0., 0., 0.], [10., 0., 0.]]);
+        assert_eq!(read[0].visibility, vec![false]);
+        assert_eq!(read[1].points, vec![[0., 5., 0.], [0., 6., 0.]]);
+    }
+
+    #[test]
+    fn rejects_short_splines() {
+        assert!(read_csv(&format!("{}\n0,0,0,0,0,0,\n", HEADER)).is_err());
+        assert!(read_csv("0,0,0,0,0\n").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy_mod_picking::PickingCamera;
+use smooth_bevy_cameras::LookTransform;
+use std::collections::HashMap;
+
+use crate::control::{DefaultAssets, SplineState};
+use crate::coords::METERS_PER_UNIT;
+use crate::gvas::SplineType;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierSection, BezierSectionUpdate, DragState};
+
+/// Plugin for drawing splines as plain lines, either when they're far from the camera, see
+/// `Settings::declutter_distance`, or every spline, see `Settings::centerlines_only`
+pub struct DeclutterPlugin;
+
+impl Plugin for DeclutterPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CenterlineMaterials::default());
+        app.add_system(spawn_centerlines);
+        app.add_system(update_centerlines);
+        // Only ever hides things, after projects have set what's visible
+        app.add_system(declutter.after("isolate_project"));
+    }
+}
+
+/// Unlit copies of each spline type's color, since lines have no sides to light
+#[derive(Default)]
+struct CenterlineMaterials(HashMap<SplineType, Handle<StandardMaterial>>);
+
+impl CenterlineMaterials {
+    fn get(
+        &mut self,
+        ty: SplineType,
+        assets: &DefaultAssets,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        self.0
+            .entry(ty)
+            .or_insert_with(|| {
+                let color = materials
+                    .get(&assets.spline_material[ty][SplineState::Normal])
+                    .map_or(Color::WHITE, |m| m.base_color);
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    unlit: true,
+                    ..Default::default()
+                })
+            })
+            .clone()
+    }
+}
+
+/// The centerline drawn in place of a spline's sections, a child of the spline
+#[derive(Debug, Component)]
+pub struct Centerline {
+    line: Entity,
+    mesh: Handle<Mesh>,
+}
+
+/// Marks the entity a centerline is drawn with
+#[derive(Debug, Component)]
+struct CenterlineLine;
+
+/// Points along a spline, close enough together to draw it with straight lines
+pub fn centerline(bezier: &PolyBezier<CubicBezier>) -> Vec<Vec3> {
+    bezier.sample(8)
+}
+
+fn centerline_mesh(bezier: &PolyBezier<CubicBezier>) -> Mesh {
+    let points: Vec<[f32; 3]> = centerline(bezier).into_iter().map(|p| p.into()).collect();
+    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
+    // The standard material needs normals and uvs, even unlit
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 1., 0.]; points.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; points.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, points);
+    mesh
+}
+
+/// Gives every spline a centerline once either is turned on
+fn spawn_centerlines(
+    settings: Res<Settings>,
+    assets: Res<DefaultAssets>,
+    mut centerline_materials: ResMut<CenterlineMaterials>,
+    beziers: Query<(Entity, &PolyBezier<CubicBezier>), Without<Centerline>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    if settings.declutter_distance.is_none() && !settings.centerlines_only {
+        return;
+    }
+    for (entity, bezier) in beziers.iter() {
+        let mesh = meshes.add(centerline_mesh(bezier));
+        let line = commands
+            .spawn_bundle(PbrBundle {
+                mesh: mesh.clone(),
+                material: centerline_materials.get(bezier.ty(), &assets, &mut materials),
+                visibility: Visibility { is_visible: false },
+                ..Default::default()
+            })
+            .insert(CenterlineLine)
+            .id();
+        commands
+            .entity(entity)
+            .add_child(line)
+            .insert(Centerline { line, mesh });
+    }
+}
+
+/// Redraws centerlines of splines that changed, including their type
+fn update_centerlines(
+    mut section_update: EventReader<BezierSectionUpdate>,
+    beziers: Query<(&PolyBezier<CubicBezier>, &Centerline)>,
+    mut lines: Query<&mut Handle<StandardMaterial>, With<CenterlineLine>>,
+    assets: Res<DefaultAssets>,
+    mut centerline_materials: ResMut<CenterlineMaterials>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for update in section_update.iter() {
+        if let Ok((bezier, centerline)) = beziers.get(update.bezier) {
+            if let Some(mesh) = meshes.get_mut(&centerline.mesh) {
+                *mesh = centerline_mesh(bezier);
+            }
+            if let Ok(mut material) = lines.get_mut(centerline.line) {
+                *material = centerline_materials.get(bezier.ty(), &assets, &mut materials);
+            }
+        }
+    }
+}
+
+/// Hides the handles and sections of splines further from the camera than the declutter
+/// distance, and shows their centerline instead. Only draws centerlines and handles if
+/// `Settings::centerlines_only` is set
+fn declutter(
+    settings: Res<Settings>,
+    cameras: Query<&LookTransform, With<PickingCamera>>,
+    splines: Query<(&Children, Option<&Centerline>), With<PolyBezier<CubicBezier>>>,
+    mut parts: Query<
+        (&Transform, &mut Visibility, Option<&BezierSection>),
+        (
+            Or<(With<DragState>, With<BezierSection>)>,
+            Without<CenterlineLine>,
+        ),
+    >,
+    mut lines: Query<&mut Visibility, With<CenterlineLine>>,
+) {
+    let eye = cameras.iter().next().map(|look| look.eye);
+    let distance = settings.declutter_distance.map(|d| d / METERS_PER_UNIT);
+    for (children, centerline) in splines.iter() {
+        let far = match (eye, distance) {
+            (Some(eye), Some(distance)) => children
+                .iter()
+                .filter_map(|c| parts.get(*c).ok())
+                .all(|(t, _v, _s)| t.translation.distance(eye) > distance),
+            _ => false,
+        };
+        for child in children.iter() {
+            if let Ok((_t, mut vis, section)) = parts.get_mut(*child) {
+                let hide = far || (settings.centerlines_only && section.is_some());
+                if vis.is_visible && hide {
+                    vis.is_visible = false;
+                }
+            }
+        }
+        let line = far || settings.centerlines_only;
+        if let Some(mut vis) = centerline.and_then(|c| lines.get_mut(c.line).ok()) {
+            if vis.is_visible && !line {
+                vis.is_visible = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::asset::HandleId;
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickableButton;
+use enum_map::EnumMap;
+use std::collections::HashSet;
+
+use crate::background::Terrain;
+use crate::control::DefaultAssets;
+use crate::elevation::ElevationRamp;
+use crate::palette::Palette;
+use crate::update::{BezierSection, DragState};
+
+/// Plugin for the memory diagnostics window
+pub struct DiagnosticsPlugin;
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(diagnostics_window);
+    }
+}
+
+/// What spawned an entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, enum_map::Enum)]
+enum Origin {
+    Handles,
+    Sections,
+    Terrain,
+    Other,
+}
+
+impl Origin {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Handles => "Handles",
+            Self::Sections => "Spline sections",
+            Self::Terrain => "Terrain",
+            Self::Other => "Other",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    entities: usize,
+    meshes: HashSet<HandleId>,
+    materials: HashSet<HandleId>,
+}
+
+/// Approximate size of a mesh's vertex and index buffers, in bytes
+fn mesh_size(mesh: &Mesh) -> usize {
+    let indices = match mesh.indices() {
+        Some(Indices::U16(i)) => i.len() * 2,
+        Some(Indices::U32(i)) => i.len() * 4,
+        None => 0,
+    };
+    mesh.count_vertices() * mesh.get_vertex_size() as usize + indices
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1 << 20 {
+        format!("{:.1} MiB", bytes as f32 / (1 << 20) as f32)
+    } else {
+        format!("{:.1} KiB", bytes as f32 / (1 << 10) as f32)
+    }
+}
+
+/// Lists what's using memory, by where it came from. Assets nothing uses can be purged, except
+/// for the ones the editor keeps to spawn new objects with
+fn diagnostics_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    assets: Res<DefaultAssets>,
+    ramp: Res<ElevationRamp>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    entities: Query<(
+        Option<&Handle<Mesh>>,
+        Option<&Handle<StandardMaterial>>,
+        Option<&DragState>,
+        Option<&BezierSection>,
+        Option<&Terrain>,
+    )>,
+    buttons: Query<&PickableButton<StandardMaterial>>,
+) {
+    if !palette.show_diagnostics {
+        return;
+    }
+    let mut usage: EnumMap<Origin, Usage> = EnumMap::default();
+    for (mesh, material, handle, section, terrain) in entities.iter() {
+        let origin = match (handle, section, terrain) {
+            (Some(_), _, _) => Origin::Handles,
+            (_, Some(_), _) => Origin::Sections,
+            (_, _, Some(_)) => Origin::Terrain,
+            _ => Origin::Other,
+        };
+        let usage = &mut usage[origin];
+        usage.entities += 1;
+        usage.meshes.extend(mesh.map(|m| m.id));
+        usage.materials.extend(material.map(|m| m.id));
+    }
+    let mut used_meshes: HashSet<HandleId> = assets.meshes().iter().map(|m| m.id).collect();
+    let mut used_materials: HashSet<HandleId> = assets.materials().iter().map(|m| m.id).collect();
+    used_materials.extend(ramp.materials().iter().map(|m| m.id));
+    for usage in usage.values() {
+        used_meshes.extend(usage.meshes.iter().copied());
+        used_materials.extend(usage.materials.iter().copied());
+    }
+    for button in buttons.iter() {
+        for material in [
+            &button.initial,
+            &button.hovered,
+            &button.pressed,
+            &button.selected,
+        ] {
+            used_materials.extend(material.as_ref().map(|m| m.id));
+        }
+    }
+    let unused_meshes: Vec<HandleId> = meshes
+        .iter()
+        .map(|(id, _m)| id)
+        .filter(|id| !used_meshes.contains(id))
+        .collect();
+    let unused_materials: Vec<HandleId> = materials
+        .iter()
+        .map(|(id, _m)| id)
+        .filter(|id| !used_materials.contains(id))
+        .collect();
+    let size_of = |ids: &mut dyn Iterator<Item = &HandleId>| -> usize {
+        ids.filter_map(|id| meshes.get(*id)).map(mesh_size).sum()
+    };
+    let mut purge = false;
+    egui::Window::new("Memory Usage")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Grid::new("memory_usage")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label("Entities");
+                    ui.label("Meshes");
+                    ui.label("Mesh memory");
+                    ui.label("Materials");
+                    ui.end_row();
+                    for (origin, usage) in usage.iter() {
+                        ui.label(origin.name());
+                        ui.label(format!("{}", usage.entities));
+                        ui.label(format!("{}", usage.meshes.len()));
+                        ui.label(format_bytes(size_of(&mut usage.meshes.iter())));
+                        ui.label(format!("{}", usage.materials.len()));
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+            let total: usize = meshes.iter().map(|(_id, m)| mesh_size(m)).sum();
+            ui.label(format!(
+                "{} meshes ({}), {} materials in total",
+                meshes.len(),
+                format_bytes(total),
+                materials.len()
+            ));
+            ui.label(format!(
+                "{} meshes ({}) and {} materials are unused",
+                unused_meshes.len(),
+                format_bytes(size_of(&mut unused_meshes.iter())),
+                unused_materials.len()
+            ));
+            if ui.button("Purge Unused Assets").clicked() {
+                purge = true;
+            }
+        });
+    if purge {
+        for id in unused_meshes {
+            meshes.remove(id);
+        }
+        for id in unused_materials {
+            materials.remove(id);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext, EguiSystem};
+use std::collections::VecDeque;
+
+/// Plugin for modal dialogs, that block the rest of the editor until they're answered
+pub struct DialogPlugin;
+
+impl Plugin for DialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Dialogs::default());
+        app.add_event::<DialogEvent>();
+        app.add_system(dialog_window);
+        // Once egui has seen the input, so the dialog itself can still be used
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            block_input
+                .after(InputSystem)
+                .after(EguiSystem::ProcessInput),
+        );
+    }
+}
+
+/// What a dialog asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum DialogKind {
+    /// A yes or no question, with the label of the button that goes ahead
+    Confirm(String),
+    /// A line of text, with what's been typed so far
+    Input(String),
+    /// Progress of something that can be cancelled, from 0 to 1, and a label for the bar
+    Progress(f32, String),
+    /// Something to read, e.g. why a file couldn't be opened, with only an Ok button
+    Message,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dialog {
+    /// Who opened the dialog, so they can tell which answers are theirs
+    pub id: &'static str,
+    pub title: String,
+    pub message: String,
+    pub kind: DialogKind,
+}
+
+/// Open dialogs. Only the oldest is shown, the rest wait their turn
+#[derive(Debug, Default)]
+pub struct Dialogs {
+    queue: VecDeque<Dialog>,
+}
+
+impl Dialogs {
+    /// Opens a dialog, or replaces the one with the same id
+    fn open(&mut self, dialog: Dialog) {
+        match self.queue.iter_mut().find(|d| d.id == dialog.id) {
+            Some(open) => *open = dialog,
+            None => self.queue.push_back(dialog),
+        }
+    }
+
+    pub fn confirm(
+        &mut self,
+        id: &'static str,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        ok: impl Into<String>,
+    ) {
+        self.open(Dialog {
+            id,
+            title: title.into(),
+            message: message.into(),
+            kind: DialogKind::Confirm(ok.into()),
+        });
+    }
+
+    pub fn input(
+        &mut self,
+        id: &'static str,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        text: impl Into<String>,
+    ) {
+        self.open(Dialog {
+            id,
+            title: title.into(),
+            message: message.into(),
+            kind: DialogKind::Input(text.into()),
+        });
+    }
+
+    /// Opens a progress dialog, or updates it if it's already open. It stays open until closed
+    /// with `close`, even if it's cancelled
+    pub fn progress(
+        &mut self,
+        id: &'static str,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        fraction: f32,
+        text: impl Into<String>,
+    ) {
+        self.open(Dialog {
+            id,
+            title: title.into(),
+            message: message.into(),
+            kind: DialogKind::Progress(fraction, text.into()),
+        });
+    }
+
+    /// Tells the user something, most often that something failed
+    pub fn message(
+        &mut self,
+        id: &'static str,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.open(Dialog {
+            id,
+            title: title.into(),
+            message: message.into(),
+            kind: DialogKind::Message,
+        });
+    }
+
+    pub fn close(&mut self, id: &'static str) {
+        self.queue.retain(|d| d.id != id);
+    }
+}
+
+/// How a dialog was answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogResponse {
+    /// A confirm dialog was accepted
+    Ok,
+    /// An input dialog was accepted with this text
+    Text(String),
+    Cancel,
+}
+
+/// Sent once a dialog is answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogEvent {
+    pub id: &'static str,
+    pub response: DialogResponse,
+}
+
+/// Hides mouse buttons, keys, and mouse movement from the rest of the editor while a dialog is
+/// open, so the tools and the camera can't be used behind it. The backdrop only blocks egui
+fn block_input(
+    dialogs: Res<Dialogs>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut motion: ResMut<Events<MouseMotion>>,
+    mut wheel: ResMut<Events<MouseWheel>>,
+) {
+    if dialogs.queue.is_empty() {
+        return;
+    }
+    let buttons: Vec<MouseButton> = mouse
+        .get_pressed()
+        .chain(mouse.get_just_released())
+        .copied()
+        .collect();
+    for button in buttons {
+        mouse.reset(button);
+    }
+    let codes: Vec<KeyCode> = keys
+        .get_pressed()
+        .chain(keys.get_just_released())
+        .copied()
+        .collect();
+    for code in codes {
+        keys.reset(code);
+    }
+    motion.clear();
+    wheel.clear();
+}
+
+fn dialog_window(
+    mut egui_context: ResMut<EguiContext>,
+    mut dialogs: ResMut<Dialogs>,
+    mut events: EventWriter<DialogEvent>,
+) {
+    let dialog = match dialogs.queue.front_mut() {
+        Some(dialog) => dialog,
+        None => return,
+    };
+    let ctx = egui_context.ctx_mut();
+    // Covers every window, so nothing else can be clicked until the dialog is answered
+    egui::Area::new("dialog_backdrop")
+        .order(egui::Order::Foreground)
+        .fixed_pos(egui::Pos2::ZERO)
+        .show(ctx, |ui| {
+            let screen = ui.ctx().input().screen_rect();
+            ui.painter()
+                .rect_filled(screen, 0., egui::Color32::from_black_alpha(120));
+            ui.allocate_rect(screen, egui::Sense::click());
+        });
+    let (enter, escape) = {
+        let input = ctx.input();
+        (
+            input.key_pressed(egui::Key::Enter),
+            input.key_pressed(egui::Key::Escape),
+        )
+    };
+    let mut response = None;
+    // Above the backdrop, which is in the highest order windows can be in
+    egui::Area::new("dialog")
+        .order(egui::Order::Tooltip)
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(ctx, |ui| {
+            egui::Frame::window(ui.style()).show(ui, |ui| {
+                ui.heading(dialog.title.as_str());
+                ui.label(dialog.message.as_str());
+                match &mut dialog.kind {
+                    DialogKind::Confirm(ok) => {
+                        ui.horizontal(|ui| {
+                            if ui.button(ok.as_str()).clicked() || enter {
+                                response = Some(DialogResponse::Ok);
+                            }
+                            if ui.button("Cancel").clicked() || escape {
+                                response = Some(DialogResponse::Cancel);
+                            }
+                        });
+                    }
+                    DialogKind::Input(text) => {
+                        let edit = ui.text_edit_singleline(text);
+                        edit.request_focus();
+                        ui.horizontal(|ui| {
+                            let ok = ui.add_enabled(!text.is_empty(), egui::Button::new("Ok"));
+                            if ok.clicked() || (enter && !text.is_empty()) {
+                                response = Some(DialogResponse::Text(text.clone()));
+                            }
+                            if ui.button("Cancel").clicked() || escape {
+                                response = Some(DialogResponse::Cancel);
+                            }
+                        });
+                    }
+                    DialogKind::Message => {
+                        if ui.button("Ok").clicked() || enter || escape {
+                            response = Some(DialogResponse::Ok);
+                        }
+                    }
+                    DialogKind::Progress(fraction, text) => {
+                        ui.add(egui::ProgressBar::new(*fraction).text(text.as_str()));
+                        if ui.button("Cancel").clicked() || escape {
+                            response = Some(DialogResponse::Cancel);
+                        }
+                    }
+                }
+            });
+        });
+    if let Some(response) = response {
+        let id = dialog.id;
+        // Progress dialogs are closed by whoever opened them, once they've stopped
+        if !matches!(dialog.kind, DialogKind::Progress(..)) {
+            dialogs.queue.pop_front();
+        }
+        events.send(DialogEvent { id, response });
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+
+/// File the dock layout is kept in, in the working directory
+pub const LAYOUT_FILE: &str = "layout.json";
+
+/// Plugin for docking tool windows to the sides of the screen
+pub struct DockPlugin;
+
+impl Plugin for DockPlugin {
+    fn build(&self, app: &mut App) {
+        let layout = match File::open(LAYOUT_FILE) {
+            Ok(file) => match serde_json::from_reader(std::io::BufReader::new(file)) {
+                Ok(layout) => layout,
+                Err(e) => {
+                    println!("Error: {:?}", e);
+                    Layout::default()
+                }
+            },
+            // No layout file, every window floats
+            Err(_) => Layout::default(),
+        };
+        app.insert_resource(layout);
+    }
+}
+
+/// Where a tool window is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DockSide {
+    Floating,
+    Left,
+    Right,
+    Bottom,
+}
+
+impl Default for DockSide {
+    fn default() -> Self {
+        Self::Floating
+    }
+}
+
+impl DockSide {
+    const ALL: [Self; 4] = [Self::Floating, Self::Left, Self::Right, Self::Bottom];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Floating => "Floating",
+            Self::Left => "Left",
+            Self::Right => "Right",
+            Self::Bottom => "Bottom",
+        }
+    }
+}
+
+/// Where each dockable window is, by title, e.g.
+/// `{"docked": {"Palette": "Left", "Spline": "Right"}}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub docked: HashMap<String, DockSide>,
+}
+
+impl Layout {
+    pub fn side(&self, title: &str) -> DockSide {
+        self.docked.get(title).copied().unwrap_or_default()
+    }
+
+    /// Shows a tool window. Floating windows use `window`, docked ones are a panel on their side
+    /// of the screen. Either way, the top of the window has a choice of where it goes
+    pub fn show<R>(
+        &mut self,
+        ctx: &egui::Context,
+        title: &str,
+        window: egui::Window,
+        add_contents: impl FnOnce(&mut egui::Ui) -> R,
+    ) {
+        let side = self.side(title);
+        let mut moved = None;
+        let contents = |ui: &mut egui::Ui| {
+            ui.horizontal(|ui| {
+                if side != DockSide::Floating {
+                    ui.strong(title);
+                }
+                egui::ComboBox::from_id_source((title, "dock"))
+                    .selected_text(side.name())
+                    .width(80.)
+                    .show_ui(ui, |ui| {
+                        for option in DockSide::ALL {
+                            if ui.selectable_label(side == option, option.name()).clicked() {
+                                moved = Some(option);
+                            }
+                        }
+                    });
+            });
+            add_contents(ui);
+        };
+        match side {
+            DockSide::Floating => {
+                window.show(ctx, contents);
+            }
+            DockSide::Left => {
+                egui::SidePanel::left(egui::Id::new(title))
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        egui::ScrollArea::vertical().show(ui, contents);
+                    });
+            }
+            DockSide::Right => {
+                egui::SidePanel::right(egui::Id::new(title))
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        egui::ScrollArea::vertical().show(ui, contents);
+                    });
+            }
+            DockSide::Bottom => {
+                egui::TopBottomPanel::bottom(egui::Id::new(title))
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        egui::ScrollArea::vertical().show(ui, contents);
+                    });
+            }
+        }
+        if let Some(side) = moved.filter(|s| *s != side) {
+            self.docked.insert(title.to_string(), side);
+            if let Err(e) = self.write() {
+                println!("Error: {:?}", e);
+            }
+        }
+    }
+
+    fn write(&self) -> Result<(), serde_json::Error> {
+        match File::create(LAYOUT_FILE) {
+            Ok(file) => serde_json::to_writer_pretty(file, self),
+            Err(e) => Err(serde_json::Error::io(e)),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickableButton;
+
+use crate::control::{DefaultAssets, SplineState};
+use crate::palette::Palette;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{set_section_material, BezierSection};
+
+/// Plugin for coloring splines by elevation
+pub struct ElevationPlugin;
+
+impl Plugin for ElevationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ElevationRamp {
+            low: [0.1, 0.3, 0.9],
+            high: [0.9, 0.2, 0.1],
+            materials: vec![],
+            enabled: false,
+        });
+        app.add_startup_system(init_ramp);
+        app.add_system(elevation_window);
+        app.add_system(color_by_elevation);
+    }
+}
+
+/// Number of colors in the gradient
+const RAMP_STEPS: usize = 16;
+
+/// Gradient used to color splines by elevation
+#[derive(Debug)]
+pub struct ElevationRamp {
+    /// Color of the lowest point, as rgb
+    pub low: [f32; 3],
+    /// Color of the highest point, as rgb
+    pub high: [f32; 3],
+    /// One material per step, from low to high
+    materials: Vec<Handle<StandardMaterial>>,
+    /// Whether splines are currently colored by elevation
+    enabled: bool,
+}
+
+impl ElevationRamp {
+    pub fn materials(&self) -> &[Handle<StandardMaterial>] {
+        &self.materials
+    }
+
+    fn color(&self, i: usize) -> Color {
+        let t = i as f32 / (RAMP_STEPS - 1) as f32;
+        let [r, g, b] = [0, 1, 2].map(|c| self.low[c] + (self.high[c] - self.low[c]) * t);
+        Color::rgb(r, g, b)
+    }
+}
+
+fn init_ramp(mut ramp: ResMut<ElevationRamp>, mut materials: ResMut<Assets<StandardMaterial>>) {
+    ramp.materials = (0..RAMP_STEPS)
+        .map(|i| materials.add(ramp.color(i).into()))
+        .collect();
+}
+
+fn elevation_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut ramp: ResMut<ElevationRamp>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !palette.elevation_colors {
+        return;
+    }
+    let ramp = ramp.as_mut();
+    let mut changed = false;
+    egui::Window::new("Elevation")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Low");
+                changed |= ui.color_edit_button_rgb(&mut ramp.low).changed();
+                ui.label("High");
+                changed |= ui.color_edit_button_rgb(&mut ramp.high).changed();
+            });
+        });
+    if changed {
+        for (i, handle) in ramp.materials.iter().enumerate() {
+            if let Some(mat) = materials.get_mut(handle) {
+                mat.base_color = ramp.color(i);
+            }
+        }
+    }
+}
+
+/// Swaps the material of every section to the ramp color for its height, or back to the
+/// normal spline material when the ramp is turned off
+fn color_by_elevation(
+    palette: Res<Palette>,
+    assets: Res<DefaultAssets>,
+    mut ramp: ResMut<ElevationRamp>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut sections: Query<(
+        &mut Handle<StandardMaterial>,
+        &mut PickableButton<StandardMaterial>,
+        &Transform,
+        &Parent,
+        &BezierSection,
+    )>,
+) {
+    if !palette.elevation_colors && !ramp.enabled {
+        return;
+    }
+    ramp.enabled = palette.elevation_colors;
+    let (min, max) = beziers
+        .iter()
+        .flat_map(|b| b.get_control_points())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+            (min.min(p.y), max.max(p.y))
+        });
+    for (mut mat, mut pick, trans, parent, section) in sections.iter_mut() {
+        let bez = if let Ok(bez) = beziers.get(parent.0) {
+            bez
+        } else {
+            continue;
+        };
+        let want = if ramp.enabled {
+            let t = if max > min {
+                (trans.translation.y - min) / (max - min)
+            } else {
+                0.
+            };
+            let i = (t * (RAMP_STEPS - 1) as f32).round() as usize;
+            ramp.materials[i.min(RAMP_STEPS - 1)].clone()
+        } else if bez.segment_visible(&section.0) {
+            assets.spline_material[bez.ty()][SplineState::Normal].clone()
+        } else {
+            assets.spline_material[bez.ty()][SplineState::Hidden].clone()
+        };
+        set_section_material(&mut mat, &mut pick, want);
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickableButton;
+
+use crate::control::{DefaultAssets, SplineState};
+use crate::inspector::SelectedSpline;
+use crate::palette::Palette;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{set_section_material, BezierSection};
+
+/// Plugin for fading out every spline except the selected one
+pub struct FadePlugin;
+
+impl Plugin for FadePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SplineFade {
+            opacity: 0.2,
+            applied: None,
+            enabled: false,
+        });
+        app.add_system(fade_window);
+        app.add_system(fade_splines);
+    }
+}
+
+/// Opacity of hidden sections, relative to visible ones
+const HIDDEN_OPACITY: f32 = 0.3;
+
+#[derive(Debug)]
+pub struct SplineFade {
+    /// Opacity of splines that aren't selected
+    pub opacity: f32,
+    /// Opacity the faded materials were last set to
+    applied: Option<f32>,
+    /// Whether splines are currently faded
+    enabled: bool,
+}
+
+fn fade_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    assets: Res<DefaultAssets>,
+    mut fade: ResMut<SplineFade>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !palette.fade_unselected {
+        return;
+    }
+    egui::Window::new("Fade")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.add(egui::Slider::new(&mut fade.opacity, 0.0..=1.0).text("Opacity"));
+        });
+    if fade.applied != Some(fade.opacity) {
+        for states in assets.spline_material.values() {
+            for (state, alpha) in [
+                (SplineState::Faded, fade.opacity),
+                (SplineState::FadedHidden, fade.opacity * HIDDEN_OPACITY),
+            ] {
+                if let Some(mat) = materials.get_mut(&states[state]) {
+                    mat.base_color.set_a(alpha);
+                }
+            }
+        }
+        fade.applied = Some(fade.opacity);
+    }
+}
+
+/// Swaps the material of every section that isn't part of the selected spline to the faded
+/// material, or back to the normal spline material when fading is turned off. Coloring by
+/// elevation takes priority
+fn fade_splines(
+    palette: Res<Palette>,
+    assets: Res<DefaultAssets>,
+    selected: Res<SelectedSpline>,
+    mut fade: ResMut<SplineFade>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut sections: Query<(
+        &mut Handle<StandardMaterial>,
+        &mut PickableButton<StandardMaterial>,
+        &Parent,
+        &BezierSection,
+    )>,
+) {
+    if palette.elevation_colors || (!palette.fade_unselected && !fade.enabled) {
+        return;
+    }
+    fade.enabled = palette.fade_unselected;
+    for (mut mat, mut pick, parent, section) in sections.iter_mut() {
+        let bez = if let Ok(bez) = beziers.get(parent.0) {
+            bez
+        } else {
+            continue;
+        };
+        let faded = fade.enabled && selected.0 != Some(parent.0);
+        let state = match (faded, bez.segment_visible(&section.0)) {
+            (false, true) => SplineState::Normal,
+            (false, false) => SplineState::Hidden,
+            (true, true) => SplineState::Faded,
+            (true, false) => SplineState::FadedHidden,
+        };
+        let want = assets.spline_material[bez.ty()][state].clone();
+        set_section_material(&mut mat, &mut pick, want);
+    }
+}
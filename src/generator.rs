@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::f32::consts::TAU;
+
+use crate::coords::METERS_PER_UNIT;
+use crate::gvas::SplineType;
+use crate::inspector::SelectedSpline;
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::BezierModificaiton;
+
+/// Control points generated curves get per full turn, close enough to a circle that the handles
+/// the editor gives them don't wobble
+const POINTS_PER_TURN: usize = 12;
+
+/// Plugin for generating splines from a few numbers, e.g. a helix or a circular curve
+pub struct GeneratorPlugin;
+
+impl Plugin for GeneratorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(generator_window);
+    }
+}
+
+/// Where a generated curve starts, and which way it heads, in degrees from the x axis
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Start {
+    point: Vec3,
+    heading: f32,
+}
+
+impl Start {
+    fn dir(&self) -> Vec3 {
+        let heading = self.heading.to_radians();
+        Vec3::new(heading.cos(), 0., heading.sin())
+    }
+}
+
+/// A spiral of track climbing at a constant grade. Distances are in meters
+struct Helix {
+    radius: f32,
+    /// Percent
+    grade: f32,
+    turns: f32,
+    left: bool,
+    groundwork: bool,
+}
+
+impl Default for Helix {
+    fn default() -> Self {
+        Self {
+            radius: 60.,
+            grade: 2.5,
+            turns: 2.,
+            left: true,
+            groundwork: true,
+        }
+    }
+}
+
+impl Helix {
+    /// How far the helix climbs in one turn, in meters
+    fn rise_per_turn(&self) -> f32 {
+        TAU * self.radius * self.grade / 100.
+    }
+
+    /// Control points of the helix, in editor units
+    fn points(&self, start: Start) -> Vec<Vec3> {
+        let radius = self.radius / METERS_PER_UNIT;
+        arc(start, radius, self.turns * TAU, self.left, self.grade)
+    }
+}
+
+/// A circular curve of a single spline. The radius is in meters, and the angle turned in degrees
+struct Curve {
+    ty: SplineType,
+    radius: f32,
+    angle: f32,
+    left: bool,
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Self {
+            ty: SplineType::Track,
+            radius: 100.,
+            angle: 90.,
+            left: true,
+        }
+    }
+}
+
+/// Control points of a circular arc of `radius` editor units, turning `angle` radians from
+/// `start` and climbing at `grade` percent
+fn arc(start: Start, radius: f32, angle: f32, left: bool, grade: f32) -> Vec<Vec3> {
+    let dir = start.dir();
+    // Towards the center is to the left of the heading for a left hand arc
+    let side = if left {
+        Vec3::Y.cross(dir)
+    } else {
+        dir.cross(Vec3::Y)
+    };
+    let center = start.point + side * radius;
+    let n = ((angle / TAU * POINTS_PER_TURN as f32).ceil() as usize).max(2);
+    (0..=n)
+        .map(|i| {
+            let a = angle * i as f32 / n as f32;
+            let rise = radius * a * grade / 100.;
+            center - side * radius * a.cos() + dir * radius * a.sin() + Vec3::Y * rise
+        })
+        .collect()
+}
+
+/// Edits a distance in meters, shown in the chosen units
+fn meters(ui: &mut egui::Ui, settings: &Settings, m: &mut f32) {
+    let mut v = settings.units.from_meters(*m);
+    if ui
+        .add(egui::DragValue::new(&mut v).suffix(settings.units.suffix()))
+        .changed()
+    {
+        *m = settings.units.to_meters(v);
+    }
+}
+
+fn generator_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    selected: Res<SelectedSpline>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut start: Local<Start>,
+    (mut helix, mut curve): (Local<Helix>, Local<Curve>),
+    mut modification: EventWriter<BezierModificaiton>,
+) {
+    if !palette.show_generators {
+        return;
+    }
+    let selected_end = selected.0.and_then(|e| beziers.get(e).ok()).map(|bez| {
+        (
+            bez.get_control_point(bez.len() - 1),
+            bez.tangent(bez.len() - 1),
+        )
+    });
+    let units = settings.units;
+    egui::Window::new("Generators")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("Start");
+            ui.horizontal(|ui| {
+                // Positions are edited in meters
+                let mut point = start.point * METERS_PER_UNIT;
+                for axis in [&mut point.x, &mut point.y, &mut point.z] {
+                    meters(ui, &settings, axis);
+                }
+                start.point = point / METERS_PER_UNIT;
+            });
+            ui.horizontal(|ui| {
+                ui.label("Heading");
+                ui.add(egui::DragValue::new(&mut start.heading).suffix("°"));
+            });
+            let button = egui::Button::new("Start at End of Selected Spline");
+            if ui.add_enabled(selected_end.is_some(), button).clicked() {
+                let (point, tangent) = selected_end.unwrap();
+                start.point = point;
+                start.heading = tangent.z.atan2(tangent.x).to_degrees();
+            }
+            egui::CollapsingHeader::new("Helix")
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::Grid::new("helix").show(ui, |ui| {
+                        ui.label("Radius");
+                        meters(ui, &settings, &mut helix.radius);
+                        ui.end_row();
+                        ui.label("Grade");
+                        ui.add(
+                            egui::DragValue::new(&mut helix.grade)
+                                .speed(0.1)
+                                .clamp_range(0. ..=10.)
+                                .suffix("%"),
+                        );
+                        ui.end_row();
+                        ui.label("Turns");
+                        ui.add(
+                            egui::DragValue::new(&mut helix.turns)
+                                .speed(0.25)
+                                .clamp_range(0.25..=20.),
+                        );
+                        ui.end_row();
+                    });
+                    helix.radius = helix.radius.max(1.);
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut helix.left, true, "Left");
+                        ui.radio_value(&mut helix.left, false, "Right");
+                    });
+                    ui.checkbox(&mut helix.groundwork, "Groundwork under it");
+                    ui.label(format!(
+                        "Climbs {} per turn",
+                        units.length(helix.rise_per_turn())
+                    ));
+                    if ui.button("Place Helix").clicked() {
+                        let points = helix.points(*start);
+                        let vis = vec![true; points.len() - 1];
+                        if helix.groundwork {
+                            modification.send(BezierModificaiton::PlaceCurve(
+                                SplineType::GroundWork,
+                                points.clone(),
+                                vis.clone(),
+                            ));
+                        }
+                        modification.send(BezierModificaiton::PlaceCurve(
+                            SplineType::Track,
+                            points,
+                            vis,
+                        ));
+                    }
+                });
+            egui::CollapsingHeader::new("Curve")
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::ComboBox::from_id_source("curve_ty")
+                        .selected_text(settings.spline_types.name(curve.ty))
+                        .show_ui(ui, |ui| {
+                            for info in settings.spline_types.types.iter() {
+                                if info.placeable {
+                                    ui.selectable_value(&mut curve.ty, info.ty, info.name.as_str());
+                                }
+                            }
+                        });
+                    egui::Grid::new("curve").show(ui, |ui| {
+                        ui.label("Radius");
+                        meters(ui, &settings, &mut curve.radius);
+                        ui.end_row();
+                        ui.label("Angle");
+                        ui.add(
+                            egui::DragValue::new(&mut curve.angle)
+                                .clamp_range(1. ..=360.)
+                                .suffix("°"),
+                        );
+                        ui.end_row();
+                    });
+                    curve.radius = curve.radius.max(1.);
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut curve.left, true, "Left");
+                        ui.radio_value(&mut curve.left, false, "Right");
+                    });
+                    if ui.button("Place Curve").clicked() {
+                        let radius = curve.radius / METERS_PER_UNIT;
+                        let angle = curve.angle.to_radians();
+                        let points = arc(*start, radius, angle, curve.left, 0.);
+                        // The next curve carries on from this one
+                        start.point = points[points.len() - 1];
+                        start.heading += if curve.left {
+                            -curve.angle
+                        } else {
+                            curve.angle
+                        };
+                        let vis = vec![true; points.len() - 1];
+                        modification.send(BezierModificaiton::PlaceCurve(curve.ty, points, vis));
+                    }
+                });
+        });
+}
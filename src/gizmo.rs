@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{Hover, PickableButton, PickingCamera};
+
+use crate::control::DefaultAssets;
+use crate::dialog::Dialogs;
+use crate::gvas::SwitchData;
+use crate::palette::{MouseAction, Palette};
+use crate::patch::{EditEvent, SplineShape, SwitchPlacement};
+use crate::safety::{Moves, SafetyNet};
+use crate::selection::Selected;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierSectionUpdate, DragState};
+
+/// Length of the gizmo's arrows, as a fraction of its distance from the camera, so it stays the
+/// same size on screen
+const GIZMO_SIZE: f32 = 0.15;
+
+/// Plugin for the axis gizmo, which moves the selection along one axis or in one plane, see
+/// `Palette::gizmo`
+pub struct GizmoPlugin;
+
+impl Plugin for GizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(place_gizmo);
+        app.add_system(drag_gizmo);
+    }
+}
+
+/// Part of the gizmo. Arrows move the selection along their axis, and squares in their plane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, enum_map::Enum)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+    XY,
+    XZ,
+    YZ,
+}
+
+impl GizmoAxis {
+    const ALL: [Self; 6] = [Self::X, Self::Y, Self::Z, Self::XY, Self::XZ, Self::YZ];
+
+    /// Direction of an arrow
+    fn axis(self) -> Option<Vec3> {
+        match self {
+            Self::X => Some(Vec3::X),
+            Self::Y => Some(Vec3::Y),
+            Self::Z => Some(Vec3::Z),
+            _ => None,
+        }
+    }
+
+    /// Normal of a square's plane
+    fn normal(self) -> Option<Vec3> {
+        match self {
+            Self::XY => Some(Vec3::Z),
+            Self::XZ => Some(Vec3::Y),
+            Self::YZ => Some(Vec3::X),
+            _ => None,
+        }
+    }
+
+    /// Where the part is, as a unit cube scaled to size, on a gizmo at `center` that's `size`
+    /// long. Arrows start a little way out, so the handle in the middle can still be clicked
+    fn transform(self, center: Vec3, size: f32) -> Transform {
+        let (offset, scale) = match (self.axis(), self.normal()) {
+            (Some(axis), _) => (
+                axis * size * 0.6,
+                Vec3::splat(size * 0.05) + axis * size * 0.75,
+            ),
+            (None, Some(normal)) => (
+                (Vec3::ONE - normal) * size * 0.3,
+                Vec3::splat(size * 0.2) - normal * size * 0.19,
+            ),
+            (None, None) => (Vec3::ZERO, Vec3::ONE),
+        };
+        Transform {
+            translation: center + offset,
+            scale,
+            ..Default::default()
+        }
+    }
+
+    /// How far a drag that moved the mouse `delta` across the plane `normal` moves the selection
+    fn constrain(self, delta: Vec3, normal: Vec3) -> Vec3 {
+        match self.axis() {
+            Some(axis) => axis * delta.dot(axis),
+            None => delta - normal * delta.dot(normal),
+        }
+    }
+}
+
+/// Middle of the selected handles and switches
+fn selection_center<'a>(points: impl Iterator<Item = &'a Transform>) -> Option<Vec3> {
+    let (sum, count) = points.fold((Vec3::ZERO, 0), |(sum, count), t| {
+        (sum + t.translation, count + 1)
+    });
+    Some(sum / count as f32).filter(|_| count > 0)
+}
+
+/// Shows the gizmo in the middle of the selection, while it's turned on in drag mode
+fn place_gizmo(
+    palette: Res<Palette>,
+    assets: Res<DefaultAssets>,
+    cameras: Query<&GlobalTransform, With<PickingCamera>>,
+    selected: Query<&Transform, (With<Selected>, Without<GizmoAxis>)>,
+    mut parts: Query<(&GizmoAxis, &mut Transform, Entity)>,
+    mut commands: Commands,
+) {
+    let center = if palette.gizmo && palette.action == MouseAction::Drag {
+        selection_center(selected.iter())
+    } else {
+        None
+    };
+    let center = match center {
+        Some(center) => center,
+        None => {
+            for (_a, _t, entity) in parts.iter() {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+    };
+    let size = cameras.iter().next().map_or(1., |camera| {
+        camera.translation.distance(center) * GIZMO_SIZE
+    });
+    if parts.is_empty() {
+        for axis in GizmoAxis::ALL {
+            let material = assets.gizmo_material[axis].clone();
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: assets.cube_mesh.clone(),
+                    material: material.clone(),
+                    transform: axis.transform(center, size),
+                    ..Default::default()
+                })
+                .insert_bundle(bevy_mod_picking::PickableBundle {
+                    pickable_button: PickableButton {
+                        initial: Some(material.clone()),
+                        hovered: Some(assets.handle_hover_material.clone()),
+                        pressed: Some(assets.handle_hover_material.clone()),
+                        selected: Some(material),
+                    },
+                    ..Default::default()
+                })
+                .insert(axis);
+        }
+    } else {
+        for (axis, mut transform, _e) in parts.iter_mut() {
+            *transform = axis.transform(center, size);
+        }
+    }
+}
+
+/// A drag of the gizmo
+struct GizmoDrag {
+    axis: GizmoAxis,
+    /// Plane the mouse is followed across
+    point: Vec3,
+    normal: Vec3,
+    /// Where the mouse grabbed the plane
+    grab: Vec3,
+    /// Selected handles and switches, and where they started
+    starts: Vec<(Entity, Vec3)>,
+    /// Their splines and switches before the drag
+    moves: Moves,
+}
+
+/// Dragging a part of the gizmo moves the selected handles and switches along its axis or plane
+fn drag_gizmo(
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    pick_cam: Query<&PickingCamera>,
+    parts: Query<(&GizmoAxis, &Hover)>,
+    mut handles: Query<
+        (&DragState, &Parent, &mut Transform, Entity),
+        (With<Selected>, Without<GizmoAxis>),
+    >,
+    mut switches: Query<
+        (&mut Transform, &SwitchData, Entity),
+        (With<Selected>, Without<DragState>, Without<GizmoAxis>),
+    >,
+    mut beziers: Query<&mut PolyBezier<CubicBezier>>,
+    mut drag: Local<Option<GizmoDrag>>,
+    mut section_update: EventWriter<BezierSectionUpdate>,
+    mut edits: EventWriter<EditEvent>,
+    (mut safety, mut dialogs): (ResMut<SafetyNet>, ResMut<Dialogs>),
+) {
+    if !palette.gizmo || palette.action != MouseAction::Drag {
+        *drag = None;
+        return;
+    }
+    let camera = match pick_cam.iter().last() {
+        Some(camera) => camera,
+        None => return,
+    };
+    let hit = |point: Vec3, normal: Vec3| {
+        camera
+            .intersect_primitive(bevy_mod_picking::Primitive3d::Plane { point, normal })
+            .map(|int| int.position())
+    };
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        let axis = match parts.iter().find(|(_a, hover)| hover.hovered()) {
+            Some((axis, _h)) => *axis,
+            None => return,
+        };
+        let ray = match camera.ray() {
+            Some(ray) => ray.direction(),
+            None => return,
+        };
+        let normal = match (axis.axis(), axis.normal()) {
+            // The plane along the arrow that faces the camera the most
+            (Some(a), _) => (ray - a * ray.dot(a)).normalize_or_zero(),
+            (None, Some(normal)) => normal,
+            (None, None) => return,
+        };
+        let starts: Vec<(Entity, Vec3)> = handles
+            .iter()
+            .map(|(_s, _p, t, e)| (e, t.translation))
+            .chain(switches.iter().map(|(t, _s, e)| (e, t.translation)))
+            .collect();
+        let mut moves = Moves::default();
+        for (_s, parent, _t, _e) in handles.iter() {
+            if let Ok(bez) = beziers.get(parent.0) {
+                moves.spline(parent.0, bez);
+            }
+        }
+        for (trans, switch, entity) in switches.iter() {
+            moves.switch(entity, trans, switch);
+        }
+        let point = selection_center(
+            handles
+                .iter()
+                .map(|(_s, _p, t, _e)| t)
+                .chain(switches.iter().map(|(t, _s, _e)| t)),
+        );
+        if let Some((point, grab)) = point.and_then(|p| Some((p, hit(p, normal)?))) {
+            *drag = Some(GizmoDrag {
+                axis,
+                point,
+                normal,
+                grab,
+                starts,
+                moves,
+            });
+        }
+        return;
+    }
+    let current = match drag.as_ref() {
+        Some(current) => current,
+        None => return,
+    };
+    if mouse_button_input.pressed(MouseButton::Left) {
+        let delta = match hit(current.point, current.normal) {
+            Some(pos) => current.axis.constrain(pos - current.grab, current.normal),
+            None => return,
+        };
+        let mut splines = vec![];
+        for (entity, start) in current.starts.iter() {
+            if let Ok((state, parent, mut trans, _e)) = handles.get_mut(*entity) {
+                if let Ok(mut bez) = beziers.get_mut(parent.0) {
+                    trans.translation = *start + delta;
+                    bez.update(state.pt, *start + delta - settings.curve_offset(bez.ty()));
+                    if !splines.contains(&parent.0) {
+                        splines.push(parent.0);
+                        section_update.send(BezierSectionUpdate { bezier: parent.0 });
+                    }
+                }
+            } else if let Ok((mut trans, _s, _e)) = switches.get_mut(*entity) {
+                trans.translation = *start + delta;
+            }
+        }
+        return;
+    }
+    let moves = drag.take().unwrap().moves;
+    let (moved, size) = moves.edits(
+        |e| beziers.get(e).ok().map(SplineShape::of),
+        |e| {
+            switches
+                .get(e)
+                .ok()
+                .map(|(t, s, _e)| SwitchPlacement::of(t, s))
+        },
+    );
+    safety.keep(
+        moved,
+        size,
+        "move",
+        settings.edit_limit,
+        &mut dialogs,
+        &mut edits,
+    );
+}
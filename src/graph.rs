@@ -0,0 +1,334 @@
+use bevy::prelude::*;
+use std::fmt::Write;
+
+use crate::coords::METERS_PER_UNIT;
+use crate::gvas::SplineType;
+use crate::junction::JUNCTION_EPSILON;
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Maximum distance from a switch to a spline endpoint for the endpoint to be part of the switch
+const SWITCH_EPSILON: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A single endpoint, with nothing connected
+    End,
+    /// The endpoints of several splines
+    Junction,
+    Switch,
+}
+
+impl NodeKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::End => "end",
+            Self::Junction => "junction",
+            Self::Switch => "switch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Node {
+    /// In editor units. Exports are in meters
+    pub location: Vec3,
+    pub kind: NodeKind,
+}
+
+/// A run of track between two nodes, made of one or more splines joined end to end
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    /// Length in meters
+    pub length: f32,
+    /// Steepest percent grade of any segment
+    pub max_grade: f32,
+}
+
+/// Connectivity graph of the track network
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl NetworkGraph {
+    /// Builds the graph from the track splines in `beziers`, and the locations of every switch.
+    /// Splines joined end to end at a junction with nothing else there are one edge
+    pub fn build<'a>(
+        beziers: impl Iterator<Item = &'a PolyBezier<CubicBezier>>,
+        switches: impl Iterator<Item = Vec3>,
+    ) -> Self {
+        let mut graph = Self::default();
+        for loc in switches {
+            graph.nodes.push(Node {
+                location: loc,
+                kind: NodeKind::Switch,
+            });
+        }
+        for bez in beziers.filter(|b| b.ty() == SplineType::Track) {
+            let from = graph.node_at(bez.get_control_point(0));
+            let to = graph.node_at(bez.get_control_point(bez.len() - 1));
+            let max_grade = (0..bez.len() - 1)
+                .map(|i| bez.segment_grade(i).abs())
+                .fold(0., f32::max);
+            graph.edges.push(Edge {
+                from,
+                to,
+                length: bez.length() * METERS_PER_UNIT,
+                max_grade,
+            });
+        }
+        graph.join_runs();
+        graph
+    }
+
+    /// Joins the two edges at every junction where exactly two meet, and removes the junction
+    fn join_runs(&mut self) {
+        let mut removed = vec![false; self.nodes.len()];
+        for node in 0..self.nodes.len() {
+            if self.nodes[node].kind != NodeKind::Junction {
+                continue;
+            }
+            let ends = |e: &Edge| (e.from == node) as usize + (e.to == node) as usize;
+            let edges: Vec<usize> = (0..self.edges.len())
+                .filter(|e| ends(&self.edges[*e]) > 0)
+                .collect();
+            let degree: usize = edges.iter().map(|e| ends(&self.edges[*e])).sum();
+            // A loop back to the same junction has nothing to join with
+            let (a, b) = match edges[..] {
+                [a, b] if degree == 2 => (a, b),
+                _ => continue,
+            };
+            let other = |e: &Edge| if e.from == node { e.to } else { e.from };
+            let joined = Edge {
+                from: other(&self.edges[a]),
+                to: other(&self.edges[b]),
+                length: self.edges[a].length + self.edges[b].length,
+                max_grade: self.edges[a].max_grade.max(self.edges[b].max_grade),
+            };
+            // b > a, so removing b first leaves a where it was
+            self.edges.swap_remove(b);
+            self.edges[a] = joined;
+            removed[node] = true;
+        }
+        let mut index = vec![0; self.nodes.len()];
+        let mut kept = vec![];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !removed[i] {
+                index[i] = kept.len();
+                kept.push(*node);
+            }
+        }
+        self.nodes = kept;
+        for edge in self.edges.iter_mut() {
+            edge.from = index[edge.from];
+            edge.to = index[edge.to];
+        }
+    }
+
+    /// Finds or creates the node for an endpoint at `loc`
+    fn node_at(&mut self, loc: Vec3) -> usize {
+        let existing = self.nodes.iter().position(|n| match n.kind {
+            NodeKind::Switch => n.location.distance(loc) < SWITCH_EPSILON,
+            _ => n.location.distance(loc) < JUNCTION_EPSILON,
+        });
+        if let Some(i) = existing {
+            if self.nodes[i].kind == NodeKind::End {
+                self.nodes[i].kind = NodeKind::Junction;
+            }
+            i
+        } else {
+            self.nodes.push(Node {
+                location: loc,
+                kind: NodeKind::End,
+            });
+            self.nodes.len() - 1
+        }
+    }
+
+    /// Graphviz DOT representation of the graph, with positions in meters
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "graph network {{").unwrap();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let loc = node.location * METERS_PER_UNIT;
+            writeln!(
+                out,
+                "    n{} [kind=\"{}\", pos=\"{},{}!\", z=\"{}\"];",
+                i,
+                node.kind.name(),
+                loc.x,
+                loc.z,
+                loc.y
+            )
+            .unwrap();
+        }
+        for edge in self.edges.iter() {
+            writeln!(
+                out,
+                "    n{} -- n{} [length=\"{:.1}\", grade=\"{:.2}\"];",
+                edge.from, edge.to, edge.length, edge.max_grade
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    /// GraphML representation of the graph, with positions in meters
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#).unwrap();
+        for (id, on, ty) in [
+            ("kind", "node", "string"),
+            ("x", "node", "float"),
+            ("y", "node", "float"),
+            ("z", "node", "float"),
+            ("length", "edge", "float"),
+            ("grade", "edge", "float"),
+        ] {
+            writeln!(
+                out,
+                r#"  <key id="{0}" for="{1}" attr.name="{0}" attr.type="{2}"/>"#,
+                id, on, ty
+            )
+            .unwrap();
+        }
+        writeln!(out, r#"  <graph id="network" edgedefault="undirected">"#).unwrap();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let loc = node.location * METERS_PER_UNIT;
+            writeln!(
+                out,
+                r#"    <node id="n{}"><data key="kind">{}</data><data key="x">{}</data><data key="y">{}</data><data key="z">{}</data></node>"#,
+                i,
+                node.kind.name(),
+                loc.x,
+                loc.z,
+                loc.y
+            )
+            .unwrap();
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                out,
+                r#"    <edge id="e{}" source="n{}" target="n{}"><data key="length">{:.1}</data><data key="grade">{:.2}</data></edge>"#,
+                i, edge.from, edge.to, edge.length, edge.max_grade
+            )
+            .unwrap();
+        }
+        writeln!(out, "  </graph>").unwrap();
+        writeln!(out, "</graphml>").unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(points: &[[f32; 3]]) -> PolyBezier<CubicBezier> {
+        PolyBezier::new(
+            points.iter().map(|p| Vec3::from(*p)).collect(),
+            vec![true; points.len() - 1],
+            SplineType::Track,
+        )
+    }
+
+    #[test]
+    fn runs_are_joined_through_junctions() {
+        // a - b - c, with a branch from c to d, and a switch at e
+        let splines = [
+            track(&[[0., 0., 0.], [10., 0., 0.]]),
+            track(&[[10., 0., 0.], [20., 0., 0.]]),
+            track(&[[20., 0., 0.], [30., 0., 0.]]),
+            track(&[[20., 0., 0.], [20., 0., 10.]]),
+        ];
+        let graph = NetworkGraph::build(splines.iter(), [Vec3::new(-50., 0., 0.)].into_iter());
+        let kinds: Vec<_> = graph.nodes.iter().map(|n| n.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                NodeKind::Switch,
+                NodeKind::End,
+                NodeKind::Junction,
+                NodeKind::End,
+                NodeKind::End
+            ]
+        );
+        assert_eq!(graph.edges.len(), 3);
+        let joined = graph.edges[0];
+        assert_eq!((joined.from, joined.to), (1, 2));
+        assert!((joined.length - 20. * METERS_PER_UNIT).abs() < 0.01);
+    }
+
+    #[test]
+    fn loops_keep_one_junction() {
+        let splines = [
+            track(&[[0., 0., 0.], [10., 0., 0.], [10., 0., 10.]]),
+            track(&[[10., 0., 10.], [0., 0., 10.], [0., 0., 0.]]),
+        ];
+        let graph = NetworkGraph::build(splines.iter(), std::iter::empty());
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!((graph.edges[0].from, graph.edges[0].to), (0, 0));
+    }
+
+    fn example() -> NetworkGraph {
+        NetworkGraph {
+            nodes: vec![
+                Node {
+                    location: Vec3::new(1., 2., 3.),
+                    kind: NodeKind::End,
+                },
+                Node {
+                    location: Vec3::new(-1., 0., 0.5),
+                    kind: NodeKind::Switch,
+                },
+            ],
+            edges: vec![Edge {
+                from: 0,
+                to: 1,
+                length: 123.4,
+                max_grade: 1.5,
+            }],
+        }
+    }
+
+    #[test]
+    fn dot_has_every_node_and_edge_in_meters() {
+        let dot = example().to_dot();
+        assert!(dot.starts_with("graph network {\n"));
+        assert!(
+            dot.contains(r#"n0 [kind="end", pos="10,30!", z="20"];"#),
+            "{}",
+            dot
+        );
+        assert!(
+            dot.contains(r#"n1 [kind="switch", pos="-10,5!", z="0"];"#),
+            "{}",
+            dot
+        );
+        assert!(
+            dot.contains(r#"n0 -- n1 [length="123.4", grade="1.50"];"#),
+            "{}",
+            dot
+        );
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn graphml_has_every_node_and_edge_in_meters() {
+        let graphml = example().to_graphml();
+        assert!(graphml.contains(
+            r#"<node id="n0"><data key="kind">end</data><data key="x">10</data><data key="y">30</data><data key="z">20</data></node>"#
+        ), "{}", graphml);
+        assert!(graphml.contains(
+            r#"<edge id="e0" source="n0" target="n1"><data key="length">123.4</data><data key="grade">1.50</data></edge>"#
+        ), "{}", graphml);
+        assert_eq!(graphml.matches("<node ").count(), 2);
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+}
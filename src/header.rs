@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::dialog::{DialogEvent, DialogResponse, Dialogs};
+use crate::gvas::{GVASError, RROSave};
+use crate::palette::{save_dir, save_to, FileEvent, Palette, PendingOverwrite};
+use crate::settings::Settings;
+
+/// Plugin for editing the strings at the start of a save, like its date
+pub struct HeaderPlugin;
+
+impl Plugin for HeaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(header_window);
+    }
+}
+
+type Getter = fn(&RROSave) -> Result<&str, GVASError>;
+type Setter = fn(&mut RROSave, String) -> Result<(), GVASError>;
+
+/// Id of the dialog asking for the slot to save to
+const SAVE_AS_DIALOG: &str = "save_as";
+
+/// (label, get, set) of each header string
+const FIELDS: [(&str, Getter, Setter); 3] = [
+    ("Date", RROSave::save_date, RROSave::set_save_date),
+    ("Unique ID", RROSave::unique_id, RROSave::set_unique_id),
+    ("World ID", RROSave::world_id, RROSave::set_world_id),
+];
+
+fn header_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut gvas: ResMut<RROSave>,
+    mut file_events: EventWriter<FileEvent>,
+    mut dialogs: ResMut<Dialogs>,
+    mut responses: EventReader<DialogEvent>,
+    mut overwrite: ResMut<PendingOverwrite>,
+) {
+    for response in responses.iter().filter(|r| r.id == SAVE_AS_DIALOG) {
+        if let DialogResponse::Text(name) = &response.response {
+            let path = save_dir(&settings).join(format!("{}.sav", name));
+            save_to(
+                FileEvent::Save(path),
+                &mut overwrite,
+                &mut dialogs,
+                &mut file_events,
+            );
+        }
+    }
+    if !palette.show_header {
+        return;
+    }
+    egui::Window::new("Save Info")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Grid::new("save_header").show(ui, |ui| {
+                for (label, get, set) in FIELDS {
+                    ui.label(label);
+                    match get(&gvas).map(str::to_string) {
+                        Ok(mut value) => {
+                            if ui.text_edit_singleline(&mut value).changed() {
+                                if let Err(e) = set(&mut gvas, value) {
+                                    println!("Error: {:?}", e);
+                                }
+                            }
+                        }
+                        // Older saves don't have every field
+                        Err(_) => {
+                            ui.label("Not in this save");
+                        }
+                    }
+                    ui.end_row();
+                }
+                ui.label("Game Version");
+                ui.label(
+                    gvas.version()
+                        .save_game_version
+                        .as_deref()
+                        .unwrap_or("Unknown"),
+                );
+                ui.end_row();
+            });
+            ui.separator();
+            if ui.button("Save As").clicked() {
+                dialogs.input(
+                    SAVE_AS_DIALOG,
+                    "Save As",
+                    "Name of the slot to write in the save directory, without .sav",
+                    "",
+                );
+            }
+        });
+}
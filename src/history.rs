@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::palette::FileEvent;
+use crate::patch::{EditEvent, PatchEvent, PatchOp};
+
+/// Most edits kept to undo. Older ones are forgotten
+const MAX_HISTORY: usize = 200;
+
+/// Plugin for undoing recent edits with Ctrl+Z
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(History::default());
+        app.add_system(record_history);
+        app.add_system(undo);
+    }
+}
+
+/// Edits that can be undone, oldest first. Only edits sent as an `EditEvent` are kept, and the
+/// edits sent in one frame are undone together. Each tab keeps its own, see `Tab::history`
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    ops: VecDeque<Vec<PatchOp>>,
+    /// Splines about to be placed by restoring a recovery, which aren't edits
+    skip: usize,
+}
+
+impl History {
+    pub fn ops(&self) -> impl Iterator<Item = &Vec<PatchOp>> {
+        self.ops.iter()
+    }
+
+    /// Replaces the history with one saved by `recovery.rs`, ignoring the `placed` splines it's
+    /// about to place
+    pub fn restore(&mut self, ops: Vec<Vec<PatchOp>>, placed: usize) {
+        self.ops = ops.into();
+        self.skip = placed;
+    }
+}
+
+/// The operation that undoes `op`, if there is one. Deletions from patch files that leave out
+/// what was deleted can't be undone
+pub fn inverse(op: &PatchOp) -> Option<PatchOp> {
+    match op {
+        PatchOp::MovePoint { from, to } => Some(PatchOp::MovePoint {
+            from: *to,
+            to: *from,
+        }),
+        PatchOp::ReshapeSpline { from, to } => Some(PatchOp::ReshapeSpline {
+            from: to.clone(),
+            to: from.clone(),
+        }),
+        PatchOp::AddSpline {
+            ty,
+            points,
+            visibility,
+        } => Some(PatchOp::DeleteSpline {
+            points: points.clone(),
+            ty: Some(*ty),
+            visibility: visibility.clone(),
+        }),
+        PatchOp::DeleteSpline {
+            points,
+            ty,
+            visibility,
+        } => Some(PatchOp::AddSpline {
+            ty: (*ty)?,
+            points: points.clone(),
+            visibility: visibility.clone(),
+        }),
+        PatchOp::AddSwitch {
+            ty,
+            location,
+            rotation,
+        } => Some(PatchOp::DeleteSwitch {
+            location: *location,
+            ty: Some(*ty),
+            rotation: Some(*rotation),
+        }),
+        PatchOp::MoveSwitch { from, to } => Some(PatchOp::MoveSwitch {
+            from: *to,
+            to: *from,
+        }),
+        PatchOp::DeleteSwitch {
+            location,
+            ty,
+            rotation,
+        } => Some(PatchOp::AddSwitch {
+            ty: (*ty)?,
+            location: *location,
+            rotation: (*rotation)?,
+        }),
+        PatchOp::AddIndustry {
+            ty,
+            location,
+            rotation,
+        } => Some(PatchOp::DeleteIndustry {
+            location: *location,
+            ty: Some(*ty),
+            rotation: Some(*rotation),
+        }),
+        PatchOp::DeleteIndustry {
+            location,
+            ty,
+            rotation,
+        } => Some(PatchOp::AddIndustry {
+            ty: (*ty)?,
+            location: *location,
+            rotation: (*rotation)?,
+        }),
+    }
+}
+
+fn record_history(
+    mut edits: EventReader<EditEvent>,
+    mut file_events: EventReader<FileEvent>,
+    mut history: ResMut<History>,
+) {
+    // Edits to one save can't be undone in another
+    if file_events
+        .iter()
+        .any(|e| matches!(e, FileEvent::Load(_) | FileEvent::Import(_)))
+    {
+        history.ops.clear();
+    }
+    let mut group: Vec<PatchOp> = vec![];
+    for EditEvent(op) in edits.iter() {
+        if history.skip > 0 && matches!(op, PatchOp::AddSpline { .. }) {
+            history.skip -= 1;
+            continue;
+        }
+        // Connected moves send the same move for every endpoint
+        if !group.contains(op) {
+            group.push(op.clone());
+        }
+    }
+    if !group.is_empty() {
+        history.ops.push_back(group);
+        if history.ops.len() > MAX_HISTORY {
+            history.ops.pop_front();
+        }
+    }
+}
+
+fn undo(
+    keys: Res<Input<KeyCode>>,
+    mut history: ResMut<History>,
+    mut patch_events: EventWriter<PatchEvent>,
+) {
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    if !ctrl || !keys.just_pressed(KeyCode::Z) {
+        return;
+    }
+    let group = match history.ops.pop_back() {
+        Some(group) => group,
+        None => {
+            warn!("Nothing to undo");
+            return;
+        }
+    };
+    let mut undo = vec![];
+    for op in group.iter().rev() {
+        match inverse(op) {
+            Some(inverse) => undo.push(inverse),
+            None => warn!("Undo: {:?} can't be undone", op),
+        }
+    }
+    patch_events.send(PatchEvent::Undo(undo));
+}
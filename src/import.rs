@@ -0,0 +1,362 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+use serde::{Deserialize, Serialize};
+use smooth_bevy_cameras::LookTransform;
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::control::{spawn_industry, DefaultAssets};
+use crate::coords::{gvas_to_vec, quat_to_rotator, rotator_to_quat, vec_to_gvas, METERS_PER_UNIT};
+use crate::gvas::{GVASError, RROSave, SwitchData, SwitchType};
+use crate::palette::{save_dir, Palette};
+use crate::patch::{EditEvent, PatchOp};
+use crate::settings::Settings;
+use crate::spline::PolyBezier;
+use crate::template::{Template, TemplateCurve, Templates};
+use crate::update::{spawn_bezier, BezierModificaiton, BezierSectionUpdate};
+
+/// Plugin for importing parts of another save into the open one
+pub struct ImportPlugin;
+
+impl Plugin for ImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ImportOptions {
+            slot: 1,
+            splines: true,
+            switches: true,
+            industries: false,
+            offset: Vec3::ZERO,
+            switch_file: "switches.json".to_string(),
+            switch_radius: None,
+        });
+        app.add_event::<ImportEvent>();
+        app.add_system(import_window);
+        app.add_system(import_save);
+    }
+}
+
+/// What to import, set in the import window
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportOptions {
+    /// Save slot to import from
+    pub slot: usize,
+    pub splines: bool,
+    pub switches: bool,
+    pub industries: bool,
+    /// Moves everything imported, in meters
+    pub offset: Vec3,
+    /// File switches are exported to and imported from on their own, see `SwitchFile`
+    pub switch_file: String,
+    /// Only export switches this far from the view's target, in meters
+    pub switch_radius: Option<f32>,
+}
+
+/// A switch in a switch file, in save coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwitchEntry {
+    /// Switch type as stored in the save
+    pub ty: u32,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+}
+
+/// Just the switches of a save, so they can be moved to another one without its splines
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SwitchFile {
+    pub switches: Vec<SwitchEntry>,
+}
+
+/// Radius the export starts with once it's limited, in meters
+const SWITCH_RADIUS: f32 = 200.;
+
+fn export_switches(path: &str, switches: Vec<SwitchEntry>) -> Result<(), GVASError> {
+    serde_json::to_writer_pretty(File::create(path)?, &SwitchFile { switches })?;
+    Ok(())
+}
+
+fn import_switches(
+    path: &str,
+    offset: Vec3,
+    modification: &mut EventWriter<BezierModificaiton>,
+) -> Result<(), GVASError> {
+    let file: SwitchFile = serde_json::from_reader(std::io::BufReader::new(File::open(path)?))?;
+    for switch in file.switches {
+        match SwitchType::try_from(switch.ty) {
+            Ok(ty) => modification.send(BezierModificaiton::PlaceSw(
+                gvas_to_vec(switch.location) + offset / METERS_PER_UNIT,
+                ty,
+                rotator_to_quat(switch.rotation),
+            )),
+            Err(_) => warn!("Switch file: invalid switch type {}", switch.ty),
+        }
+    }
+    Ok(())
+}
+
+/// (path, place) Import from the save at the given path, using the current `ImportOptions`. If
+/// place is set, its splines and switches are stamped like a template instead, see
+/// `import_template`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportEvent(pub PathBuf, pub bool);
+
+fn import_window(
+    mut egui_context: ResMut<EguiContext>,
+    mut palette: ResMut<Palette>,
+    settings: Res<Settings>,
+    mut options: ResMut<ImportOptions>,
+    mut import_events: EventWriter<ImportEvent>,
+    switches: Query<(&Transform, &SwitchData)>,
+    cameras: Query<&LookTransform, With<PickingCamera>>,
+    mut modification: EventWriter<BezierModificaiton>,
+) {
+    if !palette.show_import {
+        return;
+    }
+    let options = options.as_mut();
+    let units = settings.units;
+    let (mut export, mut import) = (false, false);
+    egui::Window::new("Import from Save")
+        .resizable(false)
+        .open(&mut palette.show_import)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::ComboBox::from_label("Save")
+                .selected_text(format!("Slot {}", options.slot))
+                .show_ui(ui, |ui| {
+                    for slot in 1..=10 {
+                        ui.selectable_value(&mut options.slot, slot, format!("Slot {}", slot));
+                    }
+                });
+            ui.checkbox(&mut options.splines, "Splines");
+            ui.checkbox(&mut options.switches, "Switches");
+            ui.checkbox(&mut options.industries, "Industries");
+            ui.horizontal(|ui| {
+                ui.label("Offset");
+                for c in [&mut options.offset.x, &mut options.offset.y, &mut options.offset.z] {
+                    let mut v = units.from_meters(*c);
+                    if ui
+                        .add(egui::DragValue::new(&mut v).suffix(units.suffix()))
+                        .changed()
+                    {
+                        *c = units.to_meters(v);
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                let path = save_dir(&settings).join(format!("slot{}.sav", options.slot));
+                if ui.button("Import").clicked() {
+                    import_events.send(ImportEvent(path.clone(), false));
+                }
+                if ui.button("Import and Place").clicked() {
+                    import_events.send(ImportEvent(path, true));
+                }
+            });
+            ui.label("Import and Place follows the mouse until clicked, without industries");
+            ui.separator();
+            ui.label("Switches on their own, e.g. to move a yard to another save");
+            ui.horizontal(|ui| {
+                ui.label("File");
+                ui.text_edit_singleline(&mut options.switch_file);
+            });
+            ui.horizontal(|ui| {
+                let mut limit = options.switch_radius.is_some();
+                ui.checkbox(&mut limit, "Only within");
+                let mut v = units.from_meters(options.switch_radius.unwrap_or(SWITCH_RADIUS));
+                ui.add_enabled(
+                    limit,
+                    egui::DragValue::new(&mut v)
+                        .clamp_range(1. ..=10000.)
+                        .suffix(units.suffix()),
+                );
+                options.switch_radius = Some(units.to_meters(v)).filter(|_| limit);
+                ui.label("of the view");
+            });
+            ui.horizontal(|ui| {
+                export = ui.button("Export Switches").clicked();
+                // Uses the offset above
+                import = ui.button("Import Switches").clicked();
+            });
+        });
+    if export {
+        let target = cameras.iter().next().map_or(Vec3::ZERO, |look| look.target);
+        let radius = options.switch_radius.map(|r| r / METERS_PER_UNIT);
+        let selected = switches
+            .iter()
+            .filter(|(t, _s)| radius.map_or(true, |r| t.translation.distance(target) <= r))
+            .map(|(t, s)| SwitchEntry {
+                ty: s.ty as u32,
+                location: vec_to_gvas(t.translation),
+                rotation: quat_to_rotator(t.rotation),
+            })
+            .collect();
+        if let Err(e) = export_switches(&options.switch_file, selected) {
+            println!("Error: {:?}", e);
+        }
+    } else if import {
+        if let Err(e) = import_switches(&options.switch_file, options.offset, &mut modification) {
+            println!("Error: {:?}", e);
+        }
+    }
+}
+
+fn import_save(
+    mut events: EventReader<ImportEvent>,
+    options: Res<ImportOptions>,
+    assets: Res<DefaultAssets>,
+    settings: Res<Settings>,
+    mut templates: ResMut<Templates>,
+    mut commands: Commands,
+    mut modification: EventWriter<BezierModificaiton>,
+    mut section_update: EventWriter<BezierSectionUpdate>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    for ImportEvent(path, place) in events.iter() {
+        let gvas = File::open(path)
+            .map_err(GVASError::from)
+            .and_then(|mut file| RROSave::read(&mut file));
+        let imported = gvas.and_then(|gvas| {
+            if *place {
+                let name = path
+                    .file_stem()
+                    .map_or(String::new(), |s| s.to_string_lossy().into_owned());
+                let template = import_template(&gvas, &options, format!("Import from {}", name))?;
+                templates.stamp_new(template);
+                Ok(())
+            } else {
+                let added = import(
+                    &gvas,
+                    &options,
+                    &assets,
+                    &settings,
+                    &mut commands,
+                    &mut modification,
+                    &mut section_update,
+                )?;
+                for op in added {
+                    edits.send(EditEvent(op));
+                }
+                Ok(())
+            }
+        });
+        if let Err(e) = imported {
+            println!("Error: {:?}", e);
+        }
+    }
+}
+
+/// Makes a template of the selected splines and switches of `gvas`, anchored at their center,
+/// so they can be stamped wherever the user wants them
+fn import_template(
+    gvas: &RROSave,
+    options: &ImportOptions,
+    name: String,
+) -> Result<Template, GVASError> {
+    let mut curves = vec![];
+    if options.splines {
+        for curve in gvas.curves()? {
+            curves.push(TemplateCurve {
+                ty: curve.ty,
+                points: curve
+                    .control_points
+                    .iter()
+                    .map(|p| gvas_to_vec(*p))
+                    .collect(),
+                visibility: curve.visibility.iter().copied().collect(),
+            });
+        }
+    }
+    let mut switches = vec![];
+    if options.switches {
+        for switch in gvas.switches()? {
+            switches.push((
+                switch.ty,
+                gvas_to_vec(switch.location),
+                rotator_to_quat(switch.rotation),
+            ));
+        }
+    }
+    let points: Vec<Vec3> = curves
+        .iter()
+        .flat_map(|c| c.points.iter().copied())
+        .chain(switches.iter().map(|(_ty, p, _r)| *p))
+        .collect();
+    let origin = points.iter().fold(Vec3::ZERO, |sum, p| sum + *p) / points.len().max(1) as f32;
+    for curve in curves.iter_mut() {
+        for p in curve.points.iter_mut() {
+            *p -= origin;
+        }
+    }
+    for (_ty, p, _r) in switches.iter_mut() {
+        *p -= origin;
+    }
+    Ok(Template {
+        name,
+        origin,
+        curves,
+        switches,
+        buffer_stops: vec![],
+    })
+}
+
+/// Adds the selected parts of `gvas` to the world. Returns the edits that add them, so the
+/// import can be undone in one go
+fn import(
+    gvas: &RROSave,
+    options: &ImportOptions,
+    assets: &DefaultAssets,
+    settings: &Settings,
+    commands: &mut Commands,
+    modification: &mut EventWriter<BezierModificaiton>,
+    section_update: &mut EventWriter<BezierSectionUpdate>,
+) -> Result<Vec<PatchOp>, GVASError> {
+    let offset = options.offset / METERS_PER_UNIT;
+    let mut edits = vec![];
+    if options.splines {
+        for curve in gvas.curves()? {
+            let points = curve
+                .control_points
+                .iter()
+                .map(|p| gvas_to_vec(*p) + offset)
+                .collect();
+            let bezier = PolyBezier::new(points, curve.visibility.to_vec(), curve.ty);
+            edits.push(PatchOp::add_spline(&bezier));
+            // Spawned here rather than with `PlaceCurve`, so every edit is sent in the same frame
+            if let Some(bezier) = spawn_bezier(commands, assets, settings, bezier) {
+                section_update.send(BezierSectionUpdate { bezier });
+            }
+        }
+    }
+    if options.switches {
+        for switch in gvas.switches()? {
+            let location = gvas_to_vec(switch.location) + offset;
+            modification.send(BezierModificaiton::PlaceSw(
+                location,
+                switch.ty,
+                rotator_to_quat(switch.rotation),
+            ));
+            edits.push(PatchOp::AddSwitch {
+                ty: switch.ty as u32,
+                location: vec_to_gvas(location),
+                rotation: switch.rotation,
+            });
+        }
+    }
+    if options.industries {
+        let industries: Vec<_> = match gvas.industries() {
+            Ok(industries) => industries.collect(),
+            // Saves without industries don't have the arrays
+            Err(GVASError::Missing(_)) => vec![],
+            Err(e) => return Err(e),
+        };
+        for mut industry in industries {
+            industry.location = vec_to_gvas(gvas_to_vec(industry.location) + offset);
+            edits.push(PatchOp::AddIndustry {
+                ty: industry.ty,
+                location: industry.location,
+                rotation: industry.rotation,
+            });
+            spawn_industry(commands, assets, industry);
+        }
+    }
+    Ok(edits)
+}
@@ -0,0 +1,406 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::Hover;
+
+use crate::analysis::AnalysisSettings;
+use crate::background::{ground_height, Terrain};
+use crate::coords::METERS_PER_UNIT;
+use crate::dialog::Dialogs;
+use crate::dock::Layout;
+use crate::gvas::SplineType;
+use crate::junction::JUNCTION_EPSILON;
+use crate::settings::Settings;
+use crate::palette::Palette;
+use crate::patch::{EditEvent, PatchOp, SplineShape};
+use crate::preview::SplinePreview;
+use crate::reshape::ReshapeEvent;
+use crate::safety::{EditSize, SafetyNet};
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::support::groundwork_under;
+use crate::update::{BezierModificaiton, BezierSection, DragState};
+
+/// Plugin for the spline property panel
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SelectedSpline::default());
+        app.add_system(select_spline);
+        app.add_system(inspector_window);
+    }
+}
+
+/// The last spline clicked on
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SelectedSpline(pub Option<Entity>);
+
+/// Name given to a spline in the inspector. Only kept in workspaces, see `workspace.rs`
+#[derive(Debug, Clone, PartialEq, Component)]
+pub struct SplineName(pub String);
+
+/// Added to splines that can't be dragged
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct Locked;
+
+fn select_spline(
+    mouse_button_input: Res<Input<MouseButton>>,
+    handles: Query<(&Hover, &Parent), With<DragState>>,
+    sections: Query<(&Hover, &Parent), With<BezierSection>>,
+    mut selected: ResMut<SelectedSpline>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    // Clicks on a window don't reach the splines behind it
+    if egui_context.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        if let Some((_h, parent)) = handles
+            .iter()
+            .chain(sections.iter())
+            .find(|(hover, _p)| hover.hovered())
+        {
+            selected.0 = Some(parent.0);
+        }
+    }
+}
+
+/// Distance in meters the inspector extends spline ends by
+struct Extend(f32);
+
+impl Default for Extend {
+    fn default() -> Self {
+        Self(10.)
+    }
+}
+
+/// How many times the inspector averages a spline's path when smoothing it, see
+/// `PolyBezier::smoothed`
+struct Smooth(usize);
+
+impl Default for Smooth {
+    fn default() -> Self {
+        Self(3)
+    }
+}
+
+/// Furthest in meters a point can be from the simplified spline, see `PolyBezier::simplified`
+struct Simplify(f32);
+
+impl Default for Simplify {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+/// Spline the inspector builds under track, and how far below the track its data is, in meters.
+/// The game lays track at the height of whatever it's built on, so there's no drop by default
+struct Bed {
+    ty: SplineType,
+    drop: f32,
+}
+
+impl Default for Bed {
+    fn default() -> Self {
+        Self {
+            ty: SplineType::TrackBed,
+            drop: 0.,
+        }
+    }
+}
+
+/// Groundwork the inspector fills the gap under track with, and how deep each layer is, in
+/// meters. One layer of the game's groundwork reaches about 2m below its spline
+struct Fill {
+    ty: SplineType,
+    depth: f32,
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Self {
+            ty: SplineType::GroundWork,
+            depth: 2.,
+        }
+    }
+}
+
+fn inspector_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    selected: Res<SelectedSpline>,
+    mut beziers: Query<&mut PolyBezier<CubicBezier>>,
+    names: Query<&SplineName>,
+    locked: Query<&Locked>,
+    settings: Res<Settings>,
+    mut extend: Local<Extend>,
+    (mut bed, mut fill): (Local<Bed>, Local<Fill>),
+    (mut smooth, mut simplify): (Local<Smooth>, Local<Simplify>),
+    mut commands: Commands,
+    mut modification: EventWriter<BezierModificaiton>,
+    mut reshape: EventWriter<ReshapeEvent>,
+    (thresholds, terrain): (Res<AnalysisSettings>, Query<&Transform, With<Terrain>>),
+    (mut layout, mut preview): (ResMut<Layout>, ResMut<SplinePreview>),
+    (mut safety, mut dialogs, mut edits): (
+        ResMut<SafetyNet>,
+        ResMut<Dialogs>,
+        EventWriter<EditEvent>,
+    ),
+) {
+    // Only rendered while it's shown below
+    preview.hide();
+    if !palette.show_inspector {
+        return;
+    }
+    // Whether the selected spline already has the chosen bed under it, so it isn't built twice
+    let drop = Vec3::new(0., bed.drop / METERS_PER_UNIT, 0.);
+    let has_bed = selected.0.and_then(|e| beziers.get(e).ok()).is_some_and(|track| {
+        beziers.iter().any(|other| {
+            other.ty() == bed.ty
+                && other.len() == track.len()
+                && other
+                    .get_control_points()
+                    .zip(track.get_control_points())
+                    .all(|(b, t)| b.distance(t - drop) < JUNCTION_EPSILON)
+        })
+    });
+    let window = egui::Window::new("Spline").resizable(false);
+    layout.show(egui_context.ctx_mut(), "Spline", window, |ui| {
+        let selected = selected
+            .0
+            .and_then(|e| beziers.get_mut(e).ok().map(|b| (e, b)));
+        if let Some((entity, mut bez)) = selected {
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                let mut name = names.get(entity).map_or(String::new(), |n| n.0.clone());
+                if ui.text_edit_singleline(&mut name).changed() {
+                    if name.is_empty() {
+                        commands.entity(entity).remove::<SplineName>();
+                    } else {
+                        commands.entity(entity).insert(SplineName(name));
+                    }
+                }
+            });
+            let mut lock = locked.get(entity).is_ok();
+            if ui.checkbox(&mut lock, "Locked").changed() {
+                if lock {
+                    commands.entity(entity).insert(Locked);
+                } else {
+                    commands.entity(entity).remove::<Locked>();
+                }
+            }
+            ui.label(format!("Type: {}", settings.spline_types.name(bez.ty())));
+            ui.label(format!("Points: {}", bez.len()));
+            ui.label(format!(
+                "Length: {}",
+                settings.units.length(bez.length() * METERS_PER_UNIT)
+            ));
+            let grades: Vec<f32> = (0..bez.len() - 1).map(|i| bez.segment_grade(i)).collect();
+            let steepest = grades.iter().fold(0f32, |max, g| max.max(g.abs()));
+            egui::CollapsingHeader::new(format!("Steepest Grade: {:.2}%", steepest))
+                .id_source("grades")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_source("grades_scroll")
+                        .max_height(150.)
+                        .show(ui, |ui| {
+                            for (i, grade) in grades.iter().enumerate() {
+                                ui.label(format!("Segment {}: {:.2}%", i, grade));
+                            }
+                        });
+                });
+            let radii: Vec<f32> = (0..bez.len() - 1)
+                .map(|i| bez.segment_min_radius(i) * METERS_PER_UNIT)
+                .collect();
+            let tightest = radii.iter().fold(f32::INFINITY, |min, r| min.min(*r));
+            let header = format!("Tightest Radius: {}", settings.units.radius(tightest));
+            egui::CollapsingHeader::new(header)
+                .id_source("radii")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_source("radii_scroll")
+                        .max_height(150.)
+                        .show(ui, |ui| {
+                            for (i, radius) in radii.iter().enumerate() {
+                                let text =
+                                    format!("Segment {}: {}", i, settings.units.radius(*radius));
+                                // Too tight for the network statistics' minimum radius
+                                if *radius < thresholds.min_radius {
+                                    ui.colored_label(egui::Color32::RED, text);
+                                } else {
+                                    ui.label(text);
+                                }
+                            }
+                        });
+                });
+            ui.horizontal(|ui| {
+                ui.label("Extend by");
+                let mut v = settings.units.from_meters(extend.0);
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut v)
+                            .clamp_range(0.1..=1000.)
+                            .suffix(settings.units.suffix()),
+                    )
+                    .changed()
+                {
+                    extend.0 = settings.units.to_meters(v);
+                }
+            });
+            ui.horizontal(|ui| {
+                let start = ui.add_enabled(!lock, egui::Button::new("Extend Start"));
+                let end = ui.add_enabled(!lock, egui::Button::new("Extend End"));
+                let dist = extend.0 / METERS_PER_UNIT;
+                // The tangent includes the grade, so the new point continues it
+                if start.clicked() {
+                    let loc = bez.get_control_point(0) - bez.tangent(0) * dist;
+                    bez.insert(0, loc);
+                    modification.send(BezierModificaiton::Extrude(entity, 0));
+                } else if end.clicked() {
+                    let last = bez.len() - 1;
+                    let loc = bez.get_control_point(last) + bez.tangent(last) * dist;
+                    bez.insert(last + 1, loc);
+                    modification.send(BezierModificaiton::Extrude(entity, last + 1));
+                }
+            });
+            if ui.add_enabled(!lock, egui::Button::new("Reverse")).clicked() {
+                bez.reverse();
+                modification.send(BezierModificaiton::Reversed(entity));
+            }
+            ui.horizontal(|ui| {
+                // Refits the points to a smoothed path, which irons out kinks from dragging
+                if ui.add_enabled(!lock, egui::Button::new("Smooth")).clicked() {
+                    reshape.send(ReshapeEvent {
+                        spline: entity,
+                        points: bez.smoothed(smooth.0),
+                        action: "smooth",
+                    });
+                }
+                ui.add(
+                    egui::DragValue::new(&mut smooth.0)
+                        .clamp_range(1..=20)
+                        .suffix(" passes"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Simplify within");
+                let mut v = settings.units.from_meters(simplify.0);
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut v)
+                            .clamp_range(0.01..=50.)
+                            .suffix(settings.units.suffix()),
+                    )
+                    .changed()
+                {
+                    simplify.0 = settings.units.to_meters(v);
+                }
+            });
+            let keep = bez.simplified(simplify.0 / METERS_PER_UNIT);
+            let button = egui::Button::new(format!(
+                "Simplify to {} of {} points",
+                keep.len(),
+                bez.len()
+            ));
+            if ui.add_enabled(!lock && keep.len() < bez.len(), button).clicked() {
+                let before = SplineShape::of(&bez);
+                let visibility = bez.get_visibility();
+                let vis = keep.windows(2).map(|w| visibility[w[0]]).collect();
+                let points = keep.iter().map(|i| bez.get_control_point(*i)).collect();
+                *bez = PolyBezier::new(points, vis, bez.ty());
+                modification.send(BezierModificaiton::Rebuilt(entity));
+                // Every point after the first one dropped is now a different point
+                let size = EditSize {
+                    points: (0..before.points.len())
+                        .filter(|&i| keep.get(i) != Some(&i))
+                        .count(),
+                    splines: 1,
+                    ..Default::default()
+                };
+                safety.keep(
+                    PatchOp::reshaped(before, &bez).into_iter().collect(),
+                    size,
+                    "simplify",
+                    settings.edit_limit,
+                    &mut dialogs,
+                    &mut edits,
+                );
+            }
+            if bez.ty() == SplineType::Track {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("bed_ty")
+                        .selected_text(settings.spline_types.name(bed.ty))
+                        .show_ui(ui, |ui| {
+                            for info in settings.spline_types.types.iter() {
+                                if info.placeable && info.ty != SplineType::Track {
+                                    ui.selectable_value(&mut bed.ty, info.ty, info.name.as_str());
+                                }
+                            }
+                        });
+                    ui.label("below by");
+                    let mut v = settings.units.from_meters(bed.drop);
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut v)
+                                .clamp_range(0. ..=10.)
+                                .suffix(settings.units.suffix()),
+                        )
+                        .changed()
+                    {
+                        bed.drop = settings.units.to_meters(v);
+                    }
+                });
+                if has_bed {
+                    ui.label("This track already has one");
+                }
+                // Same control points, so the bed follows the track exactly
+                if ui.add_enabled(!has_bed, egui::Button::new("Build Under Track")).clicked() {
+                    modification.send(BezierModificaiton::PlaceCurve(
+                        bed.ty,
+                        bez.get_control_points().map(|p| p - drop).collect(),
+                        bez.get_visibility().to_vec(),
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("fill_ty")
+                        .selected_text(settings.spline_types.name(fill.ty))
+                        .show_ui(ui, |ui| {
+                            for ty in [SplineType::GroundWork, SplineType::StoneGroundWork] {
+                                let name = settings.spline_types.name(ty);
+                                ui.selectable_value(&mut fill.ty, ty, name);
+                            }
+                        });
+                    ui.label("layers of");
+                    let mut v = settings.units.from_meters(fill.depth);
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut v)
+                                .clamp_range(0.5..=10.)
+                                .suffix(settings.units.suffix()),
+                        )
+                        .changed()
+                    {
+                        fill.depth = settings.units.to_meters(v).max(0.5);
+                    }
+                });
+                if ui.button("Fill Under Track").clicked() {
+                    let layers = groundwork_under(
+                        &bez,
+                        |p| ground_height(&terrain, p),
+                        fill.depth / METERS_PER_UNIT,
+                    );
+                    if layers.is_empty() {
+                        warn!("Fill: the track is already on the ground");
+                    }
+                    for points in layers {
+                        let vis = vec![true; points.len() - 1];
+                        modification.send(BezierModificaiton::PlaceCurve(fill.ty, points, vis));
+                    }
+                }
+            }
+            preview.show(ui, &bez);
+        } else {
+            ui.label("Click a spline to select it");
+        }
+    });
+}
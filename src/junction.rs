@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::DragState;
+
+/// Maximum distance between two control points for them to be considered the same location
+pub const JUNCTION_EPSILON: f32 = 0.01;
+
+/// Plugin for finding and managing junctions between splines
+pub struct JunctionPlugin;
+
+impl Plugin for JunctionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(junction_window);
+    }
+}
+
+/// Marker component for spline handles welded into a junction. Welded handles are always
+/// dragged together with the coincident endpoints of other splines.
+#[derive(Debug, Component, Default)]
+pub struct Welded;
+
+/// A location where the endpoints of more than one spline meet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Junction {
+    pub location: Vec3,
+    /// (handle, curve) for every endpoint at this location
+    pub endpoints: Vec<(Entity, Entity)>,
+}
+
+/// Groups points that lie within `JUNCTION_EPSILON` of each other. Only groups with more
+/// than one point are returned.
+pub fn group_coincident(points: &[Vec3]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&a, &b| points[a].x.total_cmp(&points[b].x));
+    let mut used = vec![false; points.len()];
+    let mut groups = vec![];
+    for (i, &a) in order.iter().enumerate() {
+        if used[a] {
+            continue;
+        }
+        used[a] = true;
+        let mut group = vec![a];
+        for &b in order[i + 1..].iter() {
+            if points[b].x - points[a].x > JUNCTION_EPSILON {
+                break;
+            }
+            if !used[b] && points[a].distance(points[b]) < JUNCTION_EPSILON {
+                used[b] = true;
+                group.push(b);
+            }
+        }
+        if group.len() > 1 {
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Finds every junction between the spline endpoints in `endpoints`, given as
+/// (handle, curve, location)
+pub fn find_junctions(endpoints: &[(Entity, Entity, Vec3)]) -> Vec<Junction> {
+    let points: Vec<_> = endpoints.iter().map(|(_h, _c, loc)| *loc).collect();
+    group_coincident(&points)
+        .into_iter()
+        .map(|group| Junction {
+            location: points[group[0]],
+            endpoints: group
+                .iter()
+                .map(|&i| (endpoints[i].0, endpoints[i].1))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Finds every curve connected to `start` through the given junctions
+pub fn connected_curves(start: Entity, junctions: &[Junction]) -> Vec<Entity> {
+    let mut curves = vec![start];
+    let mut i = 0;
+    while i < curves.len() {
+        let cur = curves[i];
+        for junction in junctions.iter() {
+            if junction.endpoints.iter().any(|(_h, c)| *c == cur) {
+                for (_h, c) in junction.endpoints.iter() {
+                    if !curves.contains(c) {
+                        curves.push(*c);
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    curves
+}
+
+/// Finds the fewest curves leading from `start` to `end` through the given junctions, in order
+/// along the way, or `None` if they aren't connected
+pub fn route_between(start: Entity, end: Entity, junctions: &[Junction]) -> Option<Vec<Entity>> {
+    // (curve, index of the curve it was reached from)
+    let mut reached = vec![(start, usize::MAX)];
+    let mut i = 0;
+    while i < reached.len() {
+        let (cur, _from) = reached[i];
+        if cur == end {
+            let mut route = vec![];
+            let mut at = i;
+            while at != usize::MAX {
+                route.push(reached[at].0);
+                at = reached[at].1;
+            }
+            route.reverse();
+            return Some(route);
+        }
+        for junction in junctions.iter() {
+            if junction.endpoints.iter().any(|(_h, c)| *c == cur) {
+                for (_h, c) in junction.endpoints.iter() {
+                    if !reached.iter().any(|(r, _f)| r == c) {
+                        reached.push((*c, i));
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Collects (handle, curve, location) for the endpoint handles of every spline
+pub fn spline_endpoints(
+    handles: &Query<(&DragState, &Parent, Entity)>,
+    beziers: &Query<&PolyBezier<CubicBezier>>,
+) -> Vec<(Entity, Entity, Vec3)> {
+    handles
+        .iter()
+        .filter_map(|(state, parent, entity)| {
+            let bez = beziers.get(parent.0).ok()?;
+            if bez.is_endpoint(state.pt) {
+                Some((entity, parent.0, bez.get_control_point(state.pt)))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn junction_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    handles: Query<(&DragState, &Parent, Entity)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    welded: Query<&Welded>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    if !palette.show_junctions {
+        return;
+    }
+    let junctions = find_junctions(&spline_endpoints(&handles, &beziers));
+    egui::Window::new("Junctions")
+        .resizable(true)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("{} junctions", junctions.len()));
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .show(ui, |ui| {
+                    egui::Grid::new("junction_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for junction in junctions.iter() {
+                                let is_welded = junction
+                                    .endpoints
+                                    .iter()
+                                    .all(|(handle, _c)| welded.get(*handle).is_ok());
+                                ui.label(settings.units.point(junction.location));
+                                ui.label(format!("{} ends", junction.endpoints.len()));
+                                if is_welded {
+                                    ui.label("Welded");
+                                    if ui.button("Unweld").clicked() {
+                                        for (handle, _c) in junction.endpoints.iter() {
+                                            commands.entity(*handle).remove::<Welded>();
+                                        }
+                                    }
+                                } else {
+                                    ui.label("");
+                                    if ui.button("Weld").clicked() {
+                                        for (handle, _c) in junction.endpoints.iter() {
+                                            commands.entity(*handle).insert(Welded);
+                                        }
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use smooth_bevy_cameras::LookTransform;
+use std::collections::HashSet;
+
+use crate::analysis::AnalysisSettings;
+use crate::coords::METERS_PER_UNIT;
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::BezierSection;
+
+/// Furthest segments get labels from the camera, in editor units
+const LABEL_DISTANCE: f32 = 100.;
+/// Size of the screen cells only one label is drawn in, in points, so labels don't overlap
+const LABEL_CELL: (f32, f32) = (60., 30.);
+
+/// Plugin for labels floating over each segment, with its grade or radius
+pub struct LabelPlugin;
+
+impl Plugin for LabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(segment_labels);
+    }
+}
+
+/// Labels the middle of each visible segment near the camera with its grade and tightest radius,
+/// in red past `AnalysisSettings::max_grade` or under `AnalysisSettings::min_radius`
+fn segment_labels(
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    thresholds: Res<AnalysisSettings>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<LookTransform>>,
+    sections: Query<(&Parent, &BezierSection, &Visibility)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if !palette.grade_labels && !palette.radius_labels {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    // Labels are only drawn in the main window, see `view.rs`
+    let (camera, camera_transform) = match cameras.iter().find(|(c, _t)| c.window == window.id()) {
+        Some(camera) => camera,
+        None => return,
+    };
+    let ctx = egui_context.ctx_mut();
+    // Window coordinates start at the bottom left, egui's at the top left
+    let scale = window.scale_factor() as f32 / ctx.pixels_per_point();
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("segment_labels"),
+    ));
+    let mut used = HashSet::new();
+    for (parent, section, vis) in sections.iter() {
+        if !vis.is_visible {
+            continue;
+        }
+        let bez = match beziers.get(parent.0) {
+            Ok(bez) => bez,
+            Err(_) => continue,
+        };
+        let seg = match bez.get_segment(&section.0) {
+            Some(seg) => seg,
+            None => continue,
+        };
+        let middle = bez.eval_at(seg, 0.5) + settings.curve_offset(bez.ty());
+        if middle.distance(camera_transform.translation) > LABEL_DISTANCE {
+            continue;
+        }
+        let screen = match camera.world_to_screen(&windows, camera_transform, middle) {
+            Some(screen) => screen,
+            None => continue,
+        };
+        let pos = egui::pos2(screen.x * scale, (window.height() - screen.y) * scale);
+        let cell = ((pos.x / LABEL_CELL.0) as i32, (pos.y / LABEL_CELL.1) as i32);
+        if !used.insert(cell) {
+            continue;
+        }
+        let mut lines = vec![];
+        let mut outlier = false;
+        if palette.grade_labels {
+            let grade = bez.segment_grade(seg);
+            outlier |= grade.abs() > thresholds.max_grade;
+            lines.push(format!("{:.1}%", grade));
+        }
+        if palette.radius_labels {
+            let radius = bez.segment_min_radius(seg) * METERS_PER_UNIT;
+            outlier |= radius < thresholds.min_radius;
+            lines.push(settings.units.radius(radius));
+        }
+        let color = if outlier {
+            egui::Color32::RED
+        } else {
+            egui::Color32::WHITE
+        };
+        painter.text(
+            pos,
+            egui::Align2::CENTER_CENTER,
+            lines.join("\n"),
+            egui::FontId::proportional(14.),
+            color,
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::coords::METERS_PER_UNIT;
+use crate::dialog::Dialogs;
+use crate::palette::Palette;
+use crate::patch::{EditEvent, PatchOp, SplineShape};
+use crate::reshape::ReshapeEvent;
+use crate::safety::{EditSize, SafetyNet};
+use crate::selection::Selected;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierSectionUpdate, DragState};
+
+/// Plugin for setting every selected control point to the same height, e.g. to flatten a yard,
+/// or to an even grade
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(level_window);
+    }
+}
+
+/// Which height the selection is levelled to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LevelTo {
+    /// The average height of the selected points
+    Average,
+    /// The height of the earliest selected point along its spline
+    First,
+    /// A height typed in
+    Height,
+    /// An even grade between the first and last selected points of one spline
+    Grade,
+}
+
+/// How the level window levels the selection. The typed in height is in meters
+struct Level {
+    to: LevelTo,
+    height: f32,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            to: LevelTo::Average,
+            height: 0.,
+        }
+    }
+}
+
+fn level_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut handles: Query<(&DragState, &Parent, &mut Transform), With<Selected>>,
+    mut beziers: Query<&mut PolyBezier<CubicBezier>>,
+    mut level: Local<Level>,
+    mut section_update: EventWriter<BezierSectionUpdate>,
+    mut edits: EventWriter<EditEvent>,
+    mut reshape: EventWriter<ReshapeEvent>,
+    (mut safety, mut dialogs): (ResMut<SafetyNet>, ResMut<Dialogs>),
+) {
+    if !palette.show_level {
+        return;
+    }
+    // (spline, index, height without the curve offset)
+    let mut points: Vec<(Entity, usize, f32)> = handles
+        .iter()
+        .filter_map(|(state, parent, _t)| {
+            let bez = beziers.get(parent.0).ok()?;
+            Some((parent.0, state.pt, bez.get_control_point(state.pt).y))
+        })
+        .collect();
+    points.sort_by_key(|&(spline, pt, _y)| (pt, spline));
+    let average = points.iter().map(|(.., y)| y).sum::<f32>() / points.len().max(1) as f32;
+    let height = match level.to {
+        LevelTo::Average | LevelTo::Grade => average,
+        LevelTo::First => points.first().map_or(0., |(.., y)| *y),
+        LevelTo::Height => level.height / METERS_PER_UNIT,
+    };
+    // (spline, first, last) for an even grade, which only works along one spline
+    let graded = match (points.first(), points.last()) {
+        (Some(&(spline, first, _)), Some(&(_, last, _)))
+            if first < last && points.iter().all(|(s, ..)| *s == spline) =>
+        {
+            Some((spline, first, last))
+        }
+        _ => None,
+    };
+    let units = settings.units;
+    let mut apply = false;
+    egui::Window::new("Level Heights")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.radio_value(&mut level.to, LevelTo::Average, "Average");
+            ui.radio_value(&mut level.to, LevelTo::First, "First Point");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut level.to, LevelTo::Height, "Height");
+                let mut v = units.from_meters(level.height);
+                let value = egui::DragValue::new(&mut v).suffix(units.suffix());
+                if ui.add_enabled(level.to == LevelTo::Height, value).changed() {
+                    level.height = units.to_meters(v);
+                }
+            });
+            ui.radio_value(&mut level.to, LevelTo::Grade, "Even Grade");
+            if level.to == LevelTo::Grade {
+                let spline =
+                    graded.and_then(|(s, first, last)| Some((beziers.get(s).ok()?, first, last)));
+                if let Some((bez, first, last)) = spline {
+                    let from = bez.get_control_point(first);
+                    let to = bez.get_control_point(last);
+                    let grade = (to.y - from.y) / bez.run_between(first, last) * 100.;
+                    ui.label(format!(
+                        "Grades points {} to {} at {:.2}%",
+                        first, last, grade
+                    ));
+                } else {
+                    ui.label("Select two points on the same spline");
+                }
+            } else if points.is_empty() {
+                ui.label("Select the control points to level");
+            } else {
+                ui.label(format!(
+                    "Levels {} points to {}",
+                    points.len(),
+                    units.length(height * METERS_PER_UNIT)
+                ));
+            }
+            let button = egui::Button::new("Level");
+            let enabled = if level.to == LevelTo::Grade {
+                graded.is_some()
+            } else {
+                !points.is_empty()
+            };
+            apply = ui.add_enabled(enabled, button).clicked();
+        });
+    if !apply {
+        return;
+    }
+    if level.to == LevelTo::Grade {
+        if let Some((spline, first, last)) = graded {
+            if let Ok(bez) = beziers.get(spline) {
+                reshape.send(ReshapeEvent {
+                    spline,
+                    points: bez.graded(first, last),
+                    action: "grade",
+                });
+            }
+        }
+        return;
+    }
+    let mut moved = 0;
+    // (spline, its shape before levelling)
+    let mut splines: Vec<(Entity, SplineShape)> = vec![];
+    for (state, parent, mut trans) in handles.iter_mut() {
+        if let Ok(mut bez) = beziers.get_mut(parent.0) {
+            let from = bez.get_control_point(state.pt);
+            let to = Vec3::new(from.x, height, from.z);
+            if (from.y - to.y).abs() < f32::EPSILON {
+                continue;
+            }
+            if !splines.iter().any(|(s, _)| *s == parent.0) {
+                splines.push((parent.0, SplineShape::of(&bez)));
+                section_update.send(BezierSectionUpdate { bezier: parent.0 });
+            }
+            bez.update(state.pt, to);
+            trans.translation = to + settings.curve_offset(bez.ty());
+            moved += 1;
+        }
+    }
+    let size = EditSize {
+        points: moved,
+        splines: splines.len(),
+        ..Default::default()
+    };
+    let reshaped = splines
+        .into_iter()
+        .filter_map(|(spline, before)| PatchOp::reshaped(before, beziers.get(spline).ok()?))
+        .collect();
+    safety.keep(
+        reshaped,
+        size,
+        "level",
+        settings.edit_limit,
+        &mut dialogs,
+        &mut edits,
+    );
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Plugin for limits on where dragged control points can go, see `Settings::drag_max_grade` and
+/// `Settings::drag_min_radius`
+pub struct LimitPlugin;
+
+impl Plugin for LimitPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LimitFeedback::default());
+        app.add_system(show_limit_feedback);
+    }
+}
+
+/// Why the point being dragged isn't where the mouse is, if it isn't
+#[derive(Debug, Default)]
+pub struct LimitFeedback(pub Option<String>);
+
+/// Clamps the height of control point `pt` at `loc` so neither segment next to it is steeper
+/// than `max_grade` percent. Returns the clamped location, and whether it was clamped
+pub fn clamp_grade(
+    bez: &PolyBezier<CubicBezier>,
+    pt: usize,
+    loc: Vec3,
+    max_grade: f32,
+) -> (Vec3, bool) {
+    let slope = max_grade / 100.;
+    let (mut low, mut high) = (f32::NEG_INFINITY, f32::INFINITY);
+    let neighbours = [pt.checked_sub(1), Some(pt + 1).filter(|n| *n < bez.len())];
+    for n in neighbours.into_iter().flatten() {
+        let p = bez.get_control_point(n);
+        let run = Vec2::new(loc.x - p.x, loc.z - p.z).length();
+        low = low.max(p.y - slope * run);
+        high = high.min(p.y + slope * run);
+    }
+    // Neighbours too far apart in height for any point to work, so split the difference
+    let y = if low > high {
+        (low + high) / 2.
+    } else {
+        loc.y.clamp(low, high)
+    };
+    (Vec3::new(loc.x, y, loc.z), (y - loc.y).abs() > f32::EPSILON)
+}
+
+/// Moves control point `pt` from `from` to the point nearest `loc` where no segment it changes
+/// curves tighter than `min_radius`, so the point slides along the limit rather than stopping
+/// where the drag first crossed it. Returns where it went, and whether it was held back. A point
+/// that starts out too tight isn't limited, so it can be dragged out of it
+pub fn clamp_radius(
+    bez: &PolyBezier<CubicBezier>,
+    pt: usize,
+    from: Vec3,
+    loc: Vec3,
+    min_radius: f32,
+) -> (Vec3, bool) {
+    // Moving a point changes the two segments either side of it, which depend on the points
+    // either side of those
+    let (low, high) = (pt.saturating_sub(3), (pt + 3).min(bez.len() - 1));
+    let tightest = |p: Vec3| {
+        let points = (low..=high)
+            .map(|i| if i == pt { p } else { bez.get_control_point(i) })
+            .collect();
+        let window = PolyBezier::new(points, vec![true; high - low], bez.ty());
+        let first = pt.saturating_sub(2).max(low) - low;
+        let last = (pt + 1).min(high - 1) - low;
+        (first..=last)
+            .map(|i| window.segment_min_radius(i))
+            .fold(f32::INFINITY, f32::min)
+    };
+    if tightest(loc) >= min_radius || tightest(from) < min_radius {
+        return (loc, false);
+    }
+    let legal = |p: Vec3| tightest(p) >= min_radius;
+    // Where the drag line crosses the limit. This is legal, so the nearest legal point is no
+    // further from `loc` than it
+    let crossing = from.lerp(loc, last_legal(|t| legal(from.lerp(loc, t))));
+    let reach = crossing.distance(loc);
+    // Then the closest legal point out from `loc` in each direction, keeping the drag's height
+    let mut nearest = crossing;
+    for i in 0..RADIUS_DIRECTIONS {
+        let angle = i as f32 / RADIUS_DIRECTIONS as f32 * std::f32::consts::TAU;
+        let dir = Vec3::new(angle.cos(), 0., angle.sin()) * reach;
+        if !legal(loc + dir) {
+            continue;
+        }
+        let t = 1. - last_legal(|t| legal(loc + dir * (1. - t)));
+        if t * reach < nearest.distance(loc) {
+            nearest = loc + dir * t;
+        }
+    }
+    (nearest, true)
+}
+
+/// Directions searched around the cursor for the nearest point within the radius limit
+const RADIUS_DIRECTIONS: usize = 16;
+
+/// The largest `t` in 0..=1 for which `legal` holds, given that it holds at 0 and not at 1, and
+/// changes only once in between
+fn last_legal(legal: impl Fn(f32) -> bool) -> f32 {
+    let (mut low, mut high) = (0., 1.);
+    for _ in 0..16 {
+        let t = (low + high) / 2.;
+        if legal(t) {
+            low = t;
+        } else {
+            high = t;
+        }
+    }
+    low
+}
+
+/// Says why next to the mouse, while a limit is holding back the dragged point
+fn show_limit_feedback(feedback: Res<LimitFeedback>, mut egui_context: ResMut<EguiContext>) {
+    if let Some(reason) = &feedback.0 {
+        egui::show_tooltip_at_pointer(egui_context.ctx_mut(), egui::Id::new("limit"), |ui| {
+            ui.colored_label(egui::Color32::YELLOW, reason);
+        });
+    }
+}
@@ -0,0 +1,235 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::Hover;
+
+use crate::coords::vec_to_gvas;
+use crate::palette::Palette;
+use crate::patch::{EditEvent, PatchEvent, PatchOp, SplineShape, SwitchPlacement};
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::DragState;
+
+/// Plugin for recording and replaying macros of editor operations
+pub struct MacroPlugin;
+
+impl Plugin for MacroPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Macros::default());
+        app.add_system(record_macro);
+        app.add_system(track_anchor);
+        app.add_system(macro_window);
+    }
+}
+
+/// A recorded sequence of edits
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    /// Location of the first edit, in save coordinates
+    pub anchor: [f32; 3],
+    pub ops: Vec<PatchOp>,
+}
+
+impl Macro {
+    /// The macro's operations, moved so the anchor is at `anchor`
+    pub fn relative_to(&self, anchor: [f32; 3]) -> Vec<PatchOp> {
+        let off = [
+            anchor[0] - self.anchor[0],
+            anchor[1] - self.anchor[1],
+            anchor[2] - self.anchor[2],
+        ];
+        let shift = |p: [f32; 3]| [p[0] + off[0], p[1] + off[1], p[2] + off[2]];
+        let shift_shape = |shape: &SplineShape| SplineShape {
+            points: shape.points.iter().map(|p| shift(*p)).collect(),
+            visibility: shape.visibility.clone(),
+        };
+        self.ops
+            .iter()
+            .map(|op| match op {
+                PatchOp::MovePoint { from, to } => PatchOp::MovePoint {
+                    from: shift(*from),
+                    to: shift(*to),
+                },
+                PatchOp::ReshapeSpline { from, to } => PatchOp::ReshapeSpline {
+                    from: shift_shape(from),
+                    to: shift_shape(to),
+                },
+                PatchOp::AddSpline {
+                    ty,
+                    points,
+                    visibility,
+                } => PatchOp::AddSpline {
+                    ty: *ty,
+                    points: points.iter().map(|p| shift(*p)).collect(),
+                    visibility: visibility.clone(),
+                },
+                PatchOp::DeleteSwitch {
+                    location,
+                    ty,
+                    rotation,
+                } => PatchOp::DeleteSwitch {
+                    location: shift(*location),
+                    ty: *ty,
+                    rotation: *rotation,
+                },
+                PatchOp::AddSwitch {
+                    ty,
+                    location,
+                    rotation,
+                } => PatchOp::AddSwitch {
+                    ty: *ty,
+                    location: shift(*location),
+                    rotation: *rotation,
+                },
+                PatchOp::MoveSwitch { from, to } => PatchOp::MoveSwitch {
+                    from: SwitchPlacement {
+                        location: shift(from.location),
+                        ..*from
+                    },
+                    to: SwitchPlacement {
+                        location: shift(to.location),
+                        ..*to
+                    },
+                },
+                PatchOp::DeleteSpline {
+                    points,
+                    ty,
+                    visibility,
+                } => PatchOp::DeleteSpline {
+                    points: points.iter().map(|p| shift(*p)).collect(),
+                    ty: *ty,
+                    visibility: visibility.clone(),
+                },
+                PatchOp::AddIndustry {
+                    ty,
+                    location,
+                    rotation,
+                } => PatchOp::AddIndustry {
+                    ty: *ty,
+                    location: shift(*location),
+                    rotation: *rotation,
+                },
+                PatchOp::DeleteIndustry {
+                    location,
+                    ty,
+                    rotation,
+                } => PatchOp::DeleteIndustry {
+                    location: shift(*location),
+                    ty: *ty,
+                    rotation: *rotation,
+                },
+            })
+            .collect()
+    }
+}
+
+/// Location an operation is anchored to
+fn op_location(op: &PatchOp) -> [f32; 3] {
+    match op {
+        PatchOp::MovePoint { from, .. } => *from,
+        PatchOp::ReshapeSpline { from, .. } => from.points[0],
+        PatchOp::AddSpline { points, .. } => points[0],
+        PatchOp::DeleteSwitch { location, .. }
+        | PatchOp::AddSwitch { location, .. }
+        | PatchOp::DeleteIndustry { location, .. }
+        | PatchOp::AddIndustry { location, .. } => *location,
+        PatchOp::MoveSwitch { from, .. } => from.location,
+        PatchOp::DeleteSpline { points, .. } => points[0],
+    }
+}
+
+/// Recorded macros
+#[derive(Debug, Default)]
+pub struct Macros {
+    pub macros: Vec<Macro>,
+    /// Edits recorded so far, if recording
+    recording: Option<Vec<PatchOp>>,
+    /// Name to give the next recorded macro
+    new_name: String,
+    /// Last control point clicked, in save coordinates
+    anchor: Option<[f32; 3]>,
+}
+
+fn record_macro(mut edits: EventReader<EditEvent>, mut macros: ResMut<Macros>) {
+    for EditEvent(op) in edits.iter() {
+        if let Some(recording) = macros.recording.as_mut() {
+            // Connected moves send the same move for every endpoint
+            if recording.last() != Some(op) {
+                recording.push(op.clone());
+            }
+        }
+    }
+}
+
+/// Remembers the last clicked control point, to replay macros relative to it
+fn track_anchor(
+    mouse_button_input: Res<Input<MouseButton>>,
+    handles: Query<(&Hover, &DragState, &Parent)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut macros: ResMut<Macros>,
+) {
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        for (hover, state, parent) in handles.iter() {
+            if hover.hovered() {
+                if let Ok(bez) = beziers.get(parent.0) {
+                    macros.anchor = Some(vec_to_gvas(bez.get_control_point(state.pt)));
+                }
+            }
+        }
+    }
+}
+
+fn macro_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut macros: ResMut<Macros>,
+    mut patch_events: EventWriter<PatchEvent>,
+) {
+    if !palette.show_macros {
+        return;
+    }
+    let macros = macros.as_mut();
+    egui::Window::new("Macros")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut macros.new_name);
+            });
+            if let Some(recording) = &macros.recording {
+                ui.label(format!("Recording: {} edits", recording.len()));
+                if ui.button("Stop").clicked() {
+                    let ops = macros.recording.take().unwrap();
+                    if !ops.is_empty() {
+                        macros.macros.push(Macro {
+                            name: std::mem::take(&mut macros.new_name),
+                            anchor: op_location(&ops[0]),
+                            ops,
+                        });
+                    }
+                }
+            } else if ui.button("Record").clicked() {
+                macros.recording = Some(vec![]);
+            }
+            ui.separator();
+            let mut remove = None;
+            for (i, m) in macros.macros.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} ({} edits)", m.name, m.ops.len()));
+                    if ui.button("Replay").clicked() {
+                        patch_events.send(PatchEvent::Ops(m.ops.clone()));
+                    }
+                    if let Some(anchor) = macros.anchor {
+                        if ui.button("Replay at last point").clicked() {
+                            patch_events.send(PatchEvent::Ops(m.relative_to(anchor)));
+                        }
+                    }
+                    if ui.button("Delete").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                macros.macros.remove(i);
+            }
+        });
+}
@@ -5,18 +5,73 @@ use smooth_bevy_cameras::controllers::orbit::{
 
 mod bevy_obj;
 
+mod analysis;
 mod background;
-mod gvas;
+mod graph;
 mod spline;
 
+use rro_gvas as gvas;
+
+mod budget;
+mod buffers;
 mod control;
+mod coords;
+mod csv;
+mod declutter;
+mod diagnostics;
+mod dialog;
+mod dock;
+mod elevation;
+mod fade;
+mod generator;
+mod gizmo;
+mod header;
+mod history;
+mod import;
+mod inspector;
+mod junction;
+mod labels;
+mod level;
+mod limits;
+mod macros;
+mod measure;
+mod merge;
+mod mirror;
 mod palette;
+mod patch;
+mod planning;
+mod preview;
+mod project;
+mod properties;
+mod recovery;
+mod regions;
+mod registry;
+mod reshape;
+mod safety;
+mod saves;
+mod selection;
+mod settings;
+mod sidings;
 mod snaps;
+mod support;
+mod tabs;
+mod template;
+mod theme;
+mod tutorial;
 mod update;
+mod validation;
+mod vegetation;
+mod view;
+mod workspace;
 
 fn main() {
     App::new()
         .insert_resource(Msaa { samples: 4 })
+        // Closing the plan view only hides it, so `view::close_views` exits instead
+        .insert_resource(bevy::window::WindowSettings {
+            exit_on_close: false,
+            ..Default::default()
+        })
         .add_plugins(DefaultPlugins)
         .add_plugin(smooth_bevy_cameras::LookTransformPlugin)
         .add_plugin(OrbitCameraPlugin::default())
@@ -26,10 +81,56 @@ fn main() {
         .add_plugin(bevy_mod_picking::PickingPlugin)
         .add_plugin(bevy_mod_picking::InteractablePickingPlugin)
         .add_plugin(bevy_mod_picking::HighlightablePickingPlugin)
+        .add_plugin(settings::SettingsPlugin)
         .add_plugin(palette::PalettePlugin)
         .add_plugin(control::ControlPlugin)
         .add_plugin(background::Background)
         .add_plugin(snaps::SnapPlugin)
+        .add_plugin(junction::JunctionPlugin)
+        .add_plugin(template::TemplatePlugin)
+        .add_plugin(planning::PlanningPlugin)
+        .add_plugin(analysis::AnalysisPlugin)
+        .add_plugin(patch::PatchPlugin)
+        .add_plugin(csv::CsvPlugin)
+        .add_plugin(macros::MacroPlugin)
+        .add_plugin(view::ViewPlugin)
+        .add_plugin(inspector::InspectorPlugin)
+        .add_plugin(preview::PreviewPlugin)
+        .add_plugin(project::ProjectPlugin)
+        .add_plugin(elevation::ElevationPlugin)
+        .add_plugin(fade::FadePlugin)
+        .add_plugin(properties::PropertiesPlugin)
+        .add_plugin(import::ImportPlugin)
+        .add_plugin(merge::MergePlugin)
+        .add_plugin(vegetation::VegetationPlugin)
+        .add_plugin(diagnostics::DiagnosticsPlugin)
+        .add_plugin(recovery::RecoveryPlugin)
+        .add_plugin(saves::SaveBrowserPlugin)
+        .add_plugin(validation::ValidationPlugin)
+        .add_plugin(tabs::TabsPlugin)
+        .add_plugin(workspace::WorkspacePlugin)
+        .add_plugin(header::HeaderPlugin)
+        .add_plugin(buffers::BufferStopPlugin)
+        .add_plugin(sidings::SidingPlugin)
+        .add_plugin(budget::BudgetPlugin)
+        .add_plugin(support::SupportPlugin)
+        .add_plugin(tutorial::TutorialPlugin)
+        .add_plugin(theme::ThemePlugin)
+        .add_plugin(dock::DockPlugin)
+        .add_plugin(dialog::DialogPlugin)
+        .add_plugin(history::HistoryPlugin)
+        .add_plugin(declutter::DeclutterPlugin)
+        .add_plugin(measure::MeasurePlugin)
+        .add_plugin(selection::SelectionPlugin)
+        .add_plugin(regions::RegionPlugin)
+        .add_plugin(safety::SafetyPlugin)
+        .add_plugin(mirror::MirrorPlugin)
+        .add_plugin(reshape::ReshapePlugin)
+        .add_plugin(labels::LabelPlugin)
+        .add_plugin(limits::LimitPlugin)
+        .add_plugin(level::LevelPlugin)
+        .add_plugin(generator::GeneratorPlugin)
+        .add_plugin(gizmo::GizmoPlugin)
         .add_startup_system(setup)
         .run();
 }
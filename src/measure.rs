@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+
+use crate::coords::METERS_PER_UNIT;
+use crate::palette::{MouseAction, Palette};
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::BezierSection;
+
+/// Plugin for the measure tool, which shows where along a spline the mouse is
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(hover_readout);
+    }
+}
+
+/// Shows the curve parameter, distance along the spline, elevation, and grade of the point
+/// under the mouse, next to the mouse
+fn hover_readout(
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    pick_cam: Query<&PickingCamera>,
+    sections: Query<(&Parent, &BezierSection)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if palette.action != MouseAction::Measure {
+        return;
+    }
+    let ctx = egui_context.ctx_mut();
+    if ctx.is_pointer_over_area() {
+        return;
+    }
+    let (hit, intersection) = match pick_cam.iter().last().and_then(|c| c.intersect_top()) {
+        Some(top) => top,
+        None => return,
+    };
+    let (parent, section) = match sections.get(hit) {
+        Ok(section) => section,
+        Err(_) => return,
+    };
+    let bez = match beziers.get(parent.0) {
+        Ok(bez) => bez,
+        Err(_) => return,
+    };
+    let seg = match bez.get_segment(&section.0) {
+        Some(seg) => seg,
+        None => return,
+    };
+    let t = bez.closest_t(seg, intersection.position());
+    let point = bez.eval_at(seg, t);
+    let units = settings.units;
+    egui::show_tooltip_at_pointer(ctx, egui::Id::new("measure"), |ui| {
+        ui.label(format!("Parameter: {:.3}", seg as f32 + t));
+        ui.label(format!(
+            "Distance: {} of {}",
+            units.length(bez.length_to(seg, t) * METERS_PER_UNIT),
+            units.length(bez.length() * METERS_PER_UNIT)
+        ));
+        ui.label(format!(
+            "Elevation: {}",
+            units.length(point.y * METERS_PER_UNIT)
+        ));
+        ui.label(format!("Grade: {:.2}%", bez.grade_at(seg, t)));
+    });
+}
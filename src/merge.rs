@@ -0,0 +1,351 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::coords::{gvas_to_vec, quat_to_rotator, rotator_to_quat, vec_to_gvas};
+use crate::gvas::{GVASError, RROSave, SplineType, SwitchData};
+use crate::palette::{save_dir, Palette};
+use crate::patch::{EditEvent, PatchOp};
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::BezierModificaiton;
+
+/// Plugin for merging another save into the open one
+pub struct MergePlugin;
+
+impl Plugin for MergePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MergeState {
+            slot: 1,
+            pending: None,
+        });
+        app.add_event::<MergeEvent>();
+        app.add_system(merge_window);
+        app.add_system(merge_saves);
+    }
+}
+
+/// Points closer than this are treated as the same point, in editor units
+const DUPLICATE_DISTANCE: f32 = 0.05;
+
+/// A spline from the other save, in editor space
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeSpline {
+    pub ty: SplineType,
+    pub points: Vec<Vec3>,
+    pub visibility: Vec<bool>,
+}
+
+/// How to resolve a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the open save's version
+    Ours,
+    /// Replace it with the other save's version
+    Theirs,
+    /// Keep both
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictKind {
+    /// A spline with the same ends as one in the open save, but a different shape or type
+    Spline { ours: Entity, theirs: MergeSpline },
+    /// A switch in the same place as one in the open save, but a different type or rotation
+    Switch { ours: Entity, theirs: SwitchData },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub description: String,
+    pub resolution: Resolution,
+}
+
+/// The result of comparing the other save with the open one, waiting to be applied
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PendingMerge {
+    /// Splines only in the other save
+    pub splines: Vec<MergeSpline>,
+    /// Switches only in the other save
+    pub switches: Vec<SwitchData>,
+    /// Splines and switches in both saves, which are skipped
+    pub duplicates: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeState {
+    /// Save slot to merge from
+    pub slot: usize,
+    pub pending: Option<PendingMerge>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeEvent {
+    /// Compare the save at the given path with the open one
+    Compare(PathBuf),
+    /// Apply the pending merge
+    Apply,
+}
+
+fn close(a: &Vec3, b: &Vec3) -> bool {
+    a.distance(*b) < DUPLICATE_DISTANCE
+}
+
+/// Whether every point of `a` matches `b`, in either direction
+fn same_points(a: &[Vec3], b: &[Vec3]) -> bool {
+    a.len() == b.len()
+        && (a.iter().zip(b).all(|(a, b)| close(a, b))
+            || a.iter().zip(b.iter().rev()).all(|(a, b)| close(a, b)))
+}
+
+/// Whether the ends of `a` match the ends of `b`, in either direction
+fn same_ends(a: &[Vec3], b: &[Vec3]) -> bool {
+    let (a0, an, b0, bn) = (&a[0], &a[a.len() - 1], &b[0], &b[b.len() - 1]);
+    (close(a0, b0) && close(an, bn)) || (close(a0, bn) && close(an, b0))
+}
+
+/// Sorts everything in `other` into new, duplicate, and conflicting splines and switches
+fn compare(
+    other: &RROSave,
+    beziers: &Query<(Entity, &PolyBezier<CubicBezier>)>,
+    switches: &Query<(Entity, &Transform, &SwitchData)>,
+) -> Result<PendingMerge, GVASError> {
+    let splines = other.curves()?.map(|curve| MergeSpline {
+        ty: curve.ty,
+        points: curve
+            .control_points
+            .iter()
+            .map(|p| gvas_to_vec(*p))
+            .collect(),
+        visibility: curve.visibility.iter().copied().collect(),
+    });
+    Ok(compare_parts(splines, other.switches()?, beziers, switches))
+}
+
+/// Sorts splines and switches from somewhere other than the open save into new, duplicate, and
+/// conflicting ones
+pub fn compare_parts(
+    splines: impl IntoIterator<Item = MergeSpline>,
+    other_switches: impl IntoIterator<Item = SwitchData>,
+    beziers: &Query<(Entity, &PolyBezier<CubicBezier>)>,
+    switches: &Query<(Entity, &Transform, &SwitchData)>,
+) -> PendingMerge {
+    let ours: Vec<(Entity, SplineType, Vec<Vec3>)> = beziers
+        .iter()
+        .map(|(e, b)| (e, b.ty(), b.get_control_points().collect()))
+        .collect();
+    let mut pending = PendingMerge::default();
+    for theirs in splines {
+        if theirs.points.len() < 2 {
+            continue;
+        }
+        if ours
+            .iter()
+            .any(|(_e, ty, points)| *ty == theirs.ty && same_points(points, &theirs.points))
+        {
+            pending.duplicates += 1;
+        } else if let Some((e, ty, _p)) = ours
+            .iter()
+            .find(|(_e, _ty, points)| same_ends(points, &theirs.points))
+        {
+            pending.conflicts.push(Conflict {
+                description: format!(
+                    "{} at {} has a different shape ({} here)",
+                    theirs.ty, theirs.points[0], ty
+                ),
+                kind: ConflictKind::Spline { ours: *e, theirs },
+                resolution: Resolution::Ours,
+            });
+        } else {
+            pending.splines.push(theirs);
+        }
+    }
+    for theirs in other_switches {
+        let location = gvas_to_vec(theirs.location);
+        let rotation = rotator_to_quat(theirs.rotation);
+        match switches
+            .iter()
+            .find(|(_e, t, _s)| close(&t.translation, &location))
+        {
+            Some((e, trans, ours)) => {
+                if ours.ty == theirs.ty && trans.rotation.dot(rotation).abs() > 0.9999 {
+                    pending.duplicates += 1;
+                } else {
+                    pending.conflicts.push(Conflict {
+                        kind: ConflictKind::Switch { ours: e, theirs },
+                        description: format!(
+                            "{:?} at {} is a different type or rotation ({:?} here)",
+                            theirs.ty, location, ours.ty
+                        ),
+                        resolution: Resolution::Ours,
+                    });
+                }
+            }
+            None => pending.switches.push(theirs),
+        }
+    }
+    pending
+}
+
+fn place_spline(spline: MergeSpline, modification: &mut EventWriter<BezierModificaiton>) {
+    modification.send(BezierModificaiton::PlaceCurve(
+        spline.ty,
+        spline.points,
+        spline.visibility,
+    ));
+}
+
+fn place_switch(switch: SwitchData, modification: &mut EventWriter<BezierModificaiton>) {
+    modification.send(BezierModificaiton::PlaceSw(
+        gvas_to_vec(switch.location),
+        switch.ty,
+        rotator_to_quat(switch.rotation),
+    ));
+}
+
+fn merge_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut state: ResMut<MergeState>,
+    mut merge_events: EventWriter<MergeEvent>,
+) {
+    if !palette.show_merge {
+        return;
+    }
+    let state = state.as_mut();
+    egui::Window::new("Merge Saves")
+        .default_height(400.)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("merge_slot")
+                    .selected_text(format!("Slot {}", state.slot))
+                    .show_ui(ui, |ui| {
+                        for slot in 1..=10 {
+                            ui.selectable_value(&mut state.slot, slot, format!("Slot {}", slot));
+                        }
+                    });
+                if ui.button("Compare").clicked() {
+                    let path = save_dir(&settings).join(format!("slot{}.sav", state.slot));
+                    merge_events.send(MergeEvent::Compare(path));
+                }
+            });
+            let pending = if let Some(pending) = &mut state.pending {
+                pending
+            } else {
+                ui.label("Compare a save with the open one to merge it");
+                return;
+            };
+            ui.label(format!(
+                "{} new splines, {} new switches, {} duplicates skipped",
+                pending.splines.len(),
+                pending.switches.len(),
+                pending.duplicates
+            ));
+            if !pending.conflicts.is_empty() {
+                ui.label(format!("{} conflicts", pending.conflicts.len()));
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        for conflict in pending.conflicts.iter_mut() {
+                            ui.label(conflict.description.as_str());
+                            ui.horizontal(|ui| {
+                                ui.radio_value(
+                                    &mut conflict.resolution,
+                                    Resolution::Ours,
+                                    "Keep ours",
+                                );
+                                ui.radio_value(
+                                    &mut conflict.resolution,
+                                    Resolution::Theirs,
+                                    "Take theirs",
+                                );
+                                ui.radio_value(
+                                    &mut conflict.resolution,
+                                    Resolution::Both,
+                                    "Keep both",
+                                );
+                            });
+                            ui.separator();
+                        }
+                    });
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Merge").clicked() {
+                    merge_events.send(MergeEvent::Apply);
+                }
+                if ui.button("Cancel").clicked() {
+                    state.pending = None;
+                }
+            });
+        });
+}
+
+fn merge_saves(
+    mut events: EventReader<MergeEvent>,
+    mut state: ResMut<MergeState>,
+    beziers: Query<(Entity, &PolyBezier<CubicBezier>)>,
+    switches: Query<(Entity, &Transform, &SwitchData)>,
+    mut modification: EventWriter<BezierModificaiton>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    for event in events.iter() {
+        match event {
+            MergeEvent::Compare(path) => {
+                match File::open(path)
+                    .map_err(GVASError::from)
+                    .and_then(|mut file| RROSave::read(&mut file))
+                    .and_then(|other| compare(&other, &beziers, &switches))
+                {
+                    Ok(pending) => state.pending = Some(pending),
+                    Err(e) => println!("Error: {:?}", e),
+                }
+            }
+            MergeEvent::Apply => {
+                let pending = if let Some(pending) = state.pending.take() {
+                    pending
+                } else {
+                    continue;
+                };
+                for spline in pending.splines {
+                    place_spline(spline, &mut modification);
+                }
+                for switch in pending.switches {
+                    place_switch(switch, &mut modification);
+                }
+                for conflict in pending.conflicts {
+                    if conflict.resolution == Resolution::Ours {
+                        continue;
+                    }
+                    let replace = conflict.resolution == Resolution::Theirs;
+                    match conflict.kind {
+                        ConflictKind::Spline { ours, theirs } => {
+                            // Ours may have been deleted since the saves were compared
+                            if replace && beziers.get(ours).is_ok() {
+                                modification.send(BezierModificaiton::DeleteCurve(ours));
+                            }
+                            place_spline(theirs, &mut modification);
+                        }
+                        ConflictKind::Switch { ours, theirs } => {
+                            match switches.get(ours) {
+                                Ok((_e, trans, switch)) if replace => {
+                                    modification.send(BezierModificaiton::DeleteSw(ours));
+                                    edits.send(EditEvent(PatchOp::DeleteSwitch {
+                                        location: vec_to_gvas(trans.translation),
+                                        ty: Some(switch.ty as u32),
+                                        rotation: Some(quat_to_rotator(trans.rotation)),
+                                    }));
+                                }
+                                _ => (),
+                            }
+                            place_switch(theirs, &mut modification);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use bevy_mod_picking::PickingCamera;
+
+use crate::control::DefaultAssets;
+use crate::dialog::Dialogs;
+use crate::gvas::SwitchData;
+use crate::palette::{MouseAction, Palette};
+use crate::patch::{EditEvent, SplineShape, SwitchPlacement};
+use crate::safety::{Moves, SafetyNet};
+use crate::selection::Selected;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierSectionUpdate, DragState};
+
+/// Height of the marker showing the mirror plane while it's drawn
+const MARKER_HEIGHT: f32 = 2.;
+
+/// Plugin for mirroring the selection across a plane, see `MouseAction::Mirror`
+pub struct MirrorPlugin;
+
+impl Plugin for MirrorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MirrorLine::default());
+        app.add_event::<MirrorEvent>();
+        app.add_system(draw_mirror_line);
+        app.add_system(show_mirror_line);
+        app.add_system(mirror_selection);
+    }
+}
+
+/// The line on the ground being dragged out, from where the drag started to the mouse
+#[derive(Debug, Default)]
+struct MirrorLine(Option<(Vec3, Vec3)>);
+
+/// Mirrors the selected control points and switches across the plane through `point` facing
+/// along `normal`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MirrorEvent {
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+impl MirrorEvent {
+    /// The mirror image of `p`
+    fn reflect(&self, p: Vec3) -> Vec3 {
+        p - 2. * (p - self.point).dot(self.normal) * self.normal
+    }
+
+    /// The mirror image of a switch's rotation. The image of a left switch is a right switch,
+    /// i.e. the same model flipped along its z axis, so that flip is undone to keep the rotation
+    /// a rotation
+    fn reflect_rotation(&self, rotation: Quat) -> Quat {
+        let n = self.normal;
+        let reflection = Mat3::IDENTITY - Mat3::from_cols(n * n.x, n * n.y, n * n.z) * 2.;
+        let flip = Mat3::from_diagonal(Vec3::new(1., 1., -1.));
+        Quat::from_mat3(&(reflection * Mat3::from_quat(rotation) * flip)).normalize()
+    }
+}
+
+/// In mirror mode, dragging out a line on the ground mirrors the selection across the upright
+/// plane through it
+fn draw_mirror_line(
+    palette: Res<Palette>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    pick_cam: Query<&PickingCamera>,
+    mut line: ResMut<MirrorLine>,
+    mut mirror: EventWriter<MirrorEvent>,
+) {
+    if palette.action != MouseAction::Mirror {
+        if line.0.is_some() {
+            line.0 = None;
+        }
+        return;
+    }
+    let ground = pick_cam.iter().last().and_then(|cam| {
+        cam.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+            point: Vec3::ZERO,
+            normal: Vec3::new(0., 1., 0.),
+        })
+    });
+    let ground = match ground {
+        Some(int) => int.position(),
+        None => return,
+    };
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        line.0 = Some((ground, ground));
+    } else if let Some((start, _end)) = line.0 {
+        if mouse_button_input.just_released(MouseButton::Left) {
+            line.0 = None;
+            let along = (ground - start) * Vec3::new(1., 0., 1.);
+            if along.length() > f32::EPSILON {
+                mirror.send(MirrorEvent {
+                    point: start,
+                    normal: along.cross(Vec3::Y).normalize(),
+                });
+            }
+        } else {
+            line.0 = Some((start, ground));
+        }
+    }
+}
+
+/// Marks the entity the mirror plane is drawn with
+#[derive(Debug, Component)]
+struct MirrorMarker;
+
+/// Draws the mirror plane as a thin wall while it's dragged out
+fn show_mirror_line(
+    line: Res<MirrorLine>,
+    assets: Res<DefaultAssets>,
+    markers: Query<Entity, With<MirrorMarker>>,
+    mut commands: Commands,
+) {
+    if !line.is_changed() {
+        return;
+    }
+    for entity in markers.iter() {
+        commands.entity(entity).despawn();
+    }
+    if let Some((start, end)) = line.0 {
+        let along = (end - start) * Vec3::new(1., 0., 1.);
+        if along.length() <= f32::EPSILON {
+            return;
+        }
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: assets.cube_mesh.clone(),
+                material: assets.ghost_material.clone(),
+                transform: Transform {
+                    translation: (start + end) / 2. + Vec3::Y * MARKER_HEIGHT / 2.,
+                    rotation: Quat::from_rotation_arc(Vec3::X, along.normalize()),
+                    scale: Vec3::new(along.length(), MARKER_HEIGHT, 0.02),
+                },
+                ..Default::default()
+            })
+            .insert(MirrorMarker);
+    }
+}
+
+/// Mirrors the selected control points and switches
+fn mirror_selection(
+    mut events: EventReader<MirrorEvent>,
+    settings: Res<Settings>,
+    assets: Res<DefaultAssets>,
+    mut handles: Query<(&DragState, &Parent, &mut Transform), With<Selected>>,
+    mut beziers: Query<&mut PolyBezier<CubicBezier>>,
+    mut switches: Query<
+        (&mut Transform, &mut SwitchData, &mut Handle<Mesh>, Entity),
+        (With<Selected>, Without<DragState>),
+    >,
+    mut section_update: EventWriter<BezierSectionUpdate>,
+    mut edits: EventWriter<EditEvent>,
+    (mut safety, mut dialogs): (ResMut<SafetyNet>, ResMut<Dialogs>),
+) {
+    for mirror in events.iter() {
+        let mut moves = Moves::default();
+        for (state, parent, mut trans) in handles.iter_mut() {
+            if let Ok(mut bez) = beziers.get_mut(parent.0) {
+                let from = bez.get_control_point(state.pt);
+                let to = mirror.reflect(from);
+                if from.distance(to) < f32::EPSILON {
+                    continue;
+                }
+                if moves.spline(parent.0, &bez) {
+                    section_update.send(BezierSectionUpdate { bezier: parent.0 });
+                }
+                bez.update(state.pt, to);
+                trans.translation = to + settings.curve_offset(bez.ty());
+            }
+        }
+        for (mut trans, mut switch, mut mesh, entity) in switches.iter_mut() {
+            moves.switch(entity, &trans, &switch);
+            trans.translation = mirror.reflect(trans.translation);
+            trans.rotation = mirror.reflect_rotation(trans.rotation);
+            switch.ty = switch.ty.mirrored();
+            trans.scale = switch.ty.scale();
+            *mesh = assets.switch_mesh[switch.ty].clone();
+        }
+        if moves.is_empty() {
+            warn!("Mirror: nothing selected");
+        }
+        let (mirrored, size) = moves.edits(
+            |e| beziers.get(e).ok().map(SplineShape::of),
+            |e| {
+                switches
+                    .get(e)
+                    .ok()
+                    .map(|(t, s, ..)| SwitchPlacement::of(t, s))
+            },
+        );
+        safety.keep(
+            mirrored,
+            size,
+            "mirror",
+            settings.edit_limit,
+            &mut dialogs,
+            &mut edits,
+        );
+    }
+}
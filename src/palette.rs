@@ -1,15 +1,104 @@
 
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-use crate::gvas::SplineType;
+use crate::control::{SaveCheck, SaveResult};
+use crate::coords::Units;
+use crate::dialog::{DialogEvent, DialogResponse, Dialogs};
+use crate::dock::Layout;
+use crate::gvas::{
+    industry_name, RROSave, ServiceKind, SplineType, FIREWOOD_DEPOT, INDUSTRY_TYPES, SERVICE_KINDS,
+};
+use crate::saves::{most_recent_save, SaveBrowser, SortBy};
+use crate::settings::Settings;
+use crate::spline::mesh::MeshQuality;
+use crate::theme::Theme;
 
 /// File events for load and save
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FileEvent {
     Load(PathBuf),
     Save(PathBuf),
+    /// Load a save exported as JSON
+    Import(PathBuf),
+    /// Save as JSON
+    Export(PathBuf),
+    /// Save without player names, money, or ids, see `RROSave::anonymize`
+    Share(PathBuf),
+}
+
+/// Steam app id of the game, which names its Proton prefix
+const STEAM_APP_ID: &str = "1696160";
+
+/// Where the game keeps its save slots, under a local appdata directory
+fn saves_in(local_appdata: PathBuf) -> PathBuf {
+    local_appdata.join("arr").join("Saved").join("SaveGames")
+}
+
+/// Value of a `"path"  "..."` line in Steam's `libraryfolders.vdf`
+fn library_path(line: &str) -> Option<PathBuf> {
+    let value = line.trim().strip_prefix("\"path\"")?.trim();
+    let value = value.strip_prefix('"')?.strip_suffix('"')?;
+    Some(PathBuf::from(value.replace("\\\\", "\\")))
+}
+
+/// Save directories in the game's Proton prefix, for each Steam library that has one. Other
+/// libraries are read from `libraryfolders.vdf`, so installs on other drives are found too
+pub fn proton_save_dirs() -> Vec<PathBuf> {
+    let home = match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home),
+        None => return vec![],
+    };
+    let mut libraries = vec![];
+    // Native, Debian's package, and Flatpak installs of Steam
+    for steam in [
+        ".steam/steam",
+        ".local/share/Steam",
+        ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    ] {
+        let steam = home.join(steam);
+        let vdf = steam.join("steamapps").join("libraryfolders.vdf");
+        if let Ok(vdf) = std::fs::read_to_string(vdf) {
+            libraries.extend(vdf.lines().filter_map(library_path));
+        }
+        libraries.push(steam);
+    }
+    let mut dirs = vec![];
+    for library in libraries {
+        let dir = saves_in(
+            library
+                .join("steamapps/compatdata")
+                .join(STEAM_APP_ID)
+                .join("pfx/drive_c/users/steamuser/AppData/Local"),
+        );
+        // ~/.steam/steam is usually a link to one of the others
+        if let Ok(dir) = dir.canonicalize() {
+            if dir.is_dir() && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+/// Directory the game keeps its save slots in. This is `Settings::save_dir` if it's set, then
+/// the one in local appdata on Windows, then the first one found in a Proton prefix
+pub fn save_dir(settings: &Settings) -> PathBuf {
+    if let Some(dir) = &settings.save_dir {
+        return dir.clone();
+    }
+    if let Some(local_appdata) = std::env::var_os("LOCALAPPDATA") {
+        return saves_in(PathBuf::from(local_appdata));
+    }
+    match proton_save_dirs().into_iter().next() {
+        Some(dir) => dir,
+        None => {
+            warn!("Could not find the save directory, set it in the file window");
+            PathBuf::from(".")
+        }
+    }
 }
 
 /// Tool Palette State
@@ -19,12 +108,79 @@ pub struct Palette {
     pub action: MouseAction,
     /// Lock z axis
     pub lock_z: bool,
+    /// In drag mode, move the selection with an axis gizmo instead of dragging handles, see
+    /// `gizmo.rs`
+    pub gizmo: bool,
     /// Enable snapping
     pub snapping: bool,
+    /// Move coincident endpoints of other splines along with the dragged endpoint
+    pub connected_move: bool,
     /// Show debug info
     pub show_debug: bool,
+    /// Show memory usage, see `diagnostics.rs`
+    pub show_diagnostics: bool,
+    /// Show the junction manager
+    pub show_junctions: bool,
+    /// Show the template list
+    pub show_templates: bool,
+    /// Show the train planner
+    pub show_planner: bool,
+    /// Show network statistics
+    pub show_stats: bool,
+    /// Show the patch file window
+    pub show_patch: bool,
+    /// Show the CSV export window
+    pub show_csv: bool,
+    /// Show the window for merging another save, see `merge.rs`
+    pub show_merge: bool,
+    /// Show the region list, see `regions.rs`
+    pub show_regions: bool,
+    /// Show the window for importing parts of another save, see `import.rs`
+    pub show_import: bool,
+    /// Show every property in the save, see `properties.rs`
+    pub show_properties: bool,
+    /// Show the player editor
+    pub show_players: bool,
+    /// Show the macro recorder
+    pub show_macros: bool,
+    /// Show the plan view, a second window looking straight down at the map, see `view.rs`
+    pub plan_view: bool,
+    /// Show the property panel for the selected spline
+    pub show_inspector: bool,
+    /// Show the project list
+    pub show_projects: bool,
+    /// Show the workspace window, see `workspace.rs`
+    pub show_workspace: bool,
+    /// Show the industry siding check, see `sidings.rs`
+    pub show_sidings: bool,
+    /// Show spline counts against their limits, see `budget.rs`
+    pub show_budget: bool,
+    /// Show track with nothing under it, see `support.rs`
+    pub show_support: bool,
+    /// Show the tool for levelling the selection, see `level.rs`
+    pub show_level: bool,
+    /// Show the spline generators, see `generator.rs`
+    pub show_generators: bool,
+    /// Show the tutorial tasks, see `tutorial.rs`
+    pub show_tutorial: bool,
+    /// Show the save date and ids, see `header.rs`
+    pub show_header: bool,
+    /// Color splines by elevation, see `elevation.rs`
+    pub elevation_colors: bool,
+    /// Fade out every spline except the selected one, see `fade.rs`
+    pub fade_unselected: bool,
+    /// Label each segment with its grade, see `labels.rs`
+    pub grade_labels: bool,
+    /// Label each segment with its tightest radius, see `labels.rs`
+    pub radius_labels: bool,
+    /// Show markers for removed trees and rocks, see `vegetation.rs`
+    pub show_vegetation: bool,
+    /// Industry type to place
+    pub industry_ty: u32,
     /// Current file action
     file_action: FileAction,
+    /// JSON file entered for importing or exporting, instead of one next to a save
+    json_path: String,
 }
 
 /// Current file action
@@ -36,6 +192,12 @@ pub enum FileAction {
     Open,
     /// Save file
     Save,
+    /// Import file from JSON
+    Import,
+    /// Export file as JSON
+    Export,
+    /// Save a copy for sharing
+    Share,
 }
 
 /// Current action when mouse is clicked
@@ -53,18 +215,36 @@ pub enum MouseAction {
     Place,
     /// Toggle visibility of individual sections
     ToggleVisibility,
+    /// Split splines in two at the clicked point
+    Split,
+    /// Add a control point to a spline at the clicked point
+    Insert,
+    /// Show where along a spline the mouse is, see `measure.rs`
+    Measure,
+    /// Select every handle and switch in a dragged out rectangle, see `selection.rs`
+    BoxSelect,
     /// Set the spline type of given spline
     SetSplineType(SplineType),
+    /// Place a preview of a template, see `template.rs`
+    Stamp,
+    /// Place new industries of the given type
+    PlaceIndustry(u32),
+    /// Place new turntables
+    PlaceTurntable,
+    /// Place new service structures of the given kind
+    PlaceService(ServiceKind),
+    /// Restore or clear removed vegetation, see `vegetation.rs`
+    VegetationBrush,
+    /// Mark or unmark the clicked spline end as the end of the track, see `buffers.rs`
+    BufferStop,
+    /// Drag out a region on the ground, see `regions.rs`
+    DrawRegion,
+    /// Mirror the selection across a line dragged out on the ground, see `mirror.rs`
+    Mirror,
+    /// Make the clicked section straight, see `reshape.rs`
+    Straighten,
 }
 
-const SPLINE_TYPES: [(SplineType, &str); 5] = [
-    (SplineType::Track, "Set Track"),
-    (SplineType::TrackBed, "Set Track Bed"),
-    (SplineType::GroundWork, "Set GroundWork"),
-    (SplineType::WoodBridge, "Set Wood Bridge"),
-    (SplineType::SteelBridge, "Set Steel Bridge"),
-];
-
 /// Plugin for the tool palette
 pub struct PalettePlugin;
 
@@ -73,13 +253,92 @@ impl Plugin for PalettePlugin {
         app.insert_resource(Palette {
             action: MouseAction::Drag,
             file_action: FileAction::None,
+            json_path: String::new(),
             lock_z: true,
+            gizmo: false,
             show_debug: cfg!(debug_assertions),
+            show_diagnostics: false,
+            show_junctions: false,
+            show_templates: false,
+            show_planner: false,
+            show_stats: false,
+            show_patch: false,
+            show_csv: false,
+            show_import: false,
+            show_merge: false,
+            show_regions: false,
+            show_properties: false,
+            show_players: false,
+            show_macros: false,
+            plan_view: false,
+            show_inspector: false,
+            show_projects: false,
+            show_workspace: false,
+            show_sidings: false,
+            show_budget: false,
+            show_support: false,
+            show_level: false,
+            show_generators: false,
+            show_tutorial: false,
+            show_header: false,
+            elevation_colors: false,
+            fade_unselected: false,
+            grade_labels: false,
+            radius_labels: false,
+            show_vegetation: false,
             snapping: false,
+            connected_move: false,
+            industry_ty: 1,
         });
         app.add_system(egui_system);
+        app.add_system(confirm_overwrite);
         app.add_event::<FileEvent>();
         app.insert_resource(DebugInfo::default());
+        app.insert_resource(PendingOverwrite::default());
+    }
+}
+
+/// Id of the dialog asking whether to replace an existing file
+const OVERWRITE_DIALOG: &str = "overwrite";
+
+/// A save or export waiting to be allowed to replace the file it writes
+#[derive(Debug, Default)]
+pub struct PendingOverwrite(Option<FileEvent>);
+
+/// Sends a save or export, first asking whether to replace the file it writes if there already
+/// is one
+pub fn save_to(
+    event: FileEvent,
+    pending: &mut PendingOverwrite,
+    dialogs: &mut Dialogs,
+    file_events: &mut EventWriter<FileEvent>,
+) {
+    match &event {
+        FileEvent::Save(path) | FileEvent::Export(path) | FileEvent::Share(path)
+            if path.exists() =>
+        {
+            dialogs.confirm(
+                OVERWRITE_DIALOG,
+                "Replace File",
+                format!("{} already exists. Replace it?", path.display()),
+                "Replace",
+            );
+            pending.0 = Some(event);
+        }
+        _ => file_events.send(event),
+    }
+}
+
+fn confirm_overwrite(
+    mut responses: EventReader<DialogEvent>,
+    mut pending: ResMut<PendingOverwrite>,
+    mut file_events: EventWriter<FileEvent>,
+) {
+    for response in responses.iter().filter(|r| r.id == OVERWRITE_DIALOG) {
+        match (&response.response, pending.0.take()) {
+            (DialogResponse::Ok, Some(event)) => file_events.send(event),
+            _ => (),
+        }
     }
 }
 
@@ -95,80 +354,509 @@ fn egui_system(
     mut state: ResMut<Palette>,
     mut file_events: EventWriter<FileEvent>,
     debug_info: Res<DebugInfo>,
+    mut gvas: ResMut<RROSave>,
+    mut settings: ResMut<Settings>,
+    mut browser: ResMut<SaveBrowser>,
+    check: Res<SaveCheck>,
+    mut proton_dirs: Local<Vec<PathBuf>>,
+    mut layout: ResMut<Layout>,
+    (mut overwrite, mut dialogs): (ResMut<PendingOverwrite>, ResMut<Dialogs>),
 ) {
     let state = state.as_mut();
-    egui::Window::new("Palette")
-        .resizable(false)
-        .show(egui_context.ctx_mut(), |ui| {
-            ui.label("File");
-            if ui.button("Open").clicked() {
-                state.file_action = FileAction::Open;
+    let window = egui::Window::new("Palette").resizable(false);
+    layout.show(egui_context.ctx_mut(), "Palette", window, |ui| {
+        if let Some(warning) = gvas.version().warning() {
+            ui.colored_label(egui::Color32::YELLOW, warning);
+        }
+        match &check.0 {
+            Some(SaveResult { path, error: None }) => {
+                ui.label(format!("Saved and verified {}", path.display()));
+            }
+            Some(SaveResult {
+                path,
+                error: Some(error),
+            }) => {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!(
+                        "Saving {} failed, the file wasn't changed: {}",
+                        path.display(),
+                        error
+                    ),
+                );
             }
-            if ui.button("Save").clicked() {
-                state.file_action = FileAction::Save;
+            None => (),
+        }
+        ui.label("File");
+        if ui.button("Open").clicked() {
+            state.file_action = FileAction::Open;
+            browser.rescan(save_dir(&settings));
+        }
+        if ui.button("Open Most Recent").clicked() {
+            let dir = save_dir(&settings);
+            match most_recent_save(&dir) {
+                Ok(Some(path)) => file_events.send(FileEvent::Load(path)),
+                Ok(None) => dialogs.message(
+                    "no_saves",
+                    "No Saves Found",
+                    format!("There are no saves in {}", dir.display()),
+                ),
+                Err(e) => dialogs.message(
+                    "scan_failed",
+                    "Could Not List Saves",
+                    format!("{}\n{}", dir.display(), e),
+                ),
             }
-            ui.label("Actions");
-            ui.radio_value(&mut state.action, MouseAction::Drag, "Drag");
-            ui.radio_value(&mut state.action, MouseAction::Extrude, "Extrude");
-            ui.radio_value(&mut state.action, MouseAction::Link, "Link(WIP)");
-            ui.radio_value(&mut state.action, MouseAction::Delete, "Delete");
-            ui.radio_value(&mut state.action, MouseAction::Place, "Place(WIP)");
-            ui.radio_value(&mut state.action, MouseAction::ToggleVisibility, "ToggleVisibility");
-            for (ty, text) in SPLINE_TYPES {
-                ui.radio_value(&mut state.action, MouseAction::SetSplineType(ty), text);
+        }
+        if ui.button("Save").clicked() {
+            state.file_action = FileAction::Save;
+            browser.rescan(save_dir(&settings));
+        }
+        if ui.button("Import JSON").clicked() {
+            state.file_action = FileAction::Import;
+            browser.rescan(save_dir(&settings));
+        }
+        if ui.button("Export JSON").clicked() {
+            state.file_action = FileAction::Export;
+            browser.rescan(save_dir(&settings));
+        }
+        if ui
+            .button("Save for Sharing")
+            .on_hover_text("Saves a copy without player names, money, XP, or steam ids")
+            .clicked()
+        {
+            state.file_action = FileAction::Share;
+            browser.rescan(save_dir(&settings));
+        }
+        if ui.button("Import from Save").clicked() {
+            state.show_import = true;
+        }
+        ui.label("Actions");
+        ui.radio_value(&mut state.action, MouseAction::Drag, "Drag");
+        if state.action == MouseAction::Drag {
+            ui.label("Once grabbed, hold Shift to drag along the track, Ctrl sideways, Alt vertically");
+            ui.label("Hold Space when grabbing to move the whole spline, Q and E turn it");
+            if state.gizmo {
+                ui.label("Click to select, then drag the gizmo to move the selection");
             }
-            ui.label("Options");
-            ui.checkbox(&mut state.lock_z, "Lock Z");
-            ui.checkbox(&mut state.show_debug, "Show Debug Info");
-            ui.checkbox(&mut state.snapping, "Snapping(WIP)");
+        }
+        ui.radio_value(&mut state.action, MouseAction::Extrude, "Extrude");
+        ui.radio_value(&mut state.action, MouseAction::Link, "Link(WIP)");
+        ui.radio_value(&mut state.action, MouseAction::Delete, "Delete");
+        ui.radio_value(&mut state.action, MouseAction::Place, "Place(WIP)");
+        ui.radio_value(&mut state.action, MouseAction::ToggleVisibility, "ToggleVisibility");
+        ui.radio_value(&mut state.action, MouseAction::Split, "Split");
+        ui.radio_value(&mut state.action, MouseAction::Insert, "Insert");
+        ui.radio_value(&mut state.action, MouseAction::Measure, "Measure");
+        ui.radio_value(&mut state.action, MouseAction::BoxSelect, "Box Select");
+        for info in settings.spline_types.types.iter().filter(|info| info.placeable) {
+            ui.radio_value(
+                &mut state.action,
+                MouseAction::SetSplineType(info.ty),
+                format!("Set {}", info.name),
+            );
+        }
+        ui.horizontal(|ui| {
+            ui.radio_value(
+                &mut state.action,
+                MouseAction::PlaceIndustry(state.industry_ty),
+                "Place Industry",
+            );
+            egui::ComboBox::from_id_source("industry_ty")
+                .selected_text(industry_name(state.industry_ty).to_string())
+                .show_ui(ui, |ui| {
+                    for ty in INDUSTRY_TYPES {
+                        ui.selectable_value(
+                            &mut state.industry_ty,
+                            ty,
+                            industry_name(ty).to_string(),
+                        );
+                    }
+                });
+        });
+        if let MouseAction::PlaceIndustry(ty) = &mut state.action {
+            *ty = state.industry_ty;
+        }
+        ui.radio_value(&mut state.action, MouseAction::PlaceTurntable, "Place Turntable");
+        for kind in SERVICE_KINDS {
+            ui.radio_value(
+                &mut state.action,
+                MouseAction::PlaceService(kind),
+                format!("Place {}", kind.name()),
+            );
+        }
+        // Firewood depots are industries, but are placed and snapped like service structures
+        let depot = MouseAction::PlaceIndustry(FIREWOOD_DEPOT);
+        if ui.radio(state.action == depot, "Place Firewood Depot").clicked() {
+            state.industry_ty = FIREWOOD_DEPOT;
+            state.action = depot;
+        }
+        ui.radio_value(&mut state.action, MouseAction::VegetationBrush, "Vegetation Brush");
+        ui.radio_value(&mut state.action, MouseAction::BufferStop, "Buffer Stop");
+        if state.action == MouseAction::BufferStop {
+            ui.label("Click the end of a spline. Stops are only kept in workspaces");
+        }
+        ui.radio_value(&mut state.action, MouseAction::DrawRegion, "Draw Region");
+        ui.radio_value(&mut state.action, MouseAction::Mirror, "Mirror");
+        if state.action == MouseAction::Mirror {
+            ui.label("Drag a line on the ground to mirror the selection across");
+        }
+        ui.radio_value(&mut state.action, MouseAction::Straighten, "Straighten");
+        if state.action == MouseAction::Straighten {
+            ui.label("Click a section, or a spline with points selected to straighten between them");
+        }
+        ui.label("Options");
+        ui.horizontal(|ui| {
+            ui.label("Units");
+            ui.radio_value(&mut settings.units, Units::Metric, "Metric");
+            ui.radio_value(&mut settings.units, Units::Imperial, "Imperial");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Theme");
+            ui.radio_value(&mut settings.theme, Theme::Dark, "Dark");
+            ui.radio_value(&mut settings.theme, Theme::Light, "Light");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Text Size");
+            ui.add(
+                egui::DragValue::new(&mut settings.text_scale)
+                    .clamp_range(0.75..=2.)
+                    .speed(0.01),
+            );
+        });
+        ui.checkbox(&mut settings.fast_load, "Fast Load (handles on hover)");
+        ui.horizontal(|ui| {
+            ui.label("Mesh Quality");
+            for (quality, name) in [
+                (MeshQuality::Draft, "Draft"),
+                (MeshQuality::Normal, "Normal"),
+                (MeshQuality::High, "High"),
+            ] {
+                if ui
+                    .radio_value(&mut settings.mesh_quality, quality, name)
+                    .clicked()
+                {
+                    settings.mesh_detail = quality.detail();
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let units = settings.units;
+            let detail = &mut settings.mesh_detail;
+            ui.label("Cut Every");
+            let mut step = units.from_meters(detail.step);
+            if ui
+                .add(
+                    egui::DragValue::new(&mut step)
+                        .clamp_range(units.from_meters(0.1)..=units.from_meters(100.))
+                        .suffix(units.suffix()),
+                )
+                .changed()
+            {
+                detail.step = units.to_meters(step);
+            }
+            ui.label("within");
+            let mut error = units.from_meters(detail.error);
+            if ui
+                .add(
+                    egui::DragValue::new(&mut error)
+                        .clamp_range(units.from_meters(0.01)..=units.from_meters(10.))
+                        .suffix(units.suffix()),
+                )
+                .changed()
+            {
+                detail.error = units.to_meters(error);
+            }
+            ui.label("and into");
+            ui.add(
+                egui::DragValue::new(&mut detail.subdivision)
+                    .clamp_range(1..=32)
+                    .suffix(" sections"),
+            );
+        });
+        ui.checkbox(&mut settings.draft_while_dragging, "Draft Meshes While Dragging");
+        ui.checkbox(&mut settings.centerlines_only, "Centerlines Only");
+        ui.horizontal(|ui| {
+            let units = settings.units;
+            let mut declutter = settings.declutter_distance.is_some();
+            ui.checkbox(&mut declutter, "Declutter Beyond");
+            let mut v = units.from_meters(settings.declutter_distance.unwrap_or(1000.));
+            ui.add_enabled(
+                declutter,
+                egui::DragValue::new(&mut v)
+                    .clamp_range(units.from_meters(50.)..=units.from_meters(20000.))
+                    .suffix(units.suffix()),
+            );
+            settings.declutter_distance = if declutter {
+                Some(units.to_meters(v))
+            } else {
+                None
+            };
+        });
+        ui.checkbox(&mut settings.verify_saves, "Verify Saves");
+        ui.horizontal(|ui| {
+            let mut limit = settings.drag_max_grade.is_some();
+            ui.checkbox(&mut limit, "Max Grade");
+            let mut grade = settings.drag_max_grade.unwrap_or(3.);
+            ui.add_enabled(
+                limit,
+                egui::DragValue::new(&mut grade)
+                    .clamp_range(0.1..=20.)
+                    .speed(0.1)
+                    .suffix("%"),
+            );
+            settings.drag_max_grade = if limit { Some(grade) } else { None };
+        });
+        ui.horizontal(|ui| {
+            let units = settings.units;
+            let mut limit = settings.drag_min_radius.is_some();
+            ui.checkbox(&mut limit, "Min Radius");
+            let mut v = units.from_meters(settings.drag_min_radius.unwrap_or(100.));
+            ui.add_enabled(
+                limit,
+                egui::DragValue::new(&mut v)
+                    .clamp_range(units.from_meters(10.)..=units.from_meters(2000.))
+                    .suffix(units.suffix()),
+            );
+            settings.drag_min_radius = if limit {
+                Some(units.to_meters(v))
+            } else {
+                None
+            };
+        });
+        ui.horizontal(|ui| {
+            let mut confirm = settings.edit_limit.is_some();
+            ui.checkbox(&mut confirm, "Confirm Edits Over");
+            let mut limit = settings.edit_limit.unwrap_or(100);
+            ui.add_enabled(
+                confirm,
+                egui::DragValue::new(&mut limit)
+                    .clamp_range(1..=10000)
+                    .suffix(" points"),
+            );
+            settings.edit_limit = if confirm { Some(limit) } else { None };
         });
-    if matches!(state.file_action, FileAction::Open | FileAction::Save) {
+        ui.checkbox(&mut state.lock_z, "Lock Z");
+        ui.checkbox(&mut state.gizmo, "Axis Gizmo");
+        ui.checkbox(&mut state.plan_view, "Plan View");
+        ui.checkbox(&mut state.elevation_colors, "Color by Elevation");
+        ui.checkbox(&mut state.fade_unselected, "Fade Unselected Splines");
+        ui.checkbox(&mut state.grade_labels, "Grade Labels");
+        ui.checkbox(&mut state.radius_labels, "Radius Labels");
+        ui.checkbox(&mut state.show_vegetation, "Show Removed Vegetation");
+        ui.checkbox(&mut state.show_debug, "Show Debug Info");
+        ui.checkbox(&mut state.show_diagnostics, "Show Memory Usage");
+        ui.checkbox(&mut state.show_header, "Show Save Info");
+        ui.checkbox(&mut state.show_properties, "Show Save Properties");
+        ui.checkbox(&mut state.show_merge, "Show Merge Saves");
+        ui.checkbox(&mut state.show_regions, "Show Regions");
+        ui.checkbox(&mut state.show_junctions, "Show Junctions");
+        ui.checkbox(&mut state.show_templates, "Show Templates");
+        ui.checkbox(&mut state.show_planner, "Show Train Planner");
+        ui.checkbox(&mut state.show_stats, "Show Network Statistics");
+        ui.checkbox(&mut state.show_patch, "Show Patch Files");
+        ui.checkbox(&mut state.show_csv, "Show CSV Export");
+        ui.checkbox(&mut state.show_players, "Show Players");
+        ui.checkbox(&mut state.show_macros, "Show Macros");
+        ui.checkbox(&mut state.show_inspector, "Show Spline Properties");
+        ui.checkbox(&mut state.show_projects, "Show Projects");
+        ui.checkbox(&mut state.show_workspace, "Show Workspace");
+        ui.checkbox(&mut state.show_sidings, "Show Industry Sidings");
+        ui.checkbox(&mut state.show_budget, "Show Spline Budget");
+        ui.checkbox(&mut state.show_support, "Show Unsupported Track");
+        ui.checkbox(&mut state.show_level, "Show Level Heights");
+        ui.checkbox(&mut state.show_generators, "Show Generators");
+        ui.checkbox(&mut state.show_tutorial, "Show Tutorial");
+        ui.checkbox(&mut state.snapping, "Snapping(WIP)");
+        ui.checkbox(&mut state.connected_move, "Connected Move");
+    });
+    if state.file_action != FileAction::None {
+        let mut chosen = None;
+        // A JSON file entered by hand, rather than one next to the chosen save
+        let mut chosen_json = None;
+        let mut cancel = false;
         egui::Window::new("File")
             .resizable(false)
             .show(egui_context.ctx_mut(), |ui| {
-                if let Some(save) = if ui.button("Slot 1").clicked() {
-                    Some("slot1.sav")
-                } else if ui.button("Slot 2").clicked() {
-                    Some("slot2.sav")
-                } else if ui.button("Slot 3").clicked() {
-                    Some("slot3.sav")
-                } else if ui.button("Slot 4").clicked() {
-                    Some("slot4.sav")
-                } else if ui.button("Slot 5").clicked() {
-                    Some("slot5.sav")
-                } else if ui.button("Slot 6").clicked() {
-                    Some("slot6.sav")
-                } else if ui.button("Slot 7").clicked() {
-                    Some("slot7.sav")
-                } else if ui.button("Slot 8").clicked() {
-                    Some("slot8.sav")
-                } else if ui.button("Slot 9").clicked() {
-                    Some("slot9.sav")
-                } else if ui.button("Slot 10").clicked() {
-                    Some("slot10.sav")
-                } else {
-                    None
-                } {
-                    let path = PathBuf::from(std::env::var("LOCALAPPDATA").expect("Could not find local appdata"))
-                        .join("arr")
-                        .join("Saved")
-                        .join("SaveGames")
-                        .join(save);
-                    match state.file_action {
-                        FileAction::Open => file_events.send(FileEvent::Load(path)),
-                        FileAction::Save => file_events.send(FileEvent::Save(path)),
-                        _ => unreachable!(),
+                ui.horizontal(|ui| {
+                    ui.label("Save Directory");
+                    let mut dir = settings
+                        .save_dir
+                        .as_ref()
+                        .map_or(String::new(), |d| d.display().to_string());
+                    let edit = ui.add(
+                        egui::TextEdit::singleline(&mut dir)
+                            .hint_text(browser.dir.display().to_string()),
+                    );
+                    if edit.changed() {
+                        settings.save_dir = if dir.is_empty() {
+                            None
+                        } else {
+                            Some(PathBuf::from(dir))
+                        };
                     }
-                    state.file_action = FileAction::None;
+                    if edit.lost_focus() {
+                        browser.rescan(save_dir(&settings));
+                    }
+                    if ui
+                        .button("Find Proton Saves")
+                        .on_hover_text("Looks for the game's saves in Steam's Proton prefixes")
+                        .clicked()
+                    {
+                        *proton_dirs = proton_save_dirs();
+                        if proton_dirs.is_empty() {
+                            warn!("No Proton saves found");
+                        }
+                    }
+                });
+                for dir in proton_dirs.iter() {
+                    let selected = settings.save_dir.as_ref() == Some(dir);
+                    if ui
+                        .selectable_label(selected, dir.display().to_string())
+                        .clicked()
+                    {
+                        settings.save_dir = Some(dir.clone());
+                        browser.rescan(dir.clone());
+                    }
+                }
+                if matches!(state.file_action, FileAction::Import | FileAction::Export) {
+                    ui.horizontal(|ui| {
+                        ui.label("JSON File");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut state.json_path)
+                                .hint_text("or pick a save below"),
+                        );
+                        let text = if state.file_action == FileAction::Import {
+                            "Import"
+                        } else {
+                            "Export"
+                        };
+                        if ui
+                            .add_enabled(!state.json_path.is_empty(), egui::Button::new(text))
+                            .clicked()
+                        {
+                            chosen_json = Some(PathBuf::from(&state.json_path));
+                        }
+                    });
                 }
+                ui.horizontal(|ui| {
+                    if ui.button("Rescan").clicked() {
+                        browser.rescan(save_dir(&settings));
+                    }
+                    cancel = ui.button("Cancel").clicked();
+                    if browser.scanning() {
+                        ui.label("Scanning...");
+                    }
+                });
+                egui::ScrollArea::vertical().max_height(400.).show(ui, |ui| {
+                    egui::Grid::new("saves").striped(true).show(ui, |ui| {
+                        for (by, text) in [
+                            (SortBy::Name, "Name"),
+                            (SortBy::Date, "Date"),
+                            (SortBy::Splines, "Splines"),
+                        ] {
+                            if ui.button(text).clicked() {
+                                browser.sort_by(by);
+                            }
+                        }
+                        ui.label("Players");
+                        ui.end_row();
+                        for save in browser.saves.iter() {
+                            // Empty slots can't be opened, but can be saved or exported to
+                            let enabled = save.exists || state.file_action != FileAction::Open;
+                            if ui
+                                .add_enabled(enabled, egui::Button::new(save.name.as_str()))
+                                .clicked()
+                            {
+                                chosen = Some(save.path.clone());
+                            }
+                            ui.label(save.date.as_deref().unwrap_or(""));
+                            ui.label(save.splines.map_or(String::new(), |n| n.to_string()));
+                            if let Some(error) = &save.error {
+                                ui.colored_label(egui::Color32::RED, "Unreadable")
+                                    .on_hover_text(error.as_str());
+                            } else {
+                                ui.label(save.players.join(", "));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
             });
+        if let Some(json) = chosen_json {
+            match state.file_action {
+                FileAction::Import => file_events.send(FileEvent::Import(json)),
+                _ => save_to(
+                    FileEvent::Export(json),
+                    &mut overwrite,
+                    &mut dialogs,
+                    &mut file_events,
+                ),
+            }
+            state.file_action = FileAction::None;
+        } else if let Some(path) = chosen {
+            // Exports sit next to the save they came from
+            let json = path.with_extension("json");
+            match state.file_action {
+                FileAction::Open => file_events.send(FileEvent::Load(path)),
+                FileAction::Save => save_to(
+                    FileEvent::Save(path),
+                    &mut overwrite,
+                    &mut dialogs,
+                    &mut file_events,
+                ),
+                FileAction::Import => file_events.send(FileEvent::Import(json)),
+                FileAction::Export => save_to(
+                    FileEvent::Export(json),
+                    &mut overwrite,
+                    &mut dialogs,
+                    &mut file_events,
+                ),
+                FileAction::Share => save_to(
+                    FileEvent::Share(path),
+                    &mut overwrite,
+                    &mut dialogs,
+                    &mut file_events,
+                ),
+                FileAction::None => unreachable!(),
+            }
+            state.file_action = FileAction::None;
+        } else if cancel {
+            state.file_action = FileAction::None;
+        }
     }
-    if state.show_debug {
-        egui::Window::new("Debugging Info")
+    if state.show_players {
+        let mut players: Vec<_> = gvas.players().map_or(vec![], |p| p.collect());
+        let mut changed = false;
+        egui::Window::new("Players")
             .resizable(false)
             .show(egui_context.ctx_mut(), |ui| {
-                ui.label("Hovered object:");
-                ui.code(&debug_info.hovered);
+                egui::Grid::new("players").show(ui, |ui| {
+                    ui.label("Name");
+                    ui.label("Money");
+                    ui.label("XP");
+                    ui.end_row();
+                    for player in players.iter_mut() {
+                        ui.label(player.name.as_str());
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut player.money).prefix("$"))
+                            .changed();
+                        changed |= ui.add(egui::DragValue::new(&mut player.xp)).changed();
+                        ui.end_row();
+                    }
+                });
             });
+        if changed {
+            if let Err(e) = gvas.set_players(players.into_iter()) {
+                println!("Error: {:?}", e);
+            }
+        }
+    }
+    if state.show_debug {
+        let window = egui::Window::new("Debugging Info").resizable(false);
+        layout.show(egui_context.ctx_mut(), "Debugging Info", window, |ui| {
+            ui.label("Hovered object:");
+            ui.code(&debug_info.hovered);
+        });
     }
 }
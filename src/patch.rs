@@ -0,0 +1,536 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::control::DefaultAssets;
+use crate::coords::{gvas_to_vec, quat_to_rotator, rotator_to_quat, vec_to_gvas};
+use crate::dialog::Dialogs;
+use crate::gvas::{IndustryData, SplineType, SwitchData, SwitchType};
+use crate::junction::JUNCTION_EPSILON;
+use crate::palette::Palette;
+use crate::safety::{EditSize, SafetyNet};
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{spawn_bezier, BezierModificaiton, BezierSectionUpdate, DragState};
+
+/// Plugin for applying patch files to the open save
+pub struct PatchPlugin;
+
+impl Plugin for PatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PatchFile {
+            path: "patch.json".to_string(),
+        });
+        app.add_event::<PatchEvent>();
+        app.add_event::<EditEvent>();
+        app.add_system(patch_window);
+        app.add_system(apply_patches);
+    }
+}
+
+/// A single edit in a patch file. Patch files are a JSON list of these, with locations in save
+/// coordinates, e.g.
+/// `[{"op": "move_point", "from": [100, 200, 0], "to": [100, 200, 50]}]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PatchOp {
+    /// Move every spline control point at `from` to `to`
+    MovePoint { from: [f32; 3], to: [f32; 3] },
+    /// Give the spline with exactly the control points of `from` the shape `to`. Edits made in
+    /// the editor are recorded this way, so undoing one only changes the splines it changed
+    ReshapeSpline { from: SplineShape, to: SplineShape },
+    /// Add a new spline. `ty` is the spline type as stored in the save, and every segment is
+    /// visible if `visibility` is left out
+    AddSpline {
+        ty: u32,
+        points: Vec<[f32; 3]>,
+        #[serde(default)]
+        visibility: Option<Vec<bool>>,
+    },
+    /// Delete the switch at `location`. Undoing it needs the switch's type and rotation
+    DeleteSwitch {
+        location: [f32; 3],
+        #[serde(default)]
+        ty: Option<u32>,
+        #[serde(default)]
+        rotation: Option<[f32; 3]>,
+    },
+    /// Add a new switch. `ty` is the switch type as stored in the save
+    AddSwitch {
+        ty: u32,
+        location: [f32; 3],
+        rotation: [f32; 3],
+    },
+    /// Move the switch placed as `from` to `to`, which can also turn it or change its type, e.g.
+    /// when it's mirrored
+    MoveSwitch {
+        from: SwitchPlacement,
+        to: SwitchPlacement,
+    },
+    /// Delete the spline with exactly these control points. Undoing it needs the spline's type,
+    /// and its visibility if any segment is hidden
+    DeleteSpline {
+        points: Vec<[f32; 3]>,
+        #[serde(default)]
+        ty: Option<u32>,
+        #[serde(default)]
+        visibility: Option<Vec<bool>>,
+    },
+    /// Add a new industry, with nothing in storage. `ty` is the industry type as stored in the
+    /// save
+    AddIndustry {
+        ty: u32,
+        location: [f32; 3],
+        rotation: [f32; 3],
+    },
+    /// Delete the industry at `location`. Undoing it needs the industry's type and rotation
+    DeleteIndustry {
+        location: [f32; 3],
+        #[serde(default)]
+        ty: Option<u32>,
+        #[serde(default)]
+        rotation: Option<[f32; 3]>,
+    },
+}
+
+impl PatchOp {
+    /// The edit that adds `bez`
+    pub fn add_spline(bez: &PolyBezier<CubicBezier>) -> Self {
+        Self::AddSpline {
+            ty: bez.ty().into(),
+            points: bez.get_control_points().map(vec_to_gvas).collect(),
+            visibility: Some(bez.get_visibility().to_vec()),
+        }
+    }
+
+    /// The edit that deletes `bez`, with everything needed to undo it
+    pub fn delete_spline(bez: &PolyBezier<CubicBezier>) -> Self {
+        Self::DeleteSpline {
+            points: bez.get_control_points().map(vec_to_gvas).collect(),
+            ty: Some(bez.ty().into()),
+            visibility: Some(bez.get_visibility().to_vec()),
+        }
+    }
+
+    /// The edit that changed `bez` from the shape `from`, if it changed at all
+    pub fn reshaped(from: SplineShape, bez: &PolyBezier<CubicBezier>) -> Option<Self> {
+        let to = SplineShape::of(bez);
+        if from == to {
+            None
+        } else {
+            Some(Self::ReshapeSpline { from, to })
+        }
+    }
+}
+
+/// A spline's control points, in save coordinates, and whether each segment is shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplineShape {
+    pub points: Vec<[f32; 3]>,
+    pub visibility: Vec<bool>,
+}
+
+impl SplineShape {
+    pub fn of(bez: &PolyBezier<CubicBezier>) -> Self {
+        Self {
+            points: bez.get_control_points().map(vec_to_gvas).collect(),
+            visibility: bez.get_visibility().to_vec(),
+        }
+    }
+
+    /// The shape `bez` had before the control points in `moved` were moved, given as the index
+    /// of each point and where it was
+    pub fn before(
+        bez: &PolyBezier<CubicBezier>,
+        moved: impl IntoIterator<Item = (usize, Vec3)>,
+    ) -> Self {
+        let mut shape = Self::of(bez);
+        for (pt, from) in moved {
+            if let Some(point) = shape.points.get_mut(pt) {
+                *point = vec_to_gvas(from);
+            }
+        }
+        shape
+    }
+
+    /// Whether `bez` has exactly these control points
+    fn matches(&self, bez: &PolyBezier<CubicBezier>) -> bool {
+        bez.len() == self.points.len()
+            && bez
+                .get_control_points()
+                .zip(self.points.iter())
+                .all(|(a, b)| a.distance(gvas_to_vec(*b)) < JUNCTION_EPSILON)
+    }
+}
+
+/// A switch's type as stored in the save, and where it is, in save coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SwitchPlacement {
+    pub ty: u32,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+}
+
+impl SwitchPlacement {
+    pub fn of(trans: &Transform, switch: &SwitchData) -> Self {
+        Self {
+            ty: switch.ty as u32,
+            location: vec_to_gvas(trans.translation),
+            rotation: quat_to_rotator(trans.rotation),
+        }
+    }
+}
+
+/// Reads a patch file
+pub fn read_patch(path: &PathBuf) -> Result<Vec<PatchOp>, serde_json::Error> {
+    let file = File::open(path).map_err(serde_json::Error::io)?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+}
+
+/// Patches to apply to the open save
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchEvent {
+    /// Apply the patch file at the given path
+    File(PathBuf),
+    /// Apply a list of operations, e.g. from a macro
+    Ops(Vec<PatchOp>),
+    /// Apply operations without asking first, even if there are a lot of them, see `safety.rs`
+    Confirmed(Vec<PatchOp>),
+    /// Undo edits, without asking and without adding them to the history, see `history.rs`
+    Undo(Vec<PatchOp>),
+}
+
+/// Sent for every edit made in the editor that can be represented as a `PatchOp`, including
+/// applied patches. Edits sent in the same frame are undone together
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditEvent(pub PatchOp);
+
+/// Path entered in the patch window
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchFile {
+    pub path: String,
+}
+
+fn patch_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut patch_file: ResMut<PatchFile>,
+    mut patch_events: EventWriter<PatchEvent>,
+) {
+    if !palette.show_patch {
+        return;
+    }
+    egui::Window::new("Patch")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("File");
+                ui.text_edit_singleline(&mut patch_file.path);
+            });
+            if ui.button("Apply").clicked() {
+                patch_events.send(PatchEvent::File(PathBuf::from(&patch_file.path)));
+            }
+        });
+}
+
+/// Control points of `bez` at `location`
+fn points_at(bez: &PolyBezier<CubicBezier>, location: Vec3) -> Vec<usize> {
+    bez.get_control_points()
+        .enumerate()
+        .filter(|(_i, p)| p.distance(location) < JUNCTION_EPSILON)
+        .map(|(i, _p)| i)
+        .collect()
+}
+
+/// How many points, splines and switches `ops` would change
+fn edit_size(ops: &[PatchOp], beziers: &Query<(Entity, &mut PolyBezier<CubicBezier>)>) -> EditSize {
+    let mut size = EditSize::default();
+    let mut splines = vec![];
+    for op in ops {
+        match op {
+            PatchOp::MovePoint { from, .. } => {
+                let from = gvas_to_vec(*from);
+                for (entity, bez) in beziers.iter() {
+                    let points = points_at(bez, from).len();
+                    if points > 0 {
+                        size.points += points;
+                        if !splines.contains(&entity) {
+                            splines.push(entity);
+                        }
+                    }
+                }
+            }
+            PatchOp::ReshapeSpline { from, to } => {
+                let len = from.points.len().max(to.points.len());
+                size.points += (0..len)
+                    .filter(|&i| from.points.get(i) != to.points.get(i))
+                    .count();
+                size.splines += 1;
+            }
+            PatchOp::MoveSwitch { .. } => size.switches += 1,
+            PatchOp::AddSpline { .. } | PatchOp::AddSwitch { .. } | PatchOp::AddIndustry { .. } => {
+                size.added += 1
+            }
+            PatchOp::DeleteSwitch { .. }
+            | PatchOp::DeleteSpline { .. }
+            | PatchOp::DeleteIndustry { .. } => size.deleted += 1,
+        }
+    }
+    size.splines += splines.len();
+    size
+}
+
+fn apply_patches(
+    mut events: EventReader<PatchEvent>,
+    mut handles: Query<(&DragState, &Parent, &mut Transform)>,
+    mut beziers: Query<(Entity, &mut PolyBezier<CubicBezier>)>,
+    mut switches: Query<
+        (Entity, &mut Transform, &mut SwitchData, &mut Handle<Mesh>),
+        Without<DragState>,
+    >,
+    industries: Query<
+        (Entity, &Transform, &IndustryData),
+        (Without<DragState>, Without<SwitchData>),
+    >,
+    mut modification: EventWriter<BezierModificaiton>,
+    mut section_update: EventWriter<BezierSectionUpdate>,
+    (settings, assets): (Res<Settings>, Res<DefaultAssets>),
+    (mut safety, mut dialogs): (ResMut<SafetyNet>, ResMut<Dialogs>),
+    mut edits: EventWriter<EditEvent>,
+    mut commands: Commands,
+) {
+    for event in events.iter() {
+        let ops = match event {
+            PatchEvent::File(path) => match read_patch(path) {
+                Ok(ops) => ops,
+                Err(e) => {
+                    dialogs.message(
+                        "patch_failed",
+                        "Could Not Apply Patch",
+                        format!("{}\n{}", path.display(), e),
+                    );
+                    continue;
+                }
+            },
+            PatchEvent::Ops(ops) | PatchEvent::Confirmed(ops) | PatchEvent::Undo(ops) => {
+                ops.clone()
+            }
+        };
+        if matches!(event, PatchEvent::File(_) | PatchEvent::Ops(_)) {
+            let size = edit_size(&ops, &beziers);
+            if size.too_big(settings.edit_limit) {
+                safety.hold_patch(ops, size, &mut dialogs);
+                continue;
+            }
+        }
+        // What was applied, to add to the history. Moved points are recorded as the shape of
+        // each spline they moved, from before the first move
+        let mut applied = vec![];
+        let mut moved: Vec<(Entity, SplineShape)> = vec![];
+        for op in ops {
+            match &op {
+                PatchOp::MovePoint { from, to } => {
+                    let (from, to) = (gvas_to_vec(*from), gvas_to_vec(*to));
+                    // Found on the splines rather than their handles, which splines loaded
+                    // with `DeferredHandles` don't have yet
+                    let mut splines = vec![];
+                    for (entity, mut bez) in beziers.iter_mut() {
+                        let points = points_at(&bez, from);
+                        if points.is_empty() {
+                            continue;
+                        }
+                        if moved.iter().all(|(e, _s)| *e != entity) {
+                            moved.push((entity, SplineShape::of(&bez)));
+                        }
+                        for pt in points {
+                            bez.update(pt, to);
+                        }
+                        section_update.send(BezierSectionUpdate { bezier: entity });
+                        splines.push(entity);
+                    }
+                    if splines.is_empty() {
+                        warn!("Patch: no control point at {}", from);
+                    }
+                    for (state, parent, mut trans) in handles.iter_mut() {
+                        if splines.contains(&parent.0) {
+                            if let Ok((_e, bez)) = beziers.get(parent.0) {
+                                trans.translation = bez.get_control_point(state.pt)
+                                    + settings.curve_offset(bez.ty());
+                            }
+                        }
+                    }
+                    continue;
+                }
+                PatchOp::ReshapeSpline { from, to } => {
+                    let found = beziers.iter_mut().find(|(_e, bez)| from.matches(bez));
+                    let (entity, mut bez) = match found {
+                        Some(found) => found,
+                        None => {
+                            warn!("Patch: no spline starting at {:?}", from.points.first());
+                            continue;
+                        }
+                    };
+                    if to.points.len() < 2 || to.visibility.len() != to.points.len() - 1 {
+                        warn!("Patch: spline visibility doesn't match its points");
+                        continue;
+                    }
+                    let points: Vec<_> = to.points.iter().copied().map(gvas_to_vec).collect();
+                    if points.len() == bez.len() && to.visibility == bez.get_visibility() {
+                        for (pt, point) in points.iter().enumerate() {
+                            bez.update(pt, *point);
+                        }
+                        let off = settings.curve_offset(bez.ty());
+                        for (state, parent, mut trans) in handles.iter_mut() {
+                            if parent.0 == entity {
+                                trans.translation = points[state.pt] + off;
+                            }
+                        }
+                        section_update.send(BezierSectionUpdate { bezier: entity });
+                    } else {
+                        // A different number of points needs new handles and sections
+                        *bez = PolyBezier::new(points, to.visibility.clone(), bez.ty());
+                        modification.send(BezierModificaiton::Rebuilt(entity));
+                    }
+                }
+                PatchOp::AddSpline {
+                    ty,
+                    points,
+                    visibility,
+                } => {
+                    if points.len() < 2 {
+                        warn!("Patch: spline needs at least two points");
+                        continue;
+                    }
+                    let visibility = visibility
+                        .clone()
+                        .unwrap_or_else(|| vec![true; points.len() - 1]);
+                    if visibility.len() != points.len() - 1 {
+                        warn!("Patch: spline visibility doesn't match its points");
+                        continue;
+                    }
+                    // Spawned here rather than with `PlaceCurve`, which would add it to the
+                    // history even when it's undoing a deletion
+                    let points = points.iter().copied().map(gvas_to_vec).collect();
+                    let bezier = PolyBezier::new(points, visibility, SplineType::from(*ty));
+                    if let Some(bezier) = spawn_bezier(&mut commands, &assets, &settings, bezier) {
+                        section_update.send(BezierSectionUpdate { bezier });
+                    }
+                }
+                PatchOp::DeleteSwitch { location, .. } => {
+                    let location = gvas_to_vec(*location);
+                    if let Some((entity, trans, switch, _m)) = switches
+                        .iter()
+                        .find(|(_e, t, ..)| t.translation.distance(location) < JUNCTION_EPSILON)
+                    {
+                        modification.send(BezierModificaiton::DeleteSw(entity));
+                        // With what's needed to put it back, which patch files leave out
+                        applied.push(PatchOp::DeleteSwitch {
+                            location: vec_to_gvas(trans.translation),
+                            ty: Some(switch.ty as u32),
+                            rotation: Some(quat_to_rotator(trans.rotation)),
+                        });
+                    } else {
+                        warn!("Patch: no switch at {}", location);
+                    }
+                    continue;
+                }
+                PatchOp::AddSwitch {
+                    ty,
+                    location,
+                    rotation,
+                } => match SwitchType::try_from(*ty) {
+                    Ok(switch) => modification.send(BezierModificaiton::PlaceSw(
+                        gvas_to_vec(*location),
+                        switch,
+                        rotator_to_quat(*rotation),
+                    )),
+                    Err(_) => {
+                        warn!("Patch: invalid switch type {}", ty);
+                        continue;
+                    }
+                },
+                PatchOp::MoveSwitch { from, to } => {
+                    let location = gvas_to_vec(from.location);
+                    let found = switches
+                        .iter_mut()
+                        .find(|(_e, t, ..)| t.translation.distance(location) < JUNCTION_EPSILON);
+                    let ty = SwitchType::try_from(to.ty);
+                    match (found, ty) {
+                        (Some((_e, mut trans, mut switch, mut mesh)), Ok(ty)) => {
+                            trans.translation = gvas_to_vec(to.location);
+                            trans.rotation = rotator_to_quat(to.rotation);
+                            trans.scale = ty.scale();
+                            switch.ty = ty;
+                            *mesh = assets.switch_mesh[ty].clone();
+                        }
+                        (None, _) => {
+                            warn!("Patch: no switch at {}", location);
+                            continue;
+                        }
+                        (_, Err(_)) => {
+                            warn!("Patch: invalid switch type {}", to.ty);
+                            continue;
+                        }
+                    }
+                }
+                PatchOp::DeleteSpline { points, .. } => {
+                    let points: Vec<_> = points.iter().copied().map(gvas_to_vec).collect();
+                    let found = beziers.iter().find(|(_e, bez)| {
+                        bez.len() == points.len()
+                            && bez
+                                .get_control_points()
+                                .zip(points.iter())
+                                .all(|(a, b)| a.distance(*b) < JUNCTION_EPSILON)
+                    });
+                    if let Some((entity, bez)) = found {
+                        commands.entity(entity).despawn_recursive();
+                        applied.push(PatchOp::delete_spline(&bez));
+                    } else {
+                        warn!("Patch: no spline starting at {:?}", points.first());
+                    }
+                    continue;
+                }
+                PatchOp::AddIndustry {
+                    ty,
+                    location,
+                    rotation,
+                } => modification.send(BezierModificaiton::PlaceIndustry(
+                    gvas_to_vec(*location),
+                    *ty,
+                    rotator_to_quat(*rotation),
+                )),
+                PatchOp::DeleteIndustry { location, .. } => {
+                    let location = gvas_to_vec(*location);
+                    if let Some((entity, trans, industry)) = industries
+                        .iter()
+                        .find(|(_e, t, _i)| t.translation.distance(location) < JUNCTION_EPSILON)
+                    {
+                        modification.send(BezierModificaiton::DeleteSw(entity));
+                        applied.push(PatchOp::DeleteIndustry {
+                            location: vec_to_gvas(trans.translation),
+                            ty: Some(industry.ty),
+                            rotation: Some(quat_to_rotator(trans.rotation)),
+                        });
+                    } else {
+                        warn!("Patch: no industry at {}", location);
+                    }
+                    continue;
+                }
+            }
+            applied.push(op);
+        }
+        if matches!(event, PatchEvent::Undo(_)) {
+            continue;
+        }
+        for (entity, from) in moved {
+            if let Ok((_e, bez)) = beziers.get(entity) {
+                applied.extend(PatchOp::reshaped(from, bez));
+            }
+        }
+        for op in applied {
+            edits.send(EditEvent(op));
+        }
+    }
+}
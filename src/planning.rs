@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::{Hover, PickingCamera};
+use smooth_bevy_cameras::LookTransform;
+
+use crate::gvas::SplineType;
+use crate::junction::{find_junctions, route_between, spline_endpoints};
+use crate::palette::Palette;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierSection, DragState};
+
+/// Plugin for the train planning window
+pub struct PlanningPlugin;
+
+impl Plugin for PlanningPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TrainPlanner {
+            loco: 0,
+            cars: 5,
+            car_mass: 4.,
+            start: None,
+            end: None,
+            route: vec![],
+        });
+        app.add_system(pick_route);
+        app.add_system(planner_window);
+    }
+}
+
+const GRAVITY: f32 = 9.81;
+/// Rolling resistance of steel wheels on steel rails
+const ROLLING_RESISTANCE: f32 = 0.002;
+/// Fraction of the locomotive's weight that can be used for traction before the wheels slip
+const ADHESION: f32 = 0.25;
+/// Grades measured along each segment, since the curve can be steeper between control points
+/// than the points themselves are apart
+const GRADE_SAMPLES: usize = 16;
+
+/// (name, mass in tonnes, tractive effort in kN). These are rough figures, and are only
+/// meant for planning.
+const LOCOMOTIVES: [(&str, f32, f32); 7] = [
+    ("Porter 0-4-0", 7.5, 17.),
+    ("Porter 0-4-2", 11., 22.),
+    ("Eureka 4-4-0", 25., 36.),
+    ("Mogul 2-6-0", 33., 53.),
+    ("Class 70 2-8-0", 40., 69.),
+    ("Climax", 30., 67.),
+    ("Heisler", 35., 75.),
+];
+
+/// (name, mass in tonnes)
+const CAR_PRESETS: [(&str, f32); 3] = [("Empty", 4.), ("Half loaded", 8.), ("Loaded", 12.)];
+
+/// Train planning state
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainPlanner {
+    /// Index into `LOCOMOTIVES`
+    pub loco: usize,
+    pub cars: u32,
+    /// Mass of each car, in tonnes
+    pub car_mass: f32,
+    /// Track splines the route starts and ends on
+    pub start: Option<Entity>,
+    pub end: Option<Entity>,
+    /// Track splines making up the route, in order from `start` to `end`. Empty if they aren't
+    /// connected
+    pub route: Vec<Entity>,
+}
+
+/// The steepest place along a route
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LimitingSection {
+    /// Percent grade
+    grade: f32,
+    /// Index into the route
+    curve: usize,
+    segment: usize,
+    location: Vec3,
+}
+
+impl TrainPlanner {
+    /// Force available at the wheels, in newtons
+    fn available_force(&self) -> f32 {
+        let (_name, mass, effort) = LOCOMOTIVES[self.loco];
+        (effort * 1000.).min(ADHESION * mass * 1000. * GRAVITY)
+    }
+
+    /// Force needed to keep the train moving on a grade, in newtons
+    fn required_force(&self, grade: f32) -> f32 {
+        let (_name, mass, _effort) = LOCOMOTIVES[self.loco];
+        let total = (mass + self.cars as f32 * self.car_mass) * 1000.;
+        total * GRAVITY * (ROLLING_RESISTANCE + grade / 100.)
+    }
+
+    /// Maximum number of cars that can be hauled up a grade
+    fn max_cars(&self, grade: f32) -> u32 {
+        let (_name, mass, _effort) = LOCOMOTIVES[self.loco];
+        let total = self.available_force() / (GRAVITY * (ROLLING_RESISTANCE + grade / 100.));
+        ((total / 1000. - mass) / self.car_mass).max(0.).floor() as u32
+    }
+}
+
+/// Starts the route on the hovered track when R is pressed, or ends it there with Shift+R. The
+/// route follows the fewest splines between them
+fn pick_route(
+    keys: Res<Input<KeyCode>>,
+    palette: Res<Palette>,
+    hovered: Query<(&Hover, &Parent), Or<(With<DragState>, With<BezierSection>)>>,
+    handles: Query<(&DragState, &Parent, Entity)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut planner: ResMut<TrainPlanner>,
+) {
+    if !palette.show_planner || !keys.just_pressed(KeyCode::R) {
+        return;
+    }
+    let is_track = |e: &Entity| beziers.get(*e).is_ok_and(|b| b.ty() == SplineType::Track);
+    let picked = match hovered.iter().find(|(h, _p)| h.hovered()) {
+        Some((_h, parent)) if is_track(&parent.0) => parent.0,
+        _ => return,
+    };
+    if keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift) {
+        planner.end = Some(picked);
+    } else {
+        planner.start = Some(picked);
+    }
+    planner.route = match (planner.start, planner.end) {
+        (Some(start), Some(end)) => {
+            let endpoints: Vec<_> = spline_endpoints(&handles, &beziers)
+                .into_iter()
+                .filter(|(_h, c, _l)| is_track(c))
+                .collect();
+            route_between(start, end, &find_junctions(&endpoints)).unwrap_or_default()
+        }
+        (Some(only), None) | (None, Some(only)) => vec![only],
+        (None, None) => vec![],
+    };
+}
+
+fn planner_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut planner: ResMut<TrainPlanner>,
+    mut cameras: Query<&mut LookTransform, With<PickingCamera>>,
+) {
+    if !palette.show_planner {
+        return;
+    }
+    let planner = planner.as_mut();
+    planner.route.retain(|e| beziers.get(*e).is_ok());
+    let mut limit: Option<LimitingSection> = None;
+    for (curve, e) in planner.route.iter().enumerate() {
+        let bez = beziers.get(*e).unwrap();
+        for segment in 0..bez.len() - 1 {
+            for i in 0..GRADE_SAMPLES {
+                let t = (i as f32 + 0.5) / GRADE_SAMPLES as f32;
+                // Trains can run either way along a route
+                let grade = bez.grade_at(segment, t).abs();
+                if limit.map_or(true, |l| grade > l.grade) {
+                    limit = Some(LimitingSection {
+                        grade,
+                        curve,
+                        segment,
+                        location: bez.eval_at(segment, t),
+                    });
+                }
+            }
+        }
+    }
+    let mut go_to = None;
+    egui::Window::new("Train Planner")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::ComboBox::from_label("Locomotive")
+                .selected_text(LOCOMOTIVES[planner.loco].0)
+                .show_ui(ui, |ui| {
+                    for (i, (name, _m, _e)) in LOCOMOTIVES.iter().enumerate() {
+                        ui.selectable_value(&mut planner.loco, i, *name);
+                    }
+                });
+            ui.add(egui::Slider::new(&mut planner.cars, 0..=40).text("Cars"));
+            ui.horizontal(|ui| {
+                for (name, mass) in CAR_PRESETS {
+                    if ui.selectable_label(planner.car_mass == mass, name).clicked() {
+                        planner.car_mass = mass;
+                    }
+                }
+            });
+            ui.add(
+                egui::Slider::new(&mut planner.car_mass, 1.0..=20.0).text("Car mass (t)"),
+            );
+            ui.separator();
+            ui.label("Press R over a track to start the route, and Shift+R to end it");
+            if planner.start.is_some() && planner.end.is_some() && planner.route.is_empty() {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    "The end of the route isn't connected to its start",
+                );
+            }
+            if let Some(limit) = limit {
+                ui.label(format!(
+                    "Route: {} splines, steepest grade {:.2}% (spline {}, segment {})",
+                    planner.route.len(),
+                    limit.grade,
+                    limit.curve,
+                    limit.segment
+                ));
+                if ui.button("Go to Steepest Section").clicked() {
+                    go_to = Some(limit.location);
+                }
+                let max_cars = planner.max_cars(limit.grade);
+                if planner.required_force(limit.grade) <= planner.available_force() {
+                    ui.colored_label(
+                        egui::Color32::GREEN,
+                        format!("Climbable, up to {} cars", max_cars),
+                    );
+                } else {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("Too steep, at most {} cars", max_cars),
+                    );
+                }
+            } else {
+                ui.label("No route selected");
+            }
+            if ui.button("Clear Route").clicked() {
+                planner.start = None;
+                planner.end = None;
+                planner.route.clear();
+            }
+        });
+    if let Some(location) = go_to {
+        // Keeps the camera's current offset from its target
+        for mut look in cameras.iter_mut() {
+            let offset = look.eye - look.target;
+            look.target = location;
+            look.eye = location + offset;
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use bevy::core_pipeline::{draw_3d_graph, node, AlphaMask3d, Opaque3d, Transparent3d};
+use bevy::prelude::*;
+use bevy::render::camera::{CameraProjection, VisibleEntities};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotValue};
+use bevy::render::render_phase::RenderPhase;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::texture::{BevyDefault, TextureCache};
+use bevy::render::view::{ExtractedView, ViewTarget};
+use bevy::render::{RenderApp, RenderStage};
+use bevy::transform::TransformSystem;
+use bevy_egui::{egui, EguiContext};
+
+use crate::gvas::SplineType;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Width and height of the preview, in pixels
+const PREVIEW_SIZE: u32 = 256;
+/// Egui texture id of the preview
+const PREVIEW_TEXTURE: u64 = 1;
+/// Name of the preview's camera. It's never made active, so it only draws into the preview
+const PREVIEW_CAMERA: &str = "spline_preview";
+/// Render graph node that draws the preview
+const PREVIEW_PASS_DRIVER: &str = "spline_preview_pass_driver";
+
+/// Plugin for the inspector's preview, which renders the selected spline from above into a
+/// texture, see `SplinePreview`
+pub struct PreviewPlugin;
+
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_preview);
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            place_preview_camera.before(TransformSystem::TransformPropagate),
+        );
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_system_to_stage(RenderStage::Extract, extract_preview);
+        render_app.add_system_to_stage(RenderStage::Prepare, prepare_preview_target);
+        let mut graph = render_app.world.get_resource_mut::<RenderGraph>().unwrap();
+        graph.add_node(PREVIEW_PASS_DRIVER, PreviewPassDriver);
+        graph
+            .add_node_edge(node::MAIN_PASS_DEPENDENCIES, PREVIEW_PASS_DRIVER)
+            .unwrap();
+    }
+}
+
+/// The preview's camera and the image it renders into. Nothing is rendered unless `show` is
+/// called that frame
+#[derive(Debug)]
+pub struct SplinePreview {
+    camera: Entity,
+    image: Handle<Image>,
+    /// Where the camera is, while the preview is shown, and the type of the spline it's looking
+    /// at
+    active: Option<(Transform, SplineType)>,
+}
+
+impl SplinePreview {
+    /// Points the camera straight down at `bez`, far enough up to fit all of it, and shows what
+    /// it sees in `ui`
+    pub fn show(&mut self, ui: &mut egui::Ui, bez: &PolyBezier<CubicBezier>) {
+        let points = bez.sample(8);
+        let (min, max) = points.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        let center = (min + max) / 2.;
+        // Half the width to fit, with a margin, seen through the default 45 degree field of view
+        let half = ((max.x - min.x).max(max.z - min.z) * 0.55).max(0.5);
+        let height = max.y - center.y + half / std::f32::consts::FRAC_PI_8.tan();
+        // Up is -z, the same way as the plan view
+        let eye = center + Vec3::Y * height;
+        let transform = Transform::from_translation(eye).looking_at(center, -Vec3::Z);
+        self.active = Some((transform, bez.ty()));
+        ui.image(egui::TextureId::User(PREVIEW_TEXTURE), [200., 200.]);
+    }
+
+    /// Stops rendering the preview, until the next time it's shown
+    pub fn hide(&mut self) {
+        self.active = None;
+    }
+}
+
+/// Marks the preview's camera
+#[derive(Debug, Component)]
+struct PreviewCamera;
+
+/// The preview's camera, while it's being shown, in the render world
+struct ExtractedPreview(Option<Entity>);
+
+/// Image the preview's camera renders into, in the render world
+#[derive(Component)]
+struct PreviewTarget(Handle<Image>);
+
+fn setup_preview(
+    mut images: ResMut<Assets<Image>>,
+    mut egui_context: ResMut<EguiContext>,
+    mut commands: Commands,
+) {
+    let size = Extent3d {
+        width: PREVIEW_SIZE,
+        height: PREVIEW_SIZE,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("spline_preview"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            // The same format as the window, since the pipelines are built for it
+            format: TextureFormat::bevy_default(),
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..Default::default()
+    };
+    image.resize(size);
+    let image = images.add(image);
+    egui_context.set_egui_texture(PREVIEW_TEXTURE, image.clone());
+    let camera = commands
+        .spawn_bundle(PerspectiveCameraBundle {
+            camera: Camera {
+                name: Some(PREVIEW_CAMERA.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(PreviewCamera)
+        .id();
+    commands.insert_resource(SplinePreview {
+        camera,
+        image,
+        active: None,
+    });
+}
+
+fn place_preview_camera(
+    preview: Res<SplinePreview>,
+    settings: Res<Settings>,
+    mut cameras: Query<&mut Transform, With<PreviewCamera>>,
+) {
+    let (mut active, ty) = match preview.active {
+        Some(active) => active,
+        None => return,
+    };
+    // The spline is drawn at its handles, which are offset from its points
+    active.translation += settings.curve_offset(ty);
+    if let Ok(mut transform) = cameras.get_mut(preview.camera) {
+        if *transform != active {
+            *transform = active;
+        }
+    }
+}
+
+fn extract_preview(
+    mut commands: Commands,
+    preview: Res<SplinePreview>,
+    cameras: Query<(&Camera, &GlobalTransform, &VisibleEntities), With<PreviewCamera>>,
+) {
+    let camera = cameras
+        .get(preview.camera)
+        .ok()
+        .filter(|_| preview.active.is_some());
+    let (camera, transform, visible) = match camera {
+        Some(camera) => camera,
+        None => {
+            commands.insert_resource(ExtractedPreview(None));
+            return;
+        }
+    };
+    // The camera's own projection follows the shape of the main window, which it's culled with
+    let mut projection = PerspectiveProjection::default();
+    projection.update(PREVIEW_SIZE as f32, PREVIEW_SIZE as f32);
+    commands.get_or_spawn(preview.camera).insert_bundle((
+        ExtractedView {
+            projection: projection.get_projection_matrix(),
+            transform: *transform,
+            width: PREVIEW_SIZE,
+            height: PREVIEW_SIZE,
+            near: camera.near,
+            far: camera.far,
+        },
+        visible.clone(),
+        PreviewTarget(preview.image.clone()),
+        RenderPhase::<Opaque3d>::default(),
+        RenderPhase::<AlphaMask3d>::default(),
+        RenderPhase::<Transparent3d>::default(),
+    ));
+    commands.insert_resource(ExtractedPreview(Some(preview.camera)));
+}
+
+/// Points the preview's view at its image, with a multisampled texture to draw into first like
+/// the window has
+fn prepare_preview_target(
+    images: Res<RenderAssets<Image>>,
+    msaa: Res<Msaa>,
+    render_device: Res<RenderDevice>,
+    mut texture_cache: ResMut<TextureCache>,
+    views: Query<(&PreviewTarget, Entity)>,
+    mut commands: Commands,
+) {
+    for (target, entity) in views.iter() {
+        let image = match images.get(&target.0) {
+            Some(image) => image,
+            None => continue,
+        };
+        let sampled_target = if msaa.samples > 1 {
+            let sampled = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("spline_preview_sampled"),
+                    size: Extent3d {
+                        width: PREVIEW_SIZE,
+                        height: PREVIEW_SIZE,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: msaa.samples,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::bevy_default(),
+                    usage: TextureUsages::RENDER_ATTACHMENT,
+                },
+            );
+            Some(sampled.default_view)
+        } else {
+            None
+        };
+        commands.entity(entity).insert(ViewTarget {
+            view: image.texture_view.clone(),
+            sampled_target,
+        });
+    }
+}
+
+/// Draws the preview's camera into its image
+struct PreviewPassDriver;
+
+impl Node for PreviewPassDriver {
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let preview = world.get_resource::<ExtractedPreview>();
+        if let Some(camera) = preview.and_then(|p| p.0) {
+            graph.run_sub_graph(draw_3d_graph::NAME, vec![SlotValue::Entity(camera)])?;
+        }
+        Ok(())
+    }
+}
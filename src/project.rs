@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::Hover;
+
+use crate::gvas::SwitchData;
+use crate::palette::Palette;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierSection, DragState};
+
+/// Plugin for grouping splines and switches into projects
+pub struct ProjectPlugin;
+
+impl Plugin for ProjectPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Projects::default());
+        app.add_system(assign_project);
+        app.add_system(isolate_project.label("isolate_project"));
+        app.add_system(project_window);
+    }
+}
+
+/// A named group of splines and switches, e.g. "Sawmill branch"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    pub id: u32,
+    pub name: String,
+}
+
+/// Added to splines and switches that belong to a project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct ProjectMember(pub u32);
+
+#[derive(Debug, Default)]
+pub struct Projects {
+    pub projects: Vec<Project>,
+    /// Id of the project new members are added to
+    pub active: Option<u32>,
+    /// Hide everything outside the active project
+    pub isolate: bool,
+    next_id: u32,
+    new_name: String,
+}
+
+impl Projects {
+    /// Adds a project, returning its id
+    pub fn create(&mut self, name: String) -> u32 {
+        let id = self.next_id;
+        self.projects.push(Project { id, name });
+        self.next_id += 1;
+        id
+    }
+}
+
+/// Pressing P over a spline or switch moves it into the active project, or out of every
+/// project if it's already in the active one
+fn assign_project(
+    keys: Res<Input<KeyCode>>,
+    projects: Res<Projects>,
+    hovered: Query<(&Hover, &Parent), Or<(With<DragState>, With<BezierSection>)>>,
+    switches: Query<(&Hover, Entity), With<SwitchData>>,
+    members: Query<&ProjectMember>,
+    mut commands: Commands,
+) {
+    if !keys.just_pressed(KeyCode::P) {
+        return;
+    }
+    let active = if let Some(active) = projects.active {
+        active
+    } else {
+        return;
+    };
+    let target = hovered
+        .iter()
+        .find(|(h, _p)| h.hovered())
+        .map(|(_h, p)| p.0)
+        .or_else(|| switches.iter().find(|(h, _e)| h.hovered()).map(|(_h, e)| e));
+    if let Some(target) = target {
+        if members.get(target).is_ok_and(|m| m.0 == active) {
+            commands.entity(target).remove::<ProjectMember>();
+        } else {
+            commands.entity(target).insert(ProjectMember(active));
+        }
+    }
+}
+
+/// Hides the handles and sections of splines, and switches, outside the active project
+fn isolate_project(
+    projects: Res<Projects>,
+    splines: Query<(Option<&ProjectMember>, &Children), With<PolyBezier<CubicBezier>>>,
+    mut visibility: Query<
+        &mut Visibility,
+        (Without<SwitchData>, Without<PolyBezier<CubicBezier>>),
+    >,
+    mut switches: Query<(Option<&ProjectMember>, &mut Visibility), With<SwitchData>>,
+) {
+    let visible = |member: Option<&ProjectMember>| match (projects.isolate, projects.active) {
+        (true, Some(active)) => member == Some(&ProjectMember(active)),
+        _ => true,
+    };
+    for (member, children) in splines.iter() {
+        let visible = visible(member);
+        for child in children.iter() {
+            if let Ok(mut vis) = visibility.get_mut(*child) {
+                if vis.is_visible != visible {
+                    vis.is_visible = visible;
+                }
+            }
+        }
+    }
+    for (member, mut vis) in switches.iter_mut() {
+        let visible = visible(member);
+        if vis.is_visible != visible {
+            vis.is_visible = visible;
+        }
+    }
+}
+
+fn project_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut projects: ResMut<Projects>,
+    members: Query<(&ProjectMember, Entity)>,
+    mut commands: Commands,
+) {
+    if !palette.show_projects {
+        return;
+    }
+    let projects = projects.as_mut();
+    egui::Window::new("Projects")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("Press P over a spline or switch to add it to the active project");
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut projects.new_name);
+                if ui.button("New").clicked() && !projects.new_name.is_empty() {
+                    let name = std::mem::take(&mut projects.new_name);
+                    projects.active = Some(projects.create(name));
+                }
+            });
+            ui.checkbox(&mut projects.isolate, "Isolate active project");
+            ui.radio_value(&mut projects.active, None, "No active project");
+            let mut remove = None;
+            for project in projects.projects.iter() {
+                ui.horizontal(|ui| {
+                    let count = members.iter().filter(|(m, _e)| m.0 == project.id).count();
+                    ui.radio_value(
+                        &mut projects.active,
+                        Some(project.id),
+                        format!("{} ({})", project.name, count),
+                    );
+                    if ui.button("Delete").clicked() {
+                        remove = Some(project.id);
+                    }
+                });
+            }
+            if let Some(id) = remove {
+                for (member, entity) in members.iter() {
+                    if member.0 == id {
+                        commands.entity(entity).remove::<ProjectMember>();
+                    }
+                }
+                projects.projects.retain(|p| p.id != id);
+                if projects.active == Some(id) {
+                    projects.active = None;
+                }
+            }
+        });
+}
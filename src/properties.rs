@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::dock::Layout;
+use crate::gvas::{RROSave, TextProperty, Value};
+use crate::palette::Palette;
+
+/// Plugin for the raw save property window
+pub struct PropertiesPlugin;
+
+impl Plugin for PropertiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PropertyTree::default());
+        app.add_system(property_window);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PropertyTree {
+    /// Allow editing scalar properties
+    pub edit: bool,
+}
+
+/// Height of one array element row
+const ROW_HEIGHT: f32 = 18.;
+
+fn text(t: &TextProperty) -> String {
+    match t {
+        TextProperty::Simple(s) => format!("{:?}", s),
+        TextProperty::FmtStr(a, b) => format!("{:?} / {:?}", a, b),
+        TextProperty::None => "None".to_string(),
+        TextProperty::Other(bytes) => format!("<{} bytes>", bytes.len()),
+    }
+}
+
+/// Shows the elements of an array. Only the visible rows are laid out, since the spline arrays
+/// can be very long
+fn array<T>(ui: &mut egui::Ui, id: &str, arr: &[T], f: impl Fn(&T) -> String) {
+    ui.label(format!("{} elements", arr.len()));
+    egui::ScrollArea::vertical()
+        .id_source(id)
+        .max_height(200.)
+        .show_rows(ui, ROW_HEIGHT, arr.len(), |ui, range| {
+            for i in range {
+                ui.label(format!("[{}] {}", i, f(&arr[i])));
+            }
+        });
+}
+
+/// Shows a single property, editable if `edit` is set and it's a scalar
+fn property(ui: &mut egui::Ui, name: &str, val: &mut Value, edit: bool) {
+    let vec = |v: &[f32; 3]| format!("({}, {}, {})", v[0], v[1], v[2]);
+    egui::CollapsingHeader::new(format!("{}: {}", name, val.type_name()))
+        .id_source(name)
+        .show(ui, |ui| match val {
+            Value::String(s) | Value::Name(s, _) => {
+                if edit {
+                    ui.text_edit_singleline(s);
+                } else {
+                    ui.label(format!("{:?}", s));
+                }
+            }
+            Value::Byte(b, _) => {
+                if edit {
+                    ui.add(egui::DragValue::new(b));
+                } else {
+                    ui.label(format!("{}", b));
+                }
+            }
+            Value::ByteEnum(e, v, _) | Value::Enum(e, v, _) => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}::", e));
+                    if edit {
+                        ui.text_edit_singleline(v);
+                    } else {
+                        ui.label(v.as_str());
+                    }
+                });
+            }
+            Value::StringArray(arr) => array(ui, name, arr, |s| format!("{:?}", s)),
+            Value::Int32Array(arr) => array(ui, name, arr, |i| format!("{}", i)),
+            Value::BoolArray(arr) => array(ui, name, arr, |b| format!("{}", b)),
+            Value::FloatArray(arr) => array(ui, name, arr, |f| format!("{}", f)),
+            Value::TextArray(arr) => array(ui, name, arr, text),
+            Value::VectorArray(arr) | Value::RotatorArray(arr) => array(ui, name, arr, vec),
+            Value::Raw(_ty, bytes) => {
+                ui.label(format!("{} bytes, not understood by the editor", bytes.len()));
+            }
+            Value::None => {
+                ui.label("End of properties");
+            }
+        });
+}
+
+fn property_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut tree: ResMut<PropertyTree>,
+    mut gvas: ResMut<RROSave>,
+    mut layout: ResMut<Layout>,
+) {
+    if !palette.show_properties {
+        return;
+    }
+    let tree = tree.as_mut();
+    let window = egui::Window::new("Save Properties").default_height(500.);
+    layout.show(egui_context.ctx_mut(), "Save Properties", window, |ui| {
+        ui.checkbox(&mut tree.edit, "Edit scalar properties");
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (name, val) in gvas.properties_mut() {
+                property(ui, name, val, tree.edit);
+            }
+        });
+    });
+}
@@ -0,0 +1,380 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::control::LoadProgress;
+use crate::coords::{gvas_to_vec, quat_to_rotator, rotator_to_quat, vec_to_gvas};
+use crate::dialog::Dialogs;
+use crate::gvas::{SplineType, SwitchData, SwitchType};
+use crate::history::History;
+use crate::palette::FileEvent;
+use crate::patch::PatchOp;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::BezierModificaiton;
+
+/// File the editing state is written to while editing, in the working directory. It's removed
+/// when the editor closes normally, so finding it at startup means the editor crashed
+pub const RECOVERY_FILE: &str = "recovery.json";
+
+/// Seconds between snapshots of the editing state
+const SNAPSHOT_INTERVAL: f64 = 5.;
+
+/// Plugin for recovering splines and switches after a crash
+pub struct RecoveryPlugin;
+
+impl Plugin for RecoveryPlugin {
+    fn build(&self, app: &mut App) {
+        let (pending, read_error) = match File::open(RECOVERY_FILE) {
+            Ok(file) => match serde_json::from_reader(std::io::BufReader::new(file)) {
+                Ok(recovery) => (Some(recovery), None),
+                Err(e) => (None, Some(e.to_string())),
+            },
+            Err(_) => (None, None),
+        };
+        let snapshot = Arc::new(Mutex::new(Recovery::default()));
+        install_panic_hook(snapshot.clone());
+        app.insert_resource(RecoveryState {
+            snapshot,
+            pending,
+            restoring: None,
+            read_error,
+        });
+        app.add_system(track_source);
+        app.add_system(take_snapshot);
+        app.add_system(recovery_window);
+        app.add_system(restore);
+        // After anything that could ask the editor to close
+        app.add_system_to_stage(CoreStage::Last, remove_on_exit);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveredSpline {
+    pub ty: SplineType,
+    pub points: Vec<[f32; 3]>,
+    pub visibility: Vec<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveredSwitch {
+    /// Switch type as stored in the save
+    pub ty: u32,
+    pub location: [f32; 3],
+    pub rotation: [f32; 3],
+}
+
+/// Splines and switches being edited, in save coordinates
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recovery {
+    /// Save the splines and switches were loaded from, if any
+    pub source: Option<FileEvent>,
+    pub splines: Vec<RecoveredSpline>,
+    pub switches: Vec<RecoveredSwitch>,
+    /// Edits that could be undone, oldest first, see `history.rs`
+    #[serde(default)]
+    pub history: Vec<Vec<PatchOp>>,
+}
+
+pub struct RecoveryState {
+    /// Latest editing state, also written out by the panic hook
+    snapshot: Arc<Mutex<Recovery>>,
+    /// Read from `RECOVERY_FILE` at startup, until restored or discarded
+    pending: Option<Recovery>,
+    /// Being restored, once its source has been loaded
+    restoring: Option<Recovery>,
+    /// Why `RECOVERY_FILE` couldn't be read at startup, until it's been shown
+    read_error: Option<String>,
+}
+
+impl RecoveryState {
+    /// Whether the journal in `RECOVERY_FILE` should be left alone, because it holds work from
+    /// before a crash that hasn't been restored or discarded yet
+    fn keep_journal(&self) -> bool {
+        self.pending.is_some() || self.restoring.is_some()
+    }
+}
+
+fn write_recovery(recovery: &Recovery) -> Result<(), serde_json::Error> {
+    let file = File::create(RECOVERY_FILE).map_err(serde_json::Error::io)?;
+    serde_json::to_writer(std::io::BufWriter::new(file), recovery)
+}
+
+/// Writes the journal, and says so the first time it fails. `failing` is whether the last write
+/// failed, so a broken journal isn't reported every few seconds
+fn write_journal(recovery: &Recovery, failing: &mut bool, dialogs: &mut Dialogs) {
+    match write_recovery(recovery) {
+        Ok(()) => *failing = false,
+        Err(e) => {
+            if !*failing {
+                dialogs.message(
+                    "recovery_failed",
+                    "Could Not Write Recovery File",
+                    format!(
+                        "Unsaved work can't be recovered if the editor crashes.\n{}\n{}",
+                        RECOVERY_FILE, e
+                    ),
+                );
+            }
+            *failing = true;
+        }
+    }
+}
+
+/// Writes the latest snapshot to `RECOVERY_FILE` before the default panic hook runs, in case it
+/// changed since the last one was written
+fn install_panic_hook(snapshot: Arc<Mutex<Recovery>>) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // The snapshot may be locked by the panicking thread
+        if let Ok(recovery) = snapshot.try_lock() {
+            match write_recovery(&recovery) {
+                Ok(()) => eprintln!("Editing state written to {}", RECOVERY_FILE),
+                Err(e) => eprintln!("Error: {:?}", e),
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// Keeps track of the save being edited, so restoring can load it first. The journal is written
+/// on every save, so it never points at a save older than the one on disk
+fn track_source(
+    mut events: EventReader<FileEvent>,
+    state: Res<RecoveryState>,
+    mut failing: Local<bool>,
+    mut dialogs: ResMut<Dialogs>,
+) {
+    for event in events.iter() {
+        let source = match event {
+            FileEvent::Load(_) | FileEvent::Import(_) => event.clone(),
+            // The saved file has everything the loaded one did
+            FileEvent::Save(path) => FileEvent::Load(path.clone()),
+            FileEvent::Export(_) | FileEvent::Share(_) => continue,
+        };
+        if let Ok(mut recovery) = state.snapshot.lock() {
+            recovery.source = Some(source);
+            if matches!(event, FileEvent::Save(_)) && !state.keep_journal() {
+                write_journal(&recovery, &mut failing, &mut dialogs);
+            }
+        }
+    }
+}
+
+/// Removes the journal when the editor closes normally
+fn remove_on_exit(mut exits: EventReader<AppExit>, state: Res<RecoveryState>) {
+    if exits.iter().next().is_some() && !state.keep_journal() {
+        if let Err(e) = std::fs::remove_file(RECOVERY_FILE) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                println!("Error: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Snapshots the editing state every few seconds, and writes it to the journal if it changed
+fn take_snapshot(
+    time: Res<Time>,
+    mut last: Local<f64>,
+    state: Res<RecoveryState>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    switches: Query<(&Transform, &SwitchData)>,
+    history: Res<History>,
+    (mut failing, mut dialogs): (Local<bool>, ResMut<Dialogs>),
+) {
+    if time.seconds_since_startup() - *last < SNAPSHOT_INTERVAL {
+        return;
+    }
+    *last = time.seconds_since_startup();
+    let splines = beziers
+        .iter()
+        .map(|b| RecoveredSpline {
+            ty: b.ty(),
+            points: b.get_control_points().map(vec_to_gvas).collect(),
+            visibility: b.get_visibility().to_vec(),
+        })
+        .collect();
+    let switches = switches
+        .iter()
+        .map(|(t, s)| RecoveredSwitch {
+            ty: s.ty as u32,
+            location: vec_to_gvas(t.translation),
+            rotation: quat_to_rotator(t.rotation),
+        })
+        .collect();
+    let history: Vec<Vec<PatchOp>> = history.ops().cloned().collect();
+    if let Ok(mut recovery) = state.snapshot.lock() {
+        if recovery.splines == splines
+            && recovery.switches == switches
+            && recovery.history == history
+        {
+            return;
+        }
+        recovery.splines = splines;
+        recovery.switches = switches;
+        recovery.history = history;
+        if !state.keep_journal() {
+            write_journal(&recovery, &mut failing, &mut dialogs);
+        }
+    }
+}
+
+fn recovery_window(
+    mut egui_context: ResMut<EguiContext>,
+    mut state: ResMut<RecoveryState>,
+    mut file_events: EventWriter<FileEvent>,
+    mut dialogs: ResMut<Dialogs>,
+) {
+    if let Some(e) = state.read_error.take() {
+        dialogs.message(
+            "recovery_unreadable",
+            "Could Not Read Recovery File",
+            format!(
+                "The work from before the editor last closed can't be recovered.\n{}\n{}",
+                RECOVERY_FILE, e
+            ),
+        );
+    }
+    let recovery = if let Some(recovery) = &state.pending {
+        recovery
+    } else {
+        return;
+    };
+    let (mut restore, mut restore_here, mut discard) = (false, false, false);
+    egui::Window::new("Recover Unsaved Work")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("The editor closed unexpectedly last time.");
+            let source = match &recovery.source {
+                Some(FileEvent::Load(path) | FileEvent::Import(path)) => path.display().to_string(),
+                _ => "the default save".to_string(),
+            };
+            ui.label(format!(
+                "Restore {} splines and {} switches, on top of {}?",
+                recovery.splines.len(),
+                recovery.switches.len(),
+                source
+            ));
+            if !recovery.history.is_empty() {
+                ui.label(format!(
+                    "The last {} edits can still be undone",
+                    recovery.history.len()
+                ));
+            }
+            ui.horizontal(|ui| {
+                restore = ui.button("Restore").clicked();
+                if recovery.source.is_some() {
+                    // For when the save it was made on is gone
+                    restore_here = ui.button("Restore on Current Save").clicked();
+                }
+                discard = ui.button("Discard").clicked();
+            });
+        });
+    if discard {
+        state.pending = None;
+        if let Err(e) = std::fs::remove_file(RECOVERY_FILE) {
+            dialogs.message(
+                "recovery_failed",
+                "Could Not Remove Recovery File",
+                format!("{}\n{}", RECOVERY_FILE, e),
+            );
+        }
+    } else if restore || restore_here {
+        // The journal is kept until the restore is done, and then replaced by the next snapshot
+        let mut recovery = state.pending.take().unwrap();
+        if restore_here {
+            recovery.source = None;
+        }
+        // Without a source, the recovery is restored on top of the current save right away
+        if let Some(source) = &recovery.source {
+            file_events.send(source.clone());
+        }
+        state.restoring = Some(recovery);
+    }
+}
+
+/// Replaces the splines and switches of the loaded save with the recovered ones
+fn restore(
+    mut state: ResMut<RecoveryState>,
+    mut loads: EventReader<LoadProgress>,
+    mut waited: Local<Option<usize>>,
+    beziers: Query<Entity, With<PolyBezier<CubicBezier>>>,
+    switches: Query<Entity, With<SwitchData>>,
+    mut commands: Commands,
+    mut modification: EventWriter<BezierModificaiton>,
+    mut history: ResMut<History>,
+    mut dialogs: ResMut<Dialogs>,
+) {
+    let recovery = if let Some(recovery) = &state.restoring {
+        recovery
+    } else {
+        return;
+    };
+    if recovery.source.is_some() && waited.is_none() {
+        let load = match loads.iter().find(|p| p.done) {
+            Some(load) => load,
+            None => return,
+        };
+        if load.failed {
+            // Back to the recovery window, which can restore it on the current save instead
+            dialogs.message(
+                "restore_failed",
+                "Could Not Restore",
+                format!(
+                    "{} couldn't be opened, so the recovered work wasn't restored on it.",
+                    load.path.display()
+                ),
+            );
+            state.pending = state.restoring.take();
+            return;
+        }
+        // The loaded save is spawned at the end of the frame it finishes loading in
+        *waited = Some(0);
+    }
+    if let Some(frames) = waited.as_mut() {
+        *frames += 1;
+        if *frames < 2 {
+            return;
+        }
+    }
+    *waited = None;
+    let recovery = state.restoring.take().unwrap();
+    for entity in beziers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for entity in switches.iter() {
+        modification.send(BezierModificaiton::DeleteSw(entity));
+    }
+    let placed = recovery
+        .splines
+        .iter()
+        .filter(|s| s.points.len() >= 2)
+        .count();
+    history.restore(recovery.history, placed);
+    for spline in recovery.splines {
+        if spline.points.len() < 2 {
+            continue;
+        }
+        // A hand edited journal could have the wrong number, and every segment needs one
+        let mut visibility = spline.visibility;
+        visibility.resize(spline.points.len() - 1, true);
+        modification.send(BezierModificaiton::PlaceCurve(
+            spline.ty,
+            spline.points.into_iter().map(gvas_to_vec).collect(),
+            visibility,
+        ));
+    }
+    for switch in recovery.switches {
+        match SwitchType::try_from(switch.ty) {
+            Ok(ty) => modification.send(BezierModificaiton::PlaceSw(
+                gvas_to_vec(switch.location),
+                ty,
+                rotator_to_quat(switch.rotation),
+            )),
+            Err(_) => warn!("Recovery: invalid switch type {}", switch.ty),
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::control::DefaultAssets;
+use crate::coords::{gvas_to_vec, quat_to_rotator, vec_to_gvas, METERS_PER_UNIT};
+use crate::gvas::{GVASError, SplineType, SwitchData, SwitchType};
+use crate::merge::{compare_parts, MergeSpline, MergeState};
+use crate::palette::{MouseAction, Palette};
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Directory region files are written to and merged from, in the working directory
+pub const REGION_DIR: &str = "regions";
+
+/// Name of the region file with everything outside every region
+const REST: &str = "rest";
+
+/// Plugin for splitting the network into regions, which are written to separate files so they
+/// can be edited on their own and merged back together
+pub struct RegionPlugin;
+
+impl Plugin for RegionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Regions::default());
+        app.add_event::<RegionEvent>();
+        app.add_system(draw_region);
+        app.add_system(show_regions);
+        app.add_system(region_window);
+        app.add_system(region_files);
+    }
+}
+
+/// An area of the map, drawn on the ground
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub name: String,
+    /// Corners in editor space, as x and z
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Region {
+    fn new(name: String, a: Vec3, b: Vec3) -> Self {
+        Self {
+            name,
+            min: Vec2::new(a.x.min(b.x), a.z.min(b.z)),
+            max: Vec2::new(a.x.max(b.x), a.z.max(b.z)),
+        }
+    }
+
+    pub fn contains(&self, p: Vec3) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.z >= self.min.y && p.z <= self.max.y
+    }
+
+    fn corners(&self) -> (Vec3, Vec3) {
+        (
+            Vec3::new(self.min.x, 0., self.min.y),
+            Vec3::new(self.max.x, 0., self.max.y),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Regions {
+    pub regions: Vec<Region>,
+    /// Corners of the region being drawn
+    drawing: Option<(Vec3, Vec3)>,
+    /// Result of the last export or merge
+    message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegionEvent {
+    /// Write every region to its own file in `REGION_DIR`
+    Export,
+    /// Merge every file in `REGION_DIR` into the open save, see `merge.rs`
+    Merge,
+}
+
+/// A spline in a region file. Locations are in save units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegionSpline {
+    ty: SplineType,
+    points: Vec<[f32; 3]>,
+    visibility: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegionSwitch {
+    ty: u32,
+    location: [f32; 3],
+    rotation: [f32; 3],
+    state: u32,
+}
+
+/// Contents of a region file. Splines are in the region their first point is in, so each is in
+/// exactly one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegionFile {
+    name: String,
+    /// Opposite corners in save units, `None` for everything outside every region
+    bounds: Option<[[f32; 3]; 2]>,
+    splines: Vec<RegionSpline>,
+    switches: Vec<RegionSwitch>,
+}
+
+/// Keeps the letters and numbers of a region name, for its file name
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Drags out a new region on the ground in draw region mode
+fn draw_region(
+    palette: Res<Palette>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    pick_cam: Query<&PickingCamera>,
+    mut regions: ResMut<Regions>,
+) {
+    if palette.action != MouseAction::DrawRegion {
+        if regions.drawing.is_some() {
+            regions.drawing = None;
+        }
+        return;
+    }
+    let ground = pick_cam.iter().last().and_then(|cam| {
+        cam.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+            point: Vec3::ZERO,
+            normal: Vec3::new(0., 1., 0.),
+        })
+    });
+    let ground = match ground {
+        Some(int) => int.position(),
+        None => return,
+    };
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        regions.drawing = Some((ground, ground));
+    } else if let Some((start, _end)) = regions.drawing {
+        if mouse_button_input.just_released(MouseButton::Left) {
+            regions.drawing = None;
+            if start.distance(ground) > f32::EPSILON {
+                let name = format!("Region {}", regions.regions.len() + 1);
+                regions.regions.push(Region::new(name, start, ground));
+            }
+        } else {
+            regions.drawing = Some((start, ground));
+        }
+    }
+}
+
+/// Marks the entity a region is drawn with
+#[derive(Debug, Component)]
+struct RegionMarker;
+
+/// Draws regions as flat boxes on the ground, while the region window is open or a region is
+/// being drawn
+fn show_regions(
+    palette: Res<Palette>,
+    regions: Res<Regions>,
+    assets: Res<DefaultAssets>,
+    mut drawn: Local<Vec<(Vec3, Vec3)>>,
+    mut markers: Query<(Entity, &mut Visibility), With<RegionMarker>>,
+    mut commands: Commands,
+) {
+    let corners: Vec<(Vec3, Vec3)> = regions
+        .regions
+        .iter()
+        .map(|r| r.corners())
+        .chain(regions.drawing)
+        .collect();
+    if *drawn != corners {
+        for (entity, _v) in markers.iter() {
+            commands.entity(entity).despawn();
+        }
+        for (a, b) in corners.iter() {
+            let size = (*a - *b).abs();
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: assets.cube_mesh.clone(),
+                    material: assets.ghost_material.clone(),
+                    transform: Transform {
+                        translation: (*a + *b) / 2.,
+                        scale: Vec3::new(size.x, 0.05, size.z),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(RegionMarker);
+        }
+        *drawn = corners;
+    }
+    let visible = palette.show_regions || palette.action == MouseAction::DrawRegion;
+    for (_e, mut vis) in markers.iter_mut() {
+        if vis.is_visible != visible {
+            vis.is_visible = visible;
+        }
+    }
+}
+
+fn region_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut regions: ResMut<Regions>,
+    mut events: EventWriter<RegionEvent>,
+) {
+    if !palette.show_regions {
+        return;
+    }
+    let units = settings.units;
+    let regions = regions.as_mut();
+    egui::Window::new("Regions").show(egui_context.ctx_mut(), |ui| {
+        if regions.regions.is_empty() {
+            ui.label("Draw regions on the ground with the Draw Region action");
+        }
+        let mut remove = None;
+        for (i, region) in regions.regions.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut region.name).desired_width(120.));
+                let size = (region.max - region.min) * METERS_PER_UNIT;
+                ui.label(format!(
+                    "{} x {}",
+                    units.length(size.x),
+                    units.length(size.y)
+                ));
+                if ui.button("Delete").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            regions.regions.remove(i);
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .button("Export Regions")
+                .on_hover_text(format!(
+                    "Replaces the files in {}/, one per region and one for the rest",
+                    REGION_DIR
+                ))
+                .clicked()
+            {
+                events.send(RegionEvent::Export);
+            }
+            if ui
+                .button("Merge Regions")
+                .on_hover_text(format!(
+                    "Merges every file in {}/ into the open save",
+                    REGION_DIR
+                ))
+                .clicked()
+            {
+                events.send(RegionEvent::Merge);
+            }
+        });
+        if !regions.message.is_empty() {
+            ui.label(regions.message.as_str());
+        }
+    });
+}
+
+/// Writes a file for each region, and one for the rest, replacing any already in `REGION_DIR`
+fn export_regions(
+    regions: &[Region],
+    beziers: &Query<(Entity, &PolyBezier<CubicBezier>)>,
+    switches: &Query<(Entity, &Transform, &SwitchData)>,
+) -> Result<usize, GVASError> {
+    let mut files: Vec<RegionFile> = regions
+        .iter()
+        .map(|r| {
+            let (a, b) = r.corners();
+            RegionFile {
+                name: r.name.clone(),
+                bounds: Some([vec_to_gvas(a), vec_to_gvas(b)]),
+                splines: vec![],
+                switches: vec![],
+            }
+        })
+        .collect();
+    files.push(RegionFile {
+        name: REST.to_string(),
+        bounds: None,
+        splines: vec![],
+        switches: vec![],
+    });
+    let rest = regions.len();
+    let file_of = |p: Vec3| regions.iter().position(|r| r.contains(p)).unwrap_or(rest);
+    for (_e, bez) in beziers.iter() {
+        files[file_of(bez.get_control_point(0))]
+            .splines
+            .push(RegionSpline {
+                ty: bez.ty(),
+                points: bez.get_control_points().map(vec_to_gvas).collect(),
+                visibility: bez.get_visibility().to_vec(),
+            });
+    }
+    for (_e, trans, switch) in switches.iter() {
+        files[file_of(trans.translation)]
+            .switches
+            .push(RegionSwitch {
+                ty: switch.ty as u32,
+                location: vec_to_gvas(trans.translation),
+                rotation: quat_to_rotator(trans.rotation),
+                state: switch.state,
+            });
+    }
+    std::fs::create_dir_all(REGION_DIR)?;
+    for entry in std::fs::read_dir(REGION_DIR)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            std::fs::remove_file(path)?;
+        }
+    }
+    for (i, file) in files.iter().enumerate() {
+        // Numbered, so names can repeat
+        let name = if i == rest {
+            format!("{}.json", REST)
+        } else {
+            format!("{:02}-{}.json", i + 1, file_name(&file.name))
+        };
+        let out = File::create(Path::new(REGION_DIR).join(name))?;
+        serde_json::to_writer_pretty(out, file)?;
+    }
+    Ok(files.len())
+}
+
+fn read_regions() -> Result<Vec<RegionFile>, GVASError> {
+    let mut paths = std::fs::read_dir(REGION_DIR)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    paths.retain(|p| p.extension().is_some_and(|e| e == "json"));
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let file = File::open(path)?;
+            Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+        })
+        .collect()
+}
+
+fn region_files(
+    mut events: EventReader<RegionEvent>,
+    mut regions: ResMut<Regions>,
+    mut merge: ResMut<MergeState>,
+    mut palette: ResMut<Palette>,
+    beziers: Query<(Entity, &PolyBezier<CubicBezier>)>,
+    switches: Query<(Entity, &Transform, &SwitchData)>,
+) {
+    for event in events.iter() {
+        match event {
+            RegionEvent::Export => {
+                let result = export_regions(&regions.regions, &beziers, &switches);
+                regions.message = match result {
+                    Ok(n) => format!("Wrote {} files to {}/", n, REGION_DIR),
+                    Err(e) => {
+                        println!("Error: {:?}", e);
+                        format!("Exporting regions failed: {}", e)
+                    }
+                };
+            }
+            RegionEvent::Merge => {
+                let files = match read_regions() {
+                    Ok(files) => files,
+                    Err(e) => {
+                        println!("Error: {:?}", e);
+                        regions.message = format!("Reading regions failed: {}", e);
+                        continue;
+                    }
+                };
+                let mut drawn = vec![];
+                let mut splines = vec![];
+                let mut other_switches = vec![];
+                for file in files.iter() {
+                    if let Some([a, b]) = file.bounds {
+                        drawn.push(Region::new(
+                            file.name.clone(),
+                            gvas_to_vec(a),
+                            gvas_to_vec(b),
+                        ));
+                    }
+                    splines.extend(file.splines.iter().map(|s| MergeSpline {
+                        ty: s.ty,
+                        points: s.points.iter().map(|p| gvas_to_vec(*p)).collect(),
+                        visibility: s.visibility.clone(),
+                    }));
+                    for s in file.switches.iter() {
+                        match SwitchType::try_from(s.ty) {
+                            Ok(ty) => other_switches.push(SwitchData {
+                                ty,
+                                location: s.location,
+                                rotation: s.rotation,
+                                state: s.state,
+                            }),
+                            Err(_) => warn!("Unknown switch type {} in {}", s.ty, file.name),
+                        }
+                    }
+                }
+                regions.message = format!("Read {} files, see Merge Saves", files.len());
+                regions.regions = drawn;
+                // Reviewed and applied like any other merge
+                merge.pending = Some(compare_parts(splines, other_switches, &beziers, &switches));
+                palette.show_merge = true;
+            }
+        }
+    }
+}
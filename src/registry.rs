@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Index;
+
+use crate::gvas::SplineType;
+
+/// File spline types are read from, in the working directory. The bundled types are used if it
+/// doesn't exist, so new types added by the game only need a copy of
+/// `assets/spline_types.json` with an entry added
+pub const SPLINE_TYPES_FILE: &str = "spline_types.json";
+
+/// How the editor draws and offers a spline type, e.g.
+/// `{"ty": 8, "name": "Concrete Bridge", "mesh": "tube.obj", "color": [0.6, 0.6, 0.6]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplineTypeInfo {
+    /// The type in the save, by name for known types or by number for any other
+    pub ty: SplineType,
+    pub name: String,
+    /// Model bent along each segment. Either one of the models in `assets/models`, like
+    /// `track.obj`, or the path to an OBJ file
+    #[serde(default)]
+    pub mesh: Option<String>,
+    /// (width, height) of the box drawn when there is no model or it can't be loaded, in model
+    /// units
+    #[serde(default = "default_cross_section")]
+    pub cross_section: [f32; 2],
+    pub color: [f32; 3],
+    /// Height handles are drawn above the spline's data, in editor units. Overridden by
+    /// `Settings::spline_offsets`
+    #[serde(default)]
+    pub offset: f32,
+    /// Listed in the palette, so splines can be changed to this type
+    #[serde(default)]
+    pub placeable: bool,
+}
+
+fn default_cross_section() -> [f32; 2] {
+    [2., 1.]
+}
+
+/// Every spline type the editor knows about
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplineRegistry {
+    pub types: Vec<SplineTypeInfo>,
+    /// Used for types in a save that aren't listed. Its `ty` isn't used
+    pub unknown: SplineTypeInfo,
+}
+
+impl Default for SplineRegistry {
+    fn default() -> Self {
+        Self::new(
+            serde_json::from_str(include_str!("../assets/spline_types.json"))
+                .expect("Failed to parse included spline types"),
+        )
+    }
+}
+
+impl SplineRegistry {
+    fn new(types: Vec<SplineTypeInfo>) -> Self {
+        Self {
+            types,
+            unknown: SplineTypeInfo {
+                ty: SplineType(u32::MAX),
+                name: "Unknown".to_string(),
+                mesh: None,
+                cross_section: default_cross_section(),
+                color: [1.0, 0.0, 1.0],
+                offset: 0.,
+                placeable: false,
+            },
+        }
+    }
+
+    /// Reads `SPLINE_TYPES_FILE`, or the bundled types if it doesn't exist or can't be read
+    pub fn load() -> Self {
+        match std::fs::read_to_string(SPLINE_TYPES_FILE) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(types) => Self::new(types),
+                Err(e) => {
+                    println!("Error: {:?}", e);
+                    Self::default()
+                }
+            },
+            // No spline types file, just use the bundled ones
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// The listed type, or `unknown`
+    pub fn get(&self, ty: SplineType) -> &SplineTypeInfo {
+        self.types
+            .iter()
+            .find(|info| info.ty == ty)
+            .unwrap_or(&self.unknown)
+    }
+
+    /// Name to show for a type. Unlisted types are shown by number
+    pub fn name(&self, ty: SplineType) -> String {
+        match self.types.iter().find(|info| info.ty == ty) {
+            Some(info) => info.name.clone(),
+            None => format!("Unknown ({})", ty.0),
+        }
+    }
+}
+
+/// A value for each type in a `SplineRegistry`, and one shared by every other type
+#[derive(Debug, Clone)]
+pub struct SplineTypeMap<T> {
+    types: HashMap<SplineType, T>,
+    unknown: T,
+}
+
+impl<T> SplineTypeMap<T> {
+    pub fn new(registry: &SplineRegistry, mut f: impl FnMut(&SplineTypeInfo) -> T) -> Self {
+        Self {
+            types: registry
+                .types
+                .iter()
+                .map(|info| (info.ty, f(info)))
+                .collect(),
+            unknown: f(&registry.unknown),
+        }
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> SplineTypeMap<U> {
+        SplineTypeMap {
+            types: self.types.iter().map(|(ty, v)| (*ty, f(v))).collect(),
+            unknown: f(&self.unknown),
+        }
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.types.values().chain(std::iter::once(&self.unknown))
+    }
+}
+
+impl<T> Index<SplineType> for SplineTypeMap<T> {
+    type Output = T;
+
+    fn index(&self, ty: SplineType) -> &T {
+        self.types.get(&ty).unwrap_or(&self.unknown)
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use bevy_mod_picking::PickingCamera;
+
+use crate::dialog::Dialogs;
+use crate::inspector::Locked;
+use crate::palette::{MouseAction, Palette};
+use crate::patch::{EditEvent, PatchOp, SplineShape};
+use crate::safety::{EditSize, SafetyNet};
+use crate::selection::Selected;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierSection, BezierSectionUpdate, DragState};
+
+/// Plugin for tools that move every control point of a spline at once, e.g. smoothing
+pub struct ReshapePlugin;
+
+impl Plugin for ReshapePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReshapeEvent>();
+        app.add_system(reshape_splines);
+        app.add_system(straighten_section);
+    }
+}
+
+/// Moves the control points of `spline` to `points`, which must have one point for each
+/// control point. The change is added to the history
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReshapeEvent {
+    pub spline: Entity,
+    pub points: Vec<Vec3>,
+    /// What's reshaping the spline, e.g. "smooth", for asking before large edits
+    pub action: &'static str,
+}
+
+fn reshape_splines(
+    mut events: EventReader<ReshapeEvent>,
+    settings: Res<Settings>,
+    mut handles: Query<(&DragState, &Parent, &mut Transform)>,
+    mut beziers: Query<&mut PolyBezier<CubicBezier>>,
+    locked: Query<&Locked>,
+    mut section_update: EventWriter<BezierSectionUpdate>,
+    mut edits: EventWriter<EditEvent>,
+    (mut safety, mut dialogs): (ResMut<SafetyNet>, ResMut<Dialogs>),
+) {
+    for event in events.iter() {
+        let mut bez = match beziers.get_mut(event.spline) {
+            Ok(bez) => bez,
+            Err(_) => continue,
+        };
+        if locked.get(event.spline).is_ok() {
+            warn!("Can't {} a locked spline", event.action);
+            continue;
+        }
+        if event.points.len() != bez.len() {
+            warn!(
+                "Can't {} a spline with {} points to {} points",
+                event.action,
+                bez.len(),
+                event.points.len()
+            );
+            continue;
+        }
+        let before = SplineShape::of(&bez);
+        let mut moved = 0;
+        for (i, to) in event.points.iter().copied().enumerate() {
+            if bez.get_control_point(i).distance(to) > f32::EPSILON {
+                bez.update(i, to);
+                moved += 1;
+            }
+        }
+        let off = settings.curve_offset(bez.ty());
+        for (state, parent, mut trans) in handles.iter_mut() {
+            if parent.0 == event.spline {
+                trans.translation = bez.get_control_point(state.pt) + off;
+            }
+        }
+        section_update.send(BezierSectionUpdate {
+            bezier: event.spline,
+        });
+        let size = EditSize {
+            points: moved,
+            splines: 1,
+            ..Default::default()
+        };
+        safety.keep(
+            PatchOp::reshaped(before, &bez).into_iter().collect(),
+            size,
+            event.action,
+            settings.edit_limit,
+            &mut dialogs,
+            &mut edits,
+        );
+    }
+}
+
+/// In straighten mode, clicking a section straightens it. If the spline has more than one point
+/// selected, everything between the first and last of them is straightened instead
+fn straighten_section(
+    palette: Res<Palette>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    pick_cam: Query<&PickingCamera>,
+    sections: Query<(&Parent, &BezierSection)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    selected: Query<(&DragState, &Parent), With<Selected>>,
+    mut reshape: EventWriter<ReshapeEvent>,
+) {
+    if palette.action != MouseAction::Straighten
+        || !mouse_button_input.just_pressed(MouseButton::Left)
+    {
+        return;
+    }
+    let hit = match pick_cam.iter().last().and_then(|cam| cam.intersect_top()) {
+        Some((hit, _intersection)) => hit,
+        None => return,
+    };
+    let (parent, section) = match sections.get(hit) {
+        Ok(section) => section,
+        Err(_) => return,
+    };
+    let bez = match beziers.get(parent.0) {
+        Ok(bez) => bez,
+        Err(_) => return,
+    };
+    let seg = match bez.get_segment(&section.0) {
+        Some(seg) => seg,
+        None => return,
+    };
+    let picked: Vec<usize> = selected
+        .iter()
+        .filter(|(_state, p)| p.0 == parent.0)
+        .map(|(state, _p)| state.pt)
+        .collect();
+    let (first, last) = match (picked.iter().min(), picked.iter().max()) {
+        (Some(&first), Some(&last)) if first < last => (first, last),
+        _ => (seg, seg + 1),
+    };
+    reshape.send(ReshapeEvent {
+        spline: parent.0,
+        points: bez.straightened(first, last),
+        action: "straighten",
+    });
+}
@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::dialog::{DialogEvent, DialogResponse, Dialogs};
+use crate::gvas::SwitchData;
+use crate::history::inverse;
+use crate::patch::{EditEvent, PatchEvent, PatchOp, SplineShape, SwitchPlacement};
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// Id of the dialog asking whether to go ahead with a large edit
+const SAFETY_DIALOG: &str = "safety";
+
+/// Plugin for asking before edits that change more than `Settings::edit_limit` points and
+/// splines
+pub struct SafetyPlugin;
+
+impl Plugin for SafetyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SafetyNet::default());
+        app.add_system(answer_safety_dialog);
+    }
+}
+
+/// How much an edit changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EditSize {
+    pub points: usize,
+    /// Splines with a moved point
+    pub splines: usize,
+    pub added: usize,
+    /// Splines and switches
+    pub deleted: usize,
+    /// Switches moved or turned
+    pub switches: usize,
+}
+
+impl EditSize {
+    /// Whether the edit changes more than `limit` things. Nothing is too big without a limit
+    pub fn too_big(&self, limit: Option<usize>) -> bool {
+        limit.is_some_and(|limit| {
+            self.points + self.added + self.deleted + self.switches > limit
+        })
+    }
+
+    /// What the edit does, e.g. "move 412 points across 37 splines"
+    fn describe(&self) -> String {
+        let mut parts = vec![];
+        if self.points > 0 {
+            parts.push(format!(
+                "move {} points across {} splines",
+                self.points, self.splines
+            ));
+        }
+        if self.added > 0 {
+            parts.push(format!("add {} splines", self.added));
+        }
+        if self.deleted > 0 {
+            parts.push(format!("delete {} splines and switches", self.deleted));
+        }
+        if self.switches > 0 {
+            parts.push(format!("move {} switches", self.switches));
+        }
+        parts.join(", ")
+    }
+}
+
+/// The splines and switches an edit moves, as they were before it, so what it changed can be
+/// kept once it's done, e.g. by a mirror or a gizmo drag
+#[derive(Debug, Default)]
+pub struct Moves {
+    splines: Vec<(Entity, SplineShape)>,
+    switches: Vec<(Entity, SwitchPlacement)>,
+}
+
+impl Moves {
+    /// Records `bez` before its first move. Returns whether it wasn't recorded yet, so its
+    /// sections can be updated once
+    pub fn spline(&mut self, entity: Entity, bez: &PolyBezier<CubicBezier>) -> bool {
+        if self.splines.iter().any(|(e, _s)| *e == entity) {
+            return false;
+        }
+        self.splines.push((entity, SplineShape::of(bez)));
+        true
+    }
+
+    /// Records a switch before its first move
+    pub fn switch(&mut self, entity: Entity, trans: &Transform, switch: &SwitchData) {
+        if self.switches.iter().all(|(e, _p)| *e != entity) {
+            self.switches
+                .push((entity, SwitchPlacement::of(trans, switch)));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.splines.is_empty() && self.switches.is_empty()
+    }
+
+    /// The edits from what was recorded to how things are now, given by `spline` and `switch`,
+    /// and how big they are. Anything that ended up where it started is left out
+    pub fn edits(
+        self,
+        spline: impl Fn(Entity) -> Option<SplineShape>,
+        switch: impl Fn(Entity) -> Option<SwitchPlacement>,
+    ) -> (Vec<PatchOp>, EditSize) {
+        let mut size = EditSize::default();
+        let mut edits = vec![];
+        for (entity, from) in self.splines {
+            let to = match spline(entity) {
+                Some(to) if to != from => to,
+                _ => continue,
+            };
+            size.points += from
+                .points
+                .iter()
+                .zip(to.points.iter())
+                .filter(|(a, b)| a != b)
+                .count();
+            size.splines += 1;
+            edits.push(PatchOp::ReshapeSpline { from, to });
+        }
+        for (entity, from) in self.switches {
+            match switch(entity) {
+                Some(to) if to != from => {
+                    size.switches += 1;
+                    edits.push(PatchOp::MoveSwitch { from, to });
+                }
+                _ => (),
+            }
+        }
+        (edits, size)
+    }
+}
+
+/// An edit waiting for the safety dialog to be answered
+#[derive(Debug, Clone, PartialEq)]
+enum Held {
+    /// Operations that haven't been applied yet
+    Patch(Vec<PatchOp>),
+    /// Edits that have already been made, which haven't been added to the history yet
+    Made(Vec<PatchOp>),
+}
+
+/// What the safety dialog asks about a held edit, and the label of the button that goes ahead
+#[derive(Debug, Clone, PartialEq)]
+struct Question {
+    message: String,
+    ok: &'static str,
+}
+
+/// Edits waiting for the safety dialog, oldest first. Only the oldest is asked about, and the
+/// next is asked about once it's answered
+#[derive(Debug, Default)]
+pub struct SafetyNet {
+    held: VecDeque<(Held, Question)>,
+}
+
+impl SafetyNet {
+    /// Holds back an edit, asking about it once the edits held before it are answered
+    fn hold(&mut self, held: Held, question: Question, dialogs: &mut Dialogs) {
+        if self.held.is_empty() {
+            question.ask(dialogs);
+        }
+        self.held.push_back((held, question));
+    }
+
+    /// Holds back operations until they're confirmed, then applies them
+    pub fn hold_patch(&mut self, ops: Vec<PatchOp>, size: EditSize, dialogs: &mut Dialogs) {
+        let question = Question {
+            message: format!("This will {}.", size.describe()),
+            ok: "Apply",
+        };
+        self.hold(Held::Patch(ops), question, dialogs);
+    }
+
+    /// Asks whether to keep edits that have already been made, e.g. by a drag. Kept edits are
+    /// added to the history, otherwise they're undone. `action` is what made them, e.g. "drag"
+    pub fn hold_edits(
+        &mut self,
+        edits: Vec<PatchOp>,
+        size: EditSize,
+        action: &str,
+        dialogs: &mut Dialogs,
+    ) {
+        let question = Question {
+            message: format!(
+                "This {} will {}. Cancel puts everything back.",
+                action,
+                size.describe()
+            ),
+            ok: "Keep",
+        };
+        self.hold(Held::Made(edits), question, dialogs);
+    }
+
+    /// Adds edits that have already been made to the history, asking first if they change more
+    /// than `limit` points, see `hold_edits`
+    pub fn keep(
+        &mut self,
+        edits: Vec<PatchOp>,
+        size: EditSize,
+        action: &str,
+        limit: Option<usize>,
+        dialogs: &mut Dialogs,
+        history: &mut EventWriter<EditEvent>,
+    ) {
+        if size.too_big(limit) {
+            self.hold_edits(edits, size, action, dialogs);
+        } else {
+            for op in edits {
+                history.send(EditEvent(op));
+            }
+        }
+    }
+}
+
+impl Question {
+    fn ask(&self, dialogs: &mut Dialogs) {
+        dialogs.confirm(SAFETY_DIALOG, "Large Edit", self.message.clone(), self.ok);
+    }
+}
+
+fn answer_safety_dialog(
+    mut dialog_events: EventReader<DialogEvent>,
+    mut safety: ResMut<SafetyNet>,
+    mut dialogs: ResMut<Dialogs>,
+    mut patch_events: EventWriter<PatchEvent>,
+    mut edits: EventWriter<EditEvent>,
+) {
+    for event in dialog_events.iter().filter(|e| e.id == SAFETY_DIALOG) {
+        let held = safety.held.pop_front().map(|(held, _q)| held);
+        if let Some((_h, question)) = safety.held.front() {
+            question.ask(&mut dialogs);
+        }
+        match (held, &event.response) {
+            (Some(Held::Patch(ops)), DialogResponse::Ok) => {
+                patch_events.send(PatchEvent::Confirmed(ops));
+            }
+            (Some(Held::Made(ops)), DialogResponse::Ok) => {
+                for op in ops {
+                    edits.send(EditEvent(op));
+                }
+            }
+            (Some(Held::Made(ops)), _) => {
+                let undo = ops.iter().rev().filter_map(inverse).collect();
+                patch_events.send(PatchEvent::Undo(undo));
+            }
+            _ => (),
+        }
+    }
+}
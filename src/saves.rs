@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Mutex;
+
+use crate::dialog::Dialogs;
+use crate::gvas::{GVASError, RROSave};
+
+/// Plugin for listing the saves in the game's save directory
+pub struct SaveBrowserPlugin;
+
+impl Plugin for SaveBrowserPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveBrowser {
+            saves: vec![],
+            sort: SortBy::Name,
+            descending: false,
+            dir: PathBuf::new(),
+            scan: None,
+        });
+        app.add_system(receive_scan);
+    }
+}
+
+/// Slots the game shows, which are listed even if they don't exist yet so they can be saved to
+const SLOTS: usize = 10;
+
+/// A save in the save directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveInfo {
+    pub path: PathBuf,
+    /// File name, without the extension
+    pub name: String,
+    /// Whether the file exists, or is an empty slot
+    pub exists: bool,
+    pub date: Option<String>,
+    pub players: Vec<String>,
+    pub splines: Option<usize>,
+    /// Why the save couldn't be read
+    pub error: Option<String>,
+}
+
+impl SaveInfo {
+    fn read(path: PathBuf) -> Self {
+        let name = path
+            .file_stem()
+            .map_or(String::new(), |n| n.to_string_lossy().into_owned());
+        let mut info = Self {
+            path,
+            name,
+            exists: true,
+            date: None,
+            players: vec![],
+            splines: None,
+            error: None,
+        };
+        match File::open(&info.path)
+            .map_err(GVASError::from)
+            .and_then(|file| RROSave::read(&mut BufReader::new(file)))
+        {
+            Ok(gvas) => {
+                info.date = gvas.save_date().ok().map(str::to_string);
+                info.players = gvas
+                    .players()
+                    .map_or(vec![], |p| p.map(|p| p.name).collect());
+                info.splines = gvas.curves().ok().map(|c| c.len());
+            }
+            Err(e) => info.error = Some(e.to_string()),
+        }
+        info
+    }
+
+    fn empty_slot(dir: &Path, slot: usize) -> Self {
+        let name = format!("slot{}", slot);
+        Self {
+            path: dir.join(format!("{}.sav", name)),
+            name,
+            exists: false,
+            date: None,
+            players: vec![],
+            splines: None,
+            error: None,
+        }
+    }
+
+    /// Sorts names by any number at the end, so `slot10` comes after `slot9`
+    fn name_key(&self) -> (&str, u64) {
+        let prefix = self.name.trim_end_matches(|c: char| c.is_ascii_digit());
+        (prefix, self.name[prefix.len()..].parse().unwrap_or(0))
+    }
+}
+
+/// The save in `dir` that was written last, which is usually the one last played. Only the
+/// modification times are read, so this is fast enough to call from the UI
+pub fn most_recent_save(dir: &Path) -> std::io::Result<Option<PathBuf>> {
+    Ok(std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "sav"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _path)| *modified)
+        .map(|(_modified, path)| path))
+}
+
+/// Column the save list is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Name,
+    Date,
+    Splines,
+}
+
+pub struct SaveBrowser {
+    pub saves: Vec<SaveInfo>,
+    pub sort: SortBy,
+    pub descending: bool,
+    /// Directory last scanned
+    pub dir: PathBuf,
+    /// Scan running on another thread, with why the directory couldn't be read
+    scan: Option<Mutex<Receiver<(Vec<SaveInfo>, Option<String>)>>>,
+}
+
+impl SaveBrowser {
+    /// Starts reading every save in `dir` on another thread, since each save has to be parsed
+    /// to find its splines
+    pub fn rescan(&mut self, dir: PathBuf) {
+        let (sender, receiver) = channel();
+        self.dir = dir.clone();
+        std::thread::spawn(move || {
+            let (mut saves, error): (Vec<_>, _) = match std::fs::read_dir(&dir) {
+                Ok(dir) => (
+                    dir.filter_map(|entry| entry.ok().map(|e| e.path()))
+                        .filter(|path| path.extension().is_some_and(|e| e == "sav"))
+                        .map(SaveInfo::read)
+                        .collect(),
+                    None,
+                ),
+                Err(e) => (vec![], Some(format!("{}\n{}", dir.display(), e))),
+            };
+            for slot in 1..=SLOTS {
+                let empty = SaveInfo::empty_slot(&dir, slot);
+                if !saves.iter().any(|s| s.path == empty.path) {
+                    saves.push(empty);
+                }
+            }
+            let _ = sender.send((saves, error));
+        });
+        self.scan = Some(Mutex::new(receiver));
+    }
+
+    pub fn scanning(&self) -> bool {
+        self.scan.is_some()
+    }
+
+    /// Sorts by `by`, or reverses the order if already sorted by it
+    pub fn sort_by(&mut self, by: SortBy) {
+        if self.sort == by {
+            self.descending = !self.descending;
+        } else {
+            self.sort = by;
+            self.descending = false;
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        match self.sort {
+            SortBy::Name => self.saves.sort_by(|a, b| a.name_key().cmp(&b.name_key())),
+            SortBy::Date => self.saves.sort_by(|a, b| a.date.cmp(&b.date)),
+            SortBy::Splines => self.saves.sort_by_key(|s| s.splines),
+        }
+        if self.descending {
+            self.saves.reverse();
+        }
+    }
+}
+
+fn receive_scan(mut browser: ResMut<SaveBrowser>, mut dialogs: ResMut<Dialogs>) {
+    let (saves, error) = if let Some(scan) = &browser.scan {
+        match scan.lock().unwrap().try_recv() {
+            Ok(scanned) => scanned,
+            Err(TryRecvError::Empty) => return,
+            // The scan panicked
+            Err(TryRecvError::Disconnected) => (vec![], Some("The scan failed".to_string())),
+        }
+    } else {
+        return;
+    };
+    if let Some(error) = error {
+        dialogs.message("scan_failed", "Could Not List Saves", error);
+    }
+    browser.saves = saves;
+    browser.scan = None;
+    browser.sort();
+}
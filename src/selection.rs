@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::{Hover, PickableButton, PickingCamera};
+
+use crate::control::DefaultAssets;
+use crate::gvas::SwitchData;
+use crate::inspector::Locked;
+use crate::palette::{MouseAction, Palette};
+use crate::update::{BezierSection, DeletePreview, DragState};
+
+/// Plugin for selecting several control points and switches at once, which are then dragged
+/// together, see `update_bezier_transform`
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(select_points);
+        app.add_system(box_select);
+        app.add_system(show_selection);
+    }
+}
+
+/// Marks a control point handle or switch as selected
+#[derive(Debug, Component, Default)]
+pub struct Selected;
+
+/// In drag mode, clicking a handle selects only it, shift clicking a handle adds or removes it,
+/// and shift clicking a section adds or removes its whole spline. Ctrl+A selects every point of
+/// the hovered spline, or of splines that already have a point selected. Escape clears the
+/// selection
+fn select_points(
+    palette: Res<Palette>,
+    keys: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    handles: Query<(&Hover, &Parent, Option<&Selected>, Entity), With<DragState>>,
+    sections: Query<(&Hover, &Parent), With<BezierSection>>,
+    switches: Query<(&Hover, Option<&Selected>, Entity), With<SwitchData>>,
+    locked: Query<&Locked>,
+    mut commands: Commands,
+) {
+    if palette.action != MouseAction::Drag {
+        return;
+    }
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let clicked = mouse_button_input.just_pressed(MouseButton::Left);
+    let hovered_handle = handles.iter().find(|(h, ..)| h.hovered());
+    let hovered_spline = hovered_handle.map(|(_h, parent, ..)| parent.0).or_else(|| {
+        sections
+            .iter()
+            .find(|(h, _p)| h.hovered())
+            .map(|(_h, p)| p.0)
+    });
+    let hovered_switch = switches.iter().find(|(h, ..)| h.hovered());
+    let mut clear = || {
+        for (_h, _p, selected, entity) in handles.iter() {
+            if selected.is_some() {
+                commands.entity(entity).remove::<Selected>();
+            }
+        }
+        for (_h, selected, entity) in switches.iter() {
+            if selected.is_some() {
+                commands.entity(entity).remove::<Selected>();
+            }
+        }
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        clear();
+    } else if ctrl && keys.just_pressed(KeyCode::A) {
+        let splines: Vec<Entity> = match hovered_spline {
+            Some(spline) => vec![spline],
+            None => handles
+                .iter()
+                .filter(|(_h, _p, selected, _e)| selected.is_some())
+                .map(|(_h, parent, ..)| parent.0)
+                .collect(),
+        };
+        for (_h, parent, selected, entity) in handles.iter() {
+            if selected.is_none() && splines.contains(&parent.0) && locked.get(parent.0).is_err() {
+                commands.entity(entity).insert(Selected);
+            }
+        }
+    } else if clicked && shift {
+        if let Some((_h, parent, selected, entity)) = hovered_handle {
+            if selected.is_some() {
+                commands.entity(entity).remove::<Selected>();
+            } else if locked.get(parent.0).is_err() {
+                commands.entity(entity).insert(Selected);
+            }
+        } else if let Some((_h, selected, entity)) = hovered_switch {
+            if selected.is_some() {
+                commands.entity(entity).remove::<Selected>();
+            } else {
+                commands.entity(entity).insert(Selected);
+            }
+        } else if let Some(spline) = hovered_spline {
+            let points: Vec<_> = handles.iter().filter(|(_h, p, ..)| p.0 == spline).collect();
+            // Selects the rest of a partly selected spline
+            let all = points
+                .iter()
+                .all(|(_h, _p, selected, _e)| selected.is_some());
+            for (_h, _p, selected, entity) in points {
+                if all {
+                    commands.entity(entity).remove::<Selected>();
+                } else if selected.is_none() && locked.get(spline).is_err() {
+                    commands.entity(entity).insert(Selected);
+                }
+            }
+        }
+    } else if clicked {
+        // Grabbing a selected handle drags the whole selection, anything else starts over
+        let grabbed = match (hovered_handle, hovered_switch) {
+            (Some((_, _, selected, entity)), _) | (None, Some((_, selected, entity))) => {
+                Some((selected, entity))
+            }
+            (None, None) => None,
+        };
+        if let Some((None, entity)) = grabbed {
+            clear();
+            commands.entity(entity).insert(Selected);
+        }
+    }
+}
+
+/// In box select mode, dragging out a rectangle selects every visible handle and switch inside
+/// it, in place of the selection. Holding shift adds them to the selection instead
+fn box_select(
+    palette: Res<Palette>,
+    keys: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PickingCamera>>,
+    handles: Query<
+        (
+            &GlobalTransform,
+            &Visibility,
+            &Parent,
+            Option<&Selected>,
+            Entity,
+        ),
+        With<DragState>,
+    >,
+    switches: Query<(&GlobalTransform, &Visibility, Option<&Selected>, Entity), With<SwitchData>>,
+    locked: Query<&Locked>,
+    mut egui_context: ResMut<EguiContext>,
+    mut start: Local<Option<Vec2>>,
+    mut commands: Commands,
+) {
+    if palette.action != MouseAction::BoxSelect {
+        *start = None;
+        return;
+    }
+    // Boxes are dragged out in whichever view is picking
+    let (camera, camera_transform) = match cameras.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    let window = match windows.get(camera.window) {
+        Some(window) => window,
+        None => return,
+    };
+    let cursor = match window.cursor_position() {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    let ctx = egui_context.ctx_for_window_mut(camera.window);
+    if mouse_button_input.just_pressed(MouseButton::Left) && !ctx.is_pointer_over_area() {
+        *start = Some(cursor);
+    }
+    let origin = match *start {
+        Some(origin) => origin,
+        None => return,
+    };
+    let (min, max) = (origin.min(cursor), origin.max(cursor));
+    if mouse_button_input.pressed(MouseButton::Left) {
+        // Window coordinates start at the bottom left, egui's at the top left
+        let scale = window.scale_factor() as f32 / ctx.pixels_per_point();
+        let to_egui = |p: Vec2| egui::pos2(p.x * scale, (window.height() - p.y) * scale);
+        let rect = egui::Rect::from_two_pos(to_egui(origin), to_egui(cursor));
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("box_select"),
+        ));
+        let color = egui::Color32::from_rgb(255, 204, 0);
+        painter.rect_filled(rect, 0., color.linear_multiply(0.1));
+        painter.rect_stroke(rect, 0., (1., color));
+        return;
+    }
+    *start = None;
+    let inside = |p: Vec3| {
+        camera
+            .world_to_screen(&windows, camera_transform, p)
+            .is_some_and(|s| s.cmpge(min).all() && s.cmple(max).all())
+    };
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    let mut select = |hit: bool, selected: Option<&Selected>, entity: Entity| {
+        if hit && selected.is_none() {
+            commands.entity(entity).insert(Selected);
+        } else if !hit && !shift && selected.is_some() {
+            commands.entity(entity).remove::<Selected>();
+        }
+    };
+    for (trans, vis, parent, selected, entity) in handles.iter() {
+        let hit = vis.is_visible && inside(trans.translation) && locked.get(parent.0).is_err();
+        select(hit, selected, entity);
+    }
+    for (trans, vis, selected, entity) in switches.iter() {
+        select(
+            vis.is_visible && inside(trans.translation),
+            selected,
+            entity,
+        );
+    }
+}
+
+/// Colors selected handles and switches, by swapping the material they have when they aren't
+/// hovered
+fn show_selection(
+    assets: Res<DefaultAssets>,
+    mut handles: Query<
+        (
+            &mut Handle<StandardMaterial>,
+            &mut PickableButton<StandardMaterial>,
+            &Hover,
+            Option<&Selected>,
+        ),
+        (With<DragState>, Without<DeletePreview>),
+    >,
+    mut switches: Query<
+        (
+            &mut Handle<StandardMaterial>,
+            &mut PickableButton<StandardMaterial>,
+            &Hover,
+            Option<&Selected>,
+            &SwitchData,
+        ),
+        (Without<DragState>, Without<DeletePreview>),
+    >,
+) {
+    for (material, pick, hover, selected) in handles.iter_mut() {
+        let initial = if selected.is_some() {
+            &assets.handle_selected_material
+        } else {
+            &assets.handle_material
+        };
+        show_initial(material, pick, hover, initial);
+    }
+    for (material, pick, hover, selected, switch) in switches.iter_mut() {
+        let initial = if selected.is_some() {
+            &assets.handle_selected_material
+        } else {
+            &assets.switch_material[switch.ty][false]
+        };
+        show_initial(material, pick, hover, initial);
+    }
+}
+
+/// Gives a handle or switch a new material for when it isn't hovered
+fn show_initial(
+    mut material: Mut<Handle<StandardMaterial>>,
+    mut pick: Mut<PickableButton<StandardMaterial>>,
+    hover: &Hover,
+    initial: &Handle<StandardMaterial>,
+) {
+    if pick.initial.as_ref() != Some(initial) {
+        pick.initial = Some(initial.clone());
+        pick.selected = Some(initial.clone());
+        if !hover.hovered() {
+            *material = initial.clone();
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::coords::Units;
+use crate::gvas::SplineType;
+use crate::registry::SplineRegistry;
+use crate::spline::mesh::{MeshDetail, MeshQuality};
+use crate::theme::Theme;
+
+/// File user settings are read from, in the working directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Plugin for loading user settings
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut settings: Settings = match File::open(SETTINGS_FILE) {
+            Ok(file) => match serde_json::from_reader(std::io::BufReader::new(file)) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    println!("Error: {:?}", e);
+                    Settings::default()
+                }
+            },
+            // No settings file, just use the defaults
+            Err(_) => Settings::default(),
+        };
+        settings.spline_types = SplineRegistry::load();
+        app.insert_resource(settings);
+    }
+}
+
+/// User settings, e.g.
+/// `{"spline_offsets": {"Track": 1.0, "WoodBridge": 0.5}, "units": "Imperial"}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Height handles of each spline type are drawn above the spline's data, in editor units.
+    /// Types that aren't listed use the offset from the spline types file
+    pub spline_offsets: HashMap<SplineType, f32>,
+    /// Units lengths are shown and entered in
+    pub units: Units,
+    /// Don't spawn spline handles when loading a save, only once each spline is first hovered
+    /// or selected. Tools that look for handles, like patches and connected moves, won't see
+    /// splines that haven't been touched yet
+    pub fast_load: bool,
+    /// Preset `mesh_detail` was last set from. Draft meshes are boxes instead of the model
+    pub mesh_quality: MeshQuality,
+    /// How finely spline meshes are cut along each segment, see `MeshDetail`
+    pub mesh_detail: MeshDetail,
+    /// Use `MeshQuality::Draft` for splines while they're dragged, and remesh them at
+    /// `mesh_quality` once the drag ends
+    pub draft_while_dragging: bool,
+    /// Draw every spline as a line colored by its type, without meshing it, see `declutter.rs`.
+    /// Splines can only be picked by their handles
+    pub centerlines_only: bool,
+    /// Splines further than this from the camera, in meters, are drawn as plain lines without
+    /// handles, see `declutter.rs`. Off if not set
+    pub declutter_distance: Option<f32>,
+    /// Read each save back after writing it and check it matches what was meant to be
+    /// written, see `RROSave::verify`
+    pub verify_saves: bool,
+    /// Most points, splines, and switches one edit can change before asking to confirm it, see
+    /// `safety.rs`. Never asks if not set
+    pub edit_limit: Option<usize>,
+    /// Steepest percent grade a dragged control point can make either segment next to it, see
+    /// `limits.rs`. Unlimited if not set
+    pub drag_max_grade: Option<f32>,
+    /// Tightest radius in meters a dragged control point can make any segment it changes curve,
+    /// see `limits.rs`. Unlimited if not set
+    pub drag_min_radius: Option<f32>,
+    /// Directory the game keeps its saves in. Found automatically if not set, see
+    /// `palette::save_dir`
+    pub save_dir: Option<PathBuf>,
+    /// Most segments of each spline type the budget window allows before warning, see
+    /// `budget.rs`. Types that aren't listed have no limit
+    pub segment_limits: HashMap<SplineType, usize>,
+    /// Most control points over every spline the budget window allows before warning
+    pub control_point_limit: Option<usize>,
+    /// Light or dark windows, see `theme.rs`
+    pub theme: Theme,
+    /// Multiplies the size of text and widgets in every window
+    pub text_scale: f32,
+    /// Read from its own file, see `SplineRegistry::load`
+    #[serde(skip)]
+    pub spline_types: SplineRegistry,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            spline_offsets: HashMap::new(),
+            units: Units::default(),
+            fast_load: false,
+            mesh_quality: MeshQuality::default(),
+            mesh_detail: MeshDetail::default(),
+            draft_while_dragging: true,
+            centerlines_only: false,
+            declutter_distance: None,
+            verify_saves: true,
+            edit_limit: Some(100),
+            drag_max_grade: None,
+            drag_min_radius: None,
+            save_dir: None,
+            segment_limits: HashMap::new(),
+            control_point_limit: None,
+            theme: Theme::default(),
+            text_scale: 1.,
+            spline_types: SplineRegistry::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Offset from a spline's data to where its handles are drawn. Every tool that converts
+    /// between handle translations and control points should go through this
+    pub fn curve_offset(&self, ty: SplineType) -> Vec3 {
+        match self.spline_offsets.get(&ty) {
+            Some(y) => Vec3::new(0., *y, 0.),
+            None => Vec3::new(0., self.spline_types.get(ty).offset, 0.),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+use serde::{Deserialize, Serialize};
+use smooth_bevy_cameras::LookTransform;
+
+use crate::coords::METERS_PER_UNIT;
+use crate::dock::Layout;
+use crate::gvas::{industry_name, IndustryData, SplineType};
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::spline::{CubicBezier, PolyBezier};
+
+/// File siding requirements are read from, in the working directory. The bundled ones are used
+/// if it doesn't exist
+pub const SIDINGS_FILE: &str = "industry_sidings.json";
+
+/// Plugin for checking that every industry has the sidings it needs
+pub struct SidingPlugin;
+
+impl Plugin for SidingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SidingCheck {
+            requirements: load_requirements(),
+            tolerance: 10.,
+            results: vec![],
+            checked: false,
+        });
+        app.add_system(siding_window);
+    }
+}
+
+/// Track segments more than this far from parallel with a siding don't count towards it
+const MAX_ANGLE: f32 = 5.;
+
+/// A straight siding an industry needs, e.g.
+/// `{"industry": 1, "name": "Log loading", "offset": [0, 0, 20], "length": 40}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidingRequirement {
+    pub industry: u32,
+    pub name: String,
+    /// Center of the siding relative to the industry, in meters. X is along the industry's
+    /// length, Z across it
+    pub offset: [f32; 3],
+    /// Angle of the siding from the industry's length, in degrees
+    #[serde(default)]
+    pub angle: f32,
+    /// Shortest straight track that can serve the industry, in meters
+    pub length: f32,
+}
+
+/// Reads `SIDINGS_FILE`, or the bundled requirements if it doesn't exist or can't be read
+fn load_requirements() -> Vec<SidingRequirement> {
+    let bundled = || {
+        serde_json::from_str(include_str!("../assets/industry_sidings.json"))
+            .expect("Failed to parse included industry sidings")
+    };
+    match std::fs::read_to_string(SIDINGS_FILE) {
+        Ok(json) => match serde_json::from_str(&json) {
+            Ok(requirements) => requirements,
+            Err(e) => {
+                println!("Error: {:?}", e);
+                bundled()
+            }
+        },
+        // No sidings file, just use the bundled ones
+        Err(_) => bundled(),
+    }
+}
+
+/// How well a requirement is met
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SidingStatus {
+    /// Straight track of at least this length, in meters
+    Ok(f32),
+    /// Only this much straight track, in meters
+    Short(f32),
+    /// No parallel track in reach. The nearest track is this far to the side, in meters
+    Missing(Option<f32>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SidingResult {
+    /// Industry type
+    pub ty: u32,
+    pub requirement: String,
+    /// Where the siding should be, in editor space
+    pub location: Vec3,
+    pub status: SidingStatus,
+}
+
+pub struct SidingCheck {
+    pub requirements: Vec<SidingRequirement>,
+    /// How far track can be from where a siding should be, in meters
+    pub tolerance: f32,
+    pub results: Vec<SidingResult>,
+    /// Whether `results` is from a check, even if it's empty
+    checked: bool,
+}
+
+/// Checks one requirement against every track, given as sampled points
+fn check_siding(
+    requirement: &SidingRequirement,
+    transform: &Transform,
+    tracks: &[Vec<Vec3>],
+    tolerance: f32,
+) -> (Vec3, SidingStatus) {
+    let anchor = transform.translation
+        + transform.rotation * Vec3::from(requirement.offset) / METERS_PER_UNIT;
+    let axis = transform.rotation
+        * Quat::from_rotation_y(requirement.angle.to_radians())
+        * Vec3::new(1., 0., 0.);
+    let axis = Vec3::new(axis.x, 0., axis.z).normalize_or_zero();
+    let side = Vec3::new(-axis.z, 0., axis.x);
+    // The siding can be anywhere along the industry, as long as it's beside it
+    let reach = requirement.length / METERS_PER_UNIT;
+    let tolerance = tolerance / METERS_PER_UNIT;
+    let min_dot = MAX_ANGLE.to_radians().cos();
+    let (mut best, mut nearest) = (0f32, None::<f32>);
+    for points in tracks {
+        // (start, end) along the axis of the current run of parallel segments
+        let mut run: Option<(f32, f32)> = None;
+        for pair in points.windows(2) {
+            let (a, b) = (pair[0] - anchor, pair[1] - anchor);
+            let dir = Vec3::new(b.x - a.x, 0., b.z - a.z).normalize_or_zero();
+            let (along_a, along_b) = (a.dot(axis), b.dot(axis));
+            let lateral = a.dot(side).abs().max(b.dot(side).abs());
+            let in_reach = along_a.abs().min(along_b.abs()) <= reach;
+            if in_reach {
+                nearest = Some(nearest.map_or(lateral, |n| n.min(lateral)));
+            }
+            if in_reach && lateral <= tolerance && dir.dot(axis).abs() >= min_dot {
+                let (lo, hi) = (along_a.min(along_b), along_a.max(along_b));
+                run = Some(run.map_or((lo, hi), |(s, e)| (s.min(lo), e.max(hi))));
+            } else if let Some((s, e)) = run.take() {
+                best = best.max(e - s);
+            }
+        }
+        if let Some((s, e)) = run {
+            best = best.max(e - s);
+        }
+    }
+    let best = best * METERS_PER_UNIT;
+    let status = if best >= requirement.length {
+        SidingStatus::Ok(best)
+    } else if best > 0. {
+        SidingStatus::Short(best)
+    } else {
+        SidingStatus::Missing(nearest.map(|n| n * METERS_PER_UNIT))
+    };
+    (anchor, status)
+}
+
+fn siding_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut check: ResMut<SidingCheck>,
+    industries: Query<(&Transform, &IndustryData)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut cameras: Query<&mut LookTransform, With<PickingCamera>>,
+    mut layout: ResMut<Layout>,
+) {
+    if !palette.show_sidings {
+        return;
+    }
+    let check = check.as_mut();
+    let units = settings.units;
+    let mut run = false;
+    let mut go_to = None;
+    let window = egui::Window::new("Industry Sidings").default_height(300.);
+    layout.show(egui_context.ctx_mut(), "Industry Sidings", window, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Tolerance");
+            let mut v = units.from_meters(check.tolerance);
+            if ui
+                .add(
+                    egui::DragValue::new(&mut v)
+                        .clamp_range(1. ..=100.)
+                        .suffix(units.suffix()),
+                )
+                .changed()
+            {
+                check.tolerance = units.to_meters(v);
+            }
+            run = ui.button("Check").clicked();
+        });
+        if !check.checked {
+            ui.label("Check every industry for straight track where it loads and unloads");
+            return;
+        }
+        let problems: Vec<_> = check
+            .results
+            .iter()
+            .filter(|r| !matches!(r.status, SidingStatus::Ok(_)))
+            .collect();
+        ui.label(format!(
+            "{} of {} sidings have problems",
+            problems.len(),
+            check.results.len()
+        ));
+        egui::ScrollArea::vertical()
+            .max_height(250.)
+            .show(ui, |ui| {
+                for result in problems {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Go").clicked() {
+                            go_to = Some(result.location);
+                        }
+                        let status = match result.status {
+                            SidingStatus::Ok(_) => unreachable!(),
+                            SidingStatus::Short(length) => {
+                                format!("only {} of straight track", units.length(length))
+                            }
+                            SidingStatus::Missing(Some(distance)) => {
+                                format!(
+                                    "no parallel track, nearest is {} away",
+                                    units.length(distance)
+                                )
+                            }
+                            SidingStatus::Missing(None) => "no track nearby".to_string(),
+                        };
+                        ui.label(format!(
+                            "{} {}: {}",
+                            industry_name(result.ty),
+                            result.requirement,
+                            status
+                        ));
+                    });
+                }
+            });
+    });
+    if run {
+        let tracks: Vec<_> = beziers
+            .iter()
+            .filter(|bez| bez.ty() == SplineType::Track)
+            .map(|bez| bez.sample(8))
+            .collect();
+        let results = industries
+            .iter()
+            .flat_map(|(transform, industry)| {
+                let tracks = &tracks;
+                let tolerance = check.tolerance;
+                check
+                    .requirements
+                    .iter()
+                    .filter(move |r| r.industry == industry.ty)
+                    .map(move |requirement| {
+                        let (location, status) =
+                            check_siding(requirement, transform, tracks, tolerance);
+                        SidingResult {
+                            ty: industry.ty,
+                            requirement: requirement.name.clone(),
+                            location,
+                            status,
+                        }
+                    })
+            })
+            .collect();
+        check.results = results;
+        check.checked = true;
+    }
+    if let Some(location) = go_to {
+        // Keeps the camera's current offset from its target
+        for mut look in cameras.iter_mut() {
+            let offset = look.eye - look.target;
+            look.target = location;
+            look.eye = location + offset;
+        }
+    }
+}
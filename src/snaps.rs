@@ -1,11 +1,10 @@
-use std::cmp::Ordering;
-
 use bevy::prelude::*;
 
 use crate::{
     gvas::{SwitchData, SwitchType},
-    spline::{mesh::curve_offset, CubicBezier, PolyBezier},
-    update::DragState,
+    settings::Settings,
+    spline::{nearest_point, CubicBezier, PolyBezier},
+    update::{DragState, ObjectDrag},
 };
 // Snap points
 
@@ -14,7 +13,11 @@ pub struct SnapPlugin;
 impl Plugin for SnapPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SnapEvent>();
-        app.add_system(snap_handler);
+        app.add_system(
+            snap_handler
+                .label("snap_handler")
+                .after("update_bezier_transform"),
+        );
     }
 }
 
@@ -22,18 +25,22 @@ impl Plugin for SnapPlugin {
 pub enum SnapEvent {
     Spline(Entity, Entity),
     Switch(Entity),
+    /// Placed objects other than switches, such as service structures
+    Object(Entity),
 }
 
 fn snap_handler(
     mut splines: Query<&mut PolyBezier<CubicBezier>>,
     mut objects: Query<(&mut Transform, &DragState)>,
     mut switches: Query<(&mut Transform, &SwitchData), Without<DragState>>,
+    mut placed: Query<&mut Transform, (With<ObjectDrag>, Without<SwitchData>, Without<DragState>)>,
     mut event_reader: EventReader<SnapEvent>,
+    settings: Res<Settings>,
 ) {
     for event in event_reader.iter() {
         match event {
             &SnapEvent::Spline(curve, handle) => {
-                let off = curve_offset(splines.get(curve).unwrap().ty());
+                let off = settings.curve_offset(splines.get(curve).unwrap().ty());
                 let (trans, _) = objects.get(handle).unwrap();
                 let pt = find_nearest(trans.translation - off, &splines, &switches);
                 if pt != trans.translation - off {
@@ -51,6 +58,11 @@ fn snap_handler(
                     handle.translation = pt;
                 }
             }
+            &SnapEvent::Object(object) => {
+                if let Ok(mut trans) = placed.get_mut(object) {
+                    trans.translation = find_nearest(trans.translation, &splines, &switches);
+                }
+            }
         }
     }
 }
@@ -65,11 +77,7 @@ fn find_nearest(
     splines: &Query<&mut PolyBezier<CubicBezier>>,
     switches: &Query<(&mut Transform, &SwitchData), Without<DragState>>,
 ) -> Vec3 {
-    /// Comparison function to compare by distance
-    fn compare(a: &(Vec3, f32), b: &(Vec3, f32)) -> Ordering {
-        a.1.partial_cmp(&b.1).unwrap()
-    }
-    if let Some((v, dist)) = splines
+    let points = splines
         .iter()
         .flat_map(|s| s.get_control_points())
         .chain(switches.iter().flat_map(|(t, s)| {
@@ -98,11 +106,8 @@ fn find_nearest(
                 ]
                 .into_iter(),
             }
-        }))
-        .filter(|v| v != &pt)
-        .map(|v| (v, pt.distance_squared(v)))
-        .min_by(compare)
-    {
+        }));
+    if let Some((v, dist)) = nearest_point(pt, points) {
         if dist < 0.2 {
             v
         } else {
@@ -24,6 +24,77 @@ impl CubicBezier {
     pub fn get_pts(&self) -> &[Vec3; 4] {
         &self.pts
     }
+
+    /// Smallest horizontal radius of curvature along the curve, sampled at `samples` points.
+    /// Straight curves have an infinite radius.
+    pub fn min_radius(&self, samples: usize) -> f32 {
+        let d1 = self.derivative();
+        let d2 = d1.derivative();
+        (0..=samples)
+            .map(|i| {
+                let t = i as f32 / samples as f32;
+                let a = d1.eval(t);
+                let b = d2.eval(t);
+                let speed = (a.x * a.x + a.z * a.z).sqrt();
+                let cross = (a.x * b.z - a.z * b.x).abs();
+                if cross > f32::EPSILON {
+                    speed.powi(3) / cross
+                } else {
+                    f32::INFINITY
+                }
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Approximate arc length of the curve, using `samples` straight pieces
+    pub fn length(&self, samples: usize) -> f32 {
+        (1..=samples)
+            .map(|i| {
+                let a = self.eval((i - 1) as f32 / samples as f32);
+                let b = self.eval(i as f32 / samples as f32);
+                a.distance(b)
+            })
+            .sum()
+    }
+
+    /// Parameter of the point on the curve closest to `p`. The curve is sampled at `samples`
+    /// points, and the closest sample is refined with a few Newton steps
+    pub fn closest_t(&self, p: Vec3, samples: usize) -> f32 {
+        let mut t = (0..=samples)
+            .map(|i| i as f32 / samples as f32)
+            .min_by(|a, b| {
+                let da = self.eval(*a).distance_squared(p);
+                let db = self.eval(*b).distance_squared(p);
+                da.total_cmp(&db)
+            })
+            .unwrap();
+        let d1 = self.derivative();
+        let d2 = d1.derivative();
+        for _ in 0..4 {
+            // Minimize |B(t) - p|^2, whose derivative is 2 (B(t) - p) . B'(t)
+            let diff = self.eval(t) - p;
+            let a = d1.eval(t);
+            let f = diff.dot(a);
+            let df = a.dot(a) + diff.dot(d2.eval(t));
+            if df.abs() < f32::EPSILON {
+                break;
+            }
+            t = (t - f / df).clamp(0., 1.);
+        }
+        t
+    }
+
+    /// Splits the curve at `t` into two curves, which together trace exactly the same path
+    pub fn split(&self, t: f32) -> (Self, Self) {
+        let [p0, p1, p2, p3] = self.pts;
+        let a = p0.lerp(p1, t);
+        let b = p1.lerp(p2, t);
+        let c = p2.lerp(p3, t);
+        let ab = a.lerp(b, t);
+        let bc = b.lerp(c, t);
+        let mid = ab.lerp(bc, t);
+        (Self::new(p0, a, ab, mid), Self::new(mid, bc, c, p3))
+    }
 }
 
 impl Bezier for CubicBezier {
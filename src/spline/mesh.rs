@@ -1,22 +1,12 @@
 
-use crate::gvas::SplineType;
-use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use bevy::{prelude::*, render::mesh::{Indices, VertexAttributeValues}};
 use bevy::math::Vec4Swizzles;
+use bevy::render::render_resource::PrimitiveTopology;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use super::CubicBezier;
-
-pub fn curve_offset(ty: SplineType) -> Vec3 {
-    match ty {
-        SplineType::Track => Vec3::new(0., 1., 0.),
-        SplineType::TrackBed => Vec3::new(0., 1., 0.),
-        SplineType::WoodBridge => Vec3::new(0., 0., 0.),
-        SplineType::SteelBridge => Vec3::new(0., 0., 0.),
-        SplineType::GroundWork => Vec3::new(0., 0., 0.),
-        SplineType::ConstGroundWork => Vec3::new(0., 0., 0.),
-        SplineType::StoneGroundWork => Vec3::new(0., 0., 0.),
-        SplineType::ConstStoneGroundWork => Vec3::new(0., 0., 0.),
-    }
-}
+use super::{Bezier, CubicBezier};
+use crate::coords::METERS_PER_UNIT;
 
 fn matrix_between(a: Vec3, b: Vec3) -> Mat4 {
     let x = b - a;
@@ -25,6 +15,9 @@ fn matrix_between(a: Vec3, b: Vec3) -> Mat4 {
     Mat4::from_cols(Vec4::from((x, 0.)), Vec4::from((y, 0.)), Vec4::from((z, 0.)), Vec4::from((a, 1.)))
 }
 
+/// Length of a spline model, in model units. It's bent so this length spans the whole segment
+const SCALE_FACTOR: f32 = 10.;
+
 fn bend_mesh_on_curve(loc: Vec3, curve: &CubicBezier, points: &mut Vec<[f32; 3]>, normals: &mut Vec<[f32; 3]>) {
     // Step one: Express points and normals as a function of a bezier curve. Then undo, but with the provided curve.
     // Trivialize by aligning the initial points such that one coordinate represents the distance along the curve.
@@ -43,7 +36,6 @@ fn bend_mesh_on_curve(loc: Vec3, curve: &CubicBezier, points: &mut Vec<[f32; 3]>
     let ab = matrix_between(a, b);
     let bc = matrix_between(b, c);
     let cd = matrix_between(c, d);
-    const SCALE_FACTOR: f32 = 10.;
     for (p, n) in points.iter_mut().zip(normals.iter_mut()) {
         let point = Vec4::new(p[0] / SCALE_FACTOR, p[1] / SCALE_FACTOR, p[2] / SCALE_FACTOR, 1.);
         let normal = Vec4::new(n[0], n[1], n[2], 0.);
@@ -60,6 +52,211 @@ fn bend_mesh_on_curve(loc: Vec3, curve: &CubicBezier, points: &mut Vec<[f32; 3]>
     }
 }
 
+/// How detailed spline meshes are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MeshQuality {
+    /// A plain box along each segment, see `draft_mesh`
+    Draft,
+    /// The spline type's model, bent along each segment
+    Normal,
+    /// The model cut finely along each segment, for smoother curves
+    High,
+}
+
+impl Default for MeshQuality {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl MeshQuality {
+    /// The detail this preset meshes at
+    pub fn detail(self) -> MeshDetail {
+        match self {
+            Self::Draft => MeshDetail {
+                step: 20.,
+                error: 1.,
+                subdivision: 1,
+            },
+            Self::Normal => MeshDetail {
+                step: 5.,
+                error: 0.25,
+                subdivision: 2,
+            },
+            Self::High => MeshDetail {
+                step: 1.,
+                error: 0.05,
+                subdivision: 8,
+            },
+        }
+    }
+}
+
+/// How finely a model is cut before it's bent along a segment. The model only bends at its
+/// vertices, so straight runs of it stay straight between cuts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MeshDetail {
+    /// Length of curve between cuts, in meters, walked with `Bezier::walker`
+    pub step: f32,
+    /// How far from `step` the walker can place each cut, in meters
+    pub error: f32,
+    /// Cross sections each segment is cut into, evenly along the curve, on top of the walker's
+    pub subdivision: usize,
+}
+
+impl Default for MeshDetail {
+    fn default() -> Self {
+        MeshQuality::default().detail()
+    }
+}
+
+impl MeshDetail {
+    /// Where to cut the model along `curve`, as curve parameters between 0 and 1
+    fn cuts(&self, curve: &CubicBezier) -> Vec<f32> {
+        let step = self.step.max(0.1);
+        let error = self.error.clamp(step / 100., step / 2.);
+        let mut cuts: Vec<f32> = curve
+            .walker(step / METERS_PER_UNIT, error / METERS_PER_UNIT)
+            .map(|p| p.t)
+            .chain((1..self.subdivision).map(|i| i as f32 / self.subdivision as f32))
+            .filter(|t| *t > 0. && *t < 1.)
+            .collect();
+        cuts.sort_by(f32::total_cmp);
+        cuts.dedup_by(|a, b| (*a - *b).abs() < CUT_EPSILON);
+        cuts
+    }
+}
+
+/// Vertices closer than this to a cut, in model units, aren't cut again
+const CUT_EPSILON: f32 = 1e-4;
+
+/// Builds a box with the same bounds as `original`, see `box_mesh`. Meshes without positions
+/// are returned as they are
+pub fn draft_mesh(original: &Mesh) -> Mesh {
+    let (min, max) = if let Some(VertexAttributeValues::Float32x3(vec)) = original.attribute(Mesh::ATTRIBUTE_POSITION) {
+        vec.iter().fold((Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)), |(min, max), p| {
+            (min.min(Vec3::from(*p)), max.max(Vec3::from(*p)))
+        })
+    } else {
+        return original.clone();
+    };
+    box_mesh(min, max)
+}
+
+/// Builds a box from `min` to `max`, with a few slices along its length so it still bends along
+/// the curve
+pub fn box_mesh(min: Vec3, max: Vec3) -> Mesh {
+    const SLICES: usize = 4;
+    // (normal, the (y, z) of the face's edges). The edges are ordered so every face winds the
+    // same way
+    let faces = [
+        (Vec3::Y, [(max.y, min.z), (max.y, max.z)]),
+        (-Vec3::Y, [(min.y, max.z), (min.y, min.z)]),
+        (Vec3::Z, [(max.y, max.z), (min.y, max.z)]),
+        (-Vec3::Z, [(min.y, min.z), (max.y, min.z)]),
+    ];
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut indices = vec![];
+    for (normal, edges) in faces {
+        let base = positions.len() as u32;
+        for i in 0..=SLICES {
+            let t = i as f32 / SLICES as f32;
+            let x = min.x + (max.x - min.x) * t;
+            for (j, (y, z)) in edges.iter().enumerate() {
+                positions.push([x, *y, *z]);
+                normals.push([normal.x, normal.y, normal.z]);
+                uvs.push([t, j as f32]);
+            }
+        }
+        for i in 0..SLICES as u32 {
+            let a = base + 2 * i;
+            indices.extend([a, a + 1, a + 2, a + 1, a + 3, a + 2]);
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Cuts every triangle of `mesh` that crosses one of the planes `x = cuts[i]`, so it can bend
+/// there. Edges shared by two triangles are cut at the same new vertex, so no gaps open up
+fn cut_mesh(mesh: &mut Mesh, cuts: &[f32]) {
+    let mut positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(vec)) => vec.clone(),
+        _ => return,
+    };
+    let mut normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(vec)) => vec.clone(),
+        _ => return,
+    };
+    let mut uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(vec)) => Some(vec.clone()),
+        _ => None,
+    };
+    let mut triangles: Vec<[u32; 3]> = match mesh.indices() {
+        Some(Indices::U32(i)) => i.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect(),
+        Some(Indices::U16(i)) => i.chunks_exact(3).map(|t| [t[0] as u32, t[1] as u32, t[2] as u32]).collect(),
+        None => return,
+    };
+    // The first cut strictly inside the edge from `a` to `b`, so both triangles on an edge agree
+    let crossing = |a: f32, b: f32| {
+        let (min, max) = (a.min(b), a.max(b));
+        cuts.iter().copied().find(|x| *x > min + CUT_EPSILON && *x < max - CUT_EPSILON)
+    };
+    let mut split: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut indices = vec![];
+    while let Some(tri) = triangles.pop() {
+        let found = (0..3).find_map(|e| {
+            let (a, b) = (tri[e] as usize, tri[(e + 1) % 3] as usize);
+            crossing(positions[a][0], positions[b][0]).map(|x| (e, x))
+        });
+        let (e, x) = match found {
+            Some(found) => found,
+            None => {
+                indices.extend(tri);
+                continue;
+            }
+        };
+        let (a, b, c) = (tri[e], tri[(e + 1) % 3], tri[(e + 2) % 3]);
+        let mid = *split.entry((a.min(b), a.max(b))).or_insert_with(|| {
+            let (pa, pb) = (Vec3::from(positions[a as usize]), Vec3::from(positions[b as usize]));
+            let t = (x - pa.x) / (pb.x - pa.x);
+            let p = pa.lerp(pb, t);
+            positions.push([x, p.y, p.z]);
+            let n = Vec3::from(normals[a as usize]).lerp(Vec3::from(normals[b as usize]), t).normalize_or_zero();
+            normals.push([n.x, n.y, n.z]);
+            if let Some(uvs) = uvs.as_mut() {
+                let uv = Vec2::from(uvs[a as usize]).lerp(Vec2::from(uvs[b as usize]), t);
+                uvs.push([uv.x, uv.y]);
+            }
+            positions.len() as u32 - 1
+        });
+        triangles.push([a, mid, c]);
+        triangles.push([mid, b, c]);
+    }
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    if let Some(uvs) = uvs {
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    }
+    mesh.set_indices(Some(Indices::U32(indices)));
+}
+
+/// Bends `original` along `curve`, cut as finely as `detail` asks first. `original` should be
+/// the draft mesh for `MeshQuality::Draft`
+pub fn mesh_on_curve_detail(original: &Mesh, loc: Vec3, curve: &CubicBezier, detail: &MeshDetail) -> Mesh {
+    let mut cut = original.clone();
+    // The model is one segment long, see `bend_mesh_on_curve`
+    let cuts: Vec<f32> = detail.cuts(curve).into_iter().map(|t| t * SCALE_FACTOR).collect();
+    cut_mesh(&mut cut, &cuts);
+    mesh_on_curve(&cut, loc, curve)
+}
+
 pub fn mesh_on_curve(original: &Mesh, loc: Vec3, curve: &CubicBezier) -> Mesh {
     let mut new = original.clone();
     // Safety: This extra mutable reference is used to extract a second attribute.
@@ -77,6 +77,10 @@ impl<'a, B: Bezier + Clone + ?Sized> Iterator for BezierWalker<'a, B> {
                 if min > self.end - 0.02 {
                     break (self.curve.eval(self.end), self.end);
                 }
+                // The error is finer than the curve can be evaluated, so this is as close as it gets
+                if max - min <= f32::EPSILON {
+                    break (pt, guess);
+                }
             };
             self.t = guess;
             let tangent = self.derivative.eval(guess);
@@ -109,7 +113,6 @@ impl MeshUpdate {
         }
     }
 
-    #[allow(unused)]
     pub fn is_modified(&self) -> bool {
         match self {
             Self::None(_) => false,
@@ -153,6 +156,15 @@ impl MeshUpdate {
     }
 }
 
+/// Finds the point in `points` closest to `pt`, ignoring `pt` itself. Returns the point and its
+/// squared distance from `pt`
+pub fn nearest_point(pt: Vec3, points: impl Iterator<Item = Vec3>) -> Option<(Vec3, f32)> {
+    points
+        .filter(|v| v != &pt)
+        .map(|v| (v, pt.distance_squared(v)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
 #[derive(Debug, Component)]
 pub struct PolyBezier<C: Bezier> {
     parts: Vec<C>,
@@ -237,6 +249,20 @@ impl PolyBezier<CubicBezier> {
         self.compute_tweens();
     }
 
+    /// Reverses the direction of the spline, so the last control point comes first. The curve
+    /// itself doesn't change
+    pub fn reverse(&mut self) {
+        self.parts.reverse();
+        for part in self.parts.iter_mut() {
+            part.pts.reverse();
+        }
+        self.updates.reverse();
+        for update in self.updates.iter_mut() {
+            update.modified();
+        }
+        self.visibility.reverse();
+    }
+
     fn compute_tweens(&mut self) {
         for pt in 1..self.parts.len() {
             let tan = (self.parts[pt - 1].pts[0] - self.parts[pt].pts[3]).normalize();
@@ -254,6 +280,8 @@ impl PolyBezier<CubicBezier> {
         &mut self,
         meshes: &mut Assets<Mesh>,
         default_assets: &Res<DefaultAssets>,
+        quality: MeshQuality,
+        detail: &MeshDetail,
     ) -> Vec<(Handle<Mesh>, bool)> {
         //self.compute_derivatives();
         // const STEP: f32 = 0.1;
@@ -261,12 +289,17 @@ impl PolyBezier<CubicBezier> {
         let mut ret = vec![];
         for (i, flag) in self.updates.iter_mut().enumerate() {
             if let Some(handle) = flag.set(meshes, |assets| {
-                let mesh = default_assets.spline_mesh[self.ty].clone();
+                let mesh = if quality == MeshQuality::Draft {
+                    default_assets.draft_spline_mesh[self.ty].clone()
+                } else {
+                    default_assets.spline_mesh[self.ty].clone()
+                };
                 if let Some(mesh) = assets.get(mesh) {
-                    Some(mesh_on_curve(
+                    Some(mesh_on_curve_detail(
                         mesh,
                         self.parts[i].centroid(),
                         &self.parts[i],
+                        detail,
                     ))
                 } else {
                     None
@@ -331,11 +364,39 @@ impl PolyBezier<CubicBezier> {
         }
     }
 
-    pub fn set_ty(&mut self, ty: SplineType) {
-        self.ty = ty;
+    /// Regenerate every mesh on the next section update, e.g. after the mesh quality changes
+    pub fn remesh(&mut self) {
         self.updates.iter_mut().for_each(|m| m.modified());
     }
 
+    /// Regenerate the meshes of the given segments on the next section update
+    pub fn remesh_segments(&mut self, segments: &[usize]) {
+        for &i in segments {
+            if let Some(m) = self.updates.get_mut(i) {
+                m.modified();
+            }
+        }
+    }
+
+    /// Segments whose meshes will be regenerated on the next section update
+    pub fn modified_segments(&self) -> Vec<usize> {
+        self.updates
+            .iter()
+            .enumerate()
+            .filter(|(_i, m)| m.is_modified())
+            .map(|(i, _m)| i)
+            .collect()
+    }
+
+    /// Changes the spline type. The meshes only need to be rebuilt if the new type uses a
+    /// different model, since the materials are swapped by `BezierModificaiton::ChangeTy`
+    pub fn set_ty(&mut self, ty: SplineType, default_assets: &DefaultAssets) {
+        if default_assets.spline_mesh[ty] != default_assets.spline_mesh[self.ty] {
+            self.remesh();
+        }
+        self.ty = ty;
+    }
+
     pub fn get_transforms<'s>(&'s self) -> impl Iterator<Item = (Vec3, &MeshUpdate)> + 's {
         self.parts
             .iter()
@@ -343,6 +404,8 @@ impl PolyBezier<CubicBezier> {
             .zip(self.updates.iter())
     }
 
+    /// Splits the curve by removing control point `pt`. The halves keep the meshes of their
+    /// segments, so existing sections can be reused
     pub fn split_pt(&self, pt: usize) -> (Self, Self) {
         let end = pt.saturating_sub(1);
         (
@@ -355,11 +418,11 @@ impl PolyBezier<CubicBezier> {
                         .cloned(),
                 ),
                 updates: Vec::from_iter(
-                    self.parts
+                    self.updates
                         .get(..end)
                         .iter()
                         .flat_map(|a| a.iter())
-                        .map(|_| MeshUpdate::Insert),
+                        .cloned(),
                 ),
                 visibility: Vec::from_iter(
                     self.visibility
@@ -379,11 +442,11 @@ impl PolyBezier<CubicBezier> {
                         .cloned(),
                 ),
                 updates: Vec::from_iter(
-                    self.parts
+                    self.updates
                         .get(pt + 1..)
                         .iter()
                         .flat_map(|a| a.iter())
-                        .map(|_| MeshUpdate::Insert),
+                        .cloned(),
                 ),
                 visibility: Vec::from_iter(
                     self.visibility
@@ -397,17 +460,67 @@ impl PolyBezier<CubicBezier> {
         )
     }
 
+    /// Parameter of the point on segment `i` closest to `p`
+    pub fn closest_t(&self, i: usize, p: Vec3) -> f32 {
+        self.parts[i].closest_t(p, 16)
+    }
+
+    /// Adds a control point at `t` along segment `i`, splitting the segment in two where it is,
+    /// so the curve keeps its shape. The handles aren't recomputed until a point is moved.
+    /// Returns the index of the new point
+    pub fn insert_at(&mut self, i: usize, t: f32) -> usize {
+        let (left, right) = self.parts[i].split(t);
+        self.parts[i] = left;
+        self.parts.insert(i + 1, right);
+        self.updates[i].modified();
+        self.updates.insert(i + 1, MeshUpdate::Insert);
+        self.visibility.insert(i + 1, self.visibility[i]);
+        i + 1
+    }
+
+    /// Splits the curve at `t` along segment `i`, adding a new control point at the end of
+    /// the first half and the start of the second. The other segments keep their meshes
+    pub fn split_at(&self, i: usize, t: f32) -> (Self, Self) {
+        let (left, right) = self.parts[i].split(t);
+        let half = |parts: Vec<CubicBezier>, updates: Vec<MeshUpdate>, visibility| Self {
+            parts,
+            updates,
+            visibility,
+            ty: self.ty,
+        };
+        (
+            half(
+                self.parts[..i].iter().cloned().chain([left]).collect(),
+                self.updates[..i]
+                    .iter()
+                    .cloned()
+                    .chain([MeshUpdate::Insert])
+                    .collect(),
+                self.visibility[..=i].to_vec(),
+            ),
+            half(
+                [right].into_iter().chain(self.parts[i + 1..].iter().cloned()).collect(),
+                [MeshUpdate::Insert]
+                    .into_iter()
+                    .chain(self.updates[i + 1..].iter().cloned())
+                    .collect(),
+                self.visibility[i..].to_vec(),
+            ),
+        )
+    }
+
+    /// Splits the curve by removing a segment. The halves keep the meshes of their segments
     pub fn split_sec(&self, section: &Handle<Mesh>) -> (Self, Self) {
         let pt = self.updates.iter().position(|m| m.has(section)).unwrap();
         (
             Self {
                 parts: Vec::from_iter(self.parts.get(..pt).iter().flat_map(|a| a.iter()).cloned()),
                 updates: Vec::from_iter(
-                    self.parts
+                    self.updates
                         .get(..pt)
                         .iter()
                         .flat_map(|a| a.iter())
-                        .map(|_| MeshUpdate::Insert),
+                        .cloned(),
                 ),
                 visibility: Vec::from_iter(
                     self.visibility
@@ -427,11 +540,11 @@ impl PolyBezier<CubicBezier> {
                         .cloned(),
                 ),
                 updates: Vec::from_iter(
-                    self.parts
+                    self.updates
                         .get(pt + 1..)
                         .iter()
                         .flat_map(|a| a.iter())
-                        .map(|_| MeshUpdate::Insert),
+                        .cloned(),
                 ),
                 visibility: Vec::from_iter(
                     self.visibility
@@ -464,6 +577,31 @@ impl PolyBezier<CubicBezier> {
         self.parts.len() + 1
     }
 
+    /// The curve between each pair of control points
+    pub fn segments(&self) -> &[CubicBezier] {
+        &self.parts
+    }
+
+    /// Direction of the curve at control point `i`
+    pub fn tangent(&self, i: usize) -> Vec3 {
+        let (part, t) = if i < self.parts.len() {
+            (&self.parts[i], 0.)
+        } else {
+            (&self.parts[i - 1], 1.)
+        };
+        let d = part.derivative().eval(t);
+        if d.length_squared() > f32::EPSILON {
+            d.normalize()
+        } else {
+            // Two point curves have no derivative at the ends
+            (part.pts[3] - part.pts[0]).normalize_or_zero()
+        }
+    }
+
+    pub fn is_endpoint(&self, i: usize) -> bool {
+        i == 0 || i + 1 == self.len()
+    }
+
     pub fn get_control_point(&self, i: usize) -> Vec3 {
         if i == 0 {
             self.parts[0].pts[0]
@@ -497,6 +635,179 @@ impl PolyBezier<CubicBezier> {
         }
     }
 
+    /// Percent grade of segment `i`, measured between its two control points
+    pub fn segment_grade(&self, i: usize) -> f32 {
+        let a = self.get_control_point(i);
+        let b = self.get_control_point(i + 1);
+        let run = Vec2::new(b.x - a.x, b.z - a.z).length();
+        if run > 0. {
+            (b.y - a.y) / run * 100.
+        } else {
+            0.
+        }
+    }
+
+    /// Smallest horizontal radius of curvature of segment `i`
+    pub fn segment_min_radius(&self, i: usize) -> f32 {
+        self.parts[i].min_radius(16)
+    }
+
+    /// Points along the whole spline, with `samples` pieces per segment
+    pub fn sample(&self, samples: usize) -> Vec<Vec3> {
+        let mut points = vec![self.get_control_point(0)];
+        for part in self.parts.iter() {
+            for i in 1..=samples {
+                points.push(part.eval(i as f32 / samples as f32));
+            }
+        }
+        points
+    }
+
+    /// Approximate length of the whole spline
+    pub fn length(&self) -> f32 {
+        self.parts.iter().map(|p| p.length(16)).sum()
+    }
+
+    /// Point at `t` along segment `i`
+    pub fn eval_at(&self, i: usize, t: f32) -> Vec3 {
+        self.parts[i].eval(t)
+    }
+
+    /// Approximate length of the spline from its start to `t` along segment `i`
+    pub fn length_to(&self, i: usize, t: f32) -> f32 {
+        let before: f32 = self.parts[..i].iter().map(|p| p.length(16)).sum();
+        before + self.parts[i].split(t).0.length(16)
+    }
+
+    /// Percent grade of the curve itself at `t` along segment `i`
+    pub fn grade_at(&self, i: usize, t: f32) -> f32 {
+        let d = self.parts[i].derivative().eval(t);
+        let run = Vec2::new(d.x, d.z).length();
+        if run > 0. {
+            d.y / run * 100.
+        } else {
+            0.
+        }
+    }
+
+    /// Approximate horizontal distance along the curve from control point `first` to `last`
+    pub fn run_between(&self, first: usize, last: usize) -> f32 {
+        const SAMPLES: usize = 16;
+        self.parts[first..last]
+            .iter()
+            .map(|part| {
+                (0..SAMPLES)
+                    .map(|i| {
+                        let a = part.eval(i as f32 / SAMPLES as f32);
+                        let b = part.eval((i + 1) as f32 / SAMPLES as f32);
+                        Vec2::new(b.x - a.x, b.z - a.z).length()
+                    })
+                    .sum::<f32>()
+            })
+            .sum()
+    }
+
+    /// Control points with the heights of the ones between `first` and `last` on an even grade
+    /// from one to the other, by horizontal distance along the curve
+    pub fn graded(&self, first: usize, last: usize) -> Vec<Vec3> {
+        let mut points: Vec<Vec3> = self.get_control_points().collect();
+        let total = self.run_between(first, last);
+        if total <= 0. {
+            return points;
+        }
+        let (a, b) = (points[first].y, points[last].y);
+        let mut run = 0.;
+        for i in first + 1..last {
+            run += self.run_between(i - 1, i);
+            points[i].y = a + (b - a) * run / total;
+        }
+        points
+    }
+
+    /// Control points with the ones from `first` to `last` moved onto the line between them, so
+    /// the curve between them is straight. The handles point at the neighbouring control points,
+    /// so the points either side are also moved in line, keeping their distance
+    pub fn straightened(&self, first: usize, last: usize) -> Vec<Vec3> {
+        let mut points: Vec<Vec3> = self.get_control_points().collect();
+        let (a, b) = (points[first], points[last]);
+        let dir = (b - a).normalize_or_zero();
+        for p in points[first + 1..last].iter_mut() {
+            *p = a + dir * (*p - a).dot(dir);
+        }
+        if first > 0 {
+            points[first - 1] = a - dir * a.distance(points[first - 1]);
+        }
+        if last + 1 < points.len() {
+            points[last + 1] = b + dir * b.distance(points[last + 1]);
+        }
+        points
+    }
+
+    /// Control points for a smoother version of the curve, with the same number of points and
+    /// the same ends. The curve is sampled, each sample averaged with its neighbours `passes`
+    /// times, and the result split into even lengths
+    pub fn smoothed(&self, passes: usize) -> Vec<Vec3> {
+        const SAMPLES: usize = 8;
+        let mut path = self.sample(SAMPLES);
+        for _ in 0..passes {
+            let last = path.len() - 1;
+            path = (0..=last)
+                .map(|i| {
+                    // Narrower near the ends, so they stay put
+                    let r = SAMPLES.min(i).min(last - i);
+                    let sum: Vec3 = path[i - r..=i + r].iter().copied().sum();
+                    sum / (2 * r + 1) as f32
+                })
+                .collect();
+        }
+        resample(&path, self.len())
+    }
+
+    /// Indices of the control points to keep, so every removed point is within `tolerance` of
+    /// the straight line between the kept points either side of it, using Ramer-Douglas-Peucker.
+    /// The ends, and points where visibility changes, are always kept
+    pub fn simplified(&self, tolerance: f32) -> Vec<usize> {
+        let points: Vec<Vec3> = self.get_control_points().collect();
+        let mut keep = vec![false; points.len()];
+        keep[0] = true;
+        keep[points.len() - 1] = true;
+        for i in 1..self.visibility.len() {
+            if self.visibility[i - 1] != self.visibility[i] {
+                keep[i] = true;
+            }
+        }
+        // Each run between two points that have to be kept is simplified on its own
+        let fixed: Vec<usize> = (0..points.len()).filter(|i| keep[*i]).collect();
+        let mut stack: Vec<(usize, usize)> = fixed.windows(2).map(|w| (w[0], w[1])).collect();
+        while let Some((a, b)) = stack.pop() {
+            let (start, end) = (points[a], points[b]);
+            let chord = end - start;
+            let distance = |p: Vec3| {
+                let t = if chord.length_squared() > 0. {
+                    ((p - start).dot(chord) / chord.length_squared()).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                p.distance(start + chord * t)
+            };
+            let furthest = (a + 1..b)
+                .map(|i| (i, distance(points[i])))
+                .max_by(|x, y| x.1.total_cmp(&y.1));
+            if let Some((i, d)) = furthest {
+                if d > tolerance {
+                    keep[i] = true;
+                    stack.push((a, i));
+                    stack.push((i, b));
+                }
+            }
+        }
+        (0..points.len()).filter(|i| keep[*i]).collect()
+    }
+
+    pub fn get_visibility(&self) -> &[bool] {
+        &self.visibility
+    }
+
     pub fn segment_modified(&self, i: usize) -> bool {
         self.updates[i].is_modified()
     }
@@ -507,6 +818,31 @@ impl PolyBezier<CubicBezier> {
     }
 }
 
+/// `n` points evenly spaced along a path, including both ends
+pub fn resample(path: &[Vec3], n: usize) -> Vec<Vec3> {
+    let mut lengths = vec![0.];
+    for w in path.windows(2) {
+        lengths.push(lengths[lengths.len() - 1] + w[0].distance(w[1]));
+    }
+    let total = lengths[lengths.len() - 1];
+    let mut j = 0;
+    (0..n)
+        .map(|k| {
+            let target = total * k as f32 / (n - 1).max(1) as f32;
+            while j + 2 < path.len() && lengths[j + 1] < target {
+                j += 1;
+            }
+            let span = lengths[j + 1] - lengths[j];
+            let t = if span > 0. {
+                ((target - lengths[j]) / span).clamp(0., 1.)
+            } else {
+                0.
+            };
+            path[j].lerp(path[j + 1], t)
+        })
+        .collect()
+}
+
 pub struct ControlPointIter<'a> {
     curve: &'a PolyBezier<CubicBezier>,
     i: usize,
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+use smooth_bevy_cameras::LookTransform;
+use std::collections::HashMap;
+
+use crate::background::{ground_height, Terrain};
+use crate::coords::METERS_PER_UNIT;
+use crate::dock::Layout;
+use crate::gvas::SplineType;
+use crate::inspector::SelectedSpline;
+use crate::palette::Palette;
+use crate::settings::Settings;
+use crate::spline::{resample, CubicBezier, PolyBezier};
+
+/// Plugin for finding track with nothing under it
+pub struct SupportPlugin;
+
+impl Plugin for SupportPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SupportCheck {
+            clearance: 1.,
+            reach: 3.,
+            min_span: 5.,
+            spans: vec![],
+            checked: false,
+        });
+        app.add_system(support_window);
+    }
+}
+
+/// Limits for the check, and the spans found by the last one. Distances are in meters
+pub struct SupportCheck {
+    /// How far track can be above the ground, or above groundwork or a bridge, and still sit on it
+    pub clearance: f32,
+    /// How far to the side groundwork or a bridge can be and still hold the track up
+    pub reach: f32,
+    /// Shorter unsupported spans are ignored
+    pub min_span: f32,
+    pub spans: Vec<Span>,
+    /// Whether `spans` is from a check, even if it's empty
+    checked: bool,
+}
+
+/// A stretch of track with nothing under it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub spline: Entity,
+    /// Middle of the span, in editor space
+    pub location: Vec3,
+    /// In meters
+    pub length: f32,
+    /// Greatest height above the ground, in meters
+    pub height: f32,
+}
+
+/// Points of the splines track can sit on, bucketed by horizontal cell so each track point only
+/// looks at the ones nearby
+struct Supports {
+    cell: f32,
+    points: HashMap<(i32, i32), Vec<Vec3>>,
+}
+
+impl Supports {
+    fn key(&self, p: Vec3) -> (i32, i32) {
+        (
+            (p.x / self.cell).floor() as i32,
+            (p.z / self.cell).floor() as i32,
+        )
+    }
+
+    fn new(cell: f32, points: impl Iterator<Item = Vec3>) -> Self {
+        let mut ret = Self {
+            cell,
+            points: HashMap::new(),
+        };
+        for p in points {
+            let key = ret.key(p);
+            ret.points.entry(key).or_default().push(p);
+        }
+        ret
+    }
+
+    /// Whether any point is within `reach` to the side of `p`, and no more than `clearance`
+    /// above or below it
+    fn holds(&self, p: Vec3, reach: f32, clearance: f32) -> bool {
+        let (x, z) = self.key(p);
+        (x - 1..=x + 1)
+            .flat_map(|x| (z - 1..=z + 1).map(move |z| (x, z)))
+            .filter_map(|key| self.points.get(&key))
+            .flatten()
+            .any(|q| {
+                Vec2::new(p.x - q.x, p.z - q.z).length() <= reach && (p.y - q.y).abs() <= clearance
+            })
+    }
+}
+
+/// Finds the unsupported spans of one track spline, given as sampled points. The editor's
+/// terrain is flat, so the ground is the height of its plane
+fn unsupported_spans(
+    spline: Entity,
+    points: &[Vec3],
+    supports: &Supports,
+    ground: f32,
+    check: &SupportCheck,
+) -> Vec<Span> {
+    let clearance = check.clearance / METERS_PER_UNIT;
+    let reach = check.reach / METERS_PER_UNIT;
+    let held: Vec<bool> = points
+        .iter()
+        .map(|p| p.y - ground <= clearance || supports.holds(*p, reach, clearance))
+        .collect();
+    let mut spans = vec![];
+    let mut i = 0;
+    while i < points.len() {
+        if held[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < points.len() && !held[i] {
+            i += 1;
+        }
+        let span = &points[start..i];
+        let length: f32 = span.windows(2).map(|w| w[0].distance(w[1])).sum();
+        if length * METERS_PER_UNIT >= check.min_span {
+            spans.push(Span {
+                spline,
+                location: span[span.len() / 2],
+                length: length * METERS_PER_UNIT,
+                height: span.iter().map(|p| p.y - ground).fold(0., f32::max) * METERS_PER_UNIT,
+            });
+        }
+    }
+    spans
+}
+
+/// Furthest apart the control points of generated groundwork are, in editor units
+const FILL_SPACING: f32 = 2.;
+
+/// Paths for layers of groundwork `depth` deep filling the gap between `track` and the ground,
+/// each layer `depth` below the one above it. Gaps less than half a layer deep are left, so the
+/// fill rounds to the nearest layer. Distances are in editor units
+pub fn groundwork_under(
+    track: &PolyBezier<CubicBezier>,
+    ground: impl Fn(Vec3) -> f32,
+    depth: f32,
+) -> Vec<Vec<Vec3>> {
+    let samples = track.sample(8);
+    let highest = samples.iter().map(|p| p.y - ground(*p)).fold(0., f32::max);
+    let mut layers = vec![];
+    for k in 0..(highest / depth).ceil() as usize {
+        let mut run = vec![];
+        for p in samples.iter() {
+            let top = *p - Vec3::Y * depth * k as f32;
+            if top.y - ground(top) > depth / 2. {
+                run.push(top);
+            } else if run.len() > 1 {
+                layers.push(std::mem::take(&mut run));
+            } else {
+                run.clear();
+            }
+        }
+        if run.len() > 1 {
+            layers.push(run);
+        }
+    }
+    layers
+        .iter()
+        .map(|run| {
+            let length: f32 = run.windows(2).map(|w| w[0].distance(w[1])).sum();
+            resample(run, (length / FILL_SPACING).ceil() as usize + 1)
+        })
+        .collect()
+}
+
+fn support_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut check: ResMut<SupportCheck>,
+    beziers: Query<(Entity, &PolyBezier<CubicBezier>)>,
+    terrain: Query<&Transform, With<Terrain>>,
+    mut cameras: Query<&mut LookTransform, With<PickingCamera>>,
+    mut selected: ResMut<SelectedSpline>,
+    mut layout: ResMut<Layout>,
+) {
+    if !palette.show_support {
+        return;
+    }
+    let check = check.as_mut();
+    let units = settings.units;
+    let mut run = false;
+    let mut go_to = None;
+    let window = egui::Window::new("Unsupported Track").default_height(300.);
+    layout.show(egui_context.ctx_mut(), "Unsupported Track", window, |ui| {
+        egui::Grid::new("support_limits").show(ui, |ui| {
+            for (label, value, max) in [
+                ("Clearance", &mut check.clearance, 10.),
+                ("Reach", &mut check.reach, 20.),
+                ("Shortest Span", &mut check.min_span, 100.),
+            ] {
+                ui.label(label);
+                let mut v = units.from_meters(*value);
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut v)
+                            .clamp_range(0. ..=max)
+                            .suffix(units.suffix()),
+                    )
+                    .changed()
+                {
+                    *value = units.to_meters(v);
+                }
+                ui.end_row();
+            }
+        });
+        run = ui.button("Check").clicked();
+        if !check.checked {
+            ui.label("Find track that isn't on the ground, groundwork, or a bridge");
+            return;
+        }
+        ui.label(format!("{} unsupported spans", check.spans.len()));
+        egui::ScrollArea::vertical()
+            .max_height(250.)
+            .show(ui, |ui| {
+                for span in check.spans.iter() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Go").clicked() {
+                            go_to = Some((span.spline, span.location));
+                        }
+                        ui.label(format!(
+                            "{} long, up to {} above the ground",
+                            units.length(span.length),
+                            units.length(span.height)
+                        ));
+                    });
+                }
+            });
+    });
+    if run {
+        let ground = ground_height(&terrain, Vec3::ZERO);
+        // Anything that isn't track can carry it, e.g. groundwork, track bed, and bridges. Cells
+        // can't be smaller than the reach, or nearby points could be missed
+        let supports = Supports::new(
+            check.reach.max(1.) / METERS_PER_UNIT,
+            beziers
+                .iter()
+                .filter(|(_e, bez)| bez.ty() != SplineType::Track)
+                .flat_map(|(_e, bez)| bez.sample(8)),
+        );
+        let spans = beziers
+            .iter()
+            .filter(|(_e, bez)| bez.ty() == SplineType::Track)
+            .flat_map(|(entity, bez)| {
+                unsupported_spans(entity, &bez.sample(8), &supports, ground, &*check)
+            })
+            .collect();
+        check.spans = spans;
+        check.checked = true;
+    }
+    if let Some((spline, location)) = go_to {
+        selected.0 = Some(spline);
+        // Keeps the camera's current offset from its target
+        for mut look in cameras.iter_mut() {
+            let offset = look.eye - look.target;
+            look.target = location;
+            look.eye = location + offset;
+        }
+    }
+}
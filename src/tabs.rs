@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::path::Path;
+
+use crate::buffers::PendingStops;
+use crate::control::PendingLoad;
+use crate::gvas::RROSave;
+use crate::history::History;
+use crate::template::Templates;
+use crate::update::BezierModificaiton;
+
+/// Plugin for keeping several saves open as tabs
+pub struct TabsPlugin;
+
+impl Plugin for TabsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Tabs {
+            tabs: vec![Tab {
+                name: "New Save".to_string(),
+                save: None,
+                history: History::default(),
+            }],
+            active: 0,
+        });
+        app.add_event::<TabEvent>();
+        app.add_system(tab_bar);
+    }
+}
+
+/// An open save
+#[derive(Debug, Clone)]
+pub struct Tab {
+    pub name: String,
+    /// The save as of when the tab was last active. The active tab is always `None`, since it
+    /// lives in the world and the `RROSave` resource
+    pub save: Option<RROSave>,
+    /// Edits made in the tab, so they can still be undone when it's active again. The active
+    /// tab's edits are in the `History` resource instead
+    pub history: History,
+}
+
+/// Every open save. Switching tabs is handled by `control::load_save`, which writes the world
+/// into the active tab before loading the next one
+#[derive(Debug)]
+pub struct Tabs {
+    pub tabs: Vec<Tab>,
+    pub active: usize,
+}
+
+impl Tabs {
+    /// Names the active tab after the file loaded into it
+    pub fn rename_active(&mut self, path: &Path) {
+        if let Some(stem) = path.file_stem() {
+            self.tabs[self.active].name = stem.to_string_lossy().into_owned();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabEvent {
+    /// Open a new tab with an empty save
+    New,
+    Switch(usize),
+    Close(usize),
+}
+
+/// Shows the tab bar. Templates are shared between tabs, so capturing one in a tab and pasting
+/// it in place in another copies splines and switches between saves
+fn tab_bar(
+    mut egui_context: ResMut<EguiContext>,
+    tabs: Res<Tabs>,
+    pending: Res<PendingLoad>,
+    templates: Res<Templates>,
+    mut pending_stops: ResMut<PendingStops>,
+    mut tab_events: EventWriter<TabEvent>,
+    mut modification: EventWriter<BezierModificaiton>,
+) {
+    egui::TopBottomPanel::top("tabs").show(egui_context.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.set_enabled(!pending.loading());
+            for (i, tab) in tabs.tabs.iter().enumerate() {
+                if ui
+                    .selectable_label(i == tabs.active, tab.name.as_str())
+                    .clicked()
+                    && i != tabs.active
+                {
+                    tab_events.send(TabEvent::Switch(i));
+                }
+                if tabs.tabs.len() > 1 && ui.small_button("x").clicked() {
+                    tab_events.send(TabEvent::Close(i));
+                }
+                ui.separator();
+            }
+            if ui.button("+").on_hover_text("New tab").clicked() {
+                tab_events.send(TabEvent::New);
+            }
+            ui.separator();
+            let template = templates.templates.last();
+            let label = template.map_or("Paste in Place".to_string(), |t| {
+                format!("Paste '{}' in Place", t.name)
+            });
+            let paste = ui
+                .add_enabled(template.is_some(), egui::Button::new(label))
+                .on_hover_text("Places the last captured template where it was captured from. Press T over a spline to capture it");
+            if let (true, Some(template)) = (paste.clicked(), template) {
+                for curve in template.curves.iter() {
+                    modification.send(BezierModificaiton::PlaceCurve(
+                        curve.ty,
+                        curve.points.iter().map(|p| *p + template.origin).collect(),
+                        curve.visibility.clone(),
+                    ));
+                }
+                for (ty, location, rotation) in template.switches.iter() {
+                    modification.send(BezierModificaiton::PlaceSw(
+                        *location + template.origin,
+                        *ty,
+                        *rotation,
+                    ));
+                }
+                pending_stops.locations.extend(
+                    template.buffer_stops.iter().map(|p| *p + template.origin),
+                );
+            }
+        });
+    });
+}
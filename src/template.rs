@@ -0,0 +1,384 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::{Hover, PickingCamera};
+
+use crate::buffers::{spline_end, BufferStop, PendingStops};
+use crate::control::{DefaultAssets, ParentBundle};
+use crate::gvas::{SplineType, SwitchData, SwitchType};
+use crate::junction::{
+    connected_curves, find_junctions, spline_endpoints, Welded, JUNCTION_EPSILON,
+};
+use crate::palette::{MouseAction, Palette};
+use crate::spline::mesh::MeshQuality;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::update::{BezierModificaiton, BezierSection, DragState};
+
+/// Plugin for capturing and stamping templates
+pub struct TemplatePlugin;
+
+impl Plugin for TemplatePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Templates::default());
+        app.add_system(capture_template);
+        app.add_system(stamp_preview);
+        app.add_system(template_window);
+    }
+}
+
+/// A spline stored in a template, relative to the template's anchor
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateCurve {
+    pub ty: SplineType,
+    pub points: Vec<Vec3>,
+    pub visibility: Vec<bool>,
+}
+
+/// A reusable group of splines and switches
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub name: String,
+    /// Location the template was captured from
+    pub origin: Vec3,
+    pub curves: Vec<TemplateCurve>,
+    /// (ty, location, rotation) relative to the anchor
+    pub switches: Vec<(SwitchType, Vec3, Quat)>,
+    /// Ends of the curves with a buffer stop, relative to the anchor
+    pub buffer_stops: Vec<Vec3>,
+}
+
+/// Preview state while a template is being stamped
+#[derive(Debug, Clone)]
+struct Stamp {
+    template: usize,
+    rotation: f32,
+    scale: f32,
+    nudge: Vec3,
+    ghost: Entity,
+}
+
+impl Stamp {
+    fn transform(&self, cursor: Vec3) -> Transform {
+        Transform {
+            translation: cursor + self.nudge,
+            rotation: Quat::from_rotation_y(self.rotation),
+            scale: Vec3::splat(self.scale),
+        }
+    }
+}
+
+/// Captured templates
+#[derive(Debug, Default)]
+pub struct Templates {
+    pub templates: Vec<Template>,
+    /// Name to give the next captured template
+    new_name: String,
+    stamp: Option<Stamp>,
+    /// Stamp requested from the template window
+    start_stamp: Option<usize>,
+}
+
+impl Templates {
+    /// Adds a template and starts stamping it, e.g. for splines imported from another save
+    pub fn stamp_new(&mut self, template: Template) {
+        self.templates.push(template);
+        self.start_stamp = Some(self.templates.len() - 1);
+    }
+}
+
+/// Finds every curve connected to `start` through welded junctions
+fn welded_group(
+    start: Entity,
+    handles: &Query<(&DragState, &Parent, Entity)>,
+    beziers: &Query<&PolyBezier<CubicBezier>>,
+    welded: &Query<&Welded>,
+) -> Vec<Entity> {
+    let junctions: Vec<_> = find_junctions(&spline_endpoints(handles, beziers))
+        .into_iter()
+        .filter(|j| j.endpoints.iter().all(|(h, _c)| welded.get(*h).is_ok()))
+        .collect();
+    connected_curves(start, &junctions)
+}
+
+/// Captures the hovered spline (and everything welded to it) as a template when T is pressed
+fn capture_template(
+    keys: Res<Input<KeyCode>>,
+    hovered: Query<(&Hover, &Parent), Or<(With<DragState>, With<BezierSection>)>>,
+    handles: Query<(&DragState, &Parent, Entity)>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    switches: Query<(&Transform, &SwitchData)>,
+    welded: Query<&Welded>,
+    stops: Query<&BufferStop>,
+    mut templates: ResMut<Templates>,
+) {
+    if !keys.just_pressed(KeyCode::T) {
+        return;
+    }
+    let curve = if let Some((_h, parent)) = hovered.iter().find(|(h, _p)| h.hovered()) {
+        parent.0
+    } else {
+        return;
+    };
+    let origin = beziers.get(curve).unwrap().get_control_point(0);
+    let mut template = Template {
+        name: if templates.new_name.is_empty() {
+            format!("Template {}", templates.templates.len() + 1)
+        } else {
+            std::mem::take(&mut templates.new_name)
+        },
+        origin,
+        curves: vec![],
+        switches: vec![],
+        buffer_stops: vec![],
+    };
+    let mut points = vec![];
+    let group = welded_group(curve, &handles, &beziers, &welded);
+    for &e in group.iter() {
+        if let Ok(bez) = beziers.get(e) {
+            points.extend(bez.get_control_points());
+            template.curves.push(TemplateCurve {
+                ty: bez.ty(),
+                points: bez.get_control_points().map(|p| p - origin).collect(),
+                visibility: bez.get_visibility().to_vec(),
+            });
+        }
+    }
+    for (trans, switch) in switches.iter() {
+        if points
+            .iter()
+            .any(|p| p.distance(trans.translation) < JUNCTION_EPSILON)
+        {
+            template
+                .switches
+                .push((switch.ty, trans.translation - origin, trans.rotation));
+        }
+    }
+    for stop in stops.iter().filter(|s| group.contains(&s.spline)) {
+        if let Ok(bez) = beziers.get(stop.spline) {
+            template
+                .buffer_stops
+                .push(spline_end(bez, stop.at_start).0 - origin);
+        }
+    }
+    templates.templates.push(template);
+}
+
+fn spawn_ghost(
+    template: &Template,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    assets: &Res<DefaultAssets>,
+) -> Entity {
+    let mut entity = commands.spawn_bundle(ParentBundle::default());
+    entity.with_children(|commands| {
+        for curve in template.curves.iter() {
+            let mut bez =
+                PolyBezier::new(curve.points.clone(), curve.visibility.clone(), curve.ty);
+            // Ghosts are only previews, so use the cheapest meshes
+            let created = bez.create_meshes(
+                meshes,
+                assets,
+                MeshQuality::Draft,
+                &MeshQuality::Draft.detail(),
+            );
+            for (mesh, _vis) in created {
+                // Segments without a mesh are left out, so find the one each mesh belongs to
+                let centroid = match bez.get_transforms().find(|(_c, m)| m.has(&mesh)) {
+                    Some((centroid, _m)) => centroid,
+                    None => continue,
+                };
+                commands.spawn_bundle(PbrBundle {
+                    mesh,
+                    material: assets.ghost_material.clone(),
+                    transform: Transform::from_translation(centroid),
+                    ..Default::default()
+                });
+            }
+        }
+        for &(ty, translation, rotation) in template.switches.iter() {
+            commands.spawn_bundle(PbrBundle {
+                mesh: assets.switch_mesh[ty].clone(),
+                material: assets.ghost_material.clone(),
+                transform: Transform {
+                    translation,
+                    rotation,
+                    scale: ty.scale(),
+                },
+                ..Default::default()
+            });
+        }
+    });
+    entity.id()
+}
+
+/// Moves the ghost of the template being stamped, and stamps it on click
+fn stamp_preview(
+    pick_cam: Query<&PickingCamera>,
+    keys: Res<Input<KeyCode>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    mut ghosts: Query<&mut Transform>,
+    mut templates: ResMut<Templates>,
+    mut palette: ResMut<Palette>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    assets: Res<DefaultAssets>,
+    mut pending_stops: ResMut<PendingStops>,
+    mut commands: Commands,
+    mut modification: EventWriter<BezierModificaiton>,
+) {
+    let templates = templates.as_mut();
+    if let Some(template) = templates.start_stamp.take() {
+        if let Some(stamp) = templates.stamp.take() {
+            commands.entity(stamp.ghost).despawn_recursive();
+        }
+        templates.stamp = Some(Stamp {
+            template,
+            rotation: 0.,
+            scale: 1.,
+            nudge: Vec3::ZERO,
+            ghost: spawn_ghost(
+                &templates.templates[template],
+                &mut commands,
+                &mut meshes,
+                &assets,
+            ),
+        });
+        palette.action = MouseAction::Stamp;
+    }
+    let stamp = if let Some(stamp) = templates.stamp.as_mut() {
+        stamp
+    } else {
+        return;
+    };
+    if !matches!(palette.action, MouseAction::Stamp) || keys.just_pressed(KeyCode::Escape) {
+        commands.entity(stamp.ghost).despawn_recursive();
+        templates.stamp = None;
+        if matches!(palette.action, MouseAction::Stamp) {
+            palette.action = MouseAction::Drag;
+        }
+        return;
+    }
+    let fine = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    let (angle, step) = if fine {
+        (1f32.to_radians(), 0.01)
+    } else {
+        (15f32.to_radians(), 0.1)
+    };
+    if keys.just_pressed(KeyCode::Q) {
+        stamp.rotation += angle;
+    }
+    if keys.just_pressed(KeyCode::E) {
+        stamp.rotation -= angle;
+    }
+    if keys.just_pressed(KeyCode::Equals) {
+        stamp.scale *= 1.05;
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        stamp.scale /= 1.05;
+    }
+    for (key, dir) in [
+        (KeyCode::Left, Vec3::new(-1., 0., 0.)),
+        (KeyCode::Right, Vec3::new(1., 0., 0.)),
+        (KeyCode::Up, Vec3::new(0., 0., -1.)),
+        (KeyCode::Down, Vec3::new(0., 0., 1.)),
+        (KeyCode::PageUp, Vec3::new(0., 1., 0.)),
+        (KeyCode::PageDown, Vec3::new(0., -1., 0.)),
+    ] {
+        if keys.just_pressed(key) {
+            stamp.nudge += dir * step;
+        }
+    }
+
+    let template = &templates.templates[stamp.template];
+    let cursor = pick_cam
+        .iter()
+        .last()
+        .and_then(|cam| {
+            cam.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+                point: template.origin,
+                normal: Vec3::new(0., 1., 0.),
+            })
+        })
+        .map_or(template.origin, |int| int.position());
+    let transform = stamp.transform(cursor);
+    if let Ok(mut ghost) = ghosts.get_mut(stamp.ghost) {
+        *ghost = transform;
+    }
+
+    if mouse_button_input.just_pressed(MouseButton::Left) || keys.just_pressed(KeyCode::Return) {
+        for curve in template.curves.iter() {
+            modification.send(BezierModificaiton::PlaceCurve(
+                curve.ty,
+                curve.points.iter().map(|p| transform.mul_vec3(*p)).collect(),
+                curve.visibility.clone(),
+            ));
+        }
+        for &(ty, translation, rotation) in template.switches.iter() {
+            modification.send(BezierModificaiton::PlaceSw(
+                transform.mul_vec3(translation),
+                ty,
+                transform.rotation * rotation,
+            ));
+        }
+        pending_stops.locations.extend(
+            template
+                .buffer_stops
+                .iter()
+                .map(|p| transform.mul_vec3(*p)),
+        );
+        commands.entity(stamp.ghost).despawn_recursive();
+        templates.stamp = None;
+        palette.action = MouseAction::Drag;
+    }
+}
+
+fn template_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut templates: ResMut<Templates>,
+) {
+    if !palette.show_templates {
+        return;
+    }
+    let templates = templates.as_mut();
+    egui::Window::new("Templates")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("Press T over a spline to capture it, along with everything welded to it");
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut templates.new_name);
+            });
+            let mut remove = None;
+            for (i, template) in templates.templates.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} ({} splines, {} switches)",
+                        template.name,
+                        template.curves.len(),
+                        template.switches.len()
+                    ));
+                    if ui.button("Stamp").clicked() {
+                        templates.start_stamp = Some(i);
+                    }
+                    if ui.button("Delete").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                if templates.stamp.as_ref().is_some_and(|s| s.template == i) {
+                    // Let the preview clean up its ghost before the template goes away
+                    return;
+                }
+                if let Some(stamp) = templates.stamp.as_mut() {
+                    if stamp.template > i {
+                        stamp.template -= 1;
+                    }
+                }
+                templates.templates.remove(i);
+            }
+            if templates.stamp.is_some() {
+                ui.label("Q/E: rotate, +/-: scale, arrows/PgUp/PgDn: nudge");
+                ui.label("Shift for fine steps, click to place, Esc to cancel");
+            }
+        });
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// Plugin for the editor's egui style, set from `Settings`
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_theme);
+    }
+}
+
+/// Name of the bundled bold font, used for headings
+const HEADING_FONT: &str = "FiraSans-Bold";
+
+/// Light or dark windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::Dark
+    }
+}
+
+/// The style for a theme and text scale. Widgets are taller and further apart than egui's
+/// defaults, so radio buttons and checkboxes are easier to hit
+fn style(theme: Theme, text_scale: f32) -> egui::Style {
+    let mut style = egui::Style {
+        visuals: match theme {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        },
+        ..Default::default()
+    };
+    style.spacing.item_spacing = egui::vec2(8., 5.);
+    style.spacing.button_padding = egui::vec2(6., 3.);
+    style.spacing.interact_size = egui::vec2(40., 22.) * text_scale;
+    style.spacing.icon_width = 16. * text_scale;
+    style.spacing.window_padding = egui::vec2(8., 8.);
+    for (text_style, font) in style.text_styles.iter_mut() {
+        font.size *= text_scale;
+        if *text_style == egui::TextStyle::Heading {
+            font.family = egui::FontFamily::Name(HEADING_FONT.into());
+        }
+    }
+    style
+}
+
+/// Fonts egui ships with, plus the bundled bold font for headings
+fn fonts() -> egui::FontDefinitions {
+    let mut fonts = egui::FontDefinitions::default();
+    fonts.font_data.insert(
+        HEADING_FONT.to_string(),
+        egui::FontData::from_static(include_bytes!("../assets/fonts/FiraSans-Bold.ttf")),
+    );
+    // Falls back to the default fonts for any glyphs the bold font doesn't have
+    let mut family = vec![HEADING_FONT.to_string()];
+    family.extend(fonts.families[&egui::FontFamily::Proportional].iter().cloned());
+    fonts
+        .families
+        .insert(egui::FontFamily::Name(HEADING_FONT.into()), family);
+    fonts
+}
+
+/// Sets the fonts once, and the style whenever the theme or text scale changes
+fn apply_theme(
+    mut egui_context: ResMut<EguiContext>,
+    settings: Res<Settings>,
+    mut applied: Local<Option<(Theme, f32)>>,
+) {
+    let current = (settings.theme, settings.text_scale);
+    if *applied == Some(current) {
+        return;
+    }
+    let ctx = egui_context.ctx_mut();
+    if applied.is_none() {
+        ctx.set_fonts(fonts());
+    }
+    ctx.set_style(style(settings.theme, settings.text_scale));
+    *applied = Some(current);
+}
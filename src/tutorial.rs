@@ -0,0 +1,256 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+use smooth_bevy_cameras::LookTransform;
+
+use crate::control::default_save;
+use crate::coords::vec_to_gvas;
+use crate::gvas::{CurveDataOwned, RROSave, SplineType, SwitchData};
+use crate::history::History;
+use crate::junction::JUNCTION_EPSILON;
+use crate::palette::Palette;
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::tabs::{Tab, TabEvent, Tabs};
+
+/// Written once the tutorial is finished or skipped, in the working directory, so it's only
+/// offered on the first run
+pub const TUTORIAL_FILE: &str = "tutorial_done";
+
+/// Name of the tab the sandbox save is opened in. Tasks are only checked in this tab
+const TUTORIAL_TAB: &str = "Tutorial";
+
+/// Plugin for the first run tutorial, which opens a small sandbox save in its own tab and walks
+/// through the basic tools
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Tutorial {
+            welcome: !std::path::Path::new(TUTORIAL_FILE).exists(),
+            done: [false; TASKS.len()],
+        });
+        app.add_system(welcome_window);
+        app.add_system(tutorial_window);
+        app.add_system(check_tasks);
+    }
+}
+
+/// Where the control point for the first task should be dragged to, in editor space
+const MOVE_TARGET: Vec3 = Vec3::new(0., 0., 6.);
+/// Middle of the gap between the two splines to join
+const JOIN_POINT: Vec3 = Vec3::new(6., 0., 0.);
+/// How close a point has to be to `MOVE_TARGET` to count, in editor units
+const TARGET_RADIUS: f32 = 0.5;
+/// How close ends and switches have to be to `JOIN_POINT` to count. The ends start 1 unit away
+const JOIN_RADIUS: f32 = 2.;
+
+/// A guided task, with where the camera should look for it
+struct Task {
+    title: &'static str,
+    instructions: &'static str,
+    location: Vec3,
+}
+
+const TASKS: [Task; 4] = [
+    Task {
+        title: "Move a point",
+        instructions: "Drag the middle point of the short track about 20 m further from the long \
+            tracks, so it bends. Hold a point and move the mouse to drag it.",
+        location: MOVE_TARGET,
+    },
+    Task {
+        title: "Join two splines",
+        instructions: "The two long tracks have a gap between them. Drag the end of one onto the \
+            end of the other, until they snap together.",
+        location: JOIN_POINT,
+    },
+    Task {
+        title: "Place a switch",
+        instructions: "Pick a switch in the palette, then click where the two tracks meet.",
+        location: JOIN_POINT,
+    },
+    Task {
+        title: "Build track bed",
+        instructions: "Click a track to select it, open Show Spline Properties and use Build \
+            Under Track.",
+        location: JOIN_POINT,
+    },
+];
+
+/// Progress through the tutorial
+pub struct Tutorial {
+    /// Show the first run prompt
+    pub welcome: bool,
+    pub done: [bool; TASKS.len()],
+}
+
+/// The sandbox save: the default save with a few splines to practice on
+fn sandbox_save() -> RROSave {
+    let mut save = default_save();
+    let track = |points: &[Vec3]| CurveDataOwned {
+        location: vec_to_gvas(points[0]),
+        ty: SplineType::Track,
+        control_points: points.iter().map(|p| vec_to_gvas(*p)).collect(),
+        visibility: vec![true; points.len() - 1],
+    };
+    let curves = vec![
+        track(&[
+            Vec3::new(-2., 0., 4.),
+            Vec3::new(0., 0., 4.),
+            Vec3::new(4., 0., 4.),
+        ]),
+        track(&[Vec3::new(-4., 0., 0.), Vec3::new(5., 0., 0.)]),
+        track(&[Vec3::new(7., 0., 0.), Vec3::new(16., 0., 0.)]),
+    ];
+    if let Err(e) = save.set_curves(curves.into_iter()) {
+        println!("Error: {:?}", e);
+    }
+    save
+}
+
+/// Marks the tutorial as seen, so it isn't offered again
+fn finish(tutorial: &mut Tutorial) {
+    tutorial.welcome = false;
+    if let Err(e) = std::fs::write(TUTORIAL_FILE, "") {
+        println!("Error: {:?}", e);
+    }
+}
+
+fn welcome_window(
+    mut egui_context: ResMut<EguiContext>,
+    mut tutorial: ResMut<Tutorial>,
+    mut palette: ResMut<Palette>,
+    mut tabs: ResMut<Tabs>,
+    mut tab_events: EventWriter<TabEvent>,
+) {
+    if !tutorial.welcome {
+        return;
+    }
+    let (mut start, mut skip) = (false, false);
+    egui::Window::new("Welcome")
+        .resizable(false)
+        .collapsible(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("New to the editor? The tutorial opens a sandbox save in its own tab,");
+            ui.label("so nothing you do there touches your real saves.");
+            ui.horizontal(|ui| {
+                start = ui.button("Start Tutorial").clicked();
+                skip = ui.button("Skip").clicked();
+            });
+        });
+    if start {
+        tutorial.welcome = false;
+        tutorial.done = [false; TASKS.len()];
+        palette.show_tutorial = true;
+        tabs.tabs.push(Tab {
+            name: TUTORIAL_TAB.to_string(),
+            save: Some(sandbox_save()),
+            history: History::default(),
+        });
+        tab_events.send(TabEvent::Switch(tabs.tabs.len() - 1));
+    } else if skip {
+        finish(&mut tutorial);
+    }
+}
+
+fn tutorial_window(
+    mut egui_context: ResMut<EguiContext>,
+    mut tutorial: ResMut<Tutorial>,
+    mut palette: ResMut<Palette>,
+    tabs: Res<Tabs>,
+    mut cameras: Query<&mut LookTransform, With<PickingCamera>>,
+) {
+    if !palette.show_tutorial {
+        return;
+    }
+    let in_sandbox = tabs.tabs[tabs.active].name == TUTORIAL_TAB;
+    let (mut restart, mut close) = (false, false);
+    let mut go_to = None;
+    egui::Window::new("Tutorial")
+        .default_width(300.)
+        .show(egui_context.ctx_mut(), |ui| {
+            if !in_sandbox {
+                ui.label("Switch to the Tutorial tab to continue, or restart it.");
+            }
+            // Only the first unfinished task has its instructions shown
+            let current = tutorial.done.iter().position(|done| !done);
+            for (i, task) in TASKS.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let mut done = tutorial.done[i];
+                    ui.add_enabled(false, egui::Checkbox::new(&mut done, task.title));
+                    if ui.small_button("Go").clicked() {
+                        go_to = Some(task.location);
+                    }
+                });
+                if current == Some(i) {
+                    ui.label(task.instructions);
+                }
+            }
+            if current.is_none() {
+                ui.label("That's everything. Open your own save from the palette when ready.");
+            }
+            ui.horizontal(|ui| {
+                restart = ui.button("Restart").clicked();
+                close = ui.button("Close").clicked();
+            });
+        });
+    if restart {
+        tutorial.welcome = true;
+        palette.show_tutorial = false;
+    } else if close {
+        finish(&mut tutorial);
+        palette.show_tutorial = false;
+    }
+    if let Some(location) = go_to {
+        // Keeps the camera's current offset from its target
+        for mut look in cameras.iter_mut() {
+            let offset = look.eye - look.target;
+            look.target = location;
+            look.eye = location + offset;
+        }
+    }
+}
+
+/// Marks tasks done as the world changes. Tasks stay done once they are
+fn check_tasks(
+    mut tutorial: ResMut<Tutorial>,
+    palette: Res<Palette>,
+    tabs: Res<Tabs>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    switches: Query<&Transform, With<SwitchData>>,
+) {
+    if !palette.show_tutorial || tabs.tabs[tabs.active].name != TUTORIAL_TAB {
+        return;
+    }
+    let ends: Vec<Vec3> = beziers
+        .iter()
+        .filter(|bez| bez.ty() == SplineType::Track)
+        .flat_map(|bez| {
+            [
+                bez.get_control_point(0),
+                bez.get_control_point(bez.len() - 1),
+            ]
+        })
+        .filter(|p| p.distance(JOIN_POINT) < JOIN_RADIUS)
+        .collect();
+    let checks = [
+        beziers.iter().any(|bez| {
+            bez.get_control_points().any(|p| {
+                Vec2::new(p.x - MOVE_TARGET.x, p.z - MOVE_TARGET.z).length() < TARGET_RADIUS
+            })
+        }),
+        ends.iter().enumerate().any(|(i, a)| {
+            ends[i + 1..]
+                .iter()
+                .any(|b| a.distance(*b) < JUNCTION_EPSILON)
+        }),
+        switches
+            .iter()
+            .any(|t| t.translation.distance(JOIN_POINT) < JOIN_RADIUS),
+        beziers.iter().any(|bez| bez.ty() == SplineType::TrackBed),
+    ];
+    let tutorial = tutorial.as_mut();
+    for (done, check) in tutorial.done.iter_mut().zip(checks) {
+        *done |= check;
+    }
+}
@@ -1,11 +1,26 @@
-use crate::control::{DefaultAssets, ParentBundle, SplineState};
-use crate::gvas::{quat_to_rotator, vec_to_gvas, SplineType, SwitchData, SwitchType};
+use crate::control::{
+    spawn_handles, spawn_industry, spawn_service, spawn_turntable, DefaultAssets, DeferredHandles, ParentBundle, SplineState, TURNTABLE_RADIUS,
+};
+use crate::coords::{quat_to_rotator, vec_to_gvas, METERS_PER_UNIT};
+use crate::dialog::Dialogs;
+use crate::gvas::{
+    industry_name, FrameData, IndustryData, ServiceData, ServiceKind, SplineType, SwitchData,
+    SwitchType, TurntableData, FIREWOOD_DEPOT,
+};
+use crate::inspector::Locked;
+use crate::junction::{Welded, JUNCTION_EPSILON};
+use crate::limits::{clamp_grade, clamp_radius, LimitFeedback};
 use crate::palette::{DebugInfo, MouseAction, Palette};
+use crate::patch::{EditEvent, PatchOp, SplineShape};
+use crate::safety::{EditSize, SafetyNet};
+use crate::selection::Selected;
+use crate::settings::Settings;
 use crate::snaps::SnapEvent;
-use crate::spline::mesh::curve_offset;
+use crate::spline::mesh::{MeshDetail, MeshQuality};
 use crate::spline::{CubicBezier, PolyBezier};
 use bevy::prelude::*;
 use bevy_mod_picking::{Hover, PickableButton, PickingCamera};
+use std::ops::Range;
 use std::time::{Duration, Instant};
 
 use log::warn;
@@ -16,9 +31,14 @@ pub struct UpdatePlugin;
 impl Plugin for UpdatePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<BezierSectionUpdate>();
-        app.add_system(update_bezier_transform);
+        app.add_event::<DragEnded>();
+        app.add_system(update_bezier_transform.label("update_bezier_transform"));
+        // Once the released handles have snapped, so the snap is part of the edit
+        app.add_system(record_drag.after("snap_handler"));
         app.add_system(update_curve_sections);
         app.add_system(modify_beziers);
+        app.add_system(preview_delete);
+        app.add_system(rotate_turntable_decks);
         app.add_system(debugging);
     }
 }
@@ -29,6 +49,8 @@ pub struct DragState {
     pub pt: usize,
     pub drag_start: Option<(Vec3, Vec3, Vec3)>,
     pub initial: Option<Transform>,
+    /// Tangent of the curve at the handle when the drag started, for `DragConstraint`
+    pub tangent: Vec3,
 }
 
 impl DragState {
@@ -40,34 +62,131 @@ impl DragState {
     }
 }
 
-/// The drag state for a switch
+/// The drag state for a switch or other placed object
 #[derive(Debug, Component, Default)]
-pub struct SwitchDrag {
+pub struct ObjectDrag {
     drag_start: Option<(Vec3, Vec3, Vec3)>,
     initial: Option<Transform>,
 }
 
+/// Marker component for the deck of a turntable
+#[derive(Debug, Component, Default)]
+pub struct TurntableDeck;
+
+/// Handle at the end of a turntable deck, dragged to rotate the deck
+#[derive(Debug, Component, Default)]
+pub struct DeckHandle {
+    dragging: bool,
+}
+
+impl DeckHandle {
+    /// Position of the handle relative to the turntable, for the deck's rotation
+    pub fn offset(deck: Quat) -> Vec3 {
+        deck * Vec3::new(TURNTABLE_RADIUS, 0.1, 0.)
+    }
+}
+
+/// Constrains a handle drag to a single direction while a modifier key is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DragConstraint {
+    /// Along the curve, with shift once the handle is grabbed
+    Tangent,
+    /// Sideways from the curve, with control
+    Normal,
+    /// Straight up and down, with alt
+    Vertical,
+}
+
+impl DragConstraint {
+    fn from_keys(keys: &Input<KeyCode>) -> Option<Self> {
+        if keys.pressed(KeyCode::LShift) {
+            Some(Self::Tangent)
+        } else if keys.pressed(KeyCode::LControl) {
+            Some(Self::Normal)
+        } else if keys.pressed(KeyCode::LAlt) {
+            Some(Self::Vertical)
+        } else {
+            None
+        }
+    }
+
+    /// Restricts a drag offset to the constraint's direction, given the curve's tangent. Drags
+    /// along the tangent follow its slope, moving as far horizontally as the mouse did
+    fn apply(self, dir: Vec3, tangent: Vec3) -> Vec3 {
+        let axis = match self {
+            Self::Tangent => tangent,
+            Self::Normal => tangent.cross(Vec3::Y),
+            Self::Vertical => return Vec3::new(0., dir.y, 0.),
+        };
+        let flat = Vec3::new(axis.x, 0., axis.z);
+        if flat.length_squared() < f32::EPSILON {
+            Vec3::ZERO
+        } else {
+            axis * (dir.dot(flat) / flat.length_squared())
+        }
+    }
+}
+
 /// Marker component for bezier sections
 #[derive(Debug, Component, Default)]
-pub struct BezierSection(Handle<Mesh>);
+pub struct BezierSection(pub Handle<Mesh>);
+
+/// Makes `want` the material a section rests at, e.g. when it's colored or faded. The current
+/// material only changes if the section is at rest, so hovered and delete preview materials are
+/// left alone until they end. Neither is marked changed unless it is
+pub fn set_section_material(
+    mat: &mut Mut<Handle<StandardMaterial>>,
+    pick: &mut Mut<PickableButton<StandardMaterial>>,
+    want: Handle<StandardMaterial>,
+) {
+    if pick.initial.as_ref() != Some(&want) {
+        if pick.initial.as_ref() == Some(&**mat) {
+            **mat = want.clone();
+        }
+        pick.initial = Some(want.clone());
+        pick.selected = Some(want);
+    }
+}
+
+/// Marker component for entities highlighted by the delete preview
+#[derive(Debug, Component, Default)]
+pub struct DeletePreview;
 
 /// Bezier modification events
 #[derive(Debug, Clone, PartialEq)]
 pub enum BezierModificaiton {
-    /// (curve, index) Extrude curve from point
+    /// (curve, index) Extrude curve from point, or a point was inserted at index. Either way it
+    /// needs a handle
     Extrude(Entity, usize),
     /// (curve, index) Delete point on curve
     DeletePt(Entity, usize),
     /// (curve, mesh) Delete section from curve
     DeleteSection(Entity, Handle<Mesh>),
+    /// (curve, segment, t) Split curve in two at a point along a segment
+    SplitAt(Entity, usize, f32),
     /// (pos, dir) Place new curve at pos, using dir for the spline's direction
     Place(Vec3, Vec3),
     /// (curve, old_ty, new_ty) Update spline type from old_ty to new_ty
     ChangeTy(Entity, SplineType, SplineType),
     /// (CurveSection, ty, visible) Change visibility of a curve section
     ChangeVis(Entity, SplineType, bool),
-    /// (switch) Delete switch
+    /// (switch) Delete switch or other placed object
     DeleteSw(Entity),
+    /// (curve) Delete a whole curve
+    DeleteCurve(Entity),
+    /// (pos, ty, rot) Place new industry
+    PlaceIndustry(Vec3, u32, Quat),
+    /// (pos, kind, rot) Place new service structure
+    PlaceService(Vec3, ServiceKind, Quat),
+    /// (pos, rot) Place new turntable
+    PlaceTurntable(Vec3, Quat),
+    /// (curve) Curve was reversed, renumber its handles
+    Reversed(Entity),
+    /// (curve) Curve's control points were replaced, e.g. with fewer of them, respawn its handles
+    /// and sections
+    Rebuilt(Entity),
+    /// (ty, points, visibility) Place a complete new curve
+    PlaceCurve(SplineType, Vec<Vec3>, Vec<bool>),
     /// (pos, ty, rot) Place new switch
     #[allow(unused)]
     PlaceSw(Vec3, SwitchType, Quat),
@@ -79,6 +198,10 @@ fn debugging(
     sections: Query<(&Hover, &Parent, &BezierSection)>,
     beziers: Query<&PolyBezier<CubicBezier>>,
     switches: Query<(&Hover, &Transform, &SwitchData)>,
+    frames: Query<(&Hover, &Transform, &FrameData)>,
+    industries: Query<(&Hover, &Transform, &IndustryData)>,
+    services: Query<(&Hover, &Transform, &ServiceData)>,
+    settings: Res<Settings>,
     mut debug_info: ResMut<DebugInfo>,
 ) {
     if state.show_debug {
@@ -89,7 +212,7 @@ fn debugging(
                 has_hover = true;
                 debug_info.hovered = format!(
                     "Point: {}\nty: {:?}\npt: {}",
-                    trans.translation - curve_offset(bez.ty()),
+                    trans.translation - settings.curve_offset(bez.ty()),
                     bez.ty(),
                     state.pt
                 );
@@ -101,6 +224,34 @@ fn debugging(
                 debug_info.hovered = format!("Switch: {:?}\ntrans: {:?}", state, trans);
             }
         }
+        for (hover, trans, industry) in industries.iter() {
+            if hover.hovered() {
+                has_hover = true;
+                debug_info.hovered = format!(
+                    "Industry: {}\n{:?}\ntrans: {:?}",
+                    industry_name(industry.ty),
+                    industry,
+                    trans
+                );
+            }
+        }
+        for (hover, trans, service) in services.iter() {
+            if hover.hovered() {
+                has_hover = true;
+                debug_info.hovered = format!(
+                    "{}: {:?}\ntrans: {:?}",
+                    service.kind.name(),
+                    service,
+                    trans
+                );
+            }
+        }
+        for (hover, trans, frame) in frames.iter() {
+            if hover.hovered() {
+                has_hover = true;
+                debug_info.hovered = format!("Frame: {:?}\ntrans: {:?}", frame, trans);
+            }
+        }
         for (hover, parent, section) in sections.iter() {
             if hover.hovered() {
                 let bez = beziers.get(parent.0.clone()).unwrap();
@@ -124,17 +275,206 @@ fn debugging(
     }
 }
 
+/// Highlights everything that would be removed by clicking in delete mode
+fn preview_delete(
+    palette: Res<Palette>,
+    assets: Res<DefaultAssets>,
+    objects: Query<(&Hover, &Parent, &DragState, Entity)>,
+    sections: Query<(&Hover, &Parent, &BezierSection, Entity)>,
+    switches: Query<
+        (&Hover, Entity),
+        (
+            Or<(
+                With<SwitchData>,
+                With<IndustryData>,
+                With<TurntableData>,
+                With<ServiceData>,
+            )>,
+            Without<DragState>,
+        ),
+    >,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut materials: Query<(
+        &mut Handle<StandardMaterial>,
+        &PickableButton<StandardMaterial>,
+        &Hover,
+        Option<&DeletePreview>,
+        Entity,
+    )>,
+    mut commands: Commands,
+) {
+    let mut targets = vec![];
+    if matches!(palette.action, MouseAction::Delete) {
+        let mut found_hover = false;
+        for (hover, parent, state, entity) in objects.iter() {
+            if hover.hovered() {
+                // Deleting a point removes the segments on either side of it
+                targets.push(entity);
+                if let Ok(bez) = beziers.get(parent.0) {
+                    for (_h, sec_parent, section, sec_entity) in sections.iter() {
+                        if sec_parent.0 == parent.0 {
+                            if let Some(i) = bez.get_segment(&section.0) {
+                                if i == state.pt || i + 1 == state.pt {
+                                    targets.push(sec_entity);
+                                }
+                            }
+                        }
+                    }
+                }
+                found_hover = true;
+                break;
+            }
+        }
+        if !found_hover {
+            for (hover, _parent, _sec, entity) in sections.iter() {
+                if hover.hovered() {
+                    targets.push(entity);
+                    found_hover = true;
+                    break;
+                }
+            }
+        }
+        if !found_hover {
+            for (hover, entity) in switches.iter() {
+                if hover.hovered() {
+                    targets.push(entity);
+                    break;
+                }
+            }
+        }
+    }
+    for (mut mat, pick, hover, preview, entity) in materials.iter_mut() {
+        if targets.contains(&entity) {
+            if preview.is_none() {
+                commands.entity(entity).insert(DeletePreview);
+            }
+            if *mat != assets.delete_material {
+                *mat = assets.delete_material.clone();
+            }
+        } else if preview.is_some() {
+            commands.entity(entity).remove::<DeletePreview>();
+            let restore = if hover.hovered() {
+                pick.hovered.clone()
+            } else {
+                pick.initial.clone()
+            };
+            if let Some(restore) = restore {
+                *mat = restore;
+            }
+        }
+    }
+}
+
+/// Rotates turntable decks by dragging the handle at the end of the deck
+fn rotate_turntable_decks(
+    pick_cam: Query<&PickingCamera>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    palette: Res<Palette>,
+    mut handles: Query<(&mut DeckHandle, &Hover, &Parent, &mut Transform)>,
+    mut decks: Query<(&Parent, &mut Transform), (With<TurntableDeck>, Without<DeckHandle>)>,
+    turntables: Query<&GlobalTransform, With<TurntableData>>,
+) {
+    let picking_camera: &PickingCamera = if let Some(cam) = pick_cam.iter().last() {
+        cam
+    } else {
+        return;
+    };
+    for (mut handle, hover, parent, mut trans) in handles.iter_mut() {
+        if mouse_button_input.just_pressed(MouseButton::Left) {
+            handle.dragging = hover.hovered() && matches!(palette.action, MouseAction::Drag);
+        } else if mouse_button_input.just_released(MouseButton::Left) {
+            handle.dragging = false;
+        }
+        if !handle.dragging {
+            continue;
+        }
+        let base = if let Ok(base) = turntables.get(parent.0) {
+            base
+        } else {
+            continue;
+        };
+        if let Some(int) =
+            picking_camera.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+                point: base.translation,
+                normal: base.rotation * Vec3::new(0., 1., 0.),
+            })
+        {
+            let local = base.rotation.inverse() * (int.position() - base.translation);
+            if local.length_squared() > f32::EPSILON {
+                let rotation = Quat::from_rotation_y(f32::atan2(-local.z, local.x));
+                trans.translation = DeckHandle::offset(rotation);
+                for (deck_parent, mut deck) in decks.iter_mut() {
+                    if deck_parent.0 == parent.0 {
+                        deck.rotation = rotation;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sent when handles are let go of, with the shapes of the splines they moved from before the
+/// drag. It's recorded by `record_drag`, after the handles have snapped
+struct DragEnded {
+    before: Vec<(Entity, SplineShape)>,
+    size: EditSize,
+}
+
+/// Adds finished drags to the history, asking first if they're large
+fn record_drag(
+    mut drags: EventReader<DragEnded>,
+    settings: Res<Settings>,
+    beziers: Query<&PolyBezier<CubicBezier>>,
+    mut edits: EventWriter<EditEvent>,
+    (mut safety, mut dialogs): (ResMut<SafetyNet>, ResMut<Dialogs>),
+) {
+    for drag in drags.iter() {
+        let moved = drag
+            .before
+            .iter()
+            .filter_map(|(spline, before)| {
+                PatchOp::reshaped(before.clone(), beziers.get(*spline).ok()?)
+            })
+            .collect();
+        safety.keep(moved, drag.size, "drag", settings.edit_limit, &mut dialogs, &mut edits);
+    }
+}
+
 fn update_bezier_transform(
     pick_cam: Query<&PickingCamera>,
     mouse_button_input: Res<Input<MouseButton>>,
     mut objects: Query<(&mut DragState, &Hover, &mut Transform, &Parent, Entity)>,
     sections: Query<(&Hover, &Parent, &BezierSection, Entity)>,
     mut beziers: Query<&mut PolyBezier<CubicBezier>>,
-    mut switches: Query<(&mut SwitchDrag, &Hover, &mut Transform, Entity), Without<DragState>>,
+    mut switches: Query<(&mut ObjectDrag, &Hover, &mut Transform, Entity), Without<DragState>>,
+    switch_data: Query<&SwitchData>,
+    deletable: Query<
+        Entity,
+        Or<(
+            With<SwitchData>,
+            With<IndustryData>,
+            With<TurntableData>,
+            With<ServiceData>,
+        )>,
+    >,
+    snappable: Query<(Option<&ServiceData>, Option<&IndustryData>)>,
+    welded: Query<&Welded>,
+    (keys, settings, assets, locked, selected, mut drags, mut pivot, mut limited): (
+        Res<Input<KeyCode>>,
+        Res<Settings>,
+        Res<DefaultAssets>,
+        Query<&Locked>,
+        Query<&Selected>,
+        EventWriter<DragEnded>,
+        // Where the grabbed handle started, while a whole spline is dragged
+        Local<Option<Vec3>>,
+        ResMut<LimitFeedback>,
+    ),
     mut palette: ResMut<Palette>,
     mut modification: EventWriter<BezierModificaiton>,
     mut section_update: EventWriter<BezierSectionUpdate>,
     mut snapping: EventWriter<SnapEvent>,
+    mut edits: EventWriter<EditEvent>,
 ) {
     let picking_camera: &PickingCamera = if let Some(cam) = pick_cam.iter().last() {
         cam
@@ -150,10 +490,24 @@ fn update_bezier_transform(
     };
 
     if mouse_button_input.just_pressed(MouseButton::Left) {
-        if matches!(palette.action, MouseAction::Drag | MouseAction::Extrude) {
+        let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+        // With the gizmo, clicking a handle only selects it, see `gizmo.rs`. Shift clicking adds
+        // to or removes from the selection, see `select_points`, so it doesn't grab either
+        let grab = match palette.action {
+            MouseAction::Drag => !palette.gizmo && !shift,
+            MouseAction::Extrude => true,
+            _ => false,
+        };
+        if grab {
             let mut found_hover = false;
-            for (mut state, hover, trans, _p, _e) in objects.iter_mut() {
-                if hover.hovered() {
+            let mut junction = None;
+            let mut group = None;
+            let mut rigid = None;
+            // Space grabs the whole spline, which moves without changing shape
+            let rigid_drag = keys.pressed(KeyCode::Space) && matches!(palette.action, MouseAction::Drag);
+            let group_drag = !rigid_drag && matches!(palette.action, MouseAction::Drag);
+            for (mut state, hover, trans, parent, entity) in objects.iter_mut() {
+                if hover.hovered() && locked.get(parent.0).is_err() {
                     found_hover = true;
                     state.initial = Some(trans.clone());
                     let dir = if palette.lock_z {
@@ -171,10 +525,57 @@ fn update_bezier_transform(
                         picking_ray.direction(),
                         tmp.map_or(Vec3::ZERO, |int| int.position() - trans.translation),
                     ));
+                    let bez = beziers.get(parent.0).expect("No parent found");
+                    state.tangent = bez.tangent(state.pt);
+                    if (palette.connected_move || welded.get(entity).is_ok())
+                        && matches!(palette.action, MouseAction::Drag)
+                        && !rigid_drag
+                        && bez.is_endpoint(state.pt)
+                    {
+                        junction = Some((
+                            bez.get_control_point(state.pt),
+                            parent.0,
+                            state.drag_start,
+                            state.tangent,
+                        ));
+                    }
+                    if selected.get(entity).is_ok() && group_drag {
+                        group = Some((entity, state.drag_start, state.tangent));
+                    }
+                    if rigid_drag {
+                        rigid = Some((parent.0, entity, state.drag_start, state.tangent));
+                        *pivot = Some(trans.translation);
+                    }
+                }
+            }
+            // Pick up the rest of the grabbed spline
+            if let Some((curve, grabbed, drag_start, tangent)) = rigid {
+                for (mut state, _h, trans, parent, entity) in objects.iter_mut() {
+                    if parent.0 == curve && entity != grabbed {
+                        state.initial = Some(trans.clone());
+                        state.drag_start = drag_start;
+                        state.tangent = tangent;
+                    }
+                }
+            }
+            // Pick up the endpoints of any other splines that share the dragged point
+            if let Some((loc, curve, drag_start, tangent)) = junction {
+                for (mut state, _h, trans, parent, _e) in objects.iter_mut() {
+                    if parent.0 != curve && locked.get(parent.0).is_err() {
+                        let bez = beziers.get(parent.0).expect("No parent found");
+                        if bez.is_endpoint(state.pt)
+                            && bez.get_control_point(state.pt).distance(loc) < JUNCTION_EPSILON
+                        {
+                            state.initial = Some(trans.clone());
+                            state.drag_start = drag_start;
+                            // Move along the dragged spline, so the junction stays together
+                            state.tangent = tangent;
+                        }
+                    }
                 }
             }
             if !found_hover {
-                for (mut state, hover, trans, _e) in switches.iter_mut() {
+                for (mut state, hover, trans, entity) in switches.iter_mut() {
                     if hover.hovered() {
                         // found_hover = true;
                         let dir = if palette.lock_z {
@@ -194,6 +595,28 @@ fn update_bezier_transform(
                             picking_ray.direction(),
                             tmp.map_or(Vec3::ZERO, |int| int.position() - trans.translation),
                         ));
+                        if selected.get(entity).is_ok() && group_drag {
+                            group = Some((entity, state.drag_start, Vec3::ZERO));
+                        }
+                    }
+                }
+            }
+            // Pick up the rest of the selection, which moves with whatever was grabbed
+            if let Some((grabbed, drag_start, tangent)) = group {
+                for (mut state, _h, trans, parent, entity) in objects.iter_mut() {
+                    if entity != grabbed
+                        && selected.get(entity).is_ok()
+                        && locked.get(parent.0).is_err()
+                    {
+                        state.initial = Some(trans.clone());
+                        state.drag_start = drag_start;
+                        state.tangent = tangent;
+                    }
+                }
+                for (mut state, _h, trans, entity) in switches.iter_mut() {
+                    if entity != grabbed && selected.get(entity).is_ok() {
+                        state.initial = Some(trans.clone());
+                        state.drag_start = drag_start;
                     }
                 }
             }
@@ -202,6 +625,44 @@ fn update_bezier_transform(
                 picking_ray.origin(),
                 picking_ray.direction(),
             ));
+        } else if let MouseAction::PlaceIndustry(ty) = palette.action {
+            if let Some(int) =
+                picking_camera.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+                    point: Vec3::ZERO,
+                    normal: Vec3::new(0., 1., 0.),
+                })
+            {
+                modification.send(BezierModificaiton::PlaceIndustry(
+                    int.position(),
+                    ty,
+                    Quat::IDENTITY,
+                ));
+            }
+        } else if let MouseAction::PlaceService(kind) = palette.action {
+            if let Some(int) =
+                picking_camera.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+                    point: Vec3::ZERO,
+                    normal: Vec3::new(0., 1., 0.),
+                })
+            {
+                modification.send(BezierModificaiton::PlaceService(
+                    int.position(),
+                    kind,
+                    Quat::IDENTITY,
+                ));
+            }
+        } else if matches!(palette.action, MouseAction::PlaceTurntable) {
+            if let Some(int) =
+                picking_camera.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+                    point: Vec3::ZERO,
+                    normal: Vec3::new(0., 1., 0.),
+                })
+            {
+                modification.send(BezierModificaiton::PlaceTurntable(
+                    int.position(),
+                    Quat::IDENTITY,
+                ));
+            }
         } else if matches!(palette.action, MouseAction::Delete) {
             let mut found_hover = false;
             for (state, hover, _trans, parent, _e) in objects.iter() {
@@ -224,9 +685,17 @@ fn update_bezier_transform(
                 }
             }
             if !found_hover {
-                for (_s, hover, _t, entity) in switches.iter() {
-                    if hover.hovered() {
+                for (_s, hover, trans, entity) in switches.iter() {
+                    // Other placed objects (e.g. rolling stock) can't be deleted
+                    if hover.hovered() && deletable.get(entity).is_ok() {
                         modification.send(BezierModificaiton::DeleteSw(entity));
+                        if let Ok(switch) = switch_data.get(entity) {
+                            edits.send(EditEvent(PatchOp::DeleteSwitch {
+                                location: vec_to_gvas(trans.translation),
+                                ty: Some(switch.ty as u32),
+                                rotation: Some(quat_to_rotator(trans.rotation)),
+                            }));
+                        }
                     }
                 }
             }
@@ -235,10 +704,39 @@ fn update_bezier_transform(
                 if hover.hovered() {
                     let mut bez = beziers.get_mut(parent.0.clone()).unwrap();
                     modification.send(BezierModificaiton::ChangeTy(parent.0.clone(), bez.ty(), ty));
-                    bez.set_ty(ty);
+                    bez.set_ty(ty, &assets);
                     break;
                 }
             }
+        } else if matches!(palette.action, MouseAction::Split) {
+            // Split at the exact point clicked, rather than a whole section
+            if let Some((hit, intersection)) = picking_camera.intersect_top() {
+                if let Ok((_h, parent, section, _e)) = sections.get(hit) {
+                    let bez = beziers.get(parent.0).unwrap();
+                    if let Some(seg) = bez.get_segment(&section.0) {
+                        let t = bez.closest_t(seg, intersection.position());
+                        modification.send(BezierModificaiton::SplitAt(parent.0, seg, t));
+                    }
+                }
+            }
+        } else if matches!(palette.action, MouseAction::Insert) {
+            if let Some((hit, intersection)) = picking_camera.intersect_top() {
+                if let Ok((_h, parent, section, _e)) = sections.get(hit) {
+                    let mut bez = beziers.get_mut(parent.0).unwrap();
+                    if locked.get(parent.0).is_ok() {
+                        warn!("Can't insert a point into a locked spline");
+                    } else if let Some(seg) = bez.get_segment(&section.0) {
+                        // Avoid zero length segments either side of the new point
+                        let t = bez.closest_t(seg, intersection.position()).clamp(0.01, 0.99);
+                        let before = SplineShape::of(&bez);
+                        let pt = bez.insert_at(seg, t);
+                        modification.send(BezierModificaiton::Extrude(parent.0, pt));
+                        if let Some(op) = PatchOp::reshaped(before, &bez) {
+                            edits.send(EditEvent(op));
+                        }
+                    }
+                }
+            }
         } else if matches!(palette.action, MouseAction::ToggleVisibility) {
             for (hover, parent, section, entity) in sections.iter() {
                 if hover.hovered() {
@@ -249,7 +747,22 @@ fn update_bezier_transform(
             }
         }
     } else if mouse_button_input.just_released(MouseButton::Left) {
-        for (mut state, _sel, _trans, parent, entity) in objects.iter_mut() {
+        *pivot = None;
+        let mut size = EditSize::default();
+        // (spline, moved points and where they started, without the curve offset)
+        let mut moved_splines: Vec<(Entity, Vec<(usize, Vec3)>)> = vec![];
+        for (mut state, _sel, trans, parent, entity) in objects.iter_mut() {
+            if let Some(initial) = &state.initial {
+                if initial.translation != trans.translation {
+                    let off = beziers.get(parent.0).map_or(Vec3::ZERO, |b| settings.curve_offset(b.ty()));
+                    let from = (state.pt, initial.translation - off);
+                    match moved_splines.iter_mut().find(|(s, _)| *s == parent.0) {
+                        Some((_s, moved)) => moved.push(from),
+                        None => moved_splines.push((parent.0, vec![from])),
+                    }
+                    size.points += 1;
+                }
+            }
             if palette.snapping && state.initial.is_some() {
                 snapping.send(SnapEvent::Spline(parent.0, entity));
             }
@@ -259,6 +772,14 @@ fn update_bezier_transform(
                 bezier: parent.0,
             });
         }
+        size.splines = moved_splines.len();
+        let before = moved_splines
+            .into_iter()
+            .filter_map(|(spline, moved)| {
+                Some((spline, SplineShape::before(beziers.get(spline).ok()?, moved)))
+            })
+            .collect();
+        drags.send(DragEnded { before, size });
         // Clicking on a piece of track forces an update
         for (hover, parent, _, _) in sections.iter() {
             if hover.hovered() {
@@ -269,16 +790,43 @@ fn update_bezier_transform(
         }
         for (mut state, _h, _t, entity) in switches.iter_mut() {
             if palette.snapping && state.initial.is_some() {
-                snapping.send(SnapEvent::Switch(entity));
+                if switch_data.get(entity).is_ok() {
+                    snapping.send(SnapEvent::Switch(entity));
+                } else if let Ok((service, industry)) = snappable.get(entity) {
+                    if service.is_some() || industry.is_some_and(|i| i.ty == FIREWOOD_DEPOT) {
+                        snapping.send(SnapEvent::Object(entity));
+                    }
+                }
             }
             state.initial = None;
             state.drag_start = None;
         }
     }
 
-    for (state, _sel, mut trans, parent, _e) in objects.iter_mut() {
+    // Q and E rotate the object or spline being dragged
+    let rotate = if keys.just_pressed(KeyCode::Q) {
+        15f32.to_radians()
+    } else if keys.just_pressed(KeyCode::E) {
+        -15f32.to_radians()
+    } else {
+        0.
+    };
+    let constraint = DragConstraint::from_keys(&keys);
+    // Why any dragged point was held back this frame
+    let mut limit = None;
+    for (mut state, _sel, mut trans, parent, _e) in objects.iter_mut() {
+        // A whole spline turns around the grabbed handle
+        if let (Some(pivot), true) = (*pivot, rotate != 0.) {
+            if let Some(initial) = state.initial.as_mut() {
+                initial.translation =
+                    pivot + Quat::from_rotation_y(rotate) * (initial.translation - pivot);
+            }
+        }
         if let Some((origin, dir, offset)) = state.drag_start {
-            let dir = if palette.lock_z {
+            let dir = if constraint == Some(DragConstraint::Vertical) {
+                // A vertical plane facing the camera
+                Vec3::new(dir.x, 0., dir.z).normalize_or_zero()
+            } else if palette.lock_z {
                 Vec3::new(0., 1., 0.)
             } else {
                 dir
@@ -289,25 +837,44 @@ fn update_bezier_transform(
                     normal: dir,
                 })
             {
-                let dir = int.position() - origin - offset;
+                let mut dir = int.position() - origin - offset;
+                if let Some(constraint) = constraint {
+                    dir = constraint.apply(dir, state.tangent);
+                }
                 let mut init = match state.initial {
                     Some(initial) => initial,
                     None => unreachable!(),
                 };
                 init.translation += dir;
-                *trans = init;
                 let mut bez = beziers.get_mut(parent.0).expect("No parent found");
-                let off = curve_offset(bez.ty());
+                let off = settings.curve_offset(bez.ty());
+                // Whole splines keep their shape, so only points dragged on their own are limited
+                if let (Some(max_grade), None) = (settings.drag_max_grade, *pivot) {
+                    let (loc, clamped) =
+                        clamp_grade(&bez, state.pt, init.translation - off, max_grade);
+                    init.translation = loc + off;
+                    if clamped {
+                        limit = Some(format!("Grade limited to {:.1}%", max_grade));
+                    }
+                }
+                if let (Some(min_radius), None, Some(initial)) =
+                    (settings.drag_min_radius, *pivot, state.initial)
+                {
+                    let from = initial.translation - off;
+                    let to = init.translation - off;
+                    let min = min_radius / METERS_PER_UNIT;
+                    let (loc, clamped) = clamp_radius(&bez, state.pt, from, to, min);
+                    init.translation = loc + off;
+                    if clamped {
+                        let radius = settings.units.length(min_radius);
+                        limit = Some(format!("Radius limited to {}", radius));
+                    }
+                }
+                *trans = init;
                 if dir != Vec3::ZERO {
                     if matches!(palette.action, MouseAction::Extrude) {
                         let loc = init.translation - off;
                         let before = bez.before(state.pt, init.translation);
-                        println!(
-                            "Before: {}, pt: {} -> {}",
-                            before,
-                            state.pt,
-                            state.pt + if !before { 1 } else { 0 }
-                        );
                         bez.insert(state.pt + if !before { 1 } else { 0 }, loc);
                         modification.send(BezierModificaiton::Extrude(parent.0.clone(), state.pt));
                         palette.action = MouseAction::Drag;
@@ -321,7 +888,13 @@ fn update_bezier_transform(
             }
         }
     }
-    for (state, _h, mut trans, _e) in switches.iter_mut() {
+    if limited.0 != limit {
+        limited.0 = limit;
+    }
+    for (mut state, _h, mut trans, _e) in switches.iter_mut() {
+        if let Some(initial) = state.initial.as_mut() {
+            initial.rotation = Quat::from_rotation_y(rotate) * initial.rotation;
+        }
         if let Some((origin, dir, offset)) = state.drag_start {
             let dir = if palette.lock_z {
                 Vec3::new(0., 1., 0.)
@@ -359,7 +932,9 @@ fn modify_beziers(
         &BezierSection,
     )>,
     assets: Res<DefaultAssets>,
+    settings: Res<Settings>,
     mut section_update: EventWriter<BezierSectionUpdate>,
+    mut edits: EventWriter<EditEvent>,
 ) {
     for modification in modifications.iter() {
         match modification {
@@ -384,7 +959,7 @@ fn modify_beziers(
                         },
                         ..Default::default()
                     })
-                    .insert(SwitchDrag::default())
+                    .insert(ObjectDrag::default())
                     .insert(SwitchData {
                         ty,
                         location: vec_to_gvas(translation),
@@ -392,8 +967,53 @@ fn modify_beziers(
                         state: 0,
                     });
             }
+            BezierModificaiton::PlaceCurve(ty, points, visibility) => {
+                let bezier = PolyBezier::new(points.clone(), visibility.clone(), *ty);
+                if let Some(bezier) = spawn_bezier(&mut commands, &assets, &settings, bezier) {
+                    section_update.send(BezierSectionUpdate { bezier });
+                    edits.send(EditEvent(PatchOp::AddSpline {
+                        ty: (*ty).into(),
+                        points: points.iter().map(|p| vec_to_gvas(*p)).collect(),
+                        visibility: Some(visibility.clone()),
+                    }));
+                }
+            }
+            &BezierModificaiton::PlaceIndustry(translation, ty, rotation) => {
+                spawn_industry(
+                    &mut commands,
+                    &assets,
+                    IndustryData::new(ty, vec_to_gvas(translation), quat_to_rotator(rotation)),
+                );
+            }
+            &BezierModificaiton::PlaceService(translation, kind, rotation) => {
+                spawn_service(
+                    &mut commands,
+                    &assets,
+                    ServiceData::new(kind, vec_to_gvas(translation), quat_to_rotator(rotation)),
+                );
+            }
+            &BezierModificaiton::PlaceTurntable(translation, rotation) => {
+                let rotation = quat_to_rotator(rotation);
+                spawn_turntable(
+                    &mut commands,
+                    &assets,
+                    TurntableData {
+                        ty: 0,
+                        location: vec_to_gvas(translation),
+                        rotation,
+                        deck_rotation: rotation,
+                    },
+                );
+            }
             &BezierModificaiton::DeleteSw(e) => {
-                commands.entity(e).despawn();
+                // Turntables have their deck as children
+                commands.entity(e).despawn_recursive();
+            }
+            &BezierModificaiton::DeleteCurve(e) => {
+                if let Ok((bez, _e, _c)) = beziers.get(e) {
+                    edits.send(EditEvent(PatchOp::delete_spline(bez)));
+                    commands.entity(e).despawn_recursive();
+                }
             }
             &BezierModificaiton::Extrude(e, pt) => {
                 for (mut state, _t, parent, _e) in objects.iter_mut() {
@@ -403,13 +1023,12 @@ fn modify_beziers(
                 }
                 let (bez, _e, _c) = beziers.get(e).unwrap();
                 let loc = bez.get_control_point(pt);
-                println!("Extrude: {}, {}, {:?}", loc, pt, bez.ty());
                 // bez.insert(pt, loc);
                 let child = commands
                     .spawn_bundle(PbrBundle {
                         mesh: assets.handle_mesh.clone(),
                         material: assets.handle_material.clone(),
-                        transform: Transform::from_translation(loc + curve_offset(bez.ty())),
+                        transform: Transform::from_translation(loc + settings.curve_offset(bez.ty())),
                         ..Default::default()
                     })
                     .insert_bundle(bevy_mod_picking::PickableBundle {
@@ -440,7 +1059,7 @@ fn modify_beziers(
                         .spawn_bundle(PbrBundle {
                             mesh: assets.handle_mesh.clone(),
                             material: assets.handle_material.clone(),
-                            transform: Transform::from_translation(start + curve_offset(ty)),
+                            transform: Transform::from_translation(start + settings.curve_offset(ty)),
                             ..Default::default()
                         })
                         .insert_bundle(bevy_mod_picking::PickableBundle {
@@ -456,7 +1075,7 @@ fn modify_beziers(
                             pt: 0,
                             ..DragState::default()
                         });
-                    let transform = Transform::from_translation(start + curve_offset(ty));
+                    let transform = Transform::from_translation(start + settings.curve_offset(ty));
                     commands
                         .spawn_bundle(PbrBundle {
                             mesh: assets.handle_mesh.clone(),
@@ -477,6 +1096,7 @@ fn modify_beziers(
                             pt: 1,
                             drag_start: Some((start, dir, Vec3::ZERO)),
                             initial: Some(transform),
+                            ..DragState::default()
                         });
                 });
                 let bezier = PolyBezier::new(vec![start, start], vec![true, true], ty);
@@ -485,6 +1105,26 @@ fn modify_beziers(
                     bezier: entity.id(),
                 });
             }
+            &BezierModificaiton::Reversed(e) => {
+                let (bez, _e, _c) = beziers.get(e).unwrap();
+                let last = bez.len() - 1;
+                for (mut state, _t, parent, _e) in objects.iter_mut() {
+                    if parent.0 == e {
+                        state.pt = last - state.pt;
+                    }
+                }
+                section_update.send(BezierSectionUpdate { bezier: e });
+            }
+            &BezierModificaiton::Rebuilt(e) => {
+                if let Ok((bez, _e, children)) = beziers.get(e) {
+                    for child in children.iter() {
+                        commands.entity(*child).despawn_recursive();
+                    }
+                    commands.entity(e).remove::<DeferredHandles>();
+                    spawn_handles(&mut commands, &assets, &settings, e, bez);
+                    section_update.send(BezierSectionUpdate { bezier: e });
+                }
+            }
             &BezierModificaiton::ChangeTy(e, old, ty) => {
                 for (mut mat, mut pick, _e, parent, s) in sections.iter_mut() {
                     if parent.0 == e {
@@ -504,7 +1144,7 @@ fn modify_beziers(
                         }
                     }
                 }
-                let handle_diff = curve_offset(ty) - curve_offset(old);
+                let handle_diff = settings.curve_offset(ty) - settings.curve_offset(old);
                 if handle_diff != Vec3::ZERO {
                     for (_state, mut trans, parent, _e) in objects.iter_mut() {
                         if parent.0 == e {
@@ -527,30 +1167,59 @@ fn modify_beziers(
                 }
             }
             &BezierModificaiton::DeletePt(e, pt) => {
-                let (first, entity, children) = beziers.get(e).unwrap();
-                let (first, second) = first.split_pt(pt);
-                commands.entity(entity).despawn();
-                for child in children.iter() {
-                    commands.entity(child.clone()).despawn();
+                let (bez, entity, children) = beziers.get(e).unwrap();
+                let (first, second) = bez.split_pt(pt);
+                for op in split_edits(bez, [&first, &second]) {
+                    edits.send(EditEvent(op));
                 }
-                if let Some(bezier) = spawn_bezier(&mut commands, &assets, first) {
-                    section_update.send(BezierSectionUpdate { bezier });
-                }
-                if let Some(bezier) = spawn_bezier(&mut commands, &assets, second) {
+                for bezier in split_bezier(
+                    &mut commands,
+                    (entity, children, bez),
+                    [(first, 0..pt, 0), (second, pt + 1..bez.len(), pt + 1)],
+                    &mut objects,
+                    &sections,
+                ) {
                     section_update.send(BezierSectionUpdate { bezier });
                 }
             }
             BezierModificaiton::DeleteSection(e, section) => {
-                let (first, entity, children) = beziers.get(*e).unwrap();
-                let (first, second) = first.split_sec(section);
-                commands.entity(entity).despawn();
-                for child in children.iter() {
-                    commands.entity(child.clone()).despawn();
+                let (bez, entity, children) = beziers.get(*e).unwrap();
+                let pt = bez.get_segment(section).unwrap();
+                let (first, second) = bez.split_sec(section);
+                for op in split_edits(bez, [&first, &second]) {
+                    edits.send(EditEvent(op));
                 }
-                if let Some(bezier) = spawn_bezier(&mut commands, &assets, first) {
+                for bezier in split_bezier(
+                    &mut commands,
+                    (entity, children, bez),
+                    [(first, 0..pt + 1, 0), (second, pt + 1..bez.len(), pt + 1)],
+                    &mut objects,
+                    &sections,
+                ) {
                     section_update.send(BezierSectionUpdate { bezier });
                 }
-                if let Some(bezier) = spawn_bezier(&mut commands, &assets, second) {
+            }
+            &BezierModificaiton::SplitAt(e, seg, t) => {
+                let (bez, entity, children) = beziers.get(e).unwrap();
+                // Avoid zero length segments at either end
+                let t = t.clamp(0.01, 0.99);
+                let (first, second) = bez.split_at(seg, t);
+                for op in split_edits(bez, [&first, &second]) {
+                    edits.send(EditEvent(op));
+                }
+                let loc = first.get_control_point(first.len() - 1) + settings.curve_offset(bez.ty());
+                let new_pt = first.len() - 1;
+                let halves = split_bezier(
+                    &mut commands,
+                    (entity, children, bez),
+                    [(first, 0..seg + 1, 0), (second, seg + 1..bez.len(), seg)],
+                    &mut objects,
+                    &sections,
+                );
+                // Both halves have at least two points, and need a handle at the new point
+                for (bezier, pt) in halves.into_iter().zip([new_pt, 0]) {
+                    let handle = spawn_handle(&mut commands, &assets, loc, pt);
+                    commands.entity(bezier).push_children(&[handle]);
                     section_update.send(BezierSectionUpdate { bezier });
                 }
             }
@@ -558,9 +1227,90 @@ fn modify_beziers(
     }
 }
 
-fn spawn_bezier(
+/// The edits that replace `original` with the halves it was split into, for the history. Halves
+/// with fewer than two points are left out, like `split_bezier` does
+fn split_edits(
+    original: &PolyBezier<CubicBezier>,
+    halves: [&PolyBezier<CubicBezier>; 2],
+) -> Vec<PatchOp> {
+    let added = halves
+        .into_iter()
+        .filter(|half| half.len() > 1)
+        .map(PatchOp::add_spline);
+    std::iter::once(PatchOp::delete_spline(original))
+        .chain(added)
+        .collect()
+}
+
+/// Replaces a curve with the halves it was split into. Each half is given with the range of
+/// control points of the original it reuses, and how far their indices shift in the half;
+/// segments between two reused points are reused as well. The original entity is kept for the
+/// first half with more than one point, and handles and sections are moved to the half they
+/// belong to, so only the removed ones are despawned. Returns the entities of the halves
+fn split_bezier(
+    commands: &mut Commands,
+    (entity, children, original): (Entity, &Children, &PolyBezier<CubicBezier>),
+    halves: [(PolyBezier<CubicBezier>, Range<usize>, usize); 2],
+    handles: &mut Query<(&mut DragState, &mut Transform, &Parent, Entity)>,
+    sections: &Query<(
+        &mut Handle<StandardMaterial>,
+        &mut PickableButton<StandardMaterial>,
+        Entity,
+        &Parent,
+        &BezierSection,
+    )>,
+) -> Vec<Entity> {
+    // (entity, reused, shift)
+    let mut targets = vec![];
+    for (bez, reused, shift) in halves {
+        if bez.len() > 1 {
+            let target = if targets.is_empty() {
+                entity
+            } else {
+                commands.spawn_bundle(ParentBundle::default()).id()
+            };
+            targets.push((target, reused, shift));
+            commands.entity(target).insert(bez);
+        }
+    }
+    for &child in children.iter() {
+        // (index, 1 for segments, which also need the point after them reused)
+        let index = if let Ok((state, ..)) = handles.get(child) {
+            Some((state.pt, 0))
+        } else if let Ok((.., section)) = sections.get(child) {
+            original.get_segment(&section.0).map(|i| (i, 1))
+        } else {
+            None
+        };
+        let target = index.and_then(|(i, seg)| {
+            targets
+                .iter()
+                .find(|(_t, reused, _s)| i >= reused.start && i + seg < reused.end)
+                .map(|(target, _r, shift)| (i - shift, *target))
+        });
+        if let Some((i, target)) = target {
+            if let Ok((mut state, ..)) = handles.get_mut(child) {
+                state.pt = i;
+            }
+            if target != entity {
+                commands.entity(entity).remove_children(&[child]);
+                commands.entity(target).push_children(&[child]);
+            }
+        } else {
+            commands.entity(child).despawn_recursive();
+        }
+    }
+    if targets.is_empty() {
+        commands.entity(entity).despawn();
+    }
+    targets.into_iter().map(|(target, _r, _s)| target).collect()
+}
+
+/// Spawns a spline with a handle for each control point, unless it has fewer than two
+pub fn spawn_bezier(
     commands: &mut Commands,
     assets: &DefaultAssets,
+    settings: &Settings,
     first: PolyBezier<CubicBezier>,
 ) -> Option<Entity> {
     if first.len() > 1 {
@@ -571,7 +1321,7 @@ fn spawn_bezier(
                     .spawn_bundle(PbrBundle {
                         mesh: assets.handle_mesh.clone(),
                         material: assets.handle_material.clone(),
-                        transform: Transform::from_translation(loc + curve_offset(first.ty())),
+                        transform: Transform::from_translation(loc + settings.curve_offset(first.ty())),
                         ..Default::default()
                     })
                     .insert_bundle(bevy_mod_picking::PickableBundle {
@@ -596,6 +1346,42 @@ fn spawn_bezier(
     }
 }
 
+/// Spawns a single spline handle. The caller adds it to the spline
+pub fn spawn_handle(
+    commands: &mut Commands,
+    assets: &DefaultAssets,
+    translation: Vec3,
+    pt: usize,
+) -> Entity {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: assets.handle_mesh.clone(),
+            material: assets.handle_material.clone(),
+            transform: Transform::from_translation(translation),
+            ..Default::default()
+        })
+        .insert_bundle(bevy_mod_picking::PickableBundle {
+            pickable_button: PickableButton {
+                initial: Some(assets.handle_material.clone()),
+                hovered: Some(assets.handle_hover_material.clone()),
+                pressed: Some(assets.handle_hover_material.clone()),
+                selected: Some(assets.handle_material.clone()),
+            },
+            ..Default::default()
+        })
+        .insert(DragState::new(pt))
+        .id()
+}
+
+/// A spline with segments meshed in draft during a drag
+#[derive(Debug)]
+struct DraftedSpline {
+    entity: Entity,
+    segments: Vec<usize>,
+    /// Number of points when the segments were drafted
+    len: usize,
+}
+
 /// Bezier section update event
 pub struct BezierSectionUpdate {
     pub bezier: Entity,
@@ -605,17 +1391,82 @@ fn update_curve_sections(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     assets: Res<DefaultAssets>,
-    mut beziers: Query<&mut PolyBezier<CubicBezier>>,
+    settings: Res<Settings>,
+    mut beziers: Query<(Entity, &mut PolyBezier<CubicBezier>)>,
+    handles: Query<(&DragState, &Parent)>,
     mut sections: Query<(&mut Transform, &BezierSection)>,
     mut section_update: EventReader<BezierSectionUpdate>,
+    mut last_quality: Local<Option<(MeshQuality, MeshDetail)>>,
+    mut drafted: Local<Vec<DraftedSpline>>,
+    mut backlog: Local<Vec<Entity>>,
 ) {
     let start = Instant::now();
-    for update in section_update.iter() {
-        let entity = update.bezier.clone();
-        if let Ok(mut bezier) = beziers.get_mut(entity) {
-            // println!("Has update: {:?}", bezier.ty());
-            // println!("Bez: {:?}", bezier);
-            for (mesh, visible) in bezier.create_meshes(&mut meshes, &assets) {
+    let mut updates: Vec<Entity> = backlog.drain(..).collect();
+    updates.extend(section_update.iter().map(|u| u.bezier));
+    let quality = (settings.mesh_quality, settings.mesh_detail);
+    if *last_quality != Some(quality) {
+        *last_quality = Some(quality);
+        for (entity, mut bezier) in beziers.iter_mut() {
+            bezier.remesh();
+            updates.push(entity);
+        }
+    }
+    // Splines are drawn as centerlines instead, see `declutter.rs`. They're meshed once that's
+    // turned off
+    if settings.centerlines_only {
+        updates.sort();
+        updates.dedup();
+        *backlog = updates;
+        return;
+    }
+    let dragging: Vec<Entity> = if settings.draft_while_dragging {
+        handles
+            .iter()
+            .filter(|(state, _p)| state.drag_start.is_some())
+            .map(|(_s, parent)| parent.0)
+            .collect()
+    } else {
+        vec![]
+    };
+    // Segments meshed in draft while being dragged get a full quality pass once the drag ends
+    drafted.retain(|spline| {
+        if dragging.contains(&spline.entity) {
+            return true;
+        }
+        if let Ok((_e, mut bezier)) = beziers.get_mut(spline.entity) {
+            if bezier.len() == spline.len {
+                bezier.remesh_segments(&spline.segments);
+            } else {
+                // Points were added or removed, so the segments don't line up anymore
+                bezier.remesh();
+            }
+            updates.push(spline.entity);
+        }
+        false
+    });
+    let mut updates = updates.into_iter();
+    while let Some(entity) = updates.next() {
+        if let Ok((_e, mut bezier)) = beziers.get_mut(entity) {
+            let (quality, detail) = if dragging.contains(&entity) {
+                let segments = bezier.modified_segments();
+                if let Some(spline) = drafted.iter_mut().find(|s| s.entity == entity) {
+                    for i in segments {
+                        if !spline.segments.contains(&i) {
+                            spline.segments.push(i);
+                        }
+                    }
+                } else {
+                    drafted.push(DraftedSpline {
+                        entity,
+                        segments,
+                        len: bezier.len(),
+                    });
+                }
+                (MeshQuality::Draft, MeshQuality::Draft.detail())
+            } else {
+                (settings.mesh_quality, settings.mesh_detail)
+            };
+            for (mesh, visible) in bezier.create_meshes(&mut meshes, &assets, quality, &detail) {
                 let (material, hover_mat) = if visible {
                     (
                         assets.spline_material[bezier.ty()][SplineState::Normal].clone(),
@@ -660,6 +1511,8 @@ fn update_curve_sections(
                 // This should actually be handled by some kind of event system, so I only loop through the ones
                 // that need to be updates.
                 warn!("Task overrun");
+                // Remeshing every spline at once, e.g. when the quality changes, can take a while
+                backlog.extend(updates);
                 break;
             }
         }
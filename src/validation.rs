@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::gvas::validate::{repair, Problem};
+use crate::gvas::RROSave;
+
+/// Plugin for reporting and repairing saves with broken spline indices
+pub struct ValidationPlugin;
+
+impl Plugin for ValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Validation::default());
+        app.add_system(validation_window);
+    }
+}
+
+/// Height of one problem row
+const ROW_HEIGHT: f32 = 18.;
+
+/// A loaded save that failed `gvas::validate::validate`, see `control::load_save`
+#[derive(Debug, Default)]
+pub struct Validation {
+    pub problems: Vec<Problem>,
+    /// The save with the problems, held back until it's repaired
+    pub save: Option<RROSave>,
+    /// The repaired save, to be loaded
+    pub repaired: Option<RROSave>,
+}
+
+fn validation_window(mut egui_context: ResMut<EguiContext>, mut validation: ResMut<Validation>) {
+    if validation.save.is_none() {
+        return;
+    }
+    let validation = validation.as_mut();
+    let (mut repair_clicked, mut cancel) = (false, false);
+    egui::Window::new("Save Problems")
+        .default_height(300.)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!(
+                "The save has {} problems with its spline indices, and can't be loaded as is.",
+                validation.problems.len()
+            ));
+            let problems = &validation.problems;
+            egui::ScrollArea::vertical().max_height(200.).show_rows(
+                ui,
+                ROW_HEIGHT,
+                problems.len(),
+                |ui, range| {
+                    for problem in &problems[range] {
+                        match problem.spline {
+                            Some(i) => ui.label(format!("Spline {}: {}", i, problem.description)),
+                            None => ui.label(problem.description.as_str()),
+                        };
+                    }
+                },
+            );
+            ui.label("Repairing rebuilds the indices from the control points.");
+            ui.horizontal(|ui| {
+                repair_clicked = ui.button("Repair and Load").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+    if repair_clicked {
+        let mut save = validation.save.take().unwrap();
+        match repair(&mut save) {
+            Ok(_kept) => validation.repaired = Some(save),
+            Err(e) => println!("Error: {:?}", e),
+        }
+        validation.problems.clear();
+    } else if cancel {
+        validation.save = None;
+        validation.problems.clear();
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+
+use crate::background::{ground_height, Terrain};
+use crate::control::DefaultAssets;
+use crate::coords::{gvas_to_vec, METERS_PER_UNIT};
+use crate::palette::{MouseAction, Palette};
+use crate::settings::Settings;
+
+/// Plugin for showing and editing the removed vegetation in a save
+pub struct VegetationPlugin;
+
+impl Plugin for VegetationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(VegetationBrush {
+            radius: 20.,
+            mode: BrushMode::Restore,
+        });
+        app.add_system(vegetation_visibility);
+        app.add_system(vegetation_window);
+        app.add_system(vegetation_brush);
+    }
+}
+
+/// A tree or rock removed in the save
+#[derive(Debug, Component, Clone, Copy, PartialEq)]
+pub struct RemovedVegetation {
+    /// Location from the save. This is kept as is, since the game matches it against the
+    /// location of each tree
+    pub location: [f32; 3],
+    /// Restored with the brush, so it won't be written to the save. Restored markers are kept
+    /// so the brush can clear them again
+    pub restored: bool,
+}
+
+/// What the vegetation brush does to the markers under it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushMode {
+    /// Put the trees and rocks back
+    Restore,
+    /// Remove trees and rocks that were restored with the brush. The save only knows where
+    /// vegetation has been removed, so nothing else can be cleared
+    Clear,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VegetationBrush {
+    /// Brush radius, in meters
+    pub radius: f32,
+    pub mode: BrushMode,
+}
+
+fn vegetation_visibility(
+    palette: Res<Palette>,
+    mut markers: Query<&mut Visibility, With<RemovedVegetation>>,
+) {
+    // Always show the markers while using the brush
+    let visible = palette.show_vegetation || palette.action == MouseAction::VegetationBrush;
+    for mut vis in markers.iter_mut() {
+        if vis.is_visible != visible {
+            vis.is_visible = visible;
+        }
+    }
+}
+
+fn vegetation_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut brush: ResMut<VegetationBrush>,
+    markers: Query<&RemovedVegetation>,
+) {
+    if palette.action != MouseAction::VegetationBrush {
+        return;
+    }
+    let brush = brush.as_mut();
+    egui::Window::new("Vegetation Brush")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let mut radius = settings.units.from_meters(brush.radius);
+            ui.horizontal(|ui| {
+                ui.label("Radius");
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut radius)
+                            .clamp_range(1. ..=1000.)
+                            .suffix(settings.units.suffix()),
+                    )
+                    .changed()
+                {
+                    brush.radius = settings.units.to_meters(radius);
+                }
+            });
+            ui.radio_value(&mut brush.mode, BrushMode::Restore, "Restore vegetation");
+            ui.radio_value(&mut brush.mode, BrushMode::Clear, "Clear restored vegetation");
+            let restored = markers.iter().filter(|v| v.restored).count();
+            ui.label(format!(
+                "{} removed, {} restored",
+                markers.iter().count() - restored,
+                restored
+            ));
+        });
+}
+
+fn vegetation_brush(
+    palette: Res<Palette>,
+    brush: Res<VegetationBrush>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    pick_cam: Query<&PickingCamera>,
+    assets: Res<DefaultAssets>,
+    terrain: Query<&Transform, With<Terrain>>,
+    mut markers: Query<(&mut RemovedVegetation, &mut Handle<StandardMaterial>)>,
+) {
+    if palette.action != MouseAction::VegetationBrush
+        || !mouse_button_input.pressed(MouseButton::Left)
+    {
+        return;
+    }
+    // The brush follows the ground, found first at its height in the middle of the map, then
+    // again at the height under that point
+    let ground = |p: Vec3| {
+        pick_cam.iter().last().and_then(|cam| {
+            cam.intersect_primitive(bevy_mod_picking::Primitive3d::Plane {
+                point: Vec3::new(0., ground_height(&terrain, p), 0.),
+                normal: Vec3::new(0., 1., 0.),
+            })
+        })
+    };
+    let center = match ground(Vec3::ZERO).and_then(|int| ground(int.position())) {
+        Some(int) => int.position(),
+        None => return,
+    };
+    let radius = brush.radius / METERS_PER_UNIT;
+    let restore = brush.mode == BrushMode::Restore;
+    for (mut vegetation, mut material) in markers.iter_mut() {
+        if vegetation.restored == restore {
+            continue;
+        }
+        let offset = gvas_to_vec(vegetation.location) - center;
+        if offset.x * offset.x + offset.z * offset.z <= radius * radius {
+            vegetation.restored = restore;
+            *material = assets.vegetation_material[restore].clone();
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use bevy::app::AppExit;
+use bevy::core_pipeline::{draw_3d_graph, node, AlphaMask3d, Opaque3d, Transparent3d};
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::camera::{ActiveCameras, ExtractedCameraNames};
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotValue};
+use bevy::render::render_phase::RenderPhase;
+use bevy::render::renderer::RenderContext;
+use bevy::render::{RenderApp, RenderStage};
+use bevy::window::{CreateWindow, WindowCloseRequested, WindowId};
+use bevy_mod_picking::{PickingCamera, PickingCameraBundle};
+use smooth_bevy_cameras::controllers::orbit::OrbitCameraController;
+use smooth_bevy_cameras::{LookTransform, LookTransformBundle, Smoother};
+
+use crate::palette::Palette;
+
+/// Name of the plan view's camera
+const PLAN_CAMERA: &str = "plan_camera";
+/// Render graph node that draws the plan view's window
+const PLAN_PASS_DRIVER: &str = "plan_pass_driver";
+/// Egui pass for the plan view's window, so box select can draw in it
+const PLAN_EGUI_PASS: &str = "plan_egui_pass";
+
+/// Plugin for the plan view, a second window looking straight down at the map. It has its own
+/// camera, panned with the right mouse button and zoomed with the wheel, and the tools pick
+/// through whichever window the mouse is over
+pub struct ViewPlugin;
+
+impl Plugin for ViewPlugin {
+    fn build(&self, app: &mut App) {
+        let view = PlanView::default();
+        let window = view.window;
+        app.insert_resource(view);
+        app.add_system(open_plan_view);
+        app.add_system(close_views);
+        app.add_system(control_plan_view);
+        app.add_system(pick_from_hovered_view);
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_system_to_stage(RenderStage::Extract, extract_plan_camera_phases);
+        let mut graph = render_app.world.get_resource_mut::<RenderGraph>().unwrap();
+        graph.add_node(PLAN_PASS_DRIVER, PlanPassDriver);
+        graph
+            .add_node_edge(node::MAIN_PASS_DEPENDENCIES, PLAN_PASS_DRIVER)
+            .unwrap();
+        bevy_egui::setup_pipeline(
+            &mut graph,
+            bevy_egui::RenderGraphConfig {
+                window_id: window,
+                egui_pass: PLAN_EGUI_PASS,
+            },
+        );
+    }
+}
+
+/// The plan view's window. It's opened the first time `Palette::plan_view` is turned on, and
+/// minimized while it's off, since windows can't be closed again
+#[derive(Debug)]
+pub struct PlanView {
+    pub window: WindowId,
+    opened: bool,
+    shown: bool,
+}
+
+impl Default for PlanView {
+    fn default() -> Self {
+        Self {
+            window: WindowId::new(),
+            opened: false,
+            shown: false,
+        }
+    }
+}
+
+impl PlanView {
+    /// Whether the mouse is over the plan view
+    pub fn hovered(&self, windows: &Windows) -> bool {
+        self.shown
+            && windows
+                .get(self.window)
+                .and_then(|window| window.cursor_position())
+                .is_some()
+    }
+}
+
+/// Marks the plan view's camera
+#[derive(Debug, Component)]
+struct PlanCamera;
+
+/// Eye position looking straight down at `target` from `height`. The eye is pulled back slightly,
+/// since the camera's up vector can't point along the view direction
+fn plan_eye(height: f32, target: Vec3) -> Vec3 {
+    target + Vec3::new(0., height, height * 0.01)
+}
+
+fn open_plan_view(
+    palette: Res<Palette>,
+    mut view: ResMut<PlanView>,
+    mut windows: ResMut<Windows>,
+    mut active_cameras: ResMut<ActiveCameras>,
+    main: Query<&LookTransform, With<OrbitCameraController>>,
+    mut create_window: EventWriter<CreateWindow>,
+    mut commands: Commands,
+) {
+    if palette.plan_view == view.shown {
+        return;
+    }
+    view.shown = palette.plan_view;
+    if view.opened {
+        if let Some(window) = windows.get_mut(view.window) {
+            window.set_minimized(!view.shown);
+        }
+        return;
+    }
+    view.opened = true;
+    create_window.send(CreateWindow {
+        id: view.window,
+        descriptor: WindowDescriptor {
+            title: "Plan View".to_string(),
+            width: 600.,
+            height: 600.,
+            ..Default::default()
+        },
+    });
+    // The plan view starts above whatever the main view is looking at
+    let (height, target) = main.iter().next().map_or((10., Vec3::ZERO), |look| {
+        (look.eye.distance(look.target), look.target)
+    });
+    commands
+        .spawn_bundle(PerspectiveCameraBundle {
+            camera: Camera {
+                window: view.window,
+                name: Some(PLAN_CAMERA.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert_bundle(LookTransformBundle {
+            transform: LookTransform {
+                eye: plan_eye(height, target),
+                target,
+            },
+            smoother: Smoother::new(0.),
+        })
+        .insert(PlanCamera);
+    active_cameras.add(PLAN_CAMERA);
+}
+
+/// Closing the plan view turns it off, and closing the main window exits
+fn close_views(
+    view: Res<PlanView>,
+    mut palette: ResMut<Palette>,
+    mut closed: EventReader<WindowCloseRequested>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in closed.iter() {
+        if event.id == view.window {
+            palette.plan_view = false;
+        } else {
+            exit.send(AppExit);
+        }
+    }
+}
+
+/// While the mouse is over the plan view, dragging with the right mouse button pans it and the
+/// wheel zooms it, and the main view's orbit controls are turned off
+fn control_plan_view(
+    view: Res<PlanView>,
+    windows: Res<Windows>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut orbit: Query<&mut OrbitCameraController>,
+    mut plan: Query<&mut LookTransform, With<PlanCamera>>,
+) {
+    let hovered = view.hovered(&windows);
+    for mut controller in orbit.iter_mut() {
+        if controller.enabled == hovered {
+            controller.enabled = !hovered;
+        }
+    }
+    let moved = motion
+        .iter()
+        .fold(Vec2::ZERO, |sum, event| sum + event.delta);
+    let scrolled: f32 = wheel.iter().map(|event| event.y.signum()).sum();
+    let (window, mut look) = match (windows.get(view.window), plan.iter_mut().next()) {
+        (Some(window), Some(look)) if hovered => (window, look),
+        _ => return,
+    };
+    let mut height = look.eye.distance(look.target);
+    let mut target = look.target;
+    if mouse_button_input.pressed(MouseButton::Right) {
+        // World distance across one pixel, at the default 45 degree field of view
+        let scale = 2. * height * std::f32::consts::FRAC_PI_8.tan() / window.height();
+        // Right on screen is +x and up is -z, and the map follows the mouse
+        target.x -= moved.x * scale;
+        target.z -= moved.y * scale;
+    }
+    height = (height * 0.85f32.powf(scrolled)).clamp(0.5, 2000.);
+    let eye = plan_eye(height, target);
+    if look.eye != eye || look.target != target {
+        look.eye = eye;
+        look.target = target;
+    }
+}
+
+/// Moves picking to the camera of the window the mouse is over, so the tools work in both views
+fn pick_from_hovered_view(
+    view: Res<PlanView>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, Option<&PickingCamera>, Entity), With<LookTransform>>,
+    mut commands: Commands,
+) {
+    let hovered = if view.hovered(&windows) {
+        view.window
+    } else if windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+        .is_some()
+    {
+        WindowId::primary()
+    } else {
+        return;
+    };
+    for (camera, picking, entity) in cameras.iter() {
+        match (camera.window == hovered, picking.is_some()) {
+            (true, false) => {
+                commands
+                    .entity(entity)
+                    .insert_bundle(PickingCameraBundle::default());
+            }
+            (false, true) => {
+                commands
+                    .entity(entity)
+                    .remove_bundle::<PickingCameraBundle>();
+            }
+            _ => (),
+        }
+    }
+}
+
+fn extract_plan_camera_phases(mut commands: Commands, active_cameras: Res<ActiveCameras>) {
+    if let Some(entity) = active_cameras.get(PLAN_CAMERA).and_then(|c| c.entity) {
+        commands.get_or_spawn(entity).insert_bundle((
+            RenderPhase::<Opaque3d>::default(),
+            RenderPhase::<AlphaMask3d>::default(),
+            RenderPhase::<Transparent3d>::default(),
+        ));
+    }
+}
+
+/// Draws the plan view's camera into its window
+struct PlanPassDriver;
+
+impl Node for PlanPassDriver {
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let cameras = world.get_resource::<ExtractedCameraNames>().unwrap();
+        if let Some(camera) = cameras.entities.get(PLAN_CAMERA) {
+            graph.run_sub_graph(draw_3d_graph::NAME, vec![SlotValue::Entity(*camera)])?;
+        }
+        Ok(())
+    }
+}
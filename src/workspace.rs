@@ -0,0 +1,436 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_mod_picking::PickingCamera;
+use serde::{Deserialize, Serialize};
+use smooth_bevy_cameras::LookTransform;
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::buffers::{spline_end, BufferStop, PendingStops};
+use crate::control::LoadProgress;
+use crate::coords::{gvas_to_vec, vec_to_gvas};
+use crate::gvas::{GVASError, SplineType, SwitchType};
+use crate::inspector::{Locked, SplineName};
+use crate::palette::{FileEvent, Palette};
+use crate::project::{ProjectMember, Projects};
+use crate::spline::{CubicBezier, PolyBezier};
+use crate::template::{Template, TemplateCurve, Templates};
+
+/// Extension of workspace files
+pub const WORKSPACE_EXTENSION: &str = "rrow";
+
+/// Distance between a spline's first point and the one in a workspace for them to match, in
+/// save units (cm)
+const MATCH_DISTANCE: f32 = 1.;
+
+/// Plugin for workspace files, which keep everything the editor knows about a save that the
+/// save itself can't hold
+pub struct WorkspacePlugin;
+
+impl Plugin for WorkspacePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Workspace {
+            path: format!("workspace.{}", WORKSPACE_EXTENSION),
+            save: None,
+            annotations: vec![],
+            bookmarks: vec![],
+            opening: None,
+            new_text: String::new(),
+        });
+        app.add_system(track_save);
+        app.add_system(workspace_window);
+        app.add_system(apply_workspace);
+    }
+}
+
+/// A note left on the map
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// In editor space
+    pub location: [f32; 3],
+    pub text: String,
+}
+
+/// A saved camera position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub eye: [f32; 3],
+    pub target: [f32; 3],
+}
+
+/// Names, locks and projects of a spline, found again by its first control point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplineMetadata {
+    /// First control point, in save coordinates
+    pub location: [f32; 3],
+    pub name: Option<String>,
+    pub locked: bool,
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceCurve {
+    pub ty: SplineType,
+    pub points: Vec<[f32; 3]>,
+    pub visibility: Vec<bool>,
+}
+
+/// A template, in editor space
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceTemplate {
+    pub name: String,
+    pub origin: [f32; 3],
+    pub curves: Vec<WorkspaceCurve>,
+    /// (switch type as stored in the save, location, rotation)
+    pub switches: Vec<(u32, [f32; 3], [f32; 4])>,
+    #[serde(default)]
+    pub buffer_stops: Vec<[f32; 3]>,
+}
+
+/// Contents of a `.rrow` file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceFile {
+    /// Save the workspace belongs to, loaded when the workspace is opened
+    pub save: Option<PathBuf>,
+    pub annotations: Vec<Annotation>,
+    pub bookmarks: Vec<Bookmark>,
+    pub projects: Vec<String>,
+    pub splines: Vec<SplineMetadata>,
+    pub templates: Vec<WorkspaceTemplate>,
+    /// Spline ends marked as the end of the track, in save coordinates
+    pub buffer_stops: Vec<[f32; 3]>,
+}
+
+pub struct Workspace {
+    /// Workspace file to open and save
+    pub path: String,
+    /// Save last loaded or saved
+    pub save: Option<PathBuf>,
+    pub annotations: Vec<Annotation>,
+    pub bookmarks: Vec<Bookmark>,
+    /// Being opened, along with how many frames its save has been loaded for
+    opening: Option<(WorkspaceFile, Option<usize>)>,
+    /// Text of the next annotation or bookmark
+    new_text: String,
+}
+
+fn template_to_file(template: &Template) -> WorkspaceTemplate {
+    WorkspaceTemplate {
+        name: template.name.clone(),
+        origin: template.origin.to_array(),
+        curves: template
+            .curves
+            .iter()
+            .map(|c| WorkspaceCurve {
+                ty: c.ty,
+                points: c.points.iter().map(|p| p.to_array()).collect(),
+                visibility: c.visibility.clone(),
+            })
+            .collect(),
+        switches: template
+            .switches
+            .iter()
+            .map(|(ty, location, rotation)| (*ty as u32, location.to_array(), rotation.to_array()))
+            .collect(),
+        buffer_stops: template.buffer_stops.iter().map(|p| p.to_array()).collect(),
+    }
+}
+
+fn template_from_file(template: WorkspaceTemplate) -> Template {
+    Template {
+        name: template.name,
+        origin: Vec3::from(template.origin),
+        curves: template
+            .curves
+            .into_iter()
+            .map(|c| TemplateCurve {
+                ty: c.ty,
+                points: c.points.into_iter().map(Vec3::from).collect(),
+                visibility: c.visibility,
+            })
+            .collect(),
+        switches: template
+            .switches
+            .into_iter()
+            .filter_map(|(ty, location, rotation)| match SwitchType::try_from(ty) {
+                Ok(ty) => Some((ty, Vec3::from(location), Quat::from_array(rotation))),
+                Err(_) => {
+                    warn!("Workspace: invalid switch type {}", ty);
+                    None
+                }
+            })
+            .collect(),
+        buffer_stops: template.buffer_stops.into_iter().map(Vec3::from).collect(),
+    }
+}
+
+/// Keeps track of the save being edited, so it can be referenced by the workspace
+fn track_save(mut events: EventReader<FileEvent>, mut workspace: ResMut<Workspace>) {
+    for event in events.iter() {
+        if let FileEvent::Load(path) | FileEvent::Save(path) = event {
+            workspace.save = Some(path.clone());
+        }
+    }
+}
+
+fn save_workspace(
+    path: &str,
+    workspace: &Workspace,
+    projects: &Projects,
+    templates: &Templates,
+    splines: &Query<(
+        &PolyBezier<CubicBezier>,
+        Option<&SplineName>,
+        Option<&Locked>,
+        Option<&ProjectMember>,
+    )>,
+    stops: &Query<&BufferStop>,
+) -> Result<(), GVASError> {
+    let project_name = |member: Option<&ProjectMember>| {
+        member.and_then(|m| {
+            projects
+                .projects
+                .iter()
+                .find(|p| p.id == m.0)
+                .map(|p| p.name.clone())
+        })
+    };
+    let file = WorkspaceFile {
+        save: workspace.save.clone(),
+        annotations: workspace.annotations.clone(),
+        bookmarks: workspace.bookmarks.clone(),
+        projects: projects.projects.iter().map(|p| p.name.clone()).collect(),
+        splines: splines
+            .iter()
+            .filter(|(_b, name, locked, member)| {
+                name.is_some() || locked.is_some() || member.is_some()
+            })
+            .map(|(bez, name, locked, member)| SplineMetadata {
+                location: vec_to_gvas(bez.get_control_point(0)),
+                name: name.map(|n| n.0.clone()),
+                locked: locked.is_some(),
+                project: project_name(member),
+            })
+            .collect(),
+        templates: templates.templates.iter().map(template_to_file).collect(),
+        buffer_stops: stops
+            .iter()
+            .filter_map(|stop| {
+                let (bez, _n, _l, _m) = splines.get(stop.spline).ok()?;
+                Some(vec_to_gvas(spline_end(bez, stop.at_start).0))
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(File::create(path)?, &file)?;
+    Ok(())
+}
+
+fn read_workspace(path: &str) -> Result<WorkspaceFile, GVASError> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+}
+
+fn workspace_window(
+    mut egui_context: ResMut<EguiContext>,
+    palette: Res<Palette>,
+    mut workspace: ResMut<Workspace>,
+    projects: Res<Projects>,
+    templates: Res<Templates>,
+    splines: Query<(
+        &PolyBezier<CubicBezier>,
+        Option<&SplineName>,
+        Option<&Locked>,
+        Option<&ProjectMember>,
+    )>,
+    stops: Query<&BufferStop>,
+    mut cameras: Query<&mut LookTransform, With<PickingCamera>>,
+    mut file_events: EventWriter<FileEvent>,
+) {
+    if !palette.show_workspace {
+        return;
+    }
+    let workspace = workspace.as_mut();
+    let (mut save, mut open) = (false, false);
+    egui::Window::new("Workspace")
+        .default_width(300.)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut workspace.path);
+                save = ui.button("Save").clicked();
+                open = ui.button("Open").clicked();
+            });
+            ui.label(match &workspace.save {
+                Some(path) => format!("Save: {}", path.display()),
+                None => "Save: none, load or save one first".to_string(),
+            });
+            ui.text_edit_singleline(&mut workspace.new_text);
+            let current = cameras.iter().next().map(|look| (look.eye, look.target));
+            ui.horizontal(|ui| {
+                let enabled = !workspace.new_text.is_empty();
+                if let Some((eye, target)) = current {
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Add Bookmark"))
+                        .clicked()
+                    {
+                        workspace.bookmarks.push(Bookmark {
+                            name: std::mem::take(&mut workspace.new_text),
+                            eye: eye.to_array(),
+                            target: target.to_array(),
+                        });
+                    }
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Add Note"))
+                        .on_hover_text("Leaves a note where the camera is looking")
+                        .clicked()
+                    {
+                        workspace.annotations.push(Annotation {
+                            location: target.to_array(),
+                            text: std::mem::take(&mut workspace.new_text),
+                        });
+                    }
+                }
+            });
+            // (eye, target) to move the camera to
+            let mut go_to = None;
+            ui.label("Bookmarks");
+            let mut remove = None;
+            for (i, bookmark) in workspace.bookmarks.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.button("Go").clicked() {
+                        go_to = Some((Vec3::from(bookmark.eye), Vec3::from(bookmark.target)));
+                    }
+                    if ui.small_button("x").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.label(bookmark.name.as_str());
+                });
+            }
+            if let Some(i) = remove.take() {
+                workspace.bookmarks.remove(i);
+            }
+            ui.label("Notes");
+            for (i, note) in workspace.annotations.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    // Keeps the camera's current offset from its target
+                    if let (true, Some((eye, target))) = (ui.button("Go").clicked(), current) {
+                        let location = Vec3::from(note.location);
+                        go_to = Some((location + eye - target, location));
+                    }
+                    if ui.small_button("x").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.label(note.text.as_str());
+                });
+            }
+            if let Some(i) = remove {
+                workspace.annotations.remove(i);
+            }
+            if let Some((eye, target)) = go_to {
+                for mut look in cameras.iter_mut() {
+                    look.eye = eye;
+                    look.target = target;
+                }
+            }
+        });
+    if save {
+        if let Err(e) = save_workspace(
+            &workspace.path,
+            workspace,
+            &projects,
+            &templates,
+            &splines,
+            &stops,
+        ) {
+            println!("Error: {:?}", e);
+        }
+    }
+    if open {
+        match read_workspace(&workspace.path) {
+            Ok(file) => {
+                if let Some(save) = &file.save {
+                    file_events.send(FileEvent::Load(save.clone()));
+                }
+                workspace.opening = Some((file, None));
+            }
+            Err(e) => println!("Error: {:?}", e),
+        }
+    }
+}
+
+/// Applies an opened workspace once its save has been loaded
+fn apply_workspace(
+    mut workspace: ResMut<Workspace>,
+    mut loads: EventReader<LoadProgress>,
+    mut projects: ResMut<Projects>,
+    mut templates: ResMut<Templates>,
+    mut pending_stops: ResMut<PendingStops>,
+    splines: Query<(Entity, &PolyBezier<CubicBezier>)>,
+    mut commands: Commands,
+) {
+    let (file, waited) = if let Some(opening) = &mut workspace.opening {
+        opening
+    } else {
+        return;
+    };
+    if file.save.is_some() {
+        if waited.is_none() {
+            if !loads.iter().any(|p| p.done) {
+                return;
+            }
+            *waited = Some(0);
+        }
+        // The loaded save is spawned at the end of the frame it finishes loading in
+        if let Some(frames) = waited.as_mut() {
+            *frames += 1;
+            if *frames < 2 {
+                return;
+            }
+        }
+    }
+    let (file, _waited) = workspace.opening.take().unwrap();
+    workspace.annotations = file.annotations;
+    workspace.bookmarks = file.bookmarks;
+    // Projects with the same name are merged with the existing ones
+    let mut ids = std::collections::HashMap::new();
+    for name in file.projects {
+        let id = match projects.projects.iter().find(|p| p.name == name) {
+            Some(project) => project.id,
+            None => projects.create(name.clone()),
+        };
+        ids.insert(name, id);
+    }
+    let mut missing = 0;
+    for metadata in file.splines {
+        let location = Vec3::from(metadata.location);
+        let entity = splines.iter().find(|(_e, bez)| {
+            Vec3::from(vec_to_gvas(bez.get_control_point(0))).distance(location) < MATCH_DISTANCE
+        });
+        let entity = if let Some((entity, _b)) = entity {
+            entity
+        } else {
+            missing += 1;
+            continue;
+        };
+        let mut entity = commands.entity(entity);
+        if let Some(name) = metadata.name {
+            entity.insert(SplineName(name));
+        }
+        if metadata.locked {
+            entity.insert(Locked);
+        }
+        if let Some(id) = metadata.project.and_then(|p| ids.get(&p)) {
+            entity.insert(ProjectMember(*id));
+        }
+    }
+    if missing > 0 {
+        warn!("Workspace: {} splines are no longer in the save", missing);
+    }
+    templates
+        .templates
+        .extend(file.templates.into_iter().map(template_from_file));
+    pending_stops
+        .locations
+        .extend(file.buffer_stops.into_iter().map(gvas_to_vec));
+}